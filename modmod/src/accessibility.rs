@@ -0,0 +1,89 @@
+//! Accessibility lints on the slides of topics: images without alt text, which screen
+//! readers can't describe, and slides with headings that skip a level, which breaks
+//! navigating the slides by their headings
+
+use std::path::Path;
+
+use crate::{codes::WarningCode, report::Warning, stats::split_slides};
+
+/// Warnings about the accessibility of the slides in `content`, read from the file at `path`
+pub(crate) fn accessibility_warnings(path: &Path, content: &str) -> Vec<Warning> {
+    let path = path.to_string_lossy();
+    let mut warnings = vec![];
+    for (slide, number) in split_slides(content).into_iter().zip(1..) {
+        let mut in_code = false;
+        let mut heading_level = None;
+        for line in slide.iter().map(|line| line.trim()) {
+            if line.starts_with("```") || line.starts_with("~~~") {
+                in_code = !in_code;
+                continue;
+            }
+            if in_code {
+                continue;
+            }
+
+            if let Some((level, title)) = heading(line) {
+                match heading_level {
+                    Some(previous) if level > previous + 1 => warnings.push(Warning::new(
+                        WarningCode::SkippedHeadingLevel,
+                        format!(
+                            "Slide {number} of {path} skips from heading level {previous} to {level} at '{title}'"
+                        ),
+                    )),
+                    _ => {}
+                }
+                heading_level = Some(level);
+            }
+
+            for image in images_without_alt_text(line) {
+                warnings.push(Warning::new(
+                    WarningCode::MissingAltText,
+                    format!("Image {image} on slide {number} of {path} has no alt text"),
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+/// The level and title of a markdown heading like `## Title`
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let title = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, title.trim()))
+}
+
+/// The images in `line` without alt text: markdown images like `![](image.png)`, and
+/// `<img>` tags without an `alt` attribute. An empty `alt` attribute marks an image as
+/// decorative, so that's fine.
+fn images_without_alt_text(line: &str) -> Vec<&str> {
+    let mut images = vec![];
+    let mut rest = line;
+    while let Some(start) = rest.find("![") {
+        rest = &rest[start + 2..];
+        let Some((alt, after)) = rest.split_once("](") else {
+            break;
+        };
+        let target = after.split_once(')').map_or(after, |(target, _)| target);
+        if alt.trim().is_empty() {
+            images.push(target.split_whitespace().next().unwrap_or(target));
+        }
+    }
+    let mut rest = line;
+    while let Some(start) = rest.find("<img") {
+        rest = &rest[start..];
+        let tag = rest.split_once('>').map_or(rest, |(tag, _)| tag);
+        let mut attributes = tag.split_whitespace();
+        if !attributes
+            .clone()
+            .any(|attribute| attribute.starts_with("alt="))
+        {
+            let src = attributes
+                .find_map(|attribute| attribute.strip_prefix("src="))
+                .map(|src| src.trim_end_matches('/').trim_matches(['"', '\'']));
+            images.push(src.unwrap_or("<img>"));
+        }
+        rest = &rest[tag.len()..];
+    }
+    images
+}
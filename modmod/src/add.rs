@@ -0,0 +1,353 @@
+//! Adding existing definitions to the definition they're part of, editing the definition
+//! file in place so that its formatting and comments are kept
+
+use std::{
+    fmt, fs,
+    path::{Component, Path, PathBuf},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use crate::{
+    io::PathExt,
+    load::{Load, ModuleDef, TopicDef},
+    select::selects,
+    DefinitionKind,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct AddError;
+
+impl fmt::Display for AddError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to add definition")
+    }
+}
+
+impl error_stack::Context for AddError {}
+
+/// Where to add a definition
+#[derive(Debug, Default)]
+pub struct AddOptions {
+    /// The definition to add to. Found in the directories containing the added
+    /// definition if not given: the closest `mod.toml` for topics, the closest
+    /// `topic.toml` for exercises, and the closest track definition for modules
+    pub parent: Option<PathBuf>,
+    /// The unit to add a topic to, by number or name. Only needed if the module
+    /// has more than one unit
+    pub unit: Option<String>,
+    /// The name of an added exercise. Defaults to the name of its directory
+    pub name: Option<String>,
+}
+
+/// A definition that was added to another
+#[derive(Debug)]
+pub struct Added {
+    /// The definition file that was edited
+    pub parent: PathBuf,
+    /// The path as written in the edited definition file
+    pub entry: String,
+}
+
+impl DefinitionKind {
+    /// Add the definition of this kind at `path` to the definition it's part of:
+    /// modules to the `modules` of a track, topics to the `topics` of a unit,
+    /// and exercises, given by their directory, to the `exercises` of a topic
+    pub fn add(self, path: &Path, options: AddOptions) -> Result<Added, AddError> {
+        let parent_file = match self {
+            DefinitionKind::Track => {
+                return Err(Report::new(AddError)
+                    .attach_printable("Tracks are not part of other definitions"))
+            }
+            DefinitionKind::Module => "track definition",
+            DefinitionKind::Topic => "mod.toml",
+            DefinitionKind::Exercise => "topic.toml",
+        };
+        let path = path
+            .canonicalize()
+            .into_report()
+            .attach_printable_lazy(|| {
+                format!("{path} doesn't exist", path = path.to_string_lossy())
+            })
+            .change_context(AddError)?;
+        // Make sure the definition can be loaded before referring to it
+        match self {
+            DefinitionKind::Module => {
+                ModuleDef::load(&path, None).change_context(AddError)?;
+            }
+            DefinitionKind::Topic => {
+                TopicDef::load(&path, None).change_context(AddError)?;
+            }
+            DefinitionKind::Exercise if !path.is_dir() => {
+                return Err(Report::new(AddError).attach_printable(format!(
+                    "Exercise {path} is not a directory",
+                    path = path.to_string_lossy()
+                )));
+            }
+            _ => {}
+        }
+
+        let parent = match options.parent {
+            Some(parent) => parent
+                .canonicalize()
+                .into_report()
+                .attach_printable_lazy(|| {
+                    format!("{path} doesn't exist", path = parent.to_string_lossy())
+                })
+                .change_context(AddError)?,
+            None => find_parent(self, &path).ok_or_else(|| {
+                Report::new(AddError).attach_printable(format!(
+                    "No {parent_file} found in the directories containing {path}",
+                    path = path.to_string_lossy()
+                ))
+            })??,
+        };
+        // Entries are inserted in place, which is only supported for TOML
+        if parent.extension().and_then(|e| e.to_str()) != Some("toml") {
+            return Err(Report::new(AddError).attach_printable(format!(
+                "Only definitions in TOML can be added to, not {path}",
+                path = parent.to_string_lossy()
+            )));
+        }
+
+        let entry = relative_path(&path, parent.parent().unwrap());
+        let mut doc: toml_edit::Document = parent
+            .read_to_string::<AddError>()?
+            .parse()
+            .into_report()
+            .change_context(AddError)?;
+        match self {
+            DefinitionKind::Track => unreachable!(),
+            DefinitionKind::Module => {
+                let modules = array(doc.as_table_mut(), "modules")?;
+                push_path(modules, &entry, &path, &parent)?;
+            }
+            DefinitionKind::Topic => {
+                let units = doc
+                    .get_mut("units")
+                    .and_then(toml_edit::Item::as_array_of_tables_mut)
+                    .ok_or_else(|| {
+                        Report::new(AddError).attach_printable(format!(
+                            "{path} has no `[[units]]`",
+                            path = parent.to_string_lossy()
+                        ))
+                    })?;
+                let names: Vec<String> = units
+                    .iter()
+                    .map(|u| u.get("name").and_then(|n| n.as_str()).unwrap_or("").into())
+                    .collect();
+                let index = match (options.unit, names.len()) {
+                    (Some(unit), _) => names
+                        .iter()
+                        .zip(1..)
+                        .position(|(name, index)| selects(&unit, index, name))
+                        .ok_or_else(|| {
+                            Report::new(AddError).attach_printable(format!(
+                                "No unit '{unit}' in {path}, which has units {names}",
+                                path = parent.to_string_lossy(),
+                                names = quoted_list(&names)
+                            ))
+                        })?,
+                    (None, 1) => 0,
+                    (None, _) => {
+                        return Err(Report::new(AddError).attach_printable(format!(
+                            "Choose which unit of {path} to add the topic to, out of {names}",
+                            path = parent.to_string_lossy(),
+                            names = quoted_list(&names)
+                        )))
+                    }
+                };
+                let topics = array(units.get_mut(index).unwrap(), "topics")?;
+                push_path(topics, &entry, &path, &parent)?;
+            }
+            DefinitionKind::Exercise => {
+                let name = options.name.unwrap_or_else(|| {
+                    path.file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace(['-', '_'], " ")
+                });
+                add_exercise(&mut doc, &name, &entry, &path, &parent)?;
+            }
+        }
+
+        fs::write(&parent, doc.to_string())
+            .into_report()
+            .change_context(AddError)?;
+        Ok(Added { parent, entry })
+    }
+}
+
+/// The closest definition that a definition of `kind` at `path` can be added to
+fn find_parent(kind: DefinitionKind, path: &Path) -> Option<Result<PathBuf, AddError>> {
+    path.ancestors().skip(1).find_map(|dir| {
+        let mut candidates: Vec<PathBuf> = match kind {
+            DefinitionKind::Module => fs::read_dir(dir)
+                .ok()?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.ends_with(".track.toml"))
+                })
+                .collect(),
+            DefinitionKind::Topic => vec![dir.join("mod.toml")],
+            DefinitionKind::Exercise => vec![dir.join("topic.toml")],
+            DefinitionKind::Track => vec![],
+        };
+        candidates.retain(|p| p.is_file() && *p != path);
+        match candidates.len() {
+            0 => None,
+            1 => Some(Ok(candidates.pop().unwrap())),
+            _ => {
+                candidates.sort();
+                let names: Vec<String> = candidates
+                    .iter()
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .collect();
+                Some(Err(Report::new(AddError).attach_printable(format!(
+                    "Choose which definition to add to, out of {names}",
+                    names = quoted_list(&names)
+                ))))
+            }
+        }
+    })
+}
+
+/// The array of paths at `key` in `table`, which is created if it doesn't exist yet
+fn array<'t>(
+    table: &'t mut dyn toml_edit::TableLike,
+    key: &str,
+) -> Result<&'t mut toml_edit::Array, AddError> {
+    table
+        .entry(key)
+        .or_insert(toml_edit::value(toml_edit::Array::new()))
+        .as_array_mut()
+        .ok_or_else(|| Report::new(AddError).attach_printable(format!("`{key}` is not an array")))
+}
+
+/// Append `entry`, the path to `path`, to `array`, laid out like the entries before it
+fn push_path(
+    array: &mut toml_edit::Array,
+    entry: &str,
+    path: &Path,
+    parent: &Path,
+) -> Result<(), AddError> {
+    let dir = parent.parent().unwrap();
+    if array
+        .iter()
+        .filter_map(toml_edit::Value::as_str)
+        .any(|existing| is_same_path(dir, existing, path))
+    {
+        return Err(Report::new(AddError).attach_printable(format!(
+            "{parent} refers to {entry} already",
+            parent = parent.to_string_lossy()
+        )));
+    }
+    push_formatted(array, entry);
+    Ok(())
+}
+
+/// Append `value` to `array` with the layout of the last value, which moves any line
+/// break after the last value to after the new one
+fn push_formatted(array: &mut toml_edit::Array, value: impl Into<toml_edit::Value>) {
+    let decor = array.iter_mut().last().map(|last| {
+        let decor = last.decor().clone();
+        last.decor_mut().set_suffix("");
+        decor
+    });
+    array.push(value);
+    if let Some(decor) = decor {
+        *array.iter_mut().last().unwrap().decor_mut() = decor;
+    }
+}
+
+fn add_exercise(
+    doc: &mut toml_edit::Document,
+    name: &str,
+    entry: &str,
+    path: &Path,
+    parent: &Path,
+) -> Result<(), AddError> {
+    let dir = parent.parent().unwrap();
+    let exercises = doc
+        .entry("exercises")
+        .or_insert(toml_edit::Item::ArrayOfTables(Default::default()));
+    let existing: Vec<&str> = match &*exercises {
+        toml_edit::Item::ArrayOfTables(tables) => tables
+            .iter()
+            .filter_map(|t| t.get("path").and_then(|p| p.as_str()))
+            .collect(),
+        toml_edit::Item::Value(toml_edit::Value::Array(array)) => array
+            .iter()
+            .filter_map(|v| v.as_inline_table()?.get("path")?.as_str())
+            .collect(),
+        _ => {
+            return Err(Report::new(AddError).attach_printable("`exercises` is not an array"));
+        }
+    };
+    if existing
+        .iter()
+        .any(|existing| is_same_path(dir, existing, path))
+    {
+        return Err(Report::new(AddError).attach_printable(format!(
+            "{parent} refers to {entry} already",
+            parent = parent.to_string_lossy()
+        )));
+    }
+
+    match exercises {
+        toml_edit::Item::ArrayOfTables(tables) => {
+            let mut table = toml_edit::Table::new();
+            table.insert("name", toml_edit::value(name));
+            table.insert("path", toml_edit::value(entry));
+            tables.push(table);
+        }
+        toml_edit::Item::Value(toml_edit::Value::Array(array)) => {
+            let mut table = toml_edit::InlineTable::new();
+            table.insert("name", name.into());
+            table.insert("path", entry.into());
+            push_formatted(array, table);
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Whether `written`, relative to `dir`, leads to `path`
+fn is_same_path(dir: &Path, written: &str, path: &Path) -> bool {
+    dir.join(written)
+        .canonicalize()
+        .is_ok_and(|existing| existing == path)
+}
+
+/// `path` relative to `base`, going up with `..` where needed,
+/// with `/` separators as written in definition files
+fn relative_path(path: &Path, base: &Path) -> String {
+    let common = base
+        .ancestors()
+        .find(|ancestor| path.starts_with(ancestor))
+        .unwrap_or(Path::new("/"));
+    let ups = base.strip_prefix(common).unwrap().components().count();
+    std::iter::repeat_n("..".to_string(), ups)
+        .chain(
+            path.strip_prefix(common)
+                .unwrap()
+                .components()
+                .filter_map(|c| match c {
+                    Component::Normal(c) => Some(c.to_string_lossy().into_owned()),
+                    _ => None,
+                }),
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn quoted_list(items: &[String]) -> String {
+    items
+        .iter()
+        .map(|item| format!("'{item}'"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,151 @@
+//! Including code from exercise sources into slides with `#[modmod:include(...)]`, so that
+//! the code on the slides is always the code that students get. Regions of a file are
+//! marked with anchors, like in mdbook:
+//!
+//! ```text
+//! // ANCHOR: setup
+//! let config = Config::default();
+//! // ANCHOR_END: setup
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::exercises::strip_solutions;
+
+/// Marks the start of an anchored region, followed by the name of the anchor
+const ANCHOR_START: &str = "ANCHOR:";
+/// Marks the end of an anchored region, followed by the name of the anchor
+const ANCHOR_END: &str = "ANCHOR_END:";
+
+const INCLUDE_PREFIX: &str = "#[modmod:include(";
+
+/// An include directive in slides, like `#[modmod:include(../ex/src/main.rs, anchor=setup)]`
+struct Include<'a> {
+    /// The directive as it's written
+    directive: &'a str,
+    /// The included file, relative to the slides
+    path: &'a str,
+    anchor: Option<&'a str>,
+}
+
+/// The files that the include directives in the slides `content` refer to, resolved
+/// against `dir`, the directory of the slides
+pub(crate) fn included_files(content: &str, dir: &Path) -> Vec<PathBuf> {
+    includes(content)
+        .into_iter()
+        .filter_map(|include| include.ok())
+        .map(|include| dir.join(include.path))
+        .collect()
+}
+
+/// Replace the include directives in the slides `content` by the code they include, from
+/// files relative to `dir`, the directory of the slides. Solutions are stripped, as they are
+/// from the exercises students get, and the code is dedented.
+pub(crate) fn expand_includes(content: &str, dir: &Path) -> Result<String, String> {
+    let mut expanded = content.to_string();
+    for include in includes(content) {
+        let include = include?;
+        let path = dir.join(include.path);
+        let source = fs::read_to_string(&path).map_err(|error| {
+            format!(
+                "Unable to read {path}, included by {directive}: {error}",
+                path = path.to_string_lossy(),
+                directive = include.directive
+            )
+        })?;
+        let source = strip_solutions(&source)
+            .map_err(|problem| format!("{problem} in {}", path.to_string_lossy()))?
+            .unwrap_or(source);
+        let code = match include.anchor {
+            Some(anchor) => anchored_region(&source, anchor).ok_or_else(|| {
+                format!(
+                    "Anchor '{anchor}' of {directive} isn't in {path}, or never ends",
+                    directive = include.directive,
+                    path = path.to_string_lossy()
+                )
+            })?,
+            None => source.lines().collect(),
+        };
+        let code: Vec<&str> = code
+            .into_iter()
+            .filter(|line| !line.contains(ANCHOR_START) && !line.contains(ANCHOR_END))
+            .collect();
+        expanded = expanded.replacen(include.directive, &dedent(&code), 1);
+    }
+    Ok(expanded)
+}
+
+/// The include directives in `content`, or what's wrong with them
+fn includes(content: &str) -> Vec<Result<Include<'_>, String>> {
+    content
+        .match_indices(INCLUDE_PREFIX)
+        .map(|(start, _)| {
+            let rest = &content[start..];
+            let end = rest
+                .find(")]")
+                .ok_or_else(|| format!("Include directive at '{}' is never closed", first_line(rest)))?;
+            let directive = &rest[..end + 2];
+            let mut arguments = directive[INCLUDE_PREFIX.len()..end].split(',').map(str::trim);
+            let path = arguments.next().filter(|path| !path.is_empty()).ok_or_else(|| {
+                format!("Include directive {directive} has no path")
+            })?;
+            let mut anchor = None;
+            for argument in arguments {
+                match argument.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                    Some(("anchor", name)) if !name.is_empty() => anchor = Some(name),
+                    _ => {
+                        return Err(format!(
+                            "Unknown argument '{argument}' in include directive {directive}, only anchor=<name> is known"
+                        ))
+                    }
+                }
+            }
+            Ok(Include {
+                directive,
+                path,
+                anchor,
+            })
+        })
+        .collect()
+}
+
+/// The lines between the start and the end of `anchor` in `source`, or `None` if it
+/// isn't there or never ends
+fn anchored_region<'a>(source: &'a str, anchor: &str) -> Option<Vec<&'a str>> {
+    let is_marker = |line: &str, marker: &str| {
+        line.split_once(marker)
+            .is_some_and(|(_, name)| name.trim() == anchor)
+    };
+    let mut lines = source.lines();
+    lines.by_ref().find(|line| is_marker(line, ANCHOR_START))?;
+    let mut region = vec![];
+    for line in lines {
+        if is_marker(line, ANCHOR_END) {
+            return Some(region);
+        }
+        region.push(line);
+    }
+    None
+}
+
+/// The `lines` without the indentation they all share
+fn dedent(lines: &[&str]) -> String {
+    let indentation = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line.get(indentation..).unwrap_or("").trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or(text)
+}
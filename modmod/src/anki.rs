@@ -0,0 +1,130 @@
+//! Flashcards of a track for spaced repetition with [Anki](https://apps.ankiweb.net): a deck
+//! per module, with a card for every objective of a topic that has a summary, and for every
+//! glossary term. Decks are written in the text format that Anki imports, with the note type,
+//! the deck and the columns in its header.
+
+use std::{collections::HashSet, fmt::Write, path::PathBuf};
+
+use crate::{select::Edition, to_prefixed_tag, RenderOptions, Track};
+
+/// The flashcards of a module
+#[derive(Debug, Clone)]
+pub struct AnkiDeck {
+    /// Name of the deck in Anki, under a parent deck named after the track
+    pub name: String,
+    /// Name of the file to write the deck to, like the chapter of the module in the book
+    pub file_name: PathBuf,
+    pub cards: Vec<AnkiCard>,
+}
+
+/// A card of the Basic note type, of which the sides are HTML
+#[derive(Debug, Clone)]
+pub struct AnkiCard {
+    pub front: String,
+    pub back: String,
+    pub tags: Vec<String>,
+}
+
+impl Track {
+    /// The decks of the modules that a render with `options` contains, leaving out modules
+    /// without cards
+    pub fn anki_decks(&self, options: &RenderOptions) -> Vec<AnkiDeck> {
+        let track = self.select(options, Edition::Student).track;
+        let mut decks = vec![];
+        for module in track.modules.iter() {
+            let mut cards = vec![];
+            // Terms ignoring case, which get a card at the first topic that defines them
+            let mut terms = HashSet::new();
+            let topics = module
+                .data
+                .units
+                .iter()
+                .flat_map(|unit| &unit.data.topics)
+                .map(|topic| &topic.data);
+            for topic in topics {
+                let tags: Vec<String> = topic
+                    .tags
+                    .iter()
+                    .map(|tag| tag.split_whitespace().collect::<Vec<_>>().join("_"))
+                    .collect();
+                // The summary of a topic answers what its objectives ask for
+                if !topic.summary.is_empty() {
+                    let mut summary = String::from("<ul>");
+                    for item in topic.summary.iter() {
+                        write!(summary, "<li>{}</li>", escape(item.trim())).unwrap();
+                    }
+                    summary.push_str("</ul>");
+                    for objective in topic.objectives.iter() {
+                        cards.push(AnkiCard {
+                            front: format!(
+                                "<b>{}</b><br>{}",
+                                escape(topic.name.trim()),
+                                escape(objective.trim())
+                            ),
+                            back: summary.clone(),
+                            tags: tags.clone(),
+                        });
+                    }
+                }
+                for entry in topic.glossary.iter() {
+                    if !terms.insert(entry.term.trim().to_lowercase()) {
+                        continue;
+                    }
+                    cards.push(AnkiCard {
+                        front: escape(entry.term.trim()),
+                        back: escape(entry.definition.trim()),
+                        tags: tags.iter().cloned().chain(["glossary".into()]).collect(),
+                    });
+                }
+            }
+            if cards.is_empty() {
+                continue;
+            }
+            decks.push(AnkiDeck {
+                name: format!("{}::{}", track.name.trim(), module.data.name.trim()),
+                file_name: PathBuf::from(to_prefixed_tag(&module.data.name, module.index))
+                    .with_extension("csv"),
+                cards,
+            });
+        }
+        decks
+    }
+}
+
+impl AnkiDeck {
+    /// The deck in the comma separated format that Anki imports
+    pub fn csv(&self) -> String {
+        let mut csv = format!(
+            "#separator:Comma\n#html:true\n#notetype:Basic\n#deck:{}\n#columns:Front,Back,Tags\n#tags column:3\n",
+            self.name.replace('\n', " ")
+        );
+        for card in self.cards.iter() {
+            writeln!(
+                csv,
+                "{},{},{}",
+                csv_field(&card.front),
+                csv_field(&card.back),
+                csv_field(&card.tags.join(" "))
+            )
+            .unwrap();
+        }
+        csv
+    }
+}
+
+/// `text` as HTML, keeping its line breaks
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', "<br>")
+}
+
+/// `field`, quoted if it has a separator, a quote or a line break in it
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,34 @@
+//! Writer of ZIP archives, to hand out the exercises of a unit as a single download.
+//!
+//! Every entry gets the same modification time and permissions, so that rendering the
+//! same exercises gives the same archive.
+
+use std::io::{Cursor, Write};
+
+use zip::{write::SimpleFileOptions, CompressionMethod, DateTime, ZipWriter};
+
+/// Permissions of the extracted files, `rw-r--r--`
+const FILE_PERMISSIONS: u32 = 0o644;
+
+/// A ZIP archive of `files`, given as pairs of the path inside the archive, with `/`
+/// separators, and the content
+pub(crate) fn zip<'f>(
+    files: impl IntoIterator<Item = (&'f str, &'f [u8])>,
+) -> Result<Vec<u8>, String> {
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        // 1980-01-01, the earliest date the format can express
+        .last_modified_time(DateTime::default())
+        .unix_permissions(FILE_PERMISSIONS);
+    let mut archive = ZipWriter::new(Cursor::new(vec![]));
+    for (name, content) in files {
+        archive
+            .start_file(name, options)
+            .and_then(|()| Ok(archive.write_all(content)?))
+            .map_err(|e| format!("Unable to add '{name}' to the archive: {e}"))?;
+    }
+    let archive = archive
+        .finish()
+        .map_err(|e| format!("Unable to write the archive: {e}"))?;
+    Ok(archive.into_inner())
+}
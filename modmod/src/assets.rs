@@ -0,0 +1,88 @@
+//! Shared assets of modules and topics, like diagrams that are shown in more than one
+//! topic. They're copied into an `assets` directory in both the slides and the book, so
+//! that slides refer to them as `/assets/<path>` and book pages as `assets/<path>`.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{Report, Result};
+
+use crate::{io::PathExt, output::Output, Track};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderAssetsError;
+
+impl fmt::Display for RenderAssetsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to copy assets")
+    }
+}
+
+impl error_stack::Context for RenderAssetsError {}
+
+/// The files in the assets directory `dir`
+pub(crate) fn asset_files<C: error_stack::Context + Default>(
+    dir: &Path,
+) -> Result<Vec<PathBuf>, C> {
+    let mut files: Vec<_> = dir
+        .get_dir_content::<C>()?
+        .files
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+impl Track {
+    /// Copy the assets of the modules and topics of the track into the slides and the book.
+    /// Files of different assets directories at the same path are an error, unless they're
+    /// the same file, like the assets of a topic that is part of more than one unit.
+    pub(crate) fn render_assets(&self, output: &Output) -> Result<(), RenderAssetsError> {
+        let modules = self.modules.iter().map(|m| &m.data);
+        let topics = modules
+            .clone()
+            .flat_map(|m| &m.units)
+            .flat_map(|u| &u.data.topics)
+            .map(|t| &t.data);
+        let dirs = modules
+            .filter_map(|m| m.assets.as_ref())
+            .chain(topics.filter_map(|t| t.assets.as_ref()));
+
+        // Source files by their path relative to the assets directory of the output
+        let mut assets: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
+        for dir in dirs {
+            for file in asset_files(dir)? {
+                let relative = file.strip_prefix(dir).unwrap().to_path_buf();
+                match assets.get(&relative) {
+                    Some(other) if *other != file => {
+                        return Err(Report::new(RenderAssetsError).attach_printable(format!(
+                            "Assets {file} and {other} would both be copied to assets/{relative}",
+                            file = file.to_string_lossy(),
+                            other = other.to_string_lossy(),
+                            relative = relative.to_string_lossy()
+                        )));
+                    }
+                    _ => {
+                        assets.insert(relative, file);
+                    }
+                }
+            }
+        }
+
+        let mut files = vec![];
+        for assets_dir in ["slides/assets", "book/src/assets"] {
+            let assets_dir = output.root().join(assets_dir);
+            for (relative, file) in assets.iter() {
+                let dest = assets_dir.join(relative);
+                output.create_dir_all(dest.parent().unwrap())?;
+                files.push((file.clone(), dest));
+            }
+        }
+        output.copy_all(&files, |_| Ok(None))
+    }
+}
@@ -1,17 +1,44 @@
-use indoc::indoc;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt,
     path::{Path, PathBuf},
+    process::{Command, Stdio},
 };
 
-use error_stack::Result;
+use error_stack::{IntoReport, Report, Result, ResultExt};
 
 use crate::{
+    codes::WarningCode,
+    glossary::{glossary_page, link_terms, GLOSSARY_FILE},
+    headings::normalize_headings,
     io::{PathExt, WriteExt},
-    to_tag,
+    mermaid::MermaidDiagrams,
+    notes::extract_notes,
+    output::Output,
+    qr::QrCodes,
+    quizzes::{knowledge_check, mdbook_quiz_toml},
+    report::Warning,
+    slides::{local_assets, rewrite_reference},
+    template::{unescape_placeholders, unresolved_placeholder},
+    to_prefixed_tag,
+    validate::normalize_item,
+    BookConfig, BookTheme, GlossaryEntry, Quiz,
 };
 
+/// Marks draft content, so that it can't be mistaken for finished material
+const DRAFT_NOTICE: &str =
+    "> **DRAFT**: this is work in progress, and not part of the published course\n\n";
+
+/// The index template of mdBook, which the theme of the book replaces to give pages a
+/// description of their own
+const INDEX_TEMPLATE: &str = include_str!("../include/book/index.hbs");
+/// The meta description in the [INDEX_TEMPLATE], which is the description of the book
+const BOOK_DESCRIPTION_META: &str = r#"<meta name="description" content="{{ description }}">"#;
+
+/// Placeholders in exercise descriptions that are filled in when rendering the book
+pub(crate) const EXERCISE_PLACEHOLDERS: [&str; 3] =
+    ["exercise_dir", "exercise_ref", "qr(exercise)"];
+
 #[derive(Debug, Default)]
 #[non_exhaustive]
 pub struct RenderBookError;
@@ -27,72 +54,206 @@ impl error_stack::Context for RenderBookError {}
 #[derive(Debug)]
 pub struct Book<'track> {
     pub title: &'track str,
+    pub authors: Vec<&'track str>,
+    pub description: Option<&'track str>,
+    pub language: Option<&'track str>,
+    pub config: &'track BookConfig,
     pub chapters: Vec<Chapter<'track>>,
+    pub appendices: Vec<BookAppendix<'track>>,
+    /// Markdown of the schedule page, which comes before the chapters
+    pub schedule: Option<String>,
+    /// Whether the book is part of the instructor edition, which keeps the notes in
+    /// exercise descriptions
+    pub instructor: bool,
+    /// Whether the topmost headings of every exercise description are moved right below the
+    /// heading of the exercise, in sections that don't decide for themselves
+    pub normalize_headings: bool,
+    /// Entries of the glossary page, which comes after the appendices. There is no glossary
+    /// page if there are none.
+    pub glossary: Vec<&'track GlossaryEntry>,
 }
 
 impl<'track> Book<'track> {
-    pub fn builder(title: &'track str) -> BookBuilder {
+    pub fn builder(title: &'track str, config: &'track BookConfig) -> BookBuilder<'track> {
         BookBuilder {
             book: Book {
                 title,
+                authors: vec![],
+                description: None,
+                language: None,
+                config,
                 chapters: vec![],
+                appendices: vec![],
+                schedule: None,
+                instructor: false,
+                normalize_headings: false,
+                glossary: vec![],
             },
         }
     }
 
+    /// Render the book, returning the paths of the chapter and section pages
+    /// relative to the output directory
     pub fn render(
         &self,
-        exercise_paths: &HashMap<PathBuf, PathBuf>,
-        out_dir: impl AsRef<Path>,
-    ) -> Result<(), RenderBookError> {
-        let book_out_dir = out_dir.as_ref().join("book");
+        exercise_paths: &HashMap<(usize, usize, usize), PathBuf>,
+        qr_codes: Option<&QrCodes>,
+        mermaid: Option<&MermaidDiagrams>,
+        output: &Output,
+    ) -> Result<BookPaths, RenderBookError> {
+        let mut paths = BookPaths::default();
+        // All pages, relative to the book source directory
+        let mut pages = vec![];
+        // Meta descriptions of the pages that have one, by page
+        let mut descriptions = vec![];
+        let book_out_dir = output.root().join("book");
         let book_src_dir = book_out_dir.join("src");
-        book_src_dir.create_dir_all()?;
-
-        let book_toml_path = book_out_dir.join("book.toml");
-        let mut book_toml = book_toml_path.create_file()?;
-        book_toml.write_all(indoc! {r#"
-                [book]
-                language = "en"
-                multilingual = false
-                
-                [build]
-                build-dir = "./target"
-            "#})?;
+        output.create_dir_all(&book_src_dir)?;
 
         let summary_md_path = book_src_dir.join("SUMMARY.md");
 
-        let mut summary_md = summary_md_path.create_file()?;
+        let mut summary_md = output.create_file(&summary_md_path)?;
         summary_md.write_all("# Summary\n\n")?;
 
-        for (chapter, chapter_i) in self.chapters.iter().zip(1..) {
-            // Sadly, at the time of writing, mdbook does not allow for custom section numbering.
-            // Therefore, we insert a draft chapter to keep the section numbering in sync
-            summary_md.write_fmt(format_args!("- [{}]()\n", chapter.title))?;
+        if let Some(schedule) = &self.schedule {
+            // A prefix chapter, so that it doesn't shift the numbers of the chapters
+            let schedule_file_name = PathBuf::from("schedule.md");
+            summary_md.write_fmt(format_args!(
+                "[Schedule]({})\n\n",
+                schedule_file_name.to_str().unwrap()
+            ))?;
+            output
+                .create_file(book_src_dir.join(&schedule_file_name))?
+                .write_all(schedule)?;
+            pages.push(schedule_file_name);
+        }
+
+        // Chapters and sections are numbered like the modules and units they are about,
+        // which differs from their position when only a part of the track is rendered
+        for chapter in self.chapters.iter() {
+            let chapter_i = chapter.module_index;
+            if chapter.description.is_none()
+                && chapter.objectives.is_empty()
+                && chapter.further_reading.is_empty()
+            {
+                // Sadly, at the time of writing, mdbook does not allow for custom section numbering.
+                // Therefore, we insert a draft chapter to keep the section numbering in sync
+                summary_md.write_fmt(format_args!("- [{}]()\n", chapter.title))?;
+            } else {
+                let chapter_file_name =
+                    Path::new(&to_prefixed_tag(chapter.title, chapter_i)).with_extension("md");
+                summary_md.write_fmt(format_args!(
+                    "- [{}]({})\n",
+                    chapter.title,
+                    chapter_file_name.to_str().unwrap()
+                ))?;
+
+                let chapter_file_path = book_src_dir.join(&chapter_file_name);
+                let mut chapter_file = output.create_file(&chapter_file_path)?;
+                paths.chapters.insert(
+                    chapter.module_index,
+                    relative_to_root(&chapter_file_path, output),
+                );
+                pages.push(chapter_file_name.clone());
+                if self.config.page_descriptions {
+                    descriptions.extend(
+                        page_description(&chapter.objectives)
+                            .map(|d| (chapter_file_name.clone(), d)),
+                    );
+                }
+                chapter_file
+                    .write_fmt(format_args!("# Module {chapter_i} - {}\n\n", chapter.title))?;
+                if let Some(description) = chapter.description {
+                    chapter_file.write_fmt(format_args!("{}\n\n", description.trim()))?;
+                }
+                if !chapter.objectives.is_empty() {
+                    chapter_file.write_all("## Module objectives\n\n")?;
+                    for objective in chapter.objectives.iter() {
+                        chapter_file.write_fmt(format_args!("- {}\n", objective.trim()))?;
+                    }
+                    chapter_file.write_all("\n")?;
+                }
+                if !chapter.further_reading.is_empty() {
+                    chapter_file.write_all("## Further reading\n\n")?;
+                    for item in chapter.further_reading.iter() {
+                        chapter_file.write_fmt(format_args!("- {}\n", item.trim()))?;
+                    }
+                }
+            }
 
-            for (section, section_i) in chapter.sections.iter().zip(1..) {
-                let section_file_name = Path::new(&to_tag(section.title)).with_extension("md");
+            for section in chapter.sections.iter() {
+                let section_i = section.unit_index;
+                let section_file_name = section_file_name(section.title, chapter_i, section_i);
+                let draft_suffix = if section.draft { " (DRAFT)" } else { "" };
                 summary_md.write_fmt(format_args!(
-                    "\t- [{}]({})\n",
+                    "\t- [{}{draft_suffix}]({})\n",
                     section.title,
                     section_file_name.to_str().unwrap()
                 ))?;
 
                 let section_file_path = book_src_dir.join(&section_file_name);
-                let mut section_file = section_file_path.create_file()?;
+                let mut section_file = output.create_file(&section_file_path)?;
+                paths.sections.insert(
+                    (section.module_index, section.unit_index),
+                    relative_to_root(&section_file_path, output),
+                );
+                pages.push(section_file_name.clone());
+                if self.config.page_descriptions {
+                    descriptions.extend(
+                        page_description(&section.objectives)
+                            .map(|d| (section_file_name.clone(), d)),
+                    );
+                }
                 section_file.write_fmt(format_args!(
-                    "# Unit {chapter_i}.{section_i} - {}\n\n",
+                    "# Unit {chapter_i}.{section_i} - {}{draft_suffix}\n\n",
                     section.title
                 ))?;
+                // Terms are linked once per page
+                let mut linked_terms = HashSet::new();
+                if section.draft {
+                    section_file.write_all(DRAFT_NOTICE)?;
+                }
 
                 if !section.subsections.is_empty() {
                     for (subsection, subsection_i) in section.subsections.iter().zip(1..) {
+                        let draft_suffix = if subsection.draft { " (DRAFT)" } else { "" };
                         section_file.write_fmt(format_args!(
-                            "## Exercise {chapter_i}.{section_i}.{subsection_i}: {}\n\n",
+                            "## Exercise {chapter_i}.{section_i}.{subsection_i}: {}{draft_suffix}\n\n",
                             subsection.title
                         ))?;
-                        let exercise_out_dir = &exercise_paths[subsection.exercise_path];
+                        if subsection.draft {
+                            section_file.write_all(DRAFT_NOTICE)?;
+                        }
+                        let exercise_out_dir =
+                            &exercise_paths[&(chapter_i, section_i, subsection_i)];
+                        let qr_code = match qr_codes {
+                            Some(qr_codes) => {
+                                let qr_path = Path::new("qr").join(format!(
+                                    "exercise-{chapter_i}_{section_i}_{subsection_i}.svg"
+                                ));
+                                let svg = qr_codes
+                                    .svg(exercise_out_dir)
+                                    .change_context(RenderBookError)?;
+                                output.create_dir_all(book_src_dir.join("qr"))?;
+                                output
+                                    .create_file(book_src_dir.join(&qr_path))?
+                                    .write_all(svg)?;
+                                format!(
+                                    "![QR code of exercise {chapter_i}.{section_i}.{subsection_i}]({})",
+                                    qr_path.to_string_lossy()
+                                )
+                            }
+                            None => String::new(),
+                        };
                         let content = subsection.content.read_to_string()?;
+                        let (content, _) = extract_notes(&content, |text| match self.instructor {
+                            true => {
+                                let quoted: Vec<String> =
+                                    text.lines().map(|line| format!("> {line}")).collect();
+                                format!("> **Notes**\n>\n{}\n", quoted.join("\n"))
+                            }
+                            false => String::new(),
+                        });
                         let content = content
                             // Insert exercise directory paths
                             .replace(
@@ -104,24 +265,416 @@ impl<'track> Book<'track> {
                                 "#[modmod:exercise_ref]",
                                 &format!("{chapter_i}.{section_i}.{subsection_i}"),
                             )
-                            // Convert exercise sections into subsubsections
-                            .replace("\n# ", "\n### ");
+                            .replace("#[modmod:qr(exercise)]", &qr_code);
+                        // Exercise sections become subsubsections
+                        let content = if section
+                            .normalize_headings
+                            .unwrap_or(self.normalize_headings)
+                        {
+                            normalize_headings(&content, 3)
+                        } else {
+                            content.replace("\n# ", "\n### ")
+                        };
+                        if let Some(placeholder) = unresolved_placeholder(&content) {
+                            return Err(Report::new(RenderBookError).attach_printable(format!(
+                                "Unknown placeholder '{placeholder}' in {}",
+                                subsection.content.to_string_lossy()
+                            )));
+                        }
+                        let content = match mermaid {
+                            Some(mermaid) => mermaid
+                                .render(&content, &book_src_dir.join("mermaid"), "mermaid", output)
+                                .change_context(RenderBookError)?,
+                            None => content,
+                        };
+                        let content = unescape_placeholders(&content);
+                        let content = match self.config.link_glossary_terms {
+                            true => link_terms(&content, &self.glossary, &mut linked_terms),
+                            false => content,
+                        };
                         section_file.write_fmt(format_args!("{}\n", content.trim()))?;
                     }
                 } else {
                     section_file.write_all("*There are no exercises for this unit*")?;
                 }
+                if !section.quizzes.is_empty() && self.config.mdbook_quiz {
+                    // Quizzes are included from a file of their own, which mdbook-quiz reads
+                    let quiz_path =
+                        Path::new("quizzes").join(format!("{chapter_i}_{section_i}.toml"));
+                    output.create_dir_all(book_src_dir.join("quizzes"))?;
+                    output
+                        .create_file(book_src_dir.join(&quiz_path))?
+                        .write_all(mdbook_quiz_toml(&section.quizzes))?;
+                    section_file.write_fmt(format_args!(
+                        "\n## Knowledge check\n\n{{{{#quiz {}}}}}\n",
+                        quiz_path.to_string_lossy()
+                    ))?;
+                } else if !section.quizzes.is_empty() {
+                    section_file
+                        .write_fmt(format_args!("\n{}", knowledge_check(&section.quizzes)))?;
+                }
+                if !section.instructor_notes.is_empty() {
+                    section_file.write_all("\n## Instructor notes\n\n")?;
+                    for (topic, notes) in section.instructor_notes.iter() {
+                        let notes = notes.read_to_string()?;
+                        // Convert sections of the notes into subsubsubsections
+                        let notes = format!("\n{}", notes.trim()).replace("\n# ", "\n#### ");
+                        section_file.write_fmt(format_args!("### {topic}\n{notes}\n\n"))?;
+                    }
+                }
             }
             summary_md.write_all("\n")?;
         }
 
-        Ok(())
+        if !self.appendices.is_empty() {
+            summary_md.write_all("# Appendices\n\n")?;
+            let images_dir = book_src_dir.join("images");
+            let mut appendix_titles: HashMap<String, &str> = HashMap::new();
+            for appendix in self.appendices.iter() {
+                let tag = to_prefixed_tag(appendix.title, "appendix");
+                // Appendices with the same file would overwrite each other
+                if let Some(other) = appendix_titles.insert(tag.clone(), appendix.title) {
+                    return Err(Report::new(RenderBookError).attach_printable(format!(
+                        "Appendices '{other}' and '{title}' would both be written to {tag}.md, rename one of them",
+                        title = appendix.title
+                    )));
+                }
+                let appendix_file_name = Path::new(&tag).with_extension("md");
+                let draft_suffix = if appendix.draft { " (DRAFT)" } else { "" };
+                summary_md.write_fmt(format_args!(
+                    "- [{}{draft_suffix}]({})\n",
+                    appendix.title,
+                    appendix_file_name.to_str().unwrap()
+                ))?;
+
+                pages.push(appendix_file_name.clone());
+
+                let mut content = appendix.content.read_to_string()?;
+                // The images of every appendix get a folder of their own, as those of
+                // different appendices may have the same name
+                let appendix_dir = appendix.content.parent().unwrap();
+                let appendix_images_dir = images_dir.join(&tag);
+                for (reference, image) in local_assets(&content, appendix_dir) {
+                    if let Some(relative) = appendix_image_path(appendix, &image) {
+                        let new_reference = Path::new("images").join(&tag).join(relative);
+                        content = rewrite_reference(
+                            &content,
+                            &reference,
+                            &new_reference.to_string_lossy(),
+                        );
+                    }
+                }
+                // Convert appendix sections into subsections
+                let content = content.trim().replace("\n# ", "\n## ");
+                if let Some(placeholder) = unresolved_placeholder(&content) {
+                    return Err(Report::new(RenderBookError).attach_printable(format!(
+                        "Unknown placeholder '{placeholder}' in {}",
+                        appendix.content.to_string_lossy()
+                    )));
+                }
+                let content = match mermaid {
+                    Some(mermaid) => mermaid
+                        .render(&content, &book_src_dir.join("mermaid"), "mermaid", output)
+                        .change_context(RenderBookError)?,
+                    None => content,
+                };
+                let content = unescape_placeholders(&content);
+                let content = match self.config.link_glossary_terms {
+                    true => link_terms(&content, &self.glossary, &mut HashSet::new()),
+                    false => content,
+                };
+                let mut appendix_file =
+                    output.create_file(book_src_dir.join(&appendix_file_name))?;
+                appendix_file.write_fmt(format_args!("# {}{draft_suffix}\n\n", appendix.title))?;
+                if appendix.draft {
+                    appendix_file.write_all(DRAFT_NOTICE)?;
+                }
+                appendix_file.write_fmt(format_args!("{content}\n"))?;
+
+                for image in appendix.images.iter() {
+                    let Some(relative) = appendix_image_path(appendix, image) else {
+                        continue;
+                    };
+                    let target = appendix_images_dir.join(relative);
+                    output.create_dir_all(target.parent().unwrap())?;
+                    output.copy(image, target)?;
+                }
+            }
+        }
+
+        if !self.glossary.is_empty() {
+            // A suffix chapter, like the schedule is a prefix chapter
+            summary_md.write_fmt(format_args!("\n[Glossary]({GLOSSARY_FILE})\n"))?;
+            output
+                .create_file(book_src_dir.join(GLOSSARY_FILE))?
+                .write_all(glossary_page(&self.glossary))?;
+            pages.push(PathBuf::from(GLOSSARY_FILE));
+        }
+
+        // Preprocessors find their assets next to `book.toml`, like when they install them
+        for asset in self.config.preprocessors.values().flat_map(|p| &p.assets) {
+            let Some(name) = asset.file_name() else {
+                return Err(Report::new(RenderBookError).attach_printable(format!(
+                    "Asset {} of a book preprocessor is not a file",
+                    asset.to_string_lossy()
+                )));
+            };
+            output.copy(asset, book_out_dir.join(name))?;
+        }
+
+        // The index template of the theme writes the description meta of every page, which
+        // the head template can only add to
+        if !descriptions.is_empty() {
+            let theme_dir = book_out_dir.join("theme");
+            output.create_dir_all(&theme_dir)?;
+            output
+                .create_file(theme_dir.join("index.hbs"))?
+                .write_all(index_template(&descriptions))?;
+        }
+
+        let book_toml_path = book_out_dir.join("book.toml");
+        let mut book_toml = output.create_file(&book_toml_path)?;
+        book_toml.write_all(self.book_toml(&pages, output)?)?;
+
+        Ok(paths)
+    }
+
+    /// The `book.toml` of the book
+    fn book_toml(&self, pages: &[PathBuf], output: &Output) -> Result<String, RenderBookError> {
+        let config = self.config;
+        let search = &config.search;
+
+        let mut book_toml = format!("[book]\ntitle = {}\n", toml_edit::Value::from(self.title));
+        if !self.authors.is_empty() {
+            let authors: toml_edit::Array = self.authors.iter().copied().collect();
+            book_toml += &format!("authors = {authors}\n");
+        }
+        if let Some(description) = self.description {
+            book_toml += &format!("description = {}\n", toml_edit::Value::from(description));
+        }
+        let language = toml_edit::Value::from(self.language.unwrap_or("en"));
+        book_toml += &format!("language = {language}\nmultilingual = false\n");
+        let build_dir = toml_edit::Value::from(format!("./{}", config.build_dir()));
+        book_toml += &format!("\n[build]\nbuild-dir = {build_dir}\n");
+
+        for (name, preprocessor) in config.preprocessors.iter() {
+            book_toml += &format!("\n{}", preprocessor_toml(name, &preprocessor.settings));
+        }
+        // Settings of mdbook-quiz can be given like those of other preprocessors
+        let has_quizzes = self
+            .chapters
+            .iter()
+            .flat_map(|chapter| &chapter.sections)
+            .any(|section| !section.quizzes.is_empty());
+        if config.mdbook_quiz && has_quizzes && !config.preprocessors.contains_key("quiz") {
+            book_toml += "\n[preprocessor.quiz]\n";
+        }
+
+        // Stylesheets and scripts of preprocessors are added to every page
+        let assets: Vec<String> = config
+            .preprocessors
+            .values()
+            .flat_map(|p| &p.assets)
+            .filter_map(|asset| Some(asset.file_name()?.to_string_lossy().into_owned()))
+            .collect();
+        let additional = ["css", "js"].map(|extension| {
+            let files: toml_edit::Array = assets
+                .iter()
+                .filter(|asset| asset.ends_with(&format!(".{extension}")))
+                .map(|asset| format!("./{asset}"))
+                .collect();
+            (format!("additional-{extension}"), files)
+        });
+        let html = [
+            ("default-theme", config.default_theme.map(BookTheme::name)),
+            (
+                "preferred-dark-theme",
+                config.preferred_dark_theme.map(BookTheme::name),
+            ),
+            ("git-repository-url", config.git_repository_url.as_deref()),
+            ("edit-url-template", config.edit_url_template.as_deref()),
+        ];
+        if html.iter().any(|(_, value)| value.is_some())
+            || additional.iter().any(|(_, files)| !files.is_empty())
+        {
+            book_toml += "\n[output.html]\n";
+            for (key, value) in html {
+                if let Some(value) = value {
+                    book_toml += &format!("{key} = {}\n", toml_edit::Value::from(value));
+                }
+            }
+            for (key, files) in additional.iter().filter(|(_, files)| !files.is_empty()) {
+                book_toml += &format!("{key} = {files}\n");
+            }
+        }
+        if let Some(level) = config.fold_level {
+            book_toml += &format!("\n[output.html.fold]\nenable = true\nlevel = {level}\n");
+        }
+
+        let boosts = [
+            ("boost-title", search.boost_title),
+            ("boost-hierarchy", search.boost_hierarchy),
+            ("boost-paragraph", search.boost_paragraph),
+        ];
+        if boosts.iter().any(|(_, boost)| boost.is_some()) {
+            book_toml += "\n[output.html.search]\n";
+            for (key, boost) in boosts {
+                if let Some(boost) = boost {
+                    book_toml += &format!("{key} = {boost}\n");
+                }
+            }
+        }
+
+        for pattern in search.exclude.iter() {
+            let glob = globset::Glob::new(pattern)
+                .into_report()
+                .attach_printable_lazy(|| {
+                    format!("Error parsing search exclusion glob '{pattern}'")
+                })
+                .change_context(RenderBookError)?
+                .compile_matcher();
+            let excluded: Vec<_> = pages.iter().filter(|p| glob.is_match(p)).collect();
+            if excluded.is_empty() {
+                output.warn(Warning::new(
+                    WarningCode::UnmatchedSearchExclusion,
+                    format!("Search exclusion pattern '{pattern}' matched no book pages"),
+                ));
+            }
+            for page in excluded {
+                let key = toml_edit::Value::from(page.to_string_lossy().as_ref());
+                book_toml += &format!("\n[output.html.search.chapter.{key}]\nenable = false\n");
+            }
+        }
+
+        Ok(book_toml)
     }
 }
 
+/// The `[preprocessor.<name>]` table of `book.toml` for a preprocessor with `settings`
+fn preprocessor_toml(name: &str, settings: &toml::Table) -> String {
+    let settings = toml_edit::ser::to_document(settings).unwrap();
+    let mut preprocessors = toml_edit::Table::new();
+    preprocessors.set_implicit(true);
+    preprocessors.insert(
+        name,
+        toml_edit::Item::Table(expand_tables(settings.as_table().clone())),
+    );
+    let mut document = toml_edit::Document::new();
+    document.insert("preprocessor", toml_edit::Item::Table(preprocessors));
+    document.to_string()
+}
+
+/// `table` with its inline tables made into tables of their own
+fn expand_tables(table: toml_edit::Table) -> toml_edit::Table {
+    let mut expanded = toml_edit::Table::new();
+    for (key, item) in table {
+        let item = match item {
+            toml_edit::Item::Value(toml_edit::Value::InlineTable(inline)) => {
+                toml_edit::Item::Table(expand_tables(inline.into_table()))
+            }
+            item => item,
+        };
+        expanded.insert(&key, item);
+    }
+    expanded
+}
+
+/// Meta description of a page, made up of the objectives it covers, as the text of an HTML
+/// attribute. None if there are no objectives.
+fn page_description(objectives: &[&str]) -> Option<String> {
+    let mut seen = HashSet::new();
+    let objectives: Vec<_> = objectives
+        .iter()
+        .map(|o| o.trim())
+        .filter(|o| seen.insert(normalize_item(o)))
+        .collect();
+    if objectives.is_empty() {
+        return None;
+    }
+    Some(attribute_text(&objectives.join("; ")))
+}
+
+/// `text` escaped for an HTML attribute in a Handlebars template, of which the braces would
+/// start expressions
+fn attribute_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('{', "&#123;")
+        .replace('}', "&#125;")
+}
+
+/// The `theme/index.hbs` of the book, which is the one of mdBook with the meta description of
+/// each of the `pages` that have one, and the description of the book on the other pages
+fn index_template(pages: &[(PathBuf, String)]) -> String {
+    let mut descriptions = String::new();
+    // Handlebars has no chains of conditions, so they're nested
+    for (page, description) in pages {
+        descriptions += &format!(
+            "{{{{#if (eq path \"{}\")}}}}\n        <meta name=\"description\" content=\"{description}\">\n        {{{{else}}}}\n        ",
+            page.to_string_lossy()
+        );
+    }
+    descriptions += BOOK_DESCRIPTION_META;
+    descriptions += &"{{/if}}".repeat(pages.len());
+    INDEX_TEMPLATE.replacen(BOOK_DESCRIPTION_META, &descriptions, 1)
+}
+
+/// Locations of rendered book pages, relative to the output directory
+#[derive(Debug, Default)]
+pub struct BookPaths {
+    /// Chapter pages by module index. Modules without a chapter page are left out
+    pub chapters: HashMap<usize, PathBuf>,
+    /// Section pages by module and unit index
+    pub sections: HashMap<(usize, usize), PathBuf>,
+}
+
+/// Name of the page of the unit numbered `unit_index` in the module numbered `module_index`,
+/// in the book source directory
+pub(crate) fn section_file_name(title: &str, module_index: usize, unit_index: usize) -> PathBuf {
+    // Units in different modules may have the same name
+    Path::new(&to_prefixed_tag(
+        title,
+        format!("{module_index}_{unit_index}"),
+    ))
+    .with_extension("md")
+}
+
+/// Build the rendered book with `mdbook build`, into the build directory that its `book.toml`
+/// sets, so that it can be previewed as it's published
+pub(crate) fn build_book(output: &Output) -> Result<(), RenderBookError> {
+    output.check_cancelled::<RenderBookError>()?;
+    let book_dir = output.root().join("book");
+    let result = Command::new("mdbook")
+        .arg("build")
+        .current_dir(&book_dir)
+        .stdin(Stdio::null())
+        .output()
+        .into_report()
+        .attach_printable("Unable to run mdbook, which is needed to build the book")
+        .change_context(RenderBookError)?;
+    if !result.status.success() {
+        return Err(Report::new(RenderBookError).attach_printable(format!(
+            "mdbook build failed in {dir}: {stderr}",
+            dir = book_dir.to_string_lossy(),
+            stderr = String::from_utf8_lossy(&result.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+fn relative_to_root(path: &Path, output: &Output) -> PathBuf {
+    path.strip_prefix(output.root()).unwrap().to_path_buf()
+}
+
 #[derive(Debug)]
 pub struct Chapter<'track> {
     pub title: &'track str,
+    /// Introduction of the module, shown at the top of its page
+    pub description: Option<&'track str>,
+    pub objectives: Vec<&'track str>,
+    pub further_reading: Vec<&'track str>,
     pub sections: Vec<Section<'track>>,
     pub module_index: usize,
 }
@@ -132,13 +685,42 @@ pub struct Section<'track> {
     pub subsections: Vec<SubSection<'track>>,
     pub module_index: usize,
     pub unit_index: usize,
+    pub draft: bool,
+    /// Objectives of the topics in the unit
+    pub objectives: Vec<&'track str>,
+    /// Names of the topics in the unit that have instructor notes, and the paths to those notes
+    pub instructor_notes: Vec<(&'track str, &'track Path)>,
+    /// Whether to normalize the headings of exercise descriptions, instead of what the book does
+    pub normalize_headings: Option<bool>,
+    /// Questions of the knowledge check at the end of the section
+    pub quizzes: Vec<&'track Quiz>,
 }
 
 #[derive(Debug)]
 pub struct SubSection<'track> {
     pub title: &'track str,
     pub content: &'track Path,
-    pub exercise_path: &'track Path,
+    /// Whether the exercise is part of a draft topic
+    pub draft: bool,
+}
+
+#[derive(Debug)]
+pub struct BookAppendix<'track> {
+    pub title: &'track str,
+    pub content: &'track Path,
+    pub images: &'track [PathBuf],
+    pub draft: bool,
+}
+
+/// Path of `image` in the `images` folder next to the markdown file of `appendix`
+fn appendix_image_path<'a>(appendix: &BookAppendix, image: &'a Path) -> Option<&'a Path> {
+    let images_dir = appendix.content.parent()?.join("images");
+    appendix
+        .images
+        .iter()
+        .any(|i| i == image)
+        .then(|| image.strip_prefix(images_dir).ok())
+        .flatten()
 }
 
 pub struct BookBuilder<'track> {
@@ -146,6 +728,26 @@ pub struct BookBuilder<'track> {
 }
 
 impl<'track> BookBuilder<'track> {
+    pub fn author(&mut self, author: &'track str) {
+        self.book.authors.push(author);
+    }
+
+    pub fn description(&mut self, description: &'track str) {
+        self.book.description = Some(description);
+    }
+
+    pub fn language(&mut self, language: &'track str) {
+        self.book.language = Some(language);
+    }
+
+    pub fn instructor_edition(&mut self) {
+        self.book.instructor = true;
+    }
+
+    pub fn normalize_headings(&mut self) {
+        self.book.normalize_headings = true;
+    }
+
     pub fn chapter<'b>(
         &'b mut self,
         title: &'track str,
@@ -155,12 +757,38 @@ impl<'track> BookBuilder<'track> {
             book_builder: self,
             chapter: Chapter {
                 title,
+                description: None,
+                objectives: vec![],
+                further_reading: vec![],
                 module_index,
                 sections: vec![],
             },
         }
     }
 
+    pub fn appendix(
+        &mut self,
+        title: &'track str,
+        content: &'track Path,
+        images: &'track [PathBuf],
+        draft: bool,
+    ) {
+        self.book.appendices.push(BookAppendix {
+            title,
+            content,
+            images,
+            draft,
+        })
+    }
+
+    pub fn schedule(&mut self, schedule: String) {
+        self.book.schedule = Some(schedule);
+    }
+
+    pub fn glossary_entry(&mut self, entry: &'track GlossaryEntry) {
+        self.book.glossary.push(entry);
+    }
+
     pub fn build(self) -> Book<'track> {
         self.book
     }
@@ -185,10 +813,27 @@ impl<'track, 'b> ChapterBuilder<'track, 'b> {
                 module_index,
                 unit_index,
                 subsections: vec![],
+                draft: false,
+                objectives: vec![],
+                instructor_notes: vec![],
+                normalize_headings: None,
+                quizzes: vec![],
             },
         }
     }
 
+    pub fn description(&mut self, description: &'track str) {
+        self.chapter.description = Some(description);
+    }
+
+    pub fn objective(&mut self, objective: &'track str) {
+        self.chapter.objectives.push(objective);
+    }
+
+    pub fn further_reading(&mut self, further_reading: &'track str) {
+        self.chapter.further_reading.push(further_reading);
+    }
+
     pub fn add(self) -> &'b mut BookBuilder<'track> {
         self.book_builder.book.chapters.push(self.chapter);
         self.book_builder
@@ -201,19 +846,34 @@ pub struct SectionBuilder<'track, 'b, 'c> {
 }
 
 impl<'track, 'b, 'c> SectionBuilder<'track, 'b, 'c> {
-    pub fn subsection(
-        &mut self,
-        title: &'track str,
-        content: &'track Path,
-        exercise_path: &'track Path,
-    ) {
+    pub fn subsection(&mut self, title: &'track str, content: &'track Path, draft: bool) {
         self.section.subsections.push(SubSection {
             title,
             content,
-            exercise_path,
+            draft,
         })
     }
 
+    pub fn draft(&mut self) {
+        self.section.draft = true;
+    }
+
+    pub fn objective(&mut self, objective: &'track str) {
+        self.section.objectives.push(objective);
+    }
+
+    pub fn instructor_notes(&mut self, topic: &'track str, notes: &'track Path) {
+        self.section.instructor_notes.push((topic, notes));
+    }
+
+    pub fn normalize_headings(&mut self, normalize: bool) {
+        self.section.normalize_headings = Some(normalize);
+    }
+
+    pub fn quiz(&mut self, quiz: &'track Quiz) {
+        self.section.quizzes.push(quiz);
+    }
+
     pub fn add(self) -> &'c mut ChapterBuilder<'track, 'b> {
         self.chapter_builder.chapter.sections.push(self.section);
         self.chapter_builder
@@ -0,0 +1,111 @@
+//! A single archive with everything a render produces, to hand to training
+//! environments that can't reach the course repository or the internet
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use crate::{
+    archive, cache::RENDER_CACHE_FILE, io::PathExt, to_tag, RenderOptions, RenderReport, Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct BundleError;
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to bundle track")
+    }
+}
+
+impl error_stack::Context for BundleError {}
+
+impl Track {
+    /// Render the track with `options` into a temporary directory, and pack the output into
+    /// a ZIP archive at `path`, in a directory named after the slug of the track. Only the
+    /// student edition is bundled, and the render cache, which only has a meaning for
+    /// incremental renders, is left out. The report of the render has the archive as its
+    /// output directory.
+    pub fn bundle(
+        &self,
+        path: impl AsRef<Path>,
+        options: &RenderOptions,
+    ) -> Result<RenderReport, BundleError> {
+        let path = path.as_ref();
+        let staging_dir =
+            std::env::temp_dir().join(format!("modmod-bundle-{}", std::process::id()));
+        if staging_dir.exists() {
+            remove_staging_dir(&staging_dir)?;
+        }
+        let options = RenderOptions {
+            clear_output: false,
+            atomic: false,
+            instructor_output: None,
+            incremental: false,
+            dry_run: false,
+            ..options.clone()
+        };
+        let bundled = self
+            .render(&staging_dir, &options)
+            .change_context(BundleError)
+            .and_then(|mut report| {
+                let archive = self.bundle_archive(&staging_dir)?;
+                fs::write(path, archive)
+                    .into_report()
+                    .attach_printable_lazy(|| {
+                        format!("Error writing bundle to {}", path.to_string_lossy())
+                    })
+                    .change_context(BundleError)?;
+                report.output_dir = path.to_path_buf();
+                Ok(report)
+            });
+        if staging_dir.exists() {
+            remove_staging_dir(&staging_dir)?;
+        }
+        bundled
+    }
+
+    fn bundle_archive(&self, output_dir: &Path) -> Result<Vec<u8>, BundleError> {
+        let slug = to_tag(&self.name);
+        let mut files: Vec<PathBuf> = output_dir
+            .get_dir_content::<BundleError>()?
+            .files
+            .into_iter()
+            .map(PathBuf::from)
+            .filter(|file| *file != output_dir.join(RENDER_CACHE_FILE))
+            .collect();
+        // Directories are listed in no particular order
+        files.sort();
+        let mut entries = vec![];
+        for file in files {
+            let name = file.strip_prefix(output_dir).unwrap();
+            let name: Vec<_> = name.iter().map(|part| part.to_string_lossy()).collect();
+            let content = fs::read(&file)
+                .into_report()
+                .attach_printable_lazy(|| {
+                    format!("Error reading file at path {}", file.to_string_lossy())
+                })
+                .change_context(BundleError)?;
+            entries.push((format!("{slug}/{}", name.join("/")), content));
+        }
+        archive::zip(
+            entries
+                .iter()
+                .map(|(name, content)| (name.as_str(), content.as_slice())),
+        )
+        .map_err(|problem| Report::new(BundleError).attach_printable(problem))
+    }
+}
+
+fn remove_staging_dir(dir: &Path) -> Result<(), BundleError> {
+    fs::remove_dir_all(dir)
+        .into_report()
+        .attach_printable_lazy(|| {
+            format!("Error removing directory at path {}", dir.to_string_lossy())
+        })
+        .change_context(BundleError)
+}
@@ -0,0 +1,93 @@
+//! Content hashes of the exercise files in the output of a render, so that incremental
+//! renders only copy the files that changed since the render before
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{IntoReport, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::io::{PathExt, WriteExt};
+
+/// Name of the file the cache is written to in the output directory
+pub const RENDER_CACHE_FILE: &str = "modmod-cache.json";
+
+/// Hashes of the files copied into the output, by their path relative to the output directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct RenderCache {
+    /// Settings that affect the content of the copied files, like the version of modmod
+    /// and whether solutions are stripped. A cache from a render with other settings is ignored.
+    fingerprint: String,
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl RenderCache {
+    /// Load the cache from the output directory at `root`, returning an empty cache if
+    /// there is none or it was made with another `fingerprint`
+    pub(crate) fn load(root: &Path, fingerprint: &str) -> Self {
+        fs::read_to_string(root.join(RENDER_CACHE_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+            .filter(|cache| cache.fingerprint == fingerprint)
+            .unwrap_or_else(|| Self::new(fingerprint))
+    }
+
+    /// Remove the cache from the output directory at `root`, if there is one
+    pub(crate) fn remove<C: error_stack::Context + Default>(root: &Path) -> Result<(), C> {
+        let path = root.join(RENDER_CACHE_FILE);
+        if !path.exists() {
+            return Ok(());
+        }
+        fs::remove_file(&path)
+            .into_report()
+            .attach_printable_lazy(|| {
+                format!(
+                    "Error removing render cache at path {path}",
+                    path = path.to_string_lossy()
+                )
+            })
+            .change_context(C::default())
+    }
+
+    pub(crate) fn new(fingerprint: &str) -> Self {
+        Self {
+            fingerprint: fingerprint.to_string(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    pub(crate) fn get(&self, path: &Path) -> Option<&str> {
+        self.files.get(path).map(String::as_str)
+    }
+
+    pub(crate) fn insert(&mut self, path: PathBuf, hash: String) {
+        self.files.insert(path, hash);
+    }
+
+    pub(crate) fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Write the cache into the output directory at `root`
+    pub(crate) fn save<C: error_stack::Context + Default>(&self, root: &Path) -> Result<(), C> {
+        root.join(RENDER_CACHE_FILE)
+            .create_file()?
+            .write_all(serde_json::to_string_pretty(self).unwrap())
+    }
+}
+
+/// Hash of the content of a file, as 64-bit FNV-1a. Unlike the hashers of the standard
+/// library, it's the same for every build of modmod, so caches stay valid across builds.
+pub(crate) fn content_hash(content: &[u8]) -> String {
+    let hash = content.iter().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}")
+}
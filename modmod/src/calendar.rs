@@ -0,0 +1,503 @@
+//! Calendars of the sessions of a cohort, with one event per unit of a track,
+//! in the iCalendar format that calendar applications import
+
+use std::{
+    fmt,
+    path::Path,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, LocalResult, Offset, TimeZone};
+use chrono_tz::Tz;
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use serde::Deserialize;
+
+use crate::{io::PathExt, select::Edition, to_tag, RenderOptions, Track};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct CalendarError;
+
+impl fmt::Display for CalendarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to create calendar")
+    }
+}
+
+impl error_stack::Context for CalendarError {}
+
+/// When a group of participants follows a track: one unit per session,
+/// on the given weekdays from the start date on
+#[derive(Debug, Clone)]
+pub struct Cohort {
+    pub name: Option<String>,
+    /// Date of the first session, if it's on one of the `weekdays`
+    pub start: Date,
+    /// Days of the week that sessions are on. Defaults to the weekday of the start date
+    pub weekdays: Vec<Weekday>,
+    /// When sessions start. Times are local to the participants unless `timezone` is set,
+    /// in which case calendars have them in UTC
+    pub time: TimeOfDay,
+    pub session_minutes: u32,
+    /// Dates without sessions, like holidays
+    pub skip: Vec<Date>,
+    /// Time zone of the sessions, like `Europe/Amsterdam`
+    pub timezone: Option<String>,
+    pub location: Option<String>,
+}
+
+/// A cohort definition file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CohortDef {
+    name: Option<String>,
+    start: DateDef,
+    #[serde(default)]
+    weekdays: Vec<String>,
+    time: Option<DateDef>,
+    session_minutes: Option<u32>,
+    #[serde(default)]
+    skip: Vec<DateDef>,
+    timezone: Option<String>,
+    location: Option<String>,
+}
+
+/// Dates and times can be written as TOML dates and times, or as strings
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum DateDef {
+    Toml(toml::value::Datetime),
+    Text(String),
+}
+
+impl DateDef {
+    fn parse<T: FromStr<Err = String>>(&self) -> Result<T, CalendarError> {
+        let text = match self {
+            DateDef::Toml(datetime) => datetime.to_string(),
+            DateDef::Text(text) => text.clone(),
+        };
+        text.parse()
+            .map_err(|e: String| Report::new(CalendarError).attach_printable(e))
+    }
+}
+
+impl Cohort {
+    pub const DEFAULT_TIME: TimeOfDay = TimeOfDay { hour: 9, minute: 0 };
+    pub const DEFAULT_SESSION_MINUTES: u32 = 180;
+
+    /// A cohort with a session every week on the weekday of `start`
+    pub fn new(start: Date) -> Self {
+        Self {
+            name: None,
+            start,
+            weekdays: vec![],
+            time: Self::DEFAULT_TIME,
+            session_minutes: Self::DEFAULT_SESSION_MINUTES,
+            skip: vec![],
+            timezone: None,
+            location: None,
+        }
+    }
+
+    /// Load a cohort definition from a TOML file
+    pub fn load(path: &Path) -> Result<Self, CalendarError> {
+        let content = path.read_to_string::<CalendarError>()?;
+        let def: CohortDef = toml::from_str(&content)
+            .into_report()
+            .change_context(CalendarError)
+            .attach_printable_lazy(|| {
+                format!(
+                    "Unable to parse cohort {path}",
+                    path = path.to_string_lossy()
+                )
+            })?;
+        let mut cohort = Self::new(def.start.parse()?);
+        cohort.name = def.name;
+        cohort.weekdays = def
+            .weekdays
+            .iter()
+            .map(|day| {
+                day.parse()
+                    .map_err(|e: String| Report::new(CalendarError).attach_printable(e))
+            })
+            .collect::<Result<_, _>>()?;
+        if let Some(time) = def.time {
+            cohort.time = time.parse()?;
+        }
+        if let Some(minutes) = def.session_minutes {
+            cohort.session_minutes = minutes;
+        }
+        cohort.skip = def
+            .skip
+            .iter()
+            .map(DateDef::parse)
+            .collect::<Result<_, _>>()?;
+        cohort.timezone = def.timezone;
+        cohort.location = def.location;
+        Ok(cohort)
+    }
+
+    /// The dates of the sessions, from the start date on
+    fn session_dates(&self) -> impl Iterator<Item = Date> + '_ {
+        let weekdays = match self.weekdays.is_empty() {
+            true => vec![self.start.weekday()],
+            false => self.weekdays.clone(),
+        };
+        (self.start.days..)
+            .map(|days| Date { days })
+            .filter(move |date| weekdays.contains(&date.weekday()) && !self.skip.contains(date))
+    }
+}
+
+impl Track {
+    /// An iCalendar file with an event for every unit that a render with `options` contains,
+    /// on the sessions of `cohort`. Events describe the objectives of the topics of the unit.
+    /// Sessions are in UTC if the cohort has a time zone, which has to be one of the IANA time
+    /// zone database
+    pub fn calendar(
+        &self,
+        cohort: &Cohort,
+        options: &RenderOptions,
+    ) -> Result<String, CalendarError> {
+        let outline = self.select(options, Edition::Student).track.outline();
+        let stamp = format_utc(SystemTime::now());
+        let timezone = cohort
+            .timezone
+            .as_deref()
+            .map(|name| {
+                name.parse::<Tz>().map_err(|_| {
+                    Report::new(CalendarError).attach_printable(format!(
+                        "Unknown time zone '{name}', expected one of the IANA time zone database like Europe/Amsterdam"
+                    ))
+                })
+            })
+            .transpose()?;
+        // Without a time zone, times are floating: local to wherever the calendar is opened
+        let format_time = |local: i64| match timezone {
+            Some(timezone) => format_seconds(to_utc(timezone, local)) + "Z",
+            None => format_seconds(local),
+        };
+        let cohort_id = cohort.name.as_deref().map(to_tag);
+
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            "PRODID:-//teach-rs//modmod//EN".to_string(),
+            "CALSCALE:GREGORIAN".to_string(),
+            format!(
+                "X-WR-CALNAME:{}",
+                escape(&match &cohort.name {
+                    Some(name) => format!("{} - {name}", outline.name),
+                    None => outline.name.clone(),
+                })
+            ),
+        ];
+        let units = outline
+            .modules
+            .iter()
+            .flat_map(|module| module.units.iter().map(move |unit| (module, unit)));
+        for ((module, unit), date) in units.zip(cohort.session_dates()) {
+            let start = date.days * 86400 + i64::from(cohort.time.minutes()) * 60;
+            let end = start + i64::from(cohort.session_minutes) * 60;
+            let mut description = String::new();
+            if !unit.objectives.is_empty() {
+                description.push_str("Objectives:\n");
+                for objective in unit.objectives.iter() {
+                    description.push_str(&format!("- {}\n", objective.trim()));
+                }
+            }
+            let uid = match &cohort_id {
+                Some(cohort) => format!("{}/{cohort}", unit.id),
+                None => unit.id.clone(),
+            };
+
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}@modmod", escape(&uid)));
+            lines.push(format!("DTSTAMP:{stamp}"));
+            lines.push(format!("DTSTART:{}", format_time(start)));
+            lines.push(format!("DTEND:{}", format_time(end)));
+            lines.push(format!(
+                "SUMMARY:{}",
+                escape(&format!(
+                    "Unit {}.{} - {} ({})",
+                    module.index, unit.index, unit.name, outline.name
+                ))
+            ));
+            if !description.is_empty() {
+                lines.push(format!("DESCRIPTION:{}", escape(description.trim_end())));
+            }
+            if let Some(location) = &cohort.location {
+                lines.push(format!("LOCATION:{}", escape(location)));
+            }
+            lines.push("END:VEVENT".to_string());
+        }
+        lines.push("END:VCALENDAR".to_string());
+
+        Ok(lines.iter().map(|line| fold(line)).collect())
+    }
+}
+
+/// A day in the proleptic Gregorian calendar, written like `2024-09-02`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    /// Days since 1970-01-01
+    days: i64,
+}
+
+impl Date {
+    pub fn from_ymd(year: i64, month: u32, day: u32) -> Option<Self> {
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            _ => return None,
+        };
+        if day == 0 || day > days_in_month {
+            return None;
+        }
+        // Days from civil, counting years from March so that leap days come last
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month = i64::from(month);
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        Some(Self {
+            days: era * 146097 + day_of_era - 719468,
+        })
+    }
+
+    pub fn ymd(self) -> (i64, u32, u32) {
+        let days = self.days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days.rem_euclid(146097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        (year, month as u32, day as u32)
+    }
+
+    pub fn weekday(self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        Weekday::ALL[(self.days + 3).rem_euclid(7) as usize]
+    }
+
+    /// The date like `20240902`, as iCalendar writes dates
+    fn compact(self) -> String {
+        let (year, month, day) = self.ymd();
+        format!("{year:04}{month:02}{day:02}")
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || format!("Invalid date '{s}', expected a date like 2024-09-02");
+        let mut parts = s.trim().splitn(3, '-');
+        let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let year = year.parse().map_err(|_| invalid())?;
+        let month = month.parse().map_err(|_| invalid())?;
+        let day = day.parse().map_err(|_| invalid())?;
+        Self::from_ymd(year, month, day).ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Weekday::Monday => "monday",
+            Weekday::Tuesday => "tuesday",
+            Weekday::Wednesday => "wednesday",
+            Weekday::Thursday => "thursday",
+            Weekday::Friday => "friday",
+            Weekday::Saturday => "saturday",
+            Weekday::Sunday => "sunday",
+        }
+    }
+}
+
+impl FromStr for Weekday {
+    type Err = String;
+
+    /// Weekdays are written in full or by their first three letters, in any case
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        Weekday::ALL
+            .into_iter()
+            .find(|day| s.len() >= 3 && day.name().starts_with(&s))
+            .ok_or_else(|| format!("Invalid weekday '{s}', expected a day like monday or mon"))
+    }
+}
+
+/// A time of day, written like `09:30`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeOfDay {
+    hour: u32,
+    minute: u32,
+}
+
+impl TimeOfDay {
+    fn minutes(self) -> u32 {
+        self.hour * 60 + self.minute
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    /// Seconds, as TOML times have them, are ignored
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || format!("Invalid time '{s}', expected a time like 09:30");
+        let mut parts = s.trim().split(':');
+        let (Some(hour), Some(minute)) = (parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        let hour: u32 = hour.parse().map_err(|_| invalid())?;
+        let minute: u32 = minute.parse().map_err(|_| invalid())?;
+        if hour > 23 || minute > 59 {
+            return Err(invalid());
+        }
+        Ok(Self { hour, minute })
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// The time in seconds since the epoch of the `local` time in `timezone`, which is in seconds
+/// since the epoch as if the time zone were UTC
+fn to_utc(timezone: Tz, local: i64) -> i64 {
+    let local = DateTime::from_timestamp(local, 0).unwrap().naive_utc();
+    match timezone.from_local_datetime(&local) {
+        LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => time.timestamp(),
+        // Times that are skipped when the clocks go forward are in the offset from before
+        LocalResult::None => {
+            let before = local - chrono::Duration::days(1);
+            let offset = timezone.offset_from_utc_datetime(&before).fix();
+            (local - offset).and_utc().timestamp()
+        }
+    }
+}
+
+/// `time` in UTC, like `20240902T093000Z`
+fn format_utc(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as i64;
+    format_seconds(seconds) + "Z"
+}
+
+/// `seconds` since the epoch like `20240902T093000`, as iCalendar writes times
+fn format_seconds(seconds: i64) -> String {
+    let date = Date {
+        days: seconds.div_euclid(86400),
+    };
+    let seconds = seconds.rem_euclid(86400);
+    format!(
+        "{}T{:02}{:02}{:02}",
+        date.compact(),
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Escape text for iCalendar property values
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line into lines of at most 75 bytes, each ending in CRLF,
+/// where continuation lines start with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seconds since the epoch of a date and time, as if the time zone were UTC
+    fn seconds(year: i64, month: u32, day: u32, hour: i64, minute: i64) -> i64 {
+        Date::from_ymd(year, month, day).unwrap().days * 86400 + hour * 3600 + minute * 60
+    }
+
+    #[test]
+    fn local_times_in_utc() {
+        for (timezone, local, hours) in [
+            // Daylight saving time is from the last Sunday of March to that of October
+            (Tz::Europe__Amsterdam, seconds(2024, 3, 30, 13, 30), -1),
+            (Tz::Europe__Amsterdam, seconds(2024, 9, 2, 13, 30), -2),
+            (Tz::Europe__Amsterdam, seconds(2024, 10, 27, 13, 30), -1),
+            // South of the equator, it spans the turn of the year
+            (Tz::Australia__Sydney, seconds(2024, 1, 15, 9, 0), -11),
+            (Tz::Australia__Sydney, seconds(2024, 7, 15, 9, 0), -10),
+            // 2:30 doesn't exist on the day the clocks go forward, and is taken as 3:30
+            (Tz::Europe__Amsterdam, seconds(2024, 3, 31, 2, 30), -1),
+        ] {
+            assert_eq!(
+                (to_utc(timezone, local) - local) / 3600,
+                hours,
+                "{timezone}"
+            );
+        }
+        assert_eq!(
+            format_seconds(to_utc(Tz::Asia__Kolkata, seconds(2024, 6, 1, 9, 0))),
+            "20240601T033000"
+        );
+    }
+}
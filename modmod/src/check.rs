@@ -0,0 +1,271 @@
+//! Validation of a track without rendering it, so that problems are found
+//! before anything is written to the output directory
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+};
+
+use error_stack::{AttachmentKind, Context, FrameKind, Report, Result};
+use serde::Serialize;
+
+use crate::{
+    accessibility::accessibility_warnings,
+    anchors::expand_includes,
+    book::EXERCISE_PLACEHOLDERS,
+    codes::WarningCode,
+    conditional::{select_conditional, track_id},
+    exercises,
+    io::PathExt,
+    layout::Layout,
+    qr::QrCodes,
+    report::Warning,
+    select::{Edition, Selection},
+    slides::TEMPLATE_PLACEHOLDERS,
+    template::{expand_variables, Template},
+    LoadTrackError, RenderOptions, TopicContent, Track,
+};
+
+/// Outcome of checking a track
+#[derive(Debug, Default, Serialize)]
+pub struct CheckReport {
+    pub track_name: String,
+    /// Problems that would make rendering the track fail
+    pub problems: Vec<String>,
+    pub warnings: Vec<Warning>,
+    /// Warnings that were allowed by the track or the item they are about
+    pub notes: Vec<Warning>,
+}
+
+impl CheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Track {
+    /// Check that the parts of the track that a render with `options` uses can be rendered:
+    /// that every file it reads exists and is text, that the include globs of the exercises
+    /// and the output layout are valid, and that the templates and exercise descriptions only
+    /// use placeholders and variables that are filled in. Instructor notes are checked as well,
+    /// as they are part of the instructor edition.
+    pub fn check(&self, options: &RenderOptions) -> Result<CheckReport, LoadTrackError> {
+        let Selection {
+            track,
+            warnings: selection_warnings,
+            unmatched_selectors,
+            ..
+        } = self.select(options, Edition::Instructor);
+        let mut problems = unmatched_selectors;
+        let mut warnings = selection_warnings;
+        warnings.extend(track.validate());
+        match self.check_dependencies() {
+            Ok(dependency_warnings) => warnings.extend(dependency_warnings),
+            Err(report) => problems.push(describe(&report)),
+        }
+        if let Err(report) = QrCodes::for_track(&track) {
+            problems.push(describe(&report));
+        }
+        let layout = Layout::new(&options.layout, &track)
+            .map_err(|report| problems.push(describe(&report)))
+            .ok();
+
+        let variables: BTreeMap<&str, &str> = track
+            .variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        let track_id = track_id(&self.path);
+        let mut exercise_dirs = HashSet::new();
+        // Topics that are part of more than one unit are only linted once
+        let mut linted_topics = HashSet::new();
+        for module in track.modules.iter() {
+            let module_key = (module.index, module.data.name.as_str());
+            for unit in module.data.units.iter() {
+                let unit_key = (unit.index, unit.data.name.as_str());
+                for template in unit.data.templates.iter() {
+                    check_template(&template.path, &variables, &mut problems);
+                }
+                let exercises = unit
+                    .data
+                    .topics
+                    .iter()
+                    .flat_map(|t| &t.data.exercises)
+                    .map(|e| &e.data);
+                // Exercises are numbered throughout the unit
+                for (exercise, exercise_index) in exercises.zip(1..) {
+                    if !exercise.path.is_dir() {
+                        problems.push(format!(
+                            "Exercise '{name}' at {path} is not a directory",
+                            name = exercise.name,
+                            path = exercise.path.to_string_lossy()
+                        ));
+                        continue;
+                    }
+                    check_placeholders(
+                        &exercise.description,
+                        &EXERCISE_PLACEHOLDERS,
+                        &mut problems,
+                    );
+                    match exercises::included_files(
+                        &exercise.path,
+                        &exercise.includes,
+                        &exercise.excludes,
+                    ) {
+                        Ok(included) if included.is_empty() => warnings.push(
+                            Warning::new(
+                                WarningCode::UnmatchedIncludes,
+                                format!(
+                                    "The include patterns of exercise '{name}' matched no files",
+                                    name = exercise.name
+                                ),
+                            )
+                            .allowed_by(&exercise.allow),
+                        ),
+                        Ok(_) => {}
+                        Err(report) => problems.push(format!(
+                            "Exercise '{name}': {problem}",
+                            name = exercise.name,
+                            problem = describe(&report)
+                        )),
+                    }
+
+                    let Some(layout) = &layout else {
+                        continue;
+                    };
+                    let exercise_key = (exercise_index, exercise.name.as_str());
+                    let dir = layout.module_dir(module_key).and_then(|module_dir| {
+                        Ok(module_dir
+                            .join(layout.unit_dir(module_key, unit_key)?)
+                            .join(layout.exercise_dir(module_key, unit_key, exercise_key)?))
+                    });
+                    match dir {
+                        Ok(dir) if !exercise_dirs.insert(dir.clone()) => problems.push(format!(
+                            "Exercise '{name}' would be rendered into {dir}, which another exercise is rendered into already",
+                            name = exercise.name,
+                            dir = dir.to_string_lossy()
+                        )),
+                        Ok(_) => {}
+                        Err(report) => problems.push(describe(&report)),
+                    }
+                }
+
+                for topic in unit.data.topics.iter().map(|t| &t.data) {
+                    if let TopicContent::File(content) = &topic.content {
+                        let markdown = check_readable(content, &mut problems);
+                        if let Some(markdown) = &markdown {
+                            if let Err(problem) =
+                                select_conditional(markdown, &track_id).and_then(|markdown| {
+                                    expand_includes(&markdown, content.parent().unwrap())?;
+                                    expand_variables(&markdown, &variables)
+                                })
+                            {
+                                problems.push(format!(
+                                    "{problem}, in {path}",
+                                    path = content.to_string_lossy()
+                                ));
+                            }
+                        }
+                        if let Some(markdown) = markdown.filter(|_| linted_topics.insert(content)) {
+                            warnings.extend(
+                                accessibility_warnings(content, &markdown)
+                                    .into_iter()
+                                    .map(|warning| warning.allowed_by(&unit.data.allow)),
+                            );
+                        }
+                    }
+                    if let Some(notes) = &topic.instructor_notes {
+                        check_readable(notes, &mut problems);
+                    }
+                }
+            }
+        }
+
+        for appendix in track.appendices.iter() {
+            check_readable(&appendix.content, &mut problems);
+        }
+
+        // Only report each problem once, in the order they were found
+        let mut seen = HashSet::new();
+        problems.retain(|problem| seen.insert(problem.clone()));
+
+        let (notes, warnings) = warnings
+            .into_iter()
+            .map(|warning| warning.allowed_by(&track.allow))
+            .partition(|warning| warning.allowed);
+        Ok(CheckReport {
+            track_name: track.name.clone(),
+            problems,
+            warnings,
+            notes,
+        })
+    }
+}
+
+/// Check that the file at `path` can be read as text
+fn check_readable(path: &Path, problems: &mut Vec<String>) -> Option<String> {
+    path.read_to_string::<LoadTrackError>()
+        .map_err(|report| problems.push(describe(&report)))
+        .ok()
+}
+
+/// Check that the file at `path` only uses `#[modmod:...]` placeholders from `known`
+fn check_placeholders(path: &Path, known: &[&str], problems: &mut Vec<String>) {
+    let Some(content) = check_readable(path, problems) else {
+        return;
+    };
+    let mut rest = content.as_str();
+    while let Some(start) = rest.find("#[modmod:") {
+        rest = &rest[start + "#[modmod:".len()..];
+        let name = rest.split_once(']').map_or(rest, |(name, _)| name);
+        if !known.contains(&name) {
+            problems.push(format!(
+                "Unknown placeholder '#[modmod:{name}]' in {path}, known placeholders are {known}",
+                path = path.to_string_lossy(),
+                known = known
+                    .iter()
+                    .map(|p| format!("#[modmod:{p}]"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+}
+
+/// Check that the slides template at `path` can be parsed, which also checks that it
+/// only uses known placeholders
+fn check_template(path: &Path, variables: &BTreeMap<&str, &str>, problems: &mut Vec<String>) {
+    let Some(content) = check_readable(path, problems) else {
+        return;
+    };
+    let parsed = expand_variables(&content, variables)
+        .and_then(|content| Template::parse(&content, &TEMPLATE_PLACEHOLDERS));
+    if let Err(problem) = parsed {
+        problems.push(format!(
+            "{problem} in {path}",
+            path = path.to_string_lossy()
+        ));
+    }
+}
+
+/// Describe the problem in an error report in one line: the messages attached to it,
+/// followed by the error from outside of modmod it originates from, if any
+fn describe<C: Context>(report: &Report<C>) -> String {
+    let mut messages: Vec<String> = report
+        .frames()
+        .filter_map(|frame| match frame.kind() {
+            FrameKind::Attachment(AttachmentKind::Printable(message)) => Some(message.to_string()),
+            _ => None,
+        })
+        .collect();
+    if let Some(error) = report.downcast_ref::<std::io::Error>() {
+        messages.push(error.to_string());
+    } else if let Some(error) = report.downcast_ref::<globset::Error>() {
+        messages.push(error.to_string());
+    } else if let Some(error) = report.downcast_ref::<fs_extra::error::Error>() {
+        messages.push(error.to_string());
+    } else if messages.is_empty() {
+        messages.push(report.current_context().to_string());
+    }
+    messages.join(": ")
+}
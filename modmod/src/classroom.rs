@@ -0,0 +1,161 @@
+//! Assignment repositories for GitHub Classroom, made from the rendered exercises.
+//!
+//! Every assignment is a directory that can be pushed as the template repository of
+//! an assignment, with the exercise files, a README with the exercise descriptions and
+//! a workflow that grades the Cargo exercises with their tests. The assignments are
+//! listed in the [CLASSROOM_MANIFEST_FILE], for scripts that create them in a classroom.
+
+use std::path::{Path, PathBuf};
+
+use error_stack::Result;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    exercises::{ExerciseGrouping, RenderExercisesError},
+    io::PathExt,
+    template::unescape_placeholders,
+    ExerciseKind,
+};
+
+pub const CLASSROOM_SCHEMA_VERSION: u32 = 1;
+
+/// Name of the manifest of the assignments, in the `classroom` directory of the output
+pub const CLASSROOM_MANIFEST_FILE: &str = "classroom.json";
+
+/// Path of the grading workflow in an assignment repository
+pub(crate) const WORKFLOW_FILE: &str = ".github/workflows/classroom.yml";
+
+/// The assignments of a render
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassroomManifest {
+    pub schema_version: u32,
+    pub grouping: ExerciseGrouping,
+    pub assignments: Vec<ClassroomAssignment>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassroomAssignment {
+    /// Title of the assignment, with the number of the exercise or unit
+    pub title: String,
+    /// Directory of the assignment repository, relative to the output directory
+    pub dir: PathBuf,
+    pub exercises: Vec<ClassroomExercise>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassroomExercise {
+    /// Number of the exercise as used in the book
+    pub number: String,
+    pub name: String,
+    /// Directory of the exercise, relative to the assignment repository
+    pub dir: PathBuf,
+    #[serde(skip)]
+    pub(crate) kind: ExerciseKind,
+    #[serde(skip)]
+    pub(crate) description: PathBuf,
+}
+
+impl ClassroomAssignment {
+    /// README of the assignment repository, with the descriptions of its exercises
+    pub(crate) fn readme(&self) -> Result<String, RenderExercisesError> {
+        let mut readme = format!("# {}\n", self.title);
+        for exercise in self.exercises.iter() {
+            let dir = match exercise.dir.as_os_str().is_empty() {
+                true => ".".to_string(),
+                false => to_slash_path(&exercise.dir),
+            };
+            let description = exercise
+                .description
+                .read_to_string::<RenderExercisesError>()?
+                .replace("#[modmod:exercise_dir]", &dir)
+                .replace("#[modmod:exercise_ref]", &exercise.number)
+                .replace("#[modmod:qr(exercise)]", "");
+            let description = unescape_placeholders(&description);
+            // The description of a single exercise is that of the assignment
+            let description = if self.exercises.len() > 1 {
+                readme += &format!("\n## {} {} (`{dir}`)\n", exercise.number, exercise.name);
+                description.replace("\n# ", "\n### ")
+            } else {
+                description.replace("\n# ", "\n## ")
+            };
+            readme += &format!("\n{}\n", description.trim());
+        }
+        Ok(readme)
+    }
+
+    /// GitHub Actions workflow that runs the tests of every Cargo exercise of the
+    /// assignment as a test of the autograder, or `None` if it has no Cargo exercises
+    pub(crate) fn workflow(&self) -> Option<String> {
+        let cargo_exercises: Vec<_> = self
+            .exercises
+            .iter()
+            .filter(|e| e.kind == ExerciseKind::Cargo)
+            .collect();
+        if cargo_exercises.is_empty() {
+            return None;
+        }
+
+        let mut steps = vec![json!({
+            "name": "Checkout code",
+            "uses": "actions/checkout@v4",
+        })];
+        let mut runners = vec![];
+        let mut results = serde_json::Map::new();
+        for (exercise, index) in cargo_exercises.into_iter().zip(1..) {
+            let id = format!("exercise-{index}");
+            let command = match exercise.dir.as_os_str().is_empty() {
+                true => "cargo test".to_string(),
+                false => format!(
+                    "cargo test --manifest-path {}/Cargo.toml",
+                    to_slash_path(&exercise.dir)
+                ),
+            };
+            let name = format!("{} {}", exercise.number, exercise.name);
+            steps.push(json!({
+                "name": name,
+                "id": id,
+                "uses": "classroom-resources/autograding-command-grader@v1",
+                "with": {
+                    "test-name": name,
+                    "command": command,
+                    "timeout": 10,
+                    "max-score": 1,
+                },
+            }));
+            results.insert(
+                format!("{}_RESULTS", id.to_uppercase()),
+                format!("${{{{steps.{id}.outputs.result}}}}").into(),
+            );
+            runners.push(id);
+        }
+        steps.push(json!({
+            "name": "Autograding Reporter",
+            "uses": "classroom-resources/autograding-grading-reporter@v1",
+            "env": results,
+            "with": { "runners": runners.join(",") },
+        }));
+
+        let workflow = json!({
+            "name": "Autograding Tests",
+            "on": ["push", "workflow_dispatch", "repository_dispatch"],
+            "permissions": { "checks": "write", "actions": "read", "contents": "read" },
+            "jobs": {
+                "run-autograding-tests": {
+                    "runs-on": "ubuntu-latest",
+                    "if": "github.actor != 'github-classroom[bot]'",
+                    "steps": steps,
+                },
+            },
+        });
+        Some(format!(
+            "# Generated by modmod\n{}",
+            serde_yaml::to_string(&workflow).unwrap()
+        ))
+    }
+}
+
+fn to_slash_path(path: &Path) -> String {
+    let parts: Vec<_> = path.iter().map(|part| part.to_string_lossy()).collect();
+    parts.join("/")
+}
@@ -0,0 +1,173 @@
+//! Registry of warning codes. Every [Warning](crate::Warning) has one of these codes,
+//! so that authors can allow specific warnings they consider acceptable.
+//!
+//! Codes are stable: a code is never reused for a different kind of warning.
+//! New kinds of warnings get a new variant with the next free code.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "&str", try_from = "String")]
+pub enum WarningCode {
+    DuplicateExerciseName,
+    DuplicateItem,
+    NearDuplicateItem,
+    UnmatchedIncludes,
+    UnmatchedSearchExclusion,
+    UnmatchedTopicExclusion,
+    DependencyAfterDependent,
+    MissingDependency,
+    MissingAltText,
+    SkippedHeadingLevel,
+    DeadLink,
+    FailingSnippet,
+    ConflictingGlossaryEntry,
+}
+
+impl WarningCode {
+    /// Every warning code, in order of their codes. New variants are added here too.
+    pub const ALL: [WarningCode; 13] = [
+        WarningCode::DuplicateExerciseName,
+        WarningCode::DuplicateItem,
+        WarningCode::NearDuplicateItem,
+        WarningCode::UnmatchedIncludes,
+        WarningCode::UnmatchedSearchExclusion,
+        WarningCode::UnmatchedTopicExclusion,
+        WarningCode::DependencyAfterDependent,
+        WarningCode::MissingDependency,
+        WarningCode::MissingAltText,
+        WarningCode::SkippedHeadingLevel,
+        WarningCode::DeadLink,
+        WarningCode::FailingSnippet,
+        WarningCode::ConflictingGlossaryEntry,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            WarningCode::DuplicateExerciseName => "W0001",
+            WarningCode::DuplicateItem => "W0002",
+            WarningCode::NearDuplicateItem => "W0003",
+            WarningCode::UnmatchedIncludes => "W0004",
+            WarningCode::UnmatchedSearchExclusion => "W0005",
+            WarningCode::UnmatchedTopicExclusion => "W0006",
+            WarningCode::DependencyAfterDependent => "W0007",
+            WarningCode::MissingDependency => "W0008",
+            WarningCode::MissingAltText => "W0009",
+            WarningCode::SkippedHeadingLevel => "W0010",
+            WarningCode::DeadLink => "W0011",
+            WarningCode::FailingSnippet => "W0012",
+            WarningCode::ConflictingGlossaryEntry => "W0013",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            WarningCode::DuplicateExerciseName => "a unit contains exercises with the same name",
+            WarningCode::DuplicateItem => {
+                "an objective or summary item is listed more than once in a unit"
+            }
+            WarningCode::NearDuplicateItem => {
+                "an objective or summary item starts with another one in the same unit"
+            }
+            WarningCode::UnmatchedIncludes => {
+                "the include patterns of an exercise matched no files"
+            }
+            WarningCode::UnmatchedSearchExclusion => {
+                "a search exclusion pattern matched no book pages"
+            }
+            WarningCode::UnmatchedTopicExclusion => {
+                "an excluded topic or tag is not part of the track"
+            }
+            WarningCode::DependencyAfterDependent => "a topic comes before a topic it depends on",
+            WarningCode::MissingDependency => {
+                "a topic depends on a topic that is not part of the track"
+            }
+            WarningCode::MissingAltText => "an image on the slides of a topic has no alt text",
+            WarningCode::SkippedHeadingLevel => {
+                "a slide has a heading more than one level below the heading before it"
+            }
+            WarningCode::DeadLink => "a link in the slides or the book points nowhere",
+            WarningCode::FailingSnippet => {
+                "a Rust code block on the slides of a topic doesn't compile"
+            }
+            WarningCode::ConflictingGlossaryEntry => {
+                "different topics define the same glossary term differently"
+            }
+        }
+    }
+}
+
+impl fmt::Display for WarningCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for WarningCode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WarningCode::ALL
+            .into_iter()
+            .find(|c| c.code() == s)
+            .ok_or_else(|| format!("unknown warning code '{s}'"))
+    }
+}
+
+impl TryFrom<String> for WarningCode {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<WarningCode> for &'static str {
+    fn from(code: WarningCode) -> Self {
+        code.code()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn codes_are_unique_and_described() {
+        let codes: HashSet<&str> = WarningCode::ALL.iter().map(|c| c.code()).collect();
+        assert_eq!(codes.len(), WarningCode::ALL.len());
+        for code in WarningCode::ALL {
+            assert!(
+                !code.description().is_empty(),
+                "{code:?} has no description"
+            );
+            assert_eq!(code.code().parse::<WarningCode>(), Ok(code));
+        }
+    }
+
+    #[test]
+    fn all_lists_every_code_in_order() {
+        for (index, code) in WarningCode::ALL.into_iter().enumerate() {
+            assert_eq!(code.code(), format!("W{:04}", index + 1));
+        }
+    }
+
+    #[test]
+    fn every_code_is_documented() {
+        let readme = include_str!("../README.md");
+        for code in WarningCode::ALL {
+            let description = code.description();
+            let row = format!(
+                "| {} | {}{}",
+                code.code(),
+                description[..1].to_uppercase(),
+                &description[1..]
+            );
+            assert!(readme.contains(&row), "{code} is not in the README");
+        }
+    }
+}
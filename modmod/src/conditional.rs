@@ -0,0 +1,75 @@
+//! Regions of topic slides that only some tracks show, so that one topic can serve several
+//! audiences with small variations instead of being forked:
+//!
+//! ```text
+//! #[modmod:if track="embedded"]
+//! On a microcontroller, there's no allocator by default.
+//! #[modmod:else]
+//! `Vec` allocates on the heap.
+//! #[modmod:endif]
+//! ```
+
+use std::path::Path;
+
+const IF_PREFIX: &str = "#[modmod:if ";
+const ELSE: &str = "#[modmod:else]";
+const ENDIF: &str = "#[modmod:endif]";
+
+/// The name that conditions refer to a track by: the name of its definition file without
+/// extensions, like `rust-intro` for `rust-intro.track.toml`
+pub(crate) fn track_id(track_path: &Path) -> String {
+    let file_name = track_path.file_name().unwrap_or_default().to_string_lossy();
+    file_name.split('.').next().unwrap_or_default().to_string()
+}
+
+/// The slides `content` with only the conditional regions that apply to the track with id
+/// `track`, and without the lines that mark them, or what's wrong with the regions
+pub(crate) fn select_conditional(content: &str, track: &str) -> Result<String, String> {
+    if !content.contains("#[modmod:if") {
+        return Ok(content.to_string());
+    }
+    let mut selected = String::with_capacity(content.len());
+    // For every region we're in: its line, whether it applies, and whether it's in its `else`
+    let mut regions: Vec<(usize, bool, bool)> = vec![];
+    for (line, number) in content.split_inclusive('\n').zip(1..) {
+        let trimmed = line.trim();
+        if let Some(condition) = trimmed.strip_prefix(IF_PREFIX) {
+            let applies = condition
+                .strip_suffix(']')
+                .and_then(|condition| condition_applies(condition, track))
+                .ok_or_else(|| {
+                    format!("Condition '{trimmed}' on line {number} is not like #[modmod:if track=\"name\"]")
+                })?;
+            regions.push((number, applies, false));
+        } else if trimmed == ELSE {
+            match regions.last_mut() {
+                Some((_, _, in_else @ false)) => *in_else = true,
+                Some(_) => return Err(format!("Second {ELSE} on line {number}")),
+                None => return Err(format!("{ELSE} on line {number} is not in a region")),
+            }
+        } else if trimmed == ENDIF {
+            regions
+                .pop()
+                .ok_or_else(|| format!("{ENDIF} on line {number} ends no region"))?;
+        } else if regions
+            .iter()
+            .all(|(_, applies, in_else)| applies != in_else)
+        {
+            selected.push_str(line);
+        }
+    }
+    match regions.last() {
+        Some((number, _, _)) => Err(format!(
+            "Region on line {number} is never ended with {ENDIF}"
+        )),
+        None => Ok(selected),
+    }
+}
+
+/// Whether `condition`, like `track="embedded"` or `track="embedded,intro"`, applies to the
+/// track with id `track`, or `None` if it's not a condition
+fn condition_applies(condition: &str, track: &str) -> Option<bool> {
+    let (key, value) = condition.split_once('=')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    (key.trim() == "track").then(|| value.split(',').any(|name| name.trim() == track))
+}
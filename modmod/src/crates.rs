@@ -0,0 +1,60 @@
+//! Vendoring of the crates that rendered exercises depend on, so that they can be
+//! built without internet access
+
+use std::{
+    fmt,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use crate::{io::WriteExt, output::Output};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct VendorCratesError;
+
+impl fmt::Display for VendorCratesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to vendor the dependencies of exercises")
+    }
+}
+
+impl error_stack::Context for VendorCratesError {}
+
+/// Directory the crates are vendored into, in the directory of the package or workspace
+pub(crate) const VENDOR_DIR: &str = "vendor";
+
+/// Vendor the dependencies of the Cargo package or workspace rendered into `dir` with
+/// `cargo vendor`, and write the `.cargo/config.toml` that makes Cargo use them.
+/// Packages without dependencies are left as they are.
+pub(crate) fn vendor_crates(output: &Output, dir: &Path) -> Result<(), VendorCratesError> {
+    output.check_cancelled::<VendorCratesError>()?;
+    let vendored = Command::new("cargo")
+        .args(["vendor", VENDOR_DIR])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .into_report()
+        .attach_printable("Unable to run cargo")
+        .change_context(VendorCratesError)?;
+    if !vendored.status.success() {
+        return Err(Report::new(VendorCratesError).attach_printable(format!(
+            "cargo vendor failed in {dir}: {stderr}",
+            dir = dir.to_string_lossy(),
+            stderr = String::from_utf8_lossy(&vendored.stderr).trim()
+        )));
+    }
+    // The configuration is only printed if there was anything to vendor
+    let config = String::from_utf8_lossy(&vendored.stdout);
+    if config.trim().is_empty() {
+        return Ok(());
+    }
+    let config_dir = dir.join(".cargo");
+    output.create_dir_all(&config_dir)?;
+    output
+        .create_file(config_dir.join("config.toml"))?
+        .write_all(format!("# Generated by modmod with cargo vendor\n{config}"))?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Prerequisites between topics, as declared with `dependencies` in topic definitions
+
+use error_stack::{Report, Result};
+
+use crate::{codes::WarningCode, report::Warning, select::refers_to, LoadTrackError, Topic, Track};
+
+impl Track {
+    /// Check the dependencies between the topics of the track. Cycles, and excluded topics
+    /// that other topics depend on, are errors. Topics that come before a topic they depend on,
+    /// or that depend on a topic that is not part of the track, produce warnings.
+    pub(crate) fn check_dependencies(&self) -> Result<Vec<Warning>, LoadTrackError> {
+        // Topics in the order they are taught. Topics that are part of
+        // the track more than once are taught when they first occur.
+        let mut topics: Vec<&Topic> = vec![];
+        let all_topics = self
+            .modules
+            .iter()
+            .flat_map(|m| &m.data.units)
+            .flat_map(|u| &u.data.topics)
+            .map(|t| &t.data);
+        for topic in all_topics {
+            if topic.inline || !topics.iter().any(|t| !t.inline && t.path == topic.path) {
+                topics.push(topic);
+            }
+        }
+        let is_excluded = |topic: &Topic| self.excludes(topic);
+
+        let mut warnings = vec![];
+        // Dependencies by the index of the dependent topic
+        let mut edges: Vec<Vec<usize>> = vec![vec![]; topics.len()];
+        for (i, topic) in topics.iter().enumerate() {
+            if is_excluded(topic) {
+                continue;
+            }
+            for dependency in topic.dependencies.iter() {
+                let Some(j) = topics.iter().position(|t| refers_to(dependency, t)) else {
+                    warnings.push(Warning::new(
+                        WarningCode::MissingDependency,
+                        format!(
+                            "Topic '{name}' depends on {path}, which is not part of the track",
+                            name = topic.name,
+                            path = dependency.to_string_lossy()
+                        ),
+                    ));
+                    continue;
+                };
+                if is_excluded(topics[j]) {
+                    return Err(Report::new(LoadTrackError).attach_printable(format!(
+                        "Topic '{name}' depends on topic '{dependency}', which is excluded from the track",
+                        name = topic.name,
+                        dependency = topics[j].name
+                    )));
+                }
+                if j > i {
+                    warnings.push(Warning::new(
+                        WarningCode::DependencyAfterDependent,
+                        format!(
+                            "Topic '{name}' comes before topic '{dependency}', which it depends on",
+                            name = topic.name,
+                            dependency = topics[j].name
+                        ),
+                    ));
+                }
+                edges[i].push(j);
+            }
+        }
+
+        if let Some(cycle) = find_cycle(&edges) {
+            let names: Vec<_> = cycle
+                .iter()
+                .map(|&i| format!("'{}'", topics[i].name))
+                .collect();
+            return Err(Report::new(LoadTrackError).attach_printable(format!(
+                "Topics depend on each other in a cycle: {}",
+                names.join(" -> ")
+            )));
+        }
+
+        Ok(warnings)
+    }
+}
+
+/// Find a cycle in a directed graph, returning the nodes on it with the first node repeated at the end
+fn find_cycle(edges: &[Vec<usize>]) -> Option<Vec<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        OnPath,
+        Done,
+    }
+
+    fn visit(
+        node: usize,
+        edges: &[Vec<usize>],
+        states: &mut [State],
+        path: &mut Vec<usize>,
+    ) -> Option<Vec<usize>> {
+        states[node] = State::OnPath;
+        path.push(node);
+        for &next in edges[node].iter() {
+            match states[next] {
+                State::OnPath => {
+                    let start = path.iter().position(|&n| n == next).unwrap();
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(next);
+                    return Some(cycle);
+                }
+                State::Unvisited => {
+                    if let Some(cycle) = visit(next, edges, states, path) {
+                        return Some(cycle);
+                    }
+                }
+                State::Done => {}
+            }
+        }
+        path.pop();
+        states[node] = State::Done;
+        None
+    }
+
+    let mut states = vec![State::Unvisited; edges.len()];
+    (0..edges.len()).find_map(|node| {
+        if states[node] == State::Unvisited {
+            visit(node, edges, &mut states, &mut vec![])
+        } else {
+            None
+        }
+    })
+}
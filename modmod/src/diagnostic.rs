@@ -0,0 +1,119 @@
+//! Messages about definition files that can't be parsed, pointing at the file, line and
+//! key that are wrong instead of printing the whole file
+
+use std::{fmt, path::Path};
+
+/// A problem at a location in a definition file
+pub(crate) struct Diagnostic<'a> {
+    path: &'a Path,
+    message: String,
+    location: Option<Location<'a>>,
+}
+
+struct Location<'a> {
+    /// One-based line number
+    line: usize,
+    /// One-based column number, in characters
+    column: usize,
+    /// Number of characters to mark, at least one
+    length: usize,
+    source_line: &'a str,
+}
+
+impl<'a> Diagnostic<'a> {
+    pub(crate) fn toml(path: &'a Path, content: &'a str, error: &toml::de::Error) -> Self {
+        let location = error.span().map(|span| {
+            let start = span.start.min(content.len());
+            let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+            let line = content[..start].matches('\n').count() + 1;
+            let column = content[line_start..start].chars().count() + 1;
+            let marked = content
+                .get(start..span.end.min(content.len()))
+                .unwrap_or("");
+            let length = marked.lines().next().unwrap_or("").chars().count();
+            Location::new(content, line, column, length)
+        });
+        Self {
+            path,
+            message: error.message().to_string(),
+            location,
+        }
+    }
+
+    pub(crate) fn json(path: &'a Path, content: &'a str, error: &serde_json::Error) -> Self {
+        Self::with_line_column(
+            path,
+            content,
+            error.to_string(),
+            error.line(),
+            error.column(),
+        )
+    }
+
+    pub(crate) fn yaml(path: &'a Path, content: &'a str, error: &serde_yaml::Error) -> Self {
+        let (line, column) = error
+            .location()
+            .map_or((0, 0), |location| (location.line(), location.column()));
+        Self::with_line_column(path, content, error.to_string(), line, column)
+    }
+
+    /// A message from a parser that ends it with ` at line <line> column <column>`,
+    /// which is left out as the location is shown separately
+    fn with_line_column(
+        path: &'a Path,
+        content: &'a str,
+        message: String,
+        line: usize,
+        column: usize,
+    ) -> Self {
+        let message = match message.rsplit_once(" at line ") {
+            Some((message, _)) if line > 0 => message.to_string(),
+            _ => message,
+        };
+        Self {
+            path,
+            message,
+            location: (line > 0).then(|| Location::new(content, line, column.max(1), 1)),
+        }
+    }
+}
+
+impl<'a> Location<'a> {
+    fn new(content: &'a str, line: usize, column: usize, length: usize) -> Self {
+        Self {
+            line,
+            column,
+            length: length.max(1),
+            source_line: content.lines().nth(line - 1).unwrap_or(""),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.to_string_lossy();
+        let Some(location) = &self.location else {
+            return write!(f, "{path}: {message}", message = self.message);
+        };
+        let Location {
+            line,
+            column,
+            length,
+            source_line,
+        } = location;
+        let gutter = " ".repeat(line.to_string().len());
+        writeln!(
+            f,
+            "{path}:{line}:{column}: {message}",
+            message = self.message
+        )?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line} | {source_line}")?;
+        write!(
+            f,
+            "{gutter} | {padding}{marker}",
+            padding = " ".repeat(column - 1),
+            marker = "^".repeat(*length)
+        )
+    }
+}
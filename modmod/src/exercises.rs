@@ -1,12 +1,28 @@
 use std::{
-    collections::HashMap,
-    fmt,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt, fs,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
-use error_stack::{IntoReport, Result, ResultExt};
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use serde::Serialize;
 
-use crate::{io::PathExt, to_prefixed_tag};
+use crate::{
+    archive,
+    book::section_file_name,
+    classroom::{
+        ClassroomAssignment, ClassroomExercise, ClassroomManifest, CLASSROOM_MANIFEST_FILE,
+        CLASSROOM_SCHEMA_VERSION, WORKFLOW_FILE,
+    },
+    codes::WarningCode,
+    io::{PathExt, WriteExt},
+    layout::Layout,
+    output::Output,
+    report::Warning,
+    template::{unescape_placeholders, unresolved_placeholder},
+    to_tag, Exercise, ExerciseKind,
+};
 
 #[non_exhaustive]
 #[derive(Debug, Default)]
@@ -20,6 +36,81 @@ impl fmt::Display for RenderExercisesError {
 
 impl error_stack::Context for RenderExercisesError {}
 
+/// Directory in exercises with tests for grading submissions, which is never part of
+/// the exercises that students get, whatever the include patterns of the exercise
+pub const GRADING_TESTS_DIR: &str = "tests/grading";
+
+/// Which exercises go together, into an assignment or a repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExerciseGrouping {
+    /// Every exercise on its own
+    Exercise,
+    /// The exercises of a unit together, each in a directory of its own
+    Unit,
+}
+
+impl ExerciseGrouping {
+    pub const ALL: [ExerciseGrouping; 2] = [ExerciseGrouping::Exercise, ExerciseGrouping::Unit];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ExerciseGrouping::Exercise => "exercise",
+            ExerciseGrouping::Unit => "unit",
+        }
+    }
+}
+
+impl fmt::Display for ExerciseGrouping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ExerciseGrouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|grouping| grouping.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|grouping| grouping.name()).collect();
+                format!(
+                    "unknown grouping '{s}', expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// What goes into the rendered exercise packages, besides the exercise files
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PackageOptions<'a> {
+    /// Keep the solutions in the exercise files
+    pub keep_solutions: bool,
+    /// Give the directory of every unit a Cargo workspace of its Cargo exercises
+    pub cargo_workspaces: bool,
+    /// Pack the exercises of every unit into a ZIP archive next to the directory of the unit
+    pub zip_units: bool,
+    /// Copy the [GRADING_TESTS_DIR] of every exercise into a `grading` directory
+    /// next to the `exercises` directory, with the same layout
+    pub grading_tests: bool,
+    /// Also copy the exercises into assignment repositories for GitHub Classroom, in a
+    /// `classroom` directory next to the `exercises` directory
+    pub classroom: Option<ExerciseGrouping>,
+    /// Give every exercise that has no README of its own a `README.md` with its
+    /// description and a link to the page of its unit in the book
+    pub readmes: bool,
+    /// URL under which the output directory is published, which the READMEs link to the
+    /// built book under. Without it, they link to the book sources in the output directory.
+    pub base_url: Option<&'a str>,
+    /// Directory that the book is built into, relative to the book
+    pub book_build_dir: &'a str,
+    /// Toolchain to pin the Cargo exercises to with a `rust-toolchain.toml`
+    pub rust_toolchain: Option<&'a str>,
+}
+
 #[derive(Debug)]
 pub struct ExerciseCollection<'track> {
     module_exercises: Vec<ModuleExercises<'track>>,
@@ -34,76 +125,597 @@ impl<'track> ExerciseCollection<'track> {
         }
     }
 
+    /// Render the exercise packages, returning the directories they were
+    /// rendered into relative to the output directory. If `units` is set, only the
+    /// exercises of those units are rendered again, replacing their earlier render.
     pub fn render(
         &self,
-        output_dir: impl AsRef<Path>,
-    ) -> Result<HashMap<PathBuf, PathBuf>, RenderExercisesError> {
-        let output_dir = output_dir.as_ref();
+        layout: &Layout,
+        options: PackageOptions<'_>,
+        units: Option<&BTreeSet<(usize, usize)>>,
+        output: &Output,
+    ) -> Result<ExercisePaths, RenderExercisesError> {
+        let output_dir = output.root();
         let exercise_root_dir = output_dir.join("exercises");
-        exercise_root_dir.create_dir_all()?;
-        let mut exercise_output_paths = HashMap::new();
+        output.create_dir_all(&exercise_root_dir)?;
+        let grading_root_dir = output_dir.join("grading");
+        let classroom_root_dir = output_dir.join("classroom");
+        // Assignments, and whether their units are rendered
+        let mut assignments: Vec<(ClassroomAssignment, bool)> = vec![];
+        let mut paths = ExercisePaths::default();
+        let mut exercise_dirs = HashSet::new();
+        // Files to copy, which are copied all at once when every directory is in place
+        let mut files = vec![];
+        // Files that modmod writes, like workspace manifests, by their path
+        let mut generated = BTreeMap::new();
+        // Archives to create, with the range of `files` that goes into them
+        let mut archives = vec![];
+        // Grading tests and assignment files, which are copied along with the
+        // files of the exercises but aren't archived
+        let mut extra_copies = vec![];
 
         for mod_ex in self.module_exercises.iter() {
-            let mod_ex_out_dir = {
-                let mut d = exercise_root_dir.clone();
-                d.push(to_prefixed_tag(mod_ex.name, mod_ex.index));
-                d
-            };
-            mod_ex_out_dir.create_dir_all()?;
+            let module = (mod_ex.index, mod_ex.name);
+            let mod_ex_out_dir = exercise_root_dir.join(
+                layout
+                    .module_dir(module)
+                    .change_context(RenderExercisesError)?,
+            );
+            output.create_dir_all(&mod_ex_out_dir)?;
+            paths.modules.insert(
+                mod_ex.index,
+                mod_ex_out_dir
+                    .strip_prefix(output_dir)
+                    .unwrap()
+                    .to_path_buf(),
+            );
 
             for unit_ex in mod_ex.unit_exercises.iter() {
-                let unit_ex_out_dir = {
-                    let mut d = mod_ex_out_dir.clone();
-                    d.push(to_prefixed_tag(unit_ex.name, unit_ex.index));
-                    d
-                };
-                unit_ex_out_dir.create_dir_all()?;
+                let unit = (unit_ex.index, unit_ex.name);
+                let unit_ex_out_dir = mod_ex_out_dir.join(
+                    layout
+                        .unit_dir(module, unit)
+                        .change_context(RenderExercisesError)?,
+                );
+                let render_unit =
+                    units.is_none_or(|units| units.contains(&(mod_ex.index, unit_ex.index)));
+                if units.is_some() && render_unit {
+                    // Exercises may have been renamed or lost files since the earlier render
+                    output.remove_dir_all(&unit_ex_out_dir)?;
+                }
+                output.create_dir_all(&unit_ex_out_dir)?;
+                paths.units.insert(
+                    (mod_ex.index, unit_ex.index),
+                    unit_ex_out_dir
+                        .strip_prefix(output_dir)
+                        .unwrap()
+                        .to_path_buf(),
+                );
+                if options.zip_units && !unit_ex.exercises.is_empty() {
+                    let dir_name = unit_ex_out_dir.file_name().unwrap().to_string_lossy();
+                    let archive = unit_ex_out_dir.with_file_name(format!("{dir_name}.zip"));
+                    paths.unit_archives.insert(
+                        (mod_ex.index, unit_ex.index),
+                        archive.strip_prefix(output_dir).unwrap().to_path_buf(),
+                    );
+                    if render_unit {
+                        archives.push((archive, unit_ex_out_dir.clone(), files.len()));
+                    }
+                }
 
+                if options.classroom == Some(ExerciseGrouping::Unit)
+                    && !unit_ex.exercises.is_empty()
+                {
+                    let repo = to_tag(format!(
+                        "{}-{}-{}",
+                        mod_ex.index, unit_ex.index, unit_ex.name
+                    ));
+                    assignments.push((
+                        ClassroomAssignment {
+                            title: format!("{}.{} {}", mod_ex.index, unit_ex.index, unit_ex.name),
+                            dir: classroom_root_dir
+                                .join(repo)
+                                .strip_prefix(output_dir)
+                                .unwrap()
+                                .to_path_buf(),
+                            exercises: vec![],
+                        },
+                        render_unit,
+                    ));
+                }
+
+                let mut workspace_members = vec![];
                 for ex_pack in unit_ex.exercises.iter() {
-                    let ex_pack_out_dir = {
-                        let mut d = unit_ex_out_dir.clone();
-                        d.push(to_prefixed_tag(ex_pack.name, ex_pack.index));
-                        d
+                    let ex_pack_out_dir = unit_ex_out_dir.join(
+                        layout
+                            .exercise_dir(module, unit, (ex_pack.index, ex_pack.name))
+                            .change_context(RenderExercisesError)?,
+                    );
+                    // Layouts without exercise numbers can give exercises the same directory
+                    if !exercise_dirs.insert(ex_pack_out_dir.clone()) {
+                        return Err(Report::new(RenderExercisesError).attach_printable(format!(
+                            "Exercise '{name}' would be rendered into {dir}, which another exercise is rendered into already",
+                            name = ex_pack.name,
+                            dir = ex_pack_out_dir.to_string_lossy()
+                        )));
+                    }
+                    let ex_pack_out_dir_relative = ex_pack_out_dir
+                        .strip_prefix(output_dir)
+                        .unwrap()
+                        .to_path_buf();
+                    let key = (mod_ex.index, unit_ex.index, ex_pack.index);
+                    paths
+                        .exercises
+                        .insert(key, ex_pack_out_dir_relative.clone());
+                    let grading_dir = grading_root_dir
+                        .join(ex_pack_out_dir.strip_prefix(&exercise_root_dir).unwrap());
+                    let grading_files = match options.grading_tests {
+                        true => grading_files(ex_pack.path, ex_pack.excludes)?,
+                        false => vec![],
                     };
-                    ex_pack_out_dir.create_dir_all()?;
-
-                    let content = ex_pack.path.get_dir_content()?;
-
-                    // Create globset to match included files
-                    let mut globset = globset::GlobSetBuilder::new();
-                    for include in ex_pack.includes {
-                        globset.add(
-                            globset::Glob::new(ex_pack.path.join(include).to_str().unwrap())
-                                .into_report()
-                                .attach_printable_lazy(|| {
-                                    format!("Error parsing include glob '{include}'")
-                                })
-                                .change_context(RenderExercisesError)?,
+                    if !grading_files.is_empty() {
+                        paths.grading.insert(
+                            key,
+                            grading_dir.strip_prefix(output_dir).unwrap().to_path_buf(),
                         );
                     }
-                    let globset = globset.build().unwrap();
+                    let number = format!("{}.{}.{}", mod_ex.index, unit_ex.index, ex_pack.index);
+                    let mut classroom_exercise = ClassroomExercise {
+                        number: number.clone(),
+                        name: ex_pack.name.to_string(),
+                        dir: PathBuf::new(),
+                        kind: ex_pack.kind,
+                        description: ex_pack.description.to_path_buf(),
+                    };
+                    match options.classroom {
+                        Some(ExerciseGrouping::Exercise) => {
+                            let repo =
+                                to_tag(format!("{}-{}", number.replace('.', "-"), ex_pack.name));
+                            assignments.push((
+                                ClassroomAssignment {
+                                    title: format!("{number} {}", ex_pack.name),
+                                    dir: classroom_root_dir
+                                        .join(repo)
+                                        .strip_prefix(output_dir)
+                                        .unwrap()
+                                        .to_path_buf(),
+                                    exercises: vec![classroom_exercise],
+                                },
+                                render_unit,
+                            ));
+                        }
+                        Some(ExerciseGrouping::Unit) => {
+                            classroom_exercise.dir = ex_pack_out_dir
+                                .strip_prefix(&unit_ex_out_dir)
+                                .unwrap()
+                                .to_path_buf();
+                            assignments
+                                .last_mut()
+                                .unwrap()
+                                .0
+                                .exercises
+                                .push(classroom_exercise);
+                        }
+                        None => {}
+                    }
+                    if !render_unit {
+                        continue;
+                    }
+                    output.create_dir_all(&ex_pack_out_dir)?;
 
-                    for included_file in content.files.iter().filter(|f| globset.is_match(f)) {
-                        let included_file_relative = Path::new(&included_file)
-                            .strip_prefix(ex_pack.path)
-                            .unwrap();
+                    let grading_tests_dir = ex_pack.path.join(GRADING_TESTS_DIR);
+                    let mut included =
+                        included_files(ex_pack.path, ex_pack.includes, ex_pack.excludes)?;
+                    included.retain(|file| !file.starts_with(&grading_tests_dir));
+                    if included.is_empty() {
+                        output.warn(
+                            Warning::new(
+                                WarningCode::UnmatchedIncludes,
+                                format!(
+                                    "The include patterns of exercise '{name}' matched no files",
+                                    name = ex_pack.name
+                                ),
+                            )
+                            .allowed_by(ex_pack.allow),
+                        );
+                    }
+                    if options.readmes && !included.contains(&ex_pack.path.join("README.md")) {
+                        let book_page =
+                            section_file_name(unit_ex.name, mod_ex.index, unit_ex.index);
+                        let book_link = match options.base_url {
+                            Some(base_url) => format!(
+                                "{}/book/{}/{}",
+                                base_url.trim_end_matches('/'),
+                                options.book_build_dir,
+                                book_page.with_extension("html").to_string_lossy()
+                            ),
+                            // From the exercise directory up to the output directory
+                            None => format!(
+                                "{}book/src/{}",
+                                "../".repeat(ex_pack_out_dir_relative.iter().count()),
+                                book_page.to_string_lossy()
+                            ),
+                        };
+                        generated.insert(
+                            ex_pack_out_dir.join("README.md"),
+                            exercise_readme(ex_pack, &number, (mod_ex, unit_ex), &book_link)?,
+                        );
+                    }
+                    if ex_pack.kind == ExerciseKind::Cargo {
+                        let mut scaffolding = vec![(".gitignore", GITIGNORE.to_string())];
+                        if let Some(channel) = options.rust_toolchain {
+                            scaffolding.push(("rust-toolchain.toml", rust_toolchain(channel)));
+                        }
+                        for (file_name, content) in scaffolding {
+                            if included.contains(&ex_pack.path.join(file_name)) {
+                                continue;
+                            }
+                            if let Some((assignment, _)) =
+                                assignments.last().filter(|_| options.classroom.is_some())
+                            {
+                                let exercise = assignment.exercises.last().unwrap();
+                                generated.insert(
+                                    output_dir
+                                        .join(&assignment.dir)
+                                        .join(&exercise.dir)
+                                        .join(file_name),
+                                    content.clone(),
+                                );
+                            }
+                            generated.insert(ex_pack_out_dir.join(file_name), content);
+                        }
+                    }
+                    if ex_pack.kind == ExerciseKind::Cargo
+                        && included.contains(&ex_pack.path.join("Cargo.toml"))
+                    {
+                        workspace_members.push(
+                            ex_pack_out_dir
+                                .strip_prefix(&unit_ex_out_dir)
+                                .unwrap()
+                                .to_path_buf(),
+                        );
+                    }
+
+                    for included_file in included {
+                        let included_file_relative =
+                            included_file.strip_prefix(ex_pack.path).unwrap();
                         let included_file_dest = ex_pack_out_dir.join(included_file_relative);
                         let include_file_dest_dir = included_file_dest.parent().unwrap();
-                        include_file_dest_dir.create_dir_all()?;
-                        included_file.copy(included_file_dest)?;
+                        output.create_dir_all(include_file_dest_dir)?;
+                        if let Some((assignment, _)) =
+                            assignments.last().filter(|_| options.classroom.is_some())
+                        {
+                            let exercise = assignment.exercises.last().unwrap();
+                            let assignment_file_dest = output_dir
+                                .join(&assignment.dir)
+                                .join(&exercise.dir)
+                                .join(included_file_relative);
+                            output.create_dir_all(assignment_file_dest.parent().unwrap())?;
+                            extra_copies.push((included_file.clone(), assignment_file_dest));
+                        }
+                        files.push((included_file, included_file_dest));
                     }
+                    for grading_file in grading_files {
+                        let grading_file_dest =
+                            grading_dir.join(grading_file.strip_prefix(ex_pack.path).unwrap());
+                        output.create_dir_all(grading_file_dest.parent().unwrap())?;
+                        extra_copies.push((grading_file, grading_file_dest));
+                    }
+                }
+                if options.cargo_workspaces && !workspace_members.is_empty() {
+                    generated.insert(
+                        unit_ex_out_dir.join("Cargo.toml"),
+                        workspace_manifest(&workspace_members),
+                    );
+                    // The members are built into the target directory of the workspace
+                    generated.insert(unit_ex_out_dir.join(".gitignore"), GITIGNORE.to_string());
+                }
+            }
+        }
 
-                    let ex_pack_out_dir = ex_pack_out_dir
-                        .strip_prefix(output_dir)
-                        .unwrap()
-                        .to_path_buf();
-                    exercise_output_paths.insert(ex_pack.path.to_path_buf(), ex_pack_out_dir);
+        let exercise_files = files.len();
+        files.append(&mut extra_copies);
+        output.copy_all(&files, |from| {
+            exercise_file_content(from, options.keep_solutions)
+        })?;
+        for (path, content) in generated.iter() {
+            // Assignments of exercises without files have no directory yet
+            output.create_dir_all(path.parent().unwrap())?;
+            output.create_file(path)?.write_all(content)?;
+        }
+
+        // The files of a unit come after those of the units before it
+        let ends = archives.iter().skip(1).map(|(_, _, start)| *start);
+        for ((archive, unit_dir, start), end) in archives
+            .iter()
+            .zip(ends.chain(std::iter::once(exercise_files)))
+        {
+            output.create_file(archive)?.write_all(zip_unit(
+                unit_dir,
+                &files[*start..end],
+                &generated,
+                options.keep_solutions,
+            )?)?;
+        }
+
+        if let Some(grouping) = options.classroom {
+            for (assignment, _) in assignments.iter().filter(|(_, rendered)| *rendered) {
+                let repo_dir = output_dir.join(&assignment.dir);
+                output.create_dir_all(&repo_dir)?;
+                output
+                    .create_file(repo_dir.join("README.md"))?
+                    .write_all(assignment.readme()?)?;
+                if let Some(workflow) = assignment.workflow() {
+                    let workflow_path = repo_dir.join(WORKFLOW_FILE);
+                    output.create_dir_all(workflow_path.parent().unwrap())?;
+                    output.create_file(workflow_path)?.write_all(workflow)?;
                 }
             }
+            let manifest = ClassroomManifest {
+                schema_version: CLASSROOM_SCHEMA_VERSION,
+                grouping,
+                assignments: assignments
+                    .into_iter()
+                    .map(|(assignment, _)| assignment)
+                    .collect(),
+            };
+            output
+                .create_file(classroom_root_dir.join(CLASSROOM_MANIFEST_FILE))?
+                .write_all(serde_json::to_string_pretty(&manifest).unwrap())?;
+        }
+        Ok(paths)
+    }
+}
+
+/// A ZIP archive of the exercise `files` of the unit rendered into `unit_dir`,
+/// given as pairs of source and destination, and the `generated` files in it.
+/// The archive holds the directory of the unit, so that extracting it gives the same
+/// directories as the render.
+fn zip_unit(
+    unit_dir: &Path,
+    files: &[(PathBuf, PathBuf)],
+    generated: &BTreeMap<PathBuf, String>,
+    keep_solutions: bool,
+) -> Result<Vec<u8>, RenderExercisesError> {
+    let entry_name = |path: &Path| {
+        let name = path.strip_prefix(unit_dir.parent().unwrap()).unwrap();
+        let name: Vec<_> = name.iter().map(|part| part.to_string_lossy()).collect();
+        name.join("/")
+    };
+    let mut entries: Vec<_> = generated
+        .iter()
+        .filter(|(path, _)| path.starts_with(unit_dir))
+        .map(|(path, content)| (entry_name(path), content.clone().into_bytes()))
+        .collect();
+    for (from, to) in files {
+        // The sources are read instead of the output, which dry runs and
+        // incremental renders don't write
+        let content = match exercise_file_content(from, keep_solutions)? {
+            Some(content) => content.into_bytes(),
+            None => fs::read(from)
+                .into_report()
+                .attach_printable_lazy(|| {
+                    format!(
+                        "Error reading file at path {path}",
+                        path = from.to_string_lossy()
+                    )
+                })
+                .change_context(RenderExercisesError)?,
+        };
+        entries.push((entry_name(to), content));
+    }
+    // Directories are listed in no particular order
+    entries.sort();
+    archive::zip(
+        entries
+            .iter()
+            .map(|(name, content)| (name.as_str(), content.as_slice())),
+    )
+    .map_err(|problem| {
+        Report::new(RenderExercisesError).attach_printable(format!(
+            "{problem}: {path}",
+            path = unit_dir.to_string_lossy()
+        ))
+    })
+}
+
+/// README of the exercise numbered `number`, for students that only get the exercise,
+/// with its description and where it belongs in the course
+fn exercise_readme(
+    exercise: &ExercisePackage,
+    number: &str,
+    (module, unit): (&ModuleExercises, &UnitExercises),
+    book_link: &str,
+) -> Result<String, RenderExercisesError> {
+    let description = exercise
+        .description
+        .read_to_string::<RenderExercisesError>()?
+        .replace("#[modmod:exercise_dir]", ".")
+        .replace("#[modmod:exercise_ref]", number)
+        .replace("#[modmod:qr(exercise)]", "")
+        // Sections of the description go below the title of the exercise
+        .replace("\n# ", "\n## ");
+    if let Some(placeholder) = unresolved_placeholder(&description) {
+        return Err(Report::new(RenderExercisesError).attach_printable(format!(
+            "Unknown placeholder '{placeholder}' in {}",
+            exercise.description.to_string_lossy()
+        )));
+    }
+    Ok(format!(
+        "# Exercise {number}: {name}\n\n\
+         This exercise is part of unit {module_i}.{unit_i} - {unit_name}, \
+         of module {module_i} - {module_name}. \
+         Its description is also on [the page of the unit in the exercise book]({book_link}).\n\n\
+         {description}\n",
+        name = exercise.name,
+        module_i = module.index,
+        module_name = module.name,
+        unit_i = unit.index,
+        unit_name = unit.name,
+        description = unescape_placeholders(description.trim())
+    ))
+}
+
+/// Ignores the build artifacts of a Cargo package or workspace,
+/// like the `.gitignore` of `cargo new`
+const GITIGNORE: &str = "/target\n";
+
+/// `rust-toolchain.toml` that pins an exercise to the toolchain `channel`
+fn rust_toolchain(channel: &str) -> String {
+    format!(
+        "[toolchain]\nchannel = {}\n",
+        toml_edit::Value::from(channel)
+    )
+}
+
+/// Manifest of a Cargo workspace with the exercise directories `members`, relative to the
+/// manifest, so that the exercises of a unit can be built at once and editors find them
+fn workspace_manifest(members: &[PathBuf]) -> String {
+    let mut manifest = String::from(
+        "# Generated by modmod, so that the exercises of this unit can be built at once\n\
+         [workspace]\n\
+         resolver = \"2\"\n\
+         members = [\n",
+    );
+    for member in members {
+        let member: Vec<_> = member.iter().map(|part| part.to_string_lossy()).collect();
+        manifest += &format!("    {},\n", toml_edit::Value::from(member.join("/")));
+    }
+    manifest.push_str("]\n");
+    manifest
+}
+
+/// Marks the start of a solution in an exercise file. The rest of the line after the marker,
+/// if any, replaces the solution in the output
+const SOLUTION_START: &str = "SOLUTION_START";
+/// Marks the end of a solution in an exercise file
+const SOLUTION_END: &str = "SOLUTION_END";
+
+/// The content of an exercise file without its solutions, unless `keep_solutions` is set.
+/// Returns `None` if the file can be copied as is.
+fn exercise_file_content(
+    path: &Path,
+    keep_solutions: bool,
+) -> Result<Option<String>, RenderExercisesError> {
+    // Files that aren't text can't contain solution markers
+    let content = (!keep_solutions)
+        .then(|| fs::read_to_string(path).ok())
+        .flatten();
+    let Some(content) = content else {
+        return Ok(None);
+    };
+    strip_solutions(&content).map_err(|problem| {
+        Report::new(RenderExercisesError).attach_printable(format!(
+            "{problem} in {path}",
+            path = path.to_string_lossy()
+        ))
+    })
+}
+
+/// Remove the solutions from the content of an exercise file,
+/// returning `None` if it has no solutions
+pub(crate) fn strip_solutions(content: &str) -> std::result::Result<Option<String>, String> {
+    if !content.contains(SOLUTION_START) && !content.contains(SOLUTION_END) {
+        return Ok(None);
+    }
+
+    let mut stripped = String::with_capacity(content.len());
+    // Line number of the start of the solution we're in, if any
+    let mut solution_start = None;
+    for (line, line_number) in content.split_inclusive('\n').zip(1..) {
+        if let Some((_, replacement)) = line.split_once(SOLUTION_START) {
+            if let Some(start) = solution_start {
+                return Err(format!(
+                    "Solution starting on line {line_number} is inside the solution starting on line {start}"
+                ));
+            }
+            solution_start = Some(line_number);
+            let replacement = replacement.trim();
+            if !replacement.is_empty() {
+                let indentation = &line[..line.len() - line.trim_start().len()];
+                // The replacement ends like the line of the marker, which may end with CRLF
+                let ending = &line[line.trim_end_matches(['\r', '\n']).len()..];
+                stripped += &format!("{indentation}{replacement}{ending}");
+            }
+        } else if line.contains(SOLUTION_END) {
+            if solution_start.take().is_none() {
+                return Err(format!(
+                    "Solution ending on line {line_number} was never started"
+                ));
+            }
+        } else if solution_start.is_none() {
+            stripped += line;
         }
+    }
+    match solution_start {
+        Some(start) => Err(format!("Solution starting on line {start} is never ended")),
+        None => Ok(Some(stripped)),
+    }
+}
+
+/// Directories of rendered exercises, relative to the output directory
+#[derive(Debug, Default)]
+pub struct ExercisePaths {
+    /// Module directories by module index
+    pub modules: HashMap<usize, PathBuf>,
+    /// Unit directories by module and unit index
+    pub units: HashMap<(usize, usize), PathBuf>,
+    /// ZIP archives of the exercises of units by module and unit index,
+    /// if they are created, see [RenderOptions::zip_exercises](crate::RenderOptions)
+    pub unit_archives: HashMap<(usize, usize), PathBuf>,
+    /// Directories of the grading tests of exercises like [ExercisePaths::exercises], for the
+    /// exercises that have grading tests if they are copied
+    pub grading: HashMap<(usize, usize, usize), PathBuf>,
+    /// Exercise package directories by module, unit and exercise index, where exercises are
+    /// numbered throughout their unit. Exercises of a topic that is part of more than one
+    /// unit are rendered into each of them.
+    pub exercises: HashMap<(usize, usize, usize), PathBuf>,
+}
+
+/// Collect the files in the exercise directory at `path` that match any of the `includes` globs,
+/// but none of the `excludes` globs
+pub fn included_files(
+    path: &Path,
+    includes: &[String],
+    excludes: &[String],
+) -> Result<Vec<PathBuf>, RenderExercisesError> {
+    let content = path.get_dir_content()?;
+
+    // Create globsets to match included and excluded files
+    let includes = globset(path, includes)?;
+    let excludes = globset(path, excludes)?;
+
+    Ok(content
+        .files
+        .into_iter()
+        .filter(|f| includes.is_match(f) && !excludes.is_match(f))
+        .map(PathBuf::from)
+        .collect())
+}
 
-        Ok(exercise_output_paths)
+/// Collect the files in the [GRADING_TESTS_DIR] of the exercise directory at `path`
+/// that match none of the `excludes` globs
+pub fn grading_files(
+    path: &Path,
+    excludes: &[String],
+) -> Result<Vec<PathBuf>, RenderExercisesError> {
+    if !path.join(GRADING_TESTS_DIR).is_dir() {
+        return Ok(vec![]);
     }
+    included_files(path, &[format!("{GRADING_TESTS_DIR}/**")], excludes)
+}
+
+fn globset(base_path: &Path, globs: &[String]) -> Result<globset::GlobSet, RenderExercisesError> {
+    let mut globset = globset::GlobSetBuilder::new();
+    for glob in globs {
+        globset.add(
+            globset::Glob::new(base_path.join(glob).to_str().unwrap())
+                .into_report()
+                .attach_printable_lazy(|| format!("Error parsing glob '{glob}'"))
+                .change_context(RenderExercisesError)?,
+        );
+    }
+    Ok(globset.build().unwrap())
 }
 
 #[derive(Debug)]
@@ -125,7 +737,11 @@ pub struct ExercisePackage<'track> {
     index: usize,
     name: &'track str,
     path: &'track Path,
+    description: &'track Path,
+    kind: ExerciseKind,
     includes: &'track [String],
+    excludes: &'track [String],
+    allow: &'track [WarningCode],
 }
 
 pub struct ExerciseCollectionBuilder<'track> {
@@ -189,13 +805,17 @@ pub struct UnitExercisesBuilder<'track, 'c, 'm> {
 }
 
 impl<'track, 'c, 'm> UnitExercisesBuilder<'track, 'c, 'm> {
-    pub fn package(&mut self, name: &'track str, path: &'track Path, includes: &'track [String]) {
+    pub fn package(&mut self, exercise: &'track Exercise) {
         let index = self.unit_exercises.exercises.len() + 1;
         self.unit_exercises.exercises.push(ExercisePackage {
             index,
-            name,
-            path,
-            includes,
+            name: &exercise.name,
+            path: &exercise.path,
+            description: &exercise.description,
+            kind: exercise.kind,
+            includes: &exercise.includes,
+            excludes: &exercise.excludes,
+            allow: &exercise.allow,
         })
     }
 
@@ -207,3 +827,55 @@ impl<'track, 'c, 'm> UnitExercisesBuilder<'track, 'c, 'm> {
         self.module_builder
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solutions_are_replaced_with_the_rest_of_their_start_line() {
+        let content = "fn main() {\n    // SOLUTION_START todo!()\n    println!(\"42\");\n    // SOLUTION_END\n}\n";
+        assert_eq!(
+            strip_solutions(content).unwrap().unwrap(),
+            "fn main() {\n    todo!()\n}\n"
+        );
+        // Without a replacement, the solution is just left out
+        let content = "a\n# SOLUTION_START\nb\n# SOLUTION_END\nc";
+        assert_eq!(strip_solutions(content).unwrap().unwrap(), "a\nc");
+        assert_eq!(strip_solutions("no solutions\n").unwrap(), None);
+    }
+
+    #[test]
+    fn solutions_in_files_with_crlf() {
+        let content =
+            "fn f() {\r\n\t// SOLUTION_START unimplemented!()\r\n\t1\r\n\t// SOLUTION_END\r\n}\r\n";
+        assert_eq!(
+            strip_solutions(content).unwrap().unwrap(),
+            "fn f() {\r\n\tunimplemented!()\r\n}\r\n"
+        );
+    }
+
+    #[test]
+    fn markers_that_dont_match() {
+        for (content, error) in [
+            (
+                "// SOLUTION_START\n// SOLUTION_START\n// SOLUTION_END\n",
+                "Solution starting on line 2 is inside the solution starting on line 1",
+            ),
+            (
+                "a\n// SOLUTION_START\nb\n",
+                "Solution starting on line 2 is never ended",
+            ),
+            (
+                "a\n// SOLUTION_END\n",
+                "Solution ending on line 2 was never started",
+            ),
+            (
+                "// SOLUTION_START\n// SOLUTION_END\n// SOLUTION_END\n",
+                "Solution ending on line 3 was never started",
+            ),
+        ] {
+            assert_eq!(strip_solutions(content).unwrap_err(), error, "{content}");
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! The glossary of the book: the terms that the topics of a track introduce, on a page of
+//! their own that pages of the book can link to
+
+use std::{collections::HashSet, fmt::Write, ops::Range};
+
+use crate::GlossaryEntry;
+
+/// Name of the glossary page in the book source directory
+pub(crate) const GLOSSARY_FILE: &str = "glossary.md";
+
+/// The `entries` without the ones whose term came before, ignoring case, sorted by term
+pub(crate) fn glossary<'e>(
+    entries: impl IntoIterator<Item = &'e GlossaryEntry>,
+) -> Vec<&'e GlossaryEntry> {
+    let mut glossary: Vec<&GlossaryEntry> = vec![];
+    for entry in entries {
+        let key = entry.term.trim().to_lowercase();
+        if !glossary.iter().any(|e| e.term.trim().to_lowercase() == key) {
+            glossary.push(entry);
+        }
+    }
+    glossary.sort_by_key(|entry| entry.term.trim().to_lowercase());
+    glossary
+}
+
+/// The markdown of the glossary page, with an entry per term under a heading of its own
+pub(crate) fn glossary_page(entries: &[&GlossaryEntry]) -> String {
+    let mut page = String::from("# Glossary\n");
+    for entry in entries {
+        write!(
+            page,
+            "\n## {}\n\n{}\n",
+            entry.term.trim(),
+            entry.definition.trim()
+        )
+        .unwrap();
+    }
+    page
+}
+
+/// The id that mdBook gives the heading of `term` on the glossary page
+pub(crate) fn term_anchor(term: &str) -> String {
+    term.trim()
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_alphanumeric() || c == '_' || c == '-' => Some(c.to_ascii_lowercase()),
+            c if c.is_whitespace() => Some('-'),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `content` with the first occurrence of every term of `entries` linked to its entry on
+/// the glossary page, apart from the terms in `linked`, which are those linked before on the
+/// same page. Terms match as whole words, ignoring case, and are not linked in code,
+/// headings, links and HTML tags.
+pub(crate) fn link_terms(
+    content: &str,
+    entries: &[&GlossaryEntry],
+    linked: &mut HashSet<String>,
+) -> String {
+    // Longer terms go first, so that a term inside another one doesn't take its place
+    let mut entries: Vec<&GlossaryEntry> = entries.to_vec();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.term.trim().len()));
+
+    let mut linked_content = String::with_capacity(content.len());
+    let mut in_code = false;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+        }
+        if in_code || trimmed.starts_with('#') || trimmed.starts_with("```") {
+            linked_content.push_str(line);
+            continue;
+        }
+        let mut line = line.to_string();
+        for entry in entries.iter() {
+            let term = entry.term.trim();
+            let key = term.to_lowercase();
+            if term.is_empty() || linked.contains(&key) {
+                continue;
+            }
+            if let Some(found) = find_term(&line, term) {
+                let link = format!(
+                    "[{}]({GLOSSARY_FILE}#{})",
+                    &line[found.clone()],
+                    term_anchor(term)
+                );
+                line.replace_range(found, &link);
+                linked.insert(key);
+            }
+        }
+        linked_content.push_str(&line);
+    }
+    linked_content
+}
+
+/// Where `term` first occurs in `line` as a whole word outside of inline code, links and
+/// HTML tags
+fn find_term(line: &str, term: &str) -> Option<Range<usize>> {
+    let protected = protected_ranges(line);
+    let haystack = line.to_ascii_lowercase();
+    let needle = term.to_ascii_lowercase();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    haystack
+        .match_indices(&needle)
+        .map(|(start, _)| start..start + needle.len())
+        .find(|found| {
+            let before = line[..found.start].chars().next_back();
+            let after = line[found.end..].chars().next();
+            !before.is_some_and(is_word)
+                && !after.is_some_and(is_word)
+                && !protected
+                    .iter()
+                    .any(|p| p.start < found.end && found.start < p.end)
+        })
+}
+
+/// The parts of `line` that are inline code, links or HTML tags
+fn protected_ranges(line: &str) -> Vec<Range<usize>> {
+    let bytes = line.as_bytes();
+    let mut ranges = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let end = match bytes[i] {
+            b'`' => {
+                let ticks = bytes[i..].iter().take_while(|&&b| b == b'`').count();
+                let fence = &line[i..i + ticks];
+                line[i + ticks..].find(fence).map(|j| i + ticks + j + ticks)
+            }
+            b'[' => line[i..].find(']').map(|j| {
+                let end = i + j + 1;
+                // Along with the target of the link, if there is one
+                match bytes.get(end) {
+                    Some(b'(') => line[end..].find(')').map_or(end, |k| end + k + 1),
+                    Some(b'[') => line[end..].find(']').map_or(end, |k| end + k + 1),
+                    _ => end,
+                }
+            }),
+            b'<' => line[i..].find('>').map(|j| i + j + 1),
+            _ => None,
+        };
+        match end {
+            Some(end) => {
+                ranges.push(i..end);
+                i = end;
+            }
+            None => i += 1,
+        }
+    }
+    ranges
+}
@@ -0,0 +1,179 @@
+//! Handouts of units: the slides of a unit as one document to read instead of present, for
+//! students to study from
+
+use std::fmt::Write;
+
+use crate::stats::split_slides;
+
+pub(crate) struct Handout<'a> {
+    pub title: &'a str,
+    /// Line under the title, naming the module of the unit
+    pub subtitle: String,
+    /// The slides of the topics of the unit
+    pub content: &'a str,
+    pub objectives: &'a [&'a str],
+    pub summary: &'a [&'a str],
+    /// The further reading of the topics, as a list per topic under a `###` heading
+    pub further_reading: &'a str,
+}
+
+impl Handout<'_> {
+    /// The markdown of the handout. Slide separators, the settings of slides, notes and
+    /// presentation-only markup like `<v-click>` are left out, headings are moved under the
+    /// title of the handout, and a heading that repeats the one before it on the next slide
+    /// is left out. The objectives, summary and further reading come after the slides.
+    pub fn render(&self) -> String {
+        let text: Vec<&str> = split_slides(self.content)
+            .into_iter()
+            .flat_map(|slide| slide.into_iter().chain([""]))
+            .collect();
+        let mut handout = format!("# {}\n\n{}\n\n", self.title, self.subtitle);
+        handout.push_str(&normalize_headings(&strip_presentation(&text)));
+        if !self.objectives.is_empty() {
+            handout.push_str("\n## Objectives\n\n");
+            self.objectives
+                .iter()
+                .for_each(|objective| writeln!(handout, "- {objective}").unwrap());
+        }
+        if !self.summary.is_empty() {
+            handout.push_str("\n## Summary\n\n");
+            self.summary
+                .iter()
+                .for_each(|item| writeln!(handout, "- {item}").unwrap());
+        }
+        if !self.further_reading.is_empty() {
+            write!(
+                handout,
+                "\n## Further reading\n\n{}\n",
+                self.further_reading
+            )
+            .unwrap();
+        }
+        handout
+    }
+}
+
+/// The `lines` without what's only meaningful when presenting: HTML comments, which are
+/// notes, tags of Slidev and Vue components like `<v-click>`, templates and layout `<div>`s
+/// on lines of their own, slot markers like `::right::`, styles, and line highlights of code
+/// blocks. Runs of empty lines are merged.
+fn strip_presentation(lines: &[&str]) -> Vec<String> {
+    let mut stripped: Vec<String> = vec![];
+    let mut in_code = false;
+    let mut in_comment = false;
+    let mut in_style = false;
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            let fence = if in_code {
+                line.to_string()
+            } else {
+                // Like ```rust {2|3-4}
+                line.split('{').next().unwrap().trim_end().to_string()
+            };
+            in_code = !in_code;
+            stripped.push(fence);
+            continue;
+        }
+        if in_code {
+            stripped.push(line.to_string());
+            continue;
+        }
+        if in_comment {
+            in_comment = !trimmed.ends_with("-->");
+            continue;
+        }
+        let line = &without_comments(line);
+        let trimmed = line.trim();
+        if trimmed.starts_with("<!--") {
+            in_comment = true;
+            continue;
+        }
+        if in_style || trimmed.starts_with("<style") {
+            in_style = !trimmed.ends_with("</style>");
+            continue;
+        }
+        let is_component = trimmed
+            .strip_prefix("</")
+            .or_else(|| trimmed.strip_prefix('<'))
+            .is_some_and(|tag| {
+                let is_layout = tag.starts_with(char::is_uppercase)
+                    || ["v-", "div", "template"]
+                        .iter()
+                        .any(|name| tag.starts_with(name));
+                is_layout && tag.ends_with('>')
+            });
+        let is_slot = trimmed.len() > 4 && trimmed.starts_with("::") && trimmed.ends_with("::");
+        if is_component || is_slot {
+            continue;
+        }
+        let is_empty = trimmed.is_empty();
+        if !(is_empty && stripped.last().is_none_or(|l| l.is_empty())) {
+            stripped.push(if is_empty {
+                String::new()
+            } else {
+                line.to_string()
+            });
+        }
+    }
+    stripped
+}
+
+/// `line` without the HTML comments that start and end on it
+fn without_comments(line: &str) -> String {
+    let mut line = line.to_string();
+    let mut removed = false;
+    while let Some(start) = line.find("<!--") {
+        let Some(end) = line[start..].find("-->").map(|i| start + i + 3) else {
+            break;
+        };
+        line.replace_range(start..end, "");
+        removed = true;
+    }
+    if removed {
+        line.truncate(line.trim_end().len());
+    }
+    line
+}
+
+/// The `lines` with their headings moved to level 2 and below, the ones that repeat the
+/// heading before them left out
+fn normalize_headings(lines: &[String]) -> String {
+    let level = |line: &str| {
+        let hashes = line.len() - line.trim_start_matches('#').len();
+        (hashes > 0 && line[hashes..].starts_with(' ')).then_some(hashes)
+    };
+    let mut in_code = false;
+    let mut headings = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_code = !in_code;
+        } else if let Some(level) = level(line).filter(|_| !in_code) {
+            headings.push((i, level));
+        }
+    }
+    let top = headings.iter().map(|(_, level)| *level).min().unwrap_or(2);
+
+    let mut normalized = String::new();
+    let mut previous: Option<&str> = None;
+    let mut headings = headings.into_iter().peekable();
+    let mut skip_empty = false;
+    for (i, line) in lines.iter().enumerate() {
+        if headings.peek().is_some_and(|(h, _)| *h == i) {
+            let (_, level) = headings.next().unwrap();
+            let text = line[level..].trim();
+            if previous == Some(text) {
+                skip_empty = true;
+                continue;
+            }
+            previous = Some(text);
+            let level = (level + 2 - top).min(6);
+            writeln!(normalized, "{} {text}", "#".repeat(level)).unwrap();
+        } else if !(skip_empty && line.is_empty()) {
+            normalized.push_str(line);
+            normalized.push('\n');
+        }
+        skip_empty = false;
+    }
+    normalized.trim_end().to_string() + "\n"
+}
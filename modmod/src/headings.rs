@@ -0,0 +1,35 @@
+//! Moving the headings of markdown to other levels, so that topics and exercise descriptions
+//! that start at different levels have a consistent outline when they're put together
+
+/// `content` with its headings moved so that the topmost ones are at `level`, and the others
+/// stay as far below them as they were. Headings in code blocks are left alone.
+pub(crate) fn normalize_headings(content: &str, level: usize) -> String {
+    let heading_level = |line: &str| {
+        let hashes = line.len() - line.trim_start_matches('#').len();
+        (hashes > 0 && line[hashes..].starts_with(' ')).then_some(hashes)
+    };
+    let mut in_code = false;
+    let mut levels = vec![];
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_code = !in_code;
+        }
+        levels.push(heading_level(line).filter(|_| !in_code));
+    }
+    let Some(top) = levels.iter().flatten().min() else {
+        return content.to_string();
+    };
+
+    let mut normalized = String::with_capacity(content.len());
+    for (line, heading) in content.split_inclusive('\n').zip(levels.iter()) {
+        match heading {
+            Some(heading) => {
+                let new_level = (heading + level).saturating_sub(*top).clamp(1, 6);
+                normalized.push_str(&"#".repeat(new_level));
+                normalized.push_str(&line[*heading..]);
+            }
+            None => normalized.push_str(line),
+        }
+    }
+    normalized
+}
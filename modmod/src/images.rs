@@ -0,0 +1,148 @@
+//! Downsizing and recompressing the images in the slides and the book with ImageMagick,
+//! so that raw screenshots don't make the rendered output hundreds of megabytes
+
+use std::{
+    ffi::OsString,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Output as ProcessOutput, Stdio},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use crate::{output::Output, parallel::parallel_map};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct OptimizeImagesError;
+
+impl fmt::Display for OptimizeImagesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to optimize images")
+    }
+}
+
+impl error_stack::Context for OptimizeImagesError {}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImageOptions {
+    /// Images that are wider are scaled down to this width, in pixels
+    pub max_width: u32,
+    /// Quality that JPEG images are recompressed with, from 1 to 100. For PNG images,
+    /// ImageMagick takes it as the compression level and filter.
+    pub quality: u8,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            max_width: 1600,
+            quality: 85,
+        }
+    }
+}
+
+/// Extensions of the images that are optimized
+const EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+/// Optimize the PNG and JPEG images that were written into the slides and the book during
+/// this render, in place. An optimized image only replaces the original if it's smaller,
+/// so that images that were optimized already don't grow. Images that were left in place
+/// by an incremental render aren't recompressed again.
+pub(crate) fn optimize_images(
+    output: &Output,
+    options: ImageOptions,
+) -> Result<(), OptimizeImagesError> {
+    let dirs = [output.root().join("slides"), output.root().join("book")];
+    let images: Vec<PathBuf> = output
+        .written_files()
+        .into_iter()
+        .filter(|file| dirs.iter().any(|dir| file.starts_with(dir)))
+        .filter(|file| {
+            file.extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .is_some_and(|ext| EXTENSIONS.contains(&ext.as_str()))
+        })
+        .collect();
+
+    output.check_cancelled::<OptimizeImagesError>()?;
+    let indexed: Vec<_> = images.iter().enumerate().collect();
+    parallel_map(&indexed, |(i, image)| optimize_image(image, *i, options))
+        .into_iter()
+        .collect()
+}
+
+fn optimize_image(
+    image: &Path,
+    index: usize,
+    options: ImageOptions,
+) -> Result<(), OptimizeImagesError> {
+    let extension = image.extension().unwrap().to_string_lossy();
+    // ImageMagick picks the format of the optimized image by its extension
+    let optimized = std::env::temp_dir().join(format!(
+        "modmod-image-{}-{index}.{extension}",
+        std::process::id()
+    ));
+    let args: [OsString; 7] = [
+        image.into(),
+        "-resize".into(),
+        // Only ever shrink images
+        format!("{}x>", options.max_width).into(),
+        "-strip".into(),
+        "-quality".into(),
+        options.quality.to_string().into(),
+        optimized.clone().into(),
+    ];
+    let result = run_image_magick(&args)
+        .attach_printable_lazy(|| {
+            format!("Error optimizing image at path {}", image.to_string_lossy())
+        })
+        .and_then(|result| match result.status.success() {
+            true => replace_if_smaller(image, &optimized),
+            false => Err(Report::new(OptimizeImagesError).attach_printable(format!(
+                "ImageMagick failed on image at path {}: {}",
+                image.to_string_lossy(),
+                String::from_utf8_lossy(&result.stderr).trim()
+            ))),
+        });
+    let _ = fs::remove_file(&optimized);
+    result
+}
+
+/// Run ImageMagick 7, or the `convert` command of ImageMagick 6 if that's not installed
+fn run_image_magick(args: &[OsString]) -> Result<ProcessOutput, OptimizeImagesError> {
+    let run = |program: &str| {
+        Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+    };
+    match run("magick") {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => run("convert"),
+        result => result,
+    }
+    .into_report()
+    .attach_printable("Unable to run ImageMagick, which is needed to optimize images")
+    .change_context(OptimizeImagesError)
+}
+
+fn replace_if_smaller(image: &Path, optimized: &Path) -> Result<(), OptimizeImagesError> {
+    let size = |path: &Path| {
+        fs::metadata(path)
+            .map(|metadata| metadata.len())
+            .into_report()
+            .attach_printable_lazy(|| {
+                format!("Error reading file at path {}", path.to_string_lossy())
+            })
+            .change_context(OptimizeImagesError)
+    };
+    if size(optimized)? < size(image)? {
+        fs::copy(optimized, image)
+            .into_report()
+            .attach_printable_lazy(|| {
+                format!("Error writing file at path {}", image.to_string_lossy())
+            })
+            .change_context(OptimizeImagesError)?;
+    }
+    Ok(())
+}
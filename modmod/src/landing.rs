@@ -0,0 +1,101 @@
+//! The landing page of a render, `index.html` in the output directory, which links every
+//! module and unit to its page in the book, its slide deck and its exercises, so that the
+//! output has an entry point rather than only a tree of folders
+
+use std::fmt::Write;
+
+use crate::{
+    search_index::{SearchEntry, SearchEntryKind, SearchIndex},
+    Track,
+};
+
+/// Name of the landing page in the output directory
+pub const LANDING_PAGE_FILE: &str = "index.html";
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; line-height: 1.5; }
+h2 { margin-top: 2rem; }
+li { margin: 0.25rem 0; }
+.links a { margin-left: 0.75rem; }";
+
+/// The landing page of `track`, linking to where the `index` has its items published
+pub(crate) fn landing_page(track: &Track, index: &SearchIndex) -> String {
+    let mut body = String::new();
+    if let Some(description) = track
+        .book
+        .description
+        .as_ref()
+        .or(track.description.as_ref())
+    {
+        writeln!(body, "<p>{}</p>", escape(description.trim())).unwrap();
+    }
+    // The book starts at its first page, which mdBook builds into an index of its own
+    let book = index
+        .entries
+        .iter()
+        .find_map(|entry| entry.urls.book.as_deref())
+        .and_then(|page| Some(format!("{}/index.html", page.rsplit_once('/')?.0)));
+    if let Some(book) = book {
+        writeln!(
+            body,
+            "<p><a href=\"{}\">Exercise book</a></p>",
+            escape(&book)
+        )
+        .unwrap();
+    }
+
+    let mut in_module = false;
+    for entry in index.entries.iter() {
+        match entry.kind {
+            SearchEntryKind::Module => {
+                if in_module {
+                    body.push_str("</ul>\n");
+                }
+                in_module = true;
+                let title = escape(&entry.title);
+                writeln!(body, "<h2>Module {}: {title}</h2>", entry.number).unwrap();
+                let links = links(entry);
+                if !links.is_empty() {
+                    writeln!(body, "<p class=\"links\">{links}</p>").unwrap();
+                }
+                body.push_str("<ul>\n");
+            }
+            SearchEntryKind::Unit => writeln!(
+                body,
+                "<li>Unit {} - {}<span class=\"links\">{}</span></li>",
+                entry.number,
+                escape(&entry.title),
+                links(entry)
+            )
+            .unwrap(),
+            SearchEntryKind::Topic | SearchEntryKind::Exercise => {}
+        }
+    }
+    if in_module {
+        body.push_str("</ul>\n");
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"{language}\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{title}</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n<h1>{title}</h1>\n{body}</body>\n</html>\n",
+        language = escape(track.language.as_deref().unwrap_or("en")),
+        title = escape(&track.name),
+    )
+}
+
+/// Links to the page in the book, the slide deck and the exercises of `entry`
+fn links(entry: &SearchEntry) -> String {
+    [
+        ("Book", &entry.urls.book),
+        ("Slides", &entry.urls.slides),
+        ("Exercises", &entry.urls.exercises),
+    ]
+    .into_iter()
+    .filter_map(|(name, url)| Some(format!("<a href=\"{}\">{name}</a>", escape(url.as_ref()?))))
+    .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,216 @@
+//! Templates for the paths that a track is rendered into
+
+use std::{
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+
+use error_stack::{Report, Result};
+
+use crate::{to_tag, Track};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct LayoutError;
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid output layout")
+    }
+}
+
+impl error_stack::Context for LayoutError {}
+
+/// Templates of the paths output is rendered into. Variables are written as `{name}`.
+///
+/// The `root` template is relative to the output directory, and can use `{track_slug}` and
+/// `{version}`. The directory templates of the exercises can use those, and
+/// `{module_number}` and `{module_slug}`, plus `{unit_number}` and `{unit_slug}` for units,
+/// plus `{exercise_number}` and `{exercise_slug}` for exercises.
+/// Directory names are lowercased, with whitespace replaced by dashes.
+#[derive(Debug, Clone)]
+pub struct OutputLayout {
+    pub root: String,
+    pub module_dir: String,
+    pub unit_dir: String,
+    pub exercise_dir: String,
+}
+
+impl Default for OutputLayout {
+    fn default() -> Self {
+        Self {
+            root: String::new(),
+            module_dir: "{module_number}-{module_slug}".to_string(),
+            unit_dir: "{unit_number}-{unit_slug}".to_string(),
+            exercise_dir: "{exercise_number}-{exercise_slug}".to_string(),
+        }
+    }
+}
+
+const TRACK_VARIABLES: [&str; 2] = ["track_slug", "version"];
+const MODULE_VARIABLES: [&str; 2] = ["module_number", "module_slug"];
+const UNIT_VARIABLES: [&str; 2] = ["unit_number", "unit_slug"];
+const EXERCISE_VARIABLES: [&str; 2] = ["exercise_number", "exercise_slug"];
+
+/// An [OutputLayout] for a specific track
+#[derive(Debug)]
+pub(crate) struct Layout<'a> {
+    templates: &'a OutputLayout,
+    track_slug: String,
+    version: Option<&'a str>,
+}
+
+impl<'a> Layout<'a> {
+    /// Check the templates for syntax errors and unknown variables
+    pub fn new(templates: &'a OutputLayout, track: &'a Track) -> Result<Self, LayoutError> {
+        let layout = Self {
+            templates,
+            track_slug: to_tag(&track.name),
+            version: track.version.as_deref(),
+        };
+
+        let module_variables = [&TRACK_VARIABLES[..], &MODULE_VARIABLES].concat();
+        let unit_variables = [&module_variables[..], &UNIT_VARIABLES].concat();
+        let exercise_variables = [&unit_variables[..], &EXERCISE_VARIABLES].concat();
+        for (kind, template, variables) in [
+            ("root", &templates.root, &TRACK_VARIABLES[..]),
+            ("module directory", &templates.module_dir, &module_variables),
+            ("unit directory", &templates.unit_dir, &unit_variables),
+            (
+                "exercise directory",
+                &templates.exercise_dir,
+                &exercise_variables,
+            ),
+        ] {
+            for variable in variables_in(template)? {
+                if !variables.contains(&variable) {
+                    return Err(Report::new(LayoutError).attach_printable(format!(
+                        "Unknown variable '{{{variable}}}' in {kind} template '{template}', available variables are {}",
+                        variables.iter().map(|v| format!("{{{v}}}")).collect::<Vec<_>>().join(", ")
+                    )));
+                }
+                if variable == "version" && layout.version.is_none() {
+                    return Err(Report::new(LayoutError).attach_printable(format!(
+                        "The {kind} template '{template}' uses {{version}}, but the track definition has no version"
+                    )));
+                }
+            }
+        }
+
+        Ok(layout)
+    }
+
+    /// Path of the rendered track, relative to the output directory
+    pub fn root(&self) -> Result<PathBuf, LayoutError> {
+        self.expand(&self.templates.root, &[], false)
+    }
+
+    /// Directory of the exercises of a module, relative to the exercises directory
+    pub fn module_dir(&self, module: (usize, &str)) -> Result<PathBuf, LayoutError> {
+        self.expand(&self.templates.module_dir, &module_values(module), true)
+    }
+
+    /// Directory of the exercises of a unit, relative to the directory of the module
+    pub fn unit_dir(
+        &self,
+        module: (usize, &str),
+        unit: (usize, &str),
+    ) -> Result<PathBuf, LayoutError> {
+        let values = [module_values(module), unit_values(unit)].concat();
+        self.expand(&self.templates.unit_dir, &values, true)
+    }
+
+    /// Directory of an exercise, relative to the directory of the unit
+    pub fn exercise_dir(
+        &self,
+        module: (usize, &str),
+        unit: (usize, &str),
+        exercise: (usize, &str),
+    ) -> Result<PathBuf, LayoutError> {
+        let values = [
+            module_values(module),
+            unit_values(unit),
+            [
+                ("exercise_number", exercise.0.to_string()),
+                ("exercise_slug", exercise.1.to_string()),
+            ],
+        ]
+        .concat();
+        self.expand(&self.templates.exercise_dir, &values, true)
+    }
+
+    /// Fill in the variables of a template, and check that the result is a relative
+    /// path without empty segments that stays inside the directory it's relative to.
+    /// If `tag` is set, each segment is turned into a tag like other generated names.
+    fn expand(
+        &self,
+        template: &str,
+        values: &[(&str, String)],
+        tag: bool,
+    ) -> Result<PathBuf, LayoutError> {
+        let mut expanded = template.to_string();
+        expanded = expanded.replace("{track_slug}", &self.track_slug);
+        if let Some(version) = self.version {
+            expanded = expanded.replace("{version}", version);
+        }
+        for (variable, value) in values {
+            expanded = expanded.replace(&format!("{{{variable}}}"), value);
+        }
+
+        // The root template may be empty, to render into the output directory itself
+        if expanded.is_empty() && !tag {
+            return Ok(PathBuf::new());
+        }
+
+        let mut path = PathBuf::new();
+        for segment in expanded.split('/') {
+            let segment = if tag {
+                to_tag(segment)
+            } else {
+                segment.to_string()
+            };
+            let valid = !segment.trim().is_empty()
+                && matches!(
+                    Path::new(&segment).components().collect::<Vec<_>>()[..],
+                    [Component::Normal(_)]
+                );
+            if !valid {
+                return Err(Report::new(LayoutError).attach_printable(format!(
+                    "Template '{template}' expands to '{expanded}', which has an empty segment \
+                    or a segment that leaves the output directory"
+                )));
+            }
+            path.push(segment);
+        }
+        Ok(path)
+    }
+}
+
+fn module_values((index, name): (usize, &str)) -> [(&'static str, String); 2] {
+    [
+        ("module_number", index.to_string()),
+        ("module_slug", name.to_string()),
+    ]
+}
+
+fn unit_values((index, name): (usize, &str)) -> [(&'static str, String); 2] {
+    [
+        ("unit_number", index.to_string()),
+        ("unit_slug", name.to_string()),
+    ]
+}
+
+/// The names of the variables used in a template
+fn variables_in(template: &str) -> Result<Vec<&str>, LayoutError> {
+    let mut variables = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            return Err(Report::new(LayoutError)
+                .attach_printable(format!("Unclosed '{{' in template '{template}'")));
+        };
+        variables.push(&rest[start + 1..start + end]);
+        rest = &rest[start + end + 1..];
+    }
+    Ok(variables)
+}
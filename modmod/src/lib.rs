@@ -1,77 +1,856 @@
+mod accessibility;
+mod add;
+mod anchors;
+mod anki;
+mod archive;
+mod assets;
 mod book;
+mod bundle;
+mod cache;
+mod calendar;
+mod check;
+mod classroom;
+mod codes;
+mod conditional;
+mod crates;
+mod dependencies;
+mod diagnostic;
 mod exercises;
+mod glossary;
+mod handout;
+mod headings;
+mod images;
 mod io;
+mod landing;
+mod layout;
+mod links;
+mod listing;
 mod load;
+mod marp;
+mod mermaid;
+mod notes;
+mod orphans;
+mod outline;
+mod output;
+mod output_map;
+mod parallel;
+mod pptx;
+mod qr;
+mod quizzes;
+mod remote;
+mod report;
+mod repositories;
+mod revealjs;
+mod scaffold;
+mod schedule;
+mod schema;
+mod search_index;
+mod select;
+mod serve;
 mod slides;
+mod slidev;
+mod snippets;
+mod stats;
+mod syllabus;
+mod template;
+mod validate;
+mod vendor;
+mod verify;
+mod watch;
+
+pub use add::{AddError, AddOptions, Added};
+pub use anki::{AnkiCard, AnkiDeck};
+pub use bundle::BundleError;
+pub use cache::RENDER_CACHE_FILE;
+pub use calendar::{CalendarError, Cohort, Date, TimeOfDay, Weekday};
+pub use check::CheckReport;
+pub use classroom::{
+    ClassroomAssignment, ClassroomExercise, ClassroomManifest, CLASSROOM_MANIFEST_FILE,
+    CLASSROOM_SCHEMA_VERSION,
+};
+pub use codes::WarningCode;
+pub use exercises::{ExerciseGrouping, GRADING_TESTS_DIR};
+pub use images::{ImageOptions, OptimizeImagesError};
+pub use landing::LANDING_PAGE_FILE;
+pub use layout::OutputLayout;
+pub use links::{CheckLinksError, LinkCheckOptions};
+pub use listing::{LeftOut, ListedItem, ListedKind, Listing};
+pub use orphans::{OrphanReport, OrphanedFile, TopicOrphans, DEFAULT_ORPHAN_ALLOWLIST};
+pub use outline::{
+    Outline, OutlineExercise, OutlineModule, OutlineTopic, OutlineUnit, OUTLINE_SCHEMA_VERSION,
+};
+pub use output::{CancellationToken, Cancelled, PlannedOperation};
+pub use output_map::{
+    MappedExercise, MappedModule, MappedTopic, MappedUnit, OutputMap, OUTPUT_MAP_FILE,
+    OUTPUT_MAP_SCHEMA_VERSION,
+};
+pub use quizzes::{QuizQuestion, Quizzes, UnitQuizzes, QUIZZES_FILE, QUIZZES_SCHEMA_VERSION};
+pub use remote::LOCKFILE;
+pub use report::{RenderReport, Warning};
+pub use scaffold::{Scaffold, ScaffoldError};
+pub use schema::DefinitionKind;
+pub use search_index::{
+    SearchEntry, SearchEntryKind, SearchIndex, SearchUrls, SEARCH_INDEX_FILE,
+    SEARCH_INDEX_SCHEMA_VERSION,
+};
+pub use serve::{PreviewServer, ServeError};
+pub use slidev::{BuildSlidesError, SlidesFormat};
+pub use snippets::CheckSnippetsError;
+pub use stats::{Counts, ModuleStats, TrackStats, UnitStats};
+pub use syllabus::{syllabus_pdf, SyllabusError};
+pub use vendor::{VendorError, VendorReport, VendoredModule};
+pub use verify::{ExerciseLint, ExerciseVerification, VerifyOptions, VerifyOutcome};
+pub use watch::FileTimes;
 
 use self::{
     book::{Book, BookBuilder, ChapterBuilder, SectionBuilder},
-    load::{Load, TrackDef},
+    io::WriteExt,
+    load::{collect_all, Load, TopicCache, TrackDef},
 };
 use error_stack::{IntoReport, Report, Result, ResultExt};
 use exercises::{
-    ExerciseCollection, ExerciseCollectionBuilder, ModuleExercisesBuilder, UnitExercisesBuilder,
+    ExerciseCollection, ExerciseCollectionBuilder, ModuleExercisesBuilder, PackageOptions,
+    UnitExercisesBuilder,
 };
-use io::PathExt;
+use landing::landing_page;
+use layout::Layout;
 use load::Indexed;
+use mermaid::MermaidDiagrams;
+use output::Output;
+use qr::QrCodes;
+use schedule::Schedule;
+use select::{Edition, Selection};
+use serde::{Deserialize, Serialize};
 use slides::{SlideDeckBuilder, SlidesPackage, SlidesPackageBuilder};
 use std::{
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Display},
     fs,
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
+use verify::Verifier;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Track {
     pub name: String,
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    /// Language of the course content, as used in HTML `lang` attributes
+    pub language: Option<String>,
+    /// Path to the track definition file
+    pub path: PathBuf,
     pub modules: Vec<Indexed<Module>>,
+    /// Reference pages that are rendered into the book after all modules
+    pub appendices: Vec<Appendix>,
+    /// Include sets that exercises can refer to by name
+    pub include_profiles: load::IncludeProfiles,
+    /// Warn about objectives and summary items of a unit of which one
+    /// starts with the other, on top of exact duplicates
+    pub warn_near_duplicates: bool,
+    pub book: BookConfig,
+    pub slides: SlidesConfig,
+    pub version: Option<String>,
+    /// URL under which the output directory is published
+    pub base_url: Option<String>,
+    /// Toolchain that Cargo exercises are pinned to with a `rust-toolchain.toml`,
+    /// like `1.75` or `stable`, unless they include one of their own
+    pub rust_toolchain: Option<String>,
+    /// Codes of warnings that are reported as notes
+    pub allow: Vec<WarningCode>,
+    /// Paths of the definition files or directories of topics that are left out of renders
+    pub excluded_topics: Vec<PathBuf>,
+    /// If not empty, only topics with at least one of these tags are rendered
+    pub include_tags: Vec<String>,
+    /// Topics with any of these tags are left out of renders
+    pub exclude_tags: Vec<String>,
+    /// Paths to the definitions of the tracks this track `extends`, from the nearest to the furthest
+    pub bases: Vec<PathBuf>,
+    /// Values that slides templates and topics refer to with `#[modmod:var(name)]`, by name
+    pub variables: BTreeMap<String, String>,
+    /// Move the headings of every topic and exercise description to the same levels when
+    /// they're put together into the slides and the book, unless a unit decides otherwise
+    pub normalize_headings: bool,
+}
+
+/// Settings of the exercise book, from the `[book]` table of the track definition
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BookConfig {
+    /// Description of the book, if it differs from that of the track
+    pub description: Option<String>,
+    /// Give module and unit pages an HTML meta description, based on their objectives
+    pub page_descriptions: bool,
+    /// Link the first occurrence of every glossary term on a page of the book to its entry
+    /// in the glossary
+    pub link_glossary_terms: bool,
+    /// Make the knowledge checks at the end of units interactive with the mdbook-quiz
+    /// preprocessor, instead of listing the questions with their answers folded away
+    pub mdbook_quiz: bool,
+    pub search: BookSearchConfig,
+    /// How long a day of teaching is, to divide the units over days in the schedule page
+    pub minutes_per_day: Option<u32>,
+    /// Theme that readers see until they pick one, `light` if not set
+    pub default_theme: Option<BookTheme>,
+    /// Theme that readers who prefer a dark color scheme see until they pick one
+    pub preferred_dark_theme: Option<BookTheme>,
+    /// Level up to which the table of contents is expanded, folding the rest away. The table
+    /// of contents isn't folded if not set
+    pub fold_level: Option<u8>,
+    /// URL of the repository of the course, linked to from every page
+    pub git_repository_url: Option<String>,
+    /// URL to suggest edits to a page at, in which `{path}` is replaced by the path of the page
+    pub edit_url_template: Option<String>,
+    /// Directory that mdBook builds the book into, relative to the book, `target` if not set
+    pub build_dir: Option<String>,
+    /// The mdBook preprocessors that the book is built with, like `admonish`, by name
+    pub preprocessors: BTreeMap<String, BookPreprocessor>,
+}
+
+/// An mdBook preprocessor, from a `[book.preprocessors.<name>]` table of the track definition
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BookPreprocessor {
+    /// Stylesheets and scripts that the preprocessor needs in the book, like the CSS file of
+    /// `mdbook-admonish`. They're copied into the book and added to every page.
+    #[serde(default)]
+    pub assets: Vec<PathBuf>,
+    /// The settings of the preprocessor, which are passed to it as they are
+    #[serde(flatten)]
+    pub settings: toml::Table,
+}
+
+impl BookConfig {
+    /// The directory that mdBook builds the book into, relative to the book
+    pub fn build_dir(&self) -> &str {
+        let build_dir = self.build_dir.as_deref().unwrap_or("target");
+        build_dir.trim_start_matches("./").trim_end_matches('/')
+    }
+}
+
+/// The themes that mdBook has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookTheme {
+    Light,
+    Rust,
+    Coal,
+    Navy,
+    Ayu,
+}
+
+impl BookTheme {
+    pub const ALL: [BookTheme; 5] = [
+        BookTheme::Light,
+        BookTheme::Rust,
+        BookTheme::Coal,
+        BookTheme::Navy,
+        BookTheme::Ayu,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BookTheme::Light => "light",
+            BookTheme::Rust => "rust",
+            BookTheme::Coal => "coal",
+            BookTheme::Navy => "navy",
+            BookTheme::Ayu => "ayu",
+        }
+    }
+}
+
+/// Settings of the slide decks, from the `[slides]` table of the track definition or of a
+/// unit. They are set in the headmatter of every slide deck, replacing those of its template,
+/// except for the backend, which decides what the decks are rendered for, and the topic
+/// separator and title, which decide how the slides of topics are put together.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SlidesConfig {
+    pub theme: Option<String>,
+    pub highlighter: Option<String>,
+    /// Title of the slide deck, which can use the values that slides templates can
+    pub title: Option<String>,
+    /// Background image of the cover slide
+    pub background: Option<String>,
+    /// What the slide decks are rendered for, Slidev if not set
+    pub backend: Option<SlidesBackend>,
+    /// What comes between the slides of consecutive topics, a new slide with `---` if not set
+    pub topic_separator: Option<String>,
+    /// Heading of a slide in front of every topic, which can use `topic` with its `name` and
+    /// `index`, `module`, `unit` and `variables`, like `Topic: {{ topic.name }}`
+    pub topic_title: Option<String>,
+}
+
+/// The presentation framework that slide decks are rendered for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SlidesBackend {
+    /// Markdown decks that are shown with Slidev, from a Node package
+    #[default]
+    Slidev,
+    /// An HTML page per deck that loads reveal.js from a CDN, which needs no Node tooling
+    Revealjs,
+    /// Markdown decks for Marp, which marp-cli converts into PDF, PowerPoint or HTML
+    Marp,
+    /// A PowerPoint presentation per deck, with its text and code in text boxes
+    Pptx,
+}
+
+impl SlidesBackend {
+    pub const ALL: [SlidesBackend; 4] = [
+        SlidesBackend::Slidev,
+        SlidesBackend::Revealjs,
+        SlidesBackend::Marp,
+        SlidesBackend::Pptx,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SlidesBackend::Slidev => "slidev",
+            SlidesBackend::Revealjs => "revealjs",
+            SlidesBackend::Marp => "marp",
+            SlidesBackend::Pptx => "pptx",
+        }
+    }
+
+    /// The extension of the files of the slide decks
+    pub fn extension(self) -> &'static str {
+        match self {
+            SlidesBackend::Slidev | SlidesBackend::Marp => "md",
+            SlidesBackend::Revealjs => "html",
+            SlidesBackend::Pptx => "pptx",
+        }
+    }
+}
+
+impl SlidesConfig {
+    /// The settings of `self`, with the ones it doesn't set taken from `base`
+    pub fn or(&self, base: &SlidesConfig) -> SlidesConfig {
+        SlidesConfig {
+            theme: self.theme.clone().or_else(|| base.theme.clone()),
+            highlighter: self
+                .highlighter
+                .clone()
+                .or_else(|| base.highlighter.clone()),
+            title: self.title.clone().or_else(|| base.title.clone()),
+            background: self.background.clone().or_else(|| base.background.clone()),
+            backend: self.backend.or(base.backend),
+            topic_separator: self
+                .topic_separator
+                .clone()
+                .or_else(|| base.topic_separator.clone()),
+            topic_title: self
+                .topic_title
+                .clone()
+                .or_else(|| base.topic_title.clone()),
+        }
+    }
+
+    /// The settings that are set, by their names in the headmatter
+    fn headmatter(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("theme", &self.theme),
+            ("highlighter", &self.highlighter),
+            ("title", &self.title),
+            ("background", &self.background),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value.as_deref()?)))
+        .collect()
+    }
+}
+
+/// Settings of the search index of the exercise book
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct BookSearchConfig {
+    pub boost_title: Option<u8>,
+    pub boost_hierarchy: Option<u8>,
+    pub boost_paragraph: Option<u8>,
+    /// Globs of pages, relative to the book source directory, that are left out of the search index
+    pub exclude: Vec<String>,
+}
+
+/// Settings of loading a track
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoadOptions {
+    /// Don't update the lockfile of the track, but fail if it doesn't pin every git source
+    pub locked: bool,
+    /// Fail if any definition of the track has fields that modmod doesn't know,
+    /// whether or not the track sets `deny_unknown_fields`
+    pub deny_unknown_fields: bool,
 }
 
 impl Track {
     pub fn load_toml_def(path: impl AsRef<Path>) -> Result<Self, LoadTrackError> {
-        let def = TrackDef::load(path.as_ref(), None).change_context(LoadTrackError)?;
-        def.resolve().change_context(LoadTrackError)
+        Self::load_toml_def_with(path, LoadOptions::default())
+    }
+
+    pub fn load_toml_def_with(
+        path: impl AsRef<Path>,
+        options: LoadOptions,
+    ) -> Result<Self, LoadTrackError> {
+        Self::load_toml_defs([path], options).map(|mut tracks| tracks.remove(0))
+    }
+
+    /// Load several tracks, of which topics that are part of more than one track
+    /// are only loaded once
+    pub fn load_toml_defs(
+        paths: impl IntoIterator<Item = impl AsRef<Path>>,
+        options: LoadOptions,
+    ) -> Result<Vec<Self>, LoadTrackError> {
+        let topics = TopicCache::default();
+        collect_all(paths.into_iter().map(|path| {
+            TrackDef::load_checked(path.as_ref(), None, options.deny_unknown_fields)
+                .change_context(LoadTrackError)?
+                .resolve(&topics, options.locked)
+                .change_context(LoadTrackError)
+        }))
+    }
+
+    /// Render several tracks under `output_dir`, each into a directory named after its
+    /// slug. Instructor editions are rendered into directories with the same names.
+    pub fn render_all(
+        tracks: &[Track],
+        output_dir: impl AsRef<Path>,
+        options: &RenderOptions,
+    ) -> Result<Vec<RenderReport>, LoadTrackError> {
+        let mut slugs = BTreeSet::new();
+        for track in tracks.iter() {
+            let slug = to_tag(&track.name);
+            if !slugs.insert(slug.clone()) {
+                return Err(Report::new(LoadTrackError).attach_printable(format!(
+                    "More than one track has the slug '{slug}', so they would all be rendered into {dir}",
+                    dir = output_dir.as_ref().join(&slug).to_string_lossy()
+                )));
+            }
+        }
+        tracks
+            .iter()
+            .map(|track| {
+                let slug = to_tag(&track.name);
+                let options = RenderOptions {
+                    instructor_output: options
+                        .instructor_output
+                        .as_ref()
+                        .map(|dir| dir.join(&slug)),
+                    ..options.clone()
+                };
+                track.render(output_dir.as_ref().join(&slug), &options)
+            })
+            .collect()
+    }
+
+    /// Machine-readable outline of the track, see [Outline]
+    pub fn outline(&self) -> Outline {
+        Outline::new(self)
+    }
+
+    /// The exercises of the track by module, unit and exercise index, like
+    /// [ExercisePaths::exercises](exercises::ExercisePaths::exercises). Exercises of a topic
+    /// that is part of more than one unit are there once for every unit.
+    pub(crate) fn exercises_by_index(
+        &self,
+    ) -> impl Iterator<Item = ((usize, usize, usize), &Exercise)> {
+        self.modules.iter().flat_map(|module| {
+            module.data.units.iter().flat_map(move |unit| {
+                unit.data
+                    .topics
+                    .iter()
+                    .flat_map(|topic| &topic.data.exercises)
+                    .zip(1..)
+                    .map(move |(exercise, e)| ((module.index, unit.index, e), &exercise.data))
+            })
+        })
+    }
+
+    /// All source files that are read when rendering this track: definition files,
+    /// templates, topic content, images, exercise descriptions, and included exercise files.
+    /// The paths are absolute, sorted, and deduplicated.
+    pub fn source_files(&self) -> Result<Vec<PathBuf>, LoadTrackError> {
+        let mut files = BTreeSet::new();
+        files.insert(self.path.clone());
+        files.extend(self.bases.iter().cloned());
+        for module in self.modules.iter() {
+            module.data.collect_source_files(&mut files)?;
+        }
+        for appendix in self.appendices.iter() {
+            files.insert(appendix.content.clone());
+            files.extend(appendix.images.iter().cloned());
+        }
+        files.extend(
+            self.book
+                .preprocessors
+                .values()
+                .flat_map(|p| p.assets.iter().cloned()),
+        );
+        Ok(files.into_iter().collect())
     }
 
     pub fn render(
         &self,
         output_dir: impl AsRef<Path>,
-        clear_output: bool,
+        options: &RenderOptions,
+    ) -> Result<RenderReport, LoadTrackError> {
+        self.render_scoped(output_dir.as_ref(), options, None)
+    }
+
+    /// Render the track again into `output_dir`, which contains an earlier render of it,
+    /// after the `changed` files changed. If the changes only affect some units, only
+    /// the exercises of those units are copied again, and the rest of the output is updated
+    /// in place. Otherwise, the output directory is cleared and the track is rendered anew.
+    pub fn rerender(
+        &self,
+        output_dir: impl AsRef<Path>,
+        options: &RenderOptions,
+        changed: &[PathBuf],
+    ) -> Result<RenderReport, LoadTrackError> {
+        if self.affected_units(changed).is_none() {
+            let options = RenderOptions {
+                clear_output: true,
+                ..options.clone()
+            };
+            return self.render_scoped(output_dir.as_ref(), &options, None);
+        }
+        self.render_scoped(output_dir.as_ref(), options, Some(changed))
+    }
+
+    /// Render the track, or only the parts affected by the `changed` files if set
+    fn render_scoped(
+        &self,
+        output_dir: &Path,
+        options: &RenderOptions,
+        changed: Option<&[PathBuf]>,
+    ) -> Result<RenderReport, LoadTrackError> {
+        let start = Instant::now();
+        // Find problems before anything is written, instead of halfway through the render
+        let check = self.check(options)?;
+        if !check.is_ok() {
+            let mut report = Report::new(LoadTrackError).attach_printable(format!(
+                "Checking the track found {} problem(s), so nothing was rendered",
+                check.problems.len()
+            ));
+            for problem in check.problems {
+                report = report.attach_printable(problem);
+            }
+            return Err(report);
+        }
+        let layout = Layout::new(&options.layout, self).change_context(LoadTrackError)?;
+        let root = layout.root().change_context(LoadTrackError)?;
+        // Joining an empty root would add a trailing separator
+        let in_root = |dir: &Path| match root.as_os_str().is_empty() {
+            true => dir.to_path_buf(),
+            false => dir.join(&root),
+        };
+        let output_dir = in_root(output_dir);
+        let instructor_dir = options.instructor_output.as_deref().map(in_root);
+
+        if let Some(instructor_dir) = &instructor_dir {
+            check_editions_apart(&output_dir, instructor_dir)?;
+        }
+        let editions: Vec<_> = [(output_dir, Edition::Student)]
+            .into_iter()
+            .chain(instructor_dir.map(|dir| (dir, Edition::Instructor)))
+            .collect();
+        // Check every output directory before writing to any of them
+        for (output_dir, _) in editions.iter() {
+            self.check_output_dir(
+                output_dir,
+                options,
+                changed.is_some() || options.incremental,
+            )?;
+        }
+
+        // Dry runs don't write anything, so there's nothing to swap into place,
+        // and partial renders update the output in place
+        let mut reports = if options.atomic && !options.dry_run && changed.is_none() {
+            self.render_atomic(&editions, options)?
+        } else {
+            editions
+                .iter()
+                .map(|(output_dir, edition)| {
+                    self.render_edition(output_dir, options, *edition, changed)
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        let mut report = reports.remove(0);
+        if let Some(instructor_report) = reports.pop() {
+            report.instructor_output_dir = Some(instructor_report.output_dir);
+            if let (Some(planned), Some(instructor_planned)) =
+                (&mut report.planned, instructor_report.planned)
+            {
+                planned.extend(instructor_planned);
+            }
+        }
+        report.elapsed = start.elapsed();
+        Ok(report)
+    }
+
+    /// Check that the track may be rendered into `output_dir`. Partial and incremental
+    /// renders go into the output of an earlier render, which isn't empty.
+    fn check_output_dir(
+        &self,
+        output_dir: &Path,
+        options: &RenderOptions,
+        partial: bool,
     ) -> Result<(), LoadTrackError> {
-        let output_dir = output_dir.as_ref();
-        output_dir.create_dir_all()?;
-        let output_dir = &output_dir
-            .canonicalize()
+        if !options.allow_output_in_sources {
+            self.check_output_outside_sources(output_dir)?;
+        }
+
+        if output_dir.exists() && !options.clear_output && !partial {
+            // Return error if output dir is not empty
+            let None = fs::read_dir(output_dir)
+                .into_report()
+                .change_context(LoadTrackError)?
+                .next()
+            else {
+                return Err(
+                    Report::new(LoadTrackError).attach_printable("Output directory is not empty")
+                );
+            };
+        }
+        Ok(())
+    }
+
+    /// Render one edition of the track into `output_dir`,
+    /// or only the parts affected by the `changed` files if set
+    fn render_edition(
+        &self,
+        output_dir: &Path,
+        options: &RenderOptions,
+        edition: Edition,
+        changed: Option<&[PathBuf]>,
+    ) -> Result<RenderReport, LoadTrackError> {
+        // Partial renders only copy the exercises of some units,
+        // so the files of the other units would seem to be gone
+        let fingerprint = (options.incremental && changed.is_none()).then(|| {
+            format!(
+                "modmod {version}, keep solutions: {keep_solutions}",
+                version = env!("CARGO_PKG_VERSION"),
+                keep_solutions = edition.keeps_solutions(options)
+            )
+        });
+        if options.dry_run {
+            let mut output = Output::plan(output_dir, options.cancellation.clone())?;
+            if let Some(fingerprint) = &fingerprint {
+                output.use_cache::<LoadTrackError>(fingerprint)?;
+            }
+            return self.render_to(&output, options, edition, changed);
+        }
+
+        if output_dir.exists() && options.clear_output && changed.is_none() {
+            // remove output dir and contents
+            fs::remove_dir_all(output_dir)
+                .into_report()
+                .change_context(LoadTrackError)?;
+        }
+
+        // Ensure output dir exists
+        let mut output = Output::create(output_dir, options.cancellation.clone())?;
+        if let Some(fingerprint) = &fingerprint {
+            output.use_cache::<LoadTrackError>(fingerprint)?;
+        }
+
+        // Don't leave a half-written output directory behind, nor a complete one of a render
+        // that failed in the end, like because of warnings in strict mode
+        self.render_to(&output, options, edition, changed)
+            .inspect_err(|_| output.clean_up())
+    }
+
+    /// Refuse to render into a directory the render reads from, or one that contains such a
+    /// directory. Otherwise, the output gets picked up as input by later renders, and
+    /// clearing the output directory removes sources.
+    fn check_output_outside_sources(&self, output_dir: &Path) -> Result<(), LoadTrackError> {
+        let output_dir = output::resolve_path::<LoadTrackError>(output_dir)?;
+
+        let mut source_dirs: BTreeSet<PathBuf> = self
+            .source_files()?
+            .iter()
+            .filter_map(|f| f.parent().map(Path::to_path_buf))
+            .collect();
+        source_dirs.extend(
+            self.modules
+                .iter()
+                .flat_map(|m| m.data.units.iter())
+                .flat_map(|u| u.data.topics.iter())
+                .flat_map(|t| t.data.exercises.iter())
+                .map(|e| e.data.path.clone()),
+        );
+
+        let overlapping = source_dirs
+            .iter()
+            .find(|dir| output_dir.starts_with(dir) || dir.starts_with(&output_dir));
+        match overlapping {
+            None => Ok(()),
+            Some(dir) => {
+                let problem = if output_dir.starts_with(dir) {
+                    "is inside of"
+                } else {
+                    "contains"
+                };
+                Err(Report::new(LoadTrackError).attach_printable(format!(
+                    "Output directory {output} {problem} {dir}, which the render reads sources from. \
+                    Rendered files would be picked up as sources, and clearing the output would remove sources. \
+                    Choose an output directory outside of the course sources",
+                    output = output_dir.to_string_lossy(),
+                    dir = dir.to_string_lossy(),
+                )))
+            }
+        }
+    }
+
+    /// Render each edition into a staging directory next to its output directory,
+    /// and only swap them into place once every render has succeeded.
+    /// On failure, the original output directories are left untouched.
+    fn render_atomic(
+        &self,
+        editions: &[(PathBuf, Edition)],
+        options: &RenderOptions,
+    ) -> Result<Vec<RenderReport>, LoadTrackError> {
+        let mut staged = vec![];
+        for (output_dir, edition) in editions.iter() {
+            match self.stage(output_dir, options, *edition) {
+                Ok(render) => staged.push(render),
+                Err(e) => {
+                    staged.iter().for_each(StagedRender::discard);
+                    return Err(e);
+                }
+            }
+        }
+
+        for (i, render) in staged.iter().enumerate() {
+            if let Err(e) = render.swap_in() {
+                staged[..i].iter().for_each(StagedRender::roll_back);
+                staged[i..].iter().for_each(StagedRender::discard);
+                return Err(e);
+            }
+        }
+
+        staged.into_iter().map(StagedRender::finish).collect()
+    }
+
+    /// Render an edition into a staging directory next to `output_dir`
+    fn stage(
+        &self,
+        output_dir: &Path,
+        options: &RenderOptions,
+        edition: Edition,
+    ) -> Result<StagedRender, LoadTrackError> {
+        let output_dir = std::path::absolute(output_dir)
             .into_report()
             .change_context(LoadTrackError)?;
+        let (Some(parent), Some(name)) = (output_dir.parent(), output_dir.file_name()) else {
+            return Err(Report::new(LoadTrackError).attach_printable(format!(
+                "Output directory {path} must have a parent directory and a name",
+                path = output_dir.to_string_lossy()
+            )));
+        };
+        let name = name.to_string_lossy();
+        // Staging happens in a sibling so that the final rename doesn't cross filesystems
+        let staging_dir = parent.join(format!(".{name}.modmod-staging"));
+        let old_dir = parent.join(format!(".{name}.modmod-old"));
 
-        if output_dir.exists() {
-            if clear_output {
-                // remove output dir and contents
-                fs::remove_dir_all(output_dir)
+        // Remove leftovers of an earlier render that was killed halfway
+        for dir in [&staging_dir, &old_dir] {
+            if dir.exists() {
+                fs::remove_dir_all(dir)
                     .into_report()
                     .change_context(LoadTrackError)?;
-            } else {
-                // Return error if output dir is not empty
-                let None = fs::read_dir(output_dir)
-                    .into_report()
-                    .change_context(LoadTrackError)?
-                    .next()
-                else {
-                    return Err(Report::new(LoadTrackError)
-                        .attach_printable("Output directory is not empty"));
-                };
             }
         }
-        // Ensure output dir exists
-        output_dir.create_dir_all()?;
+
+        let output = Output::create(&staging_dir, options.cancellation.clone())?;
+        let report = self
+            .render_to(&output, options, edition, None)
+            .and_then(|report| output.check_cancelled().map(|()| report))
+            .inspect_err(|_| output.clean_up())?;
+
+        Ok(StagedRender {
+            output_dir,
+            staging_dir,
+            old_dir,
+            report,
+        })
+    }
+
+    fn render_to(
+        &self,
+        output: &Output,
+        options: &RenderOptions,
+        edition: Edition,
+        changed: Option<&[PathBuf]>,
+    ) -> Result<RenderReport, LoadTrackError> {
+        let Selection {
+            track,
+            drafts_skipped,
+            drafts_included,
+            warnings: selection_warnings,
+            ..
+        } = self.select(options, edition);
+        self.check_dependencies()?
+            .into_iter()
+            .chain(selection_warnings)
+            .for_each(|warning| output.warn(warning));
+
+        track
+            .validate()
+            .into_iter()
+            .for_each(|warning| output.warn(warning));
+        let qr_codes = QrCodes::for_track(&track)?;
+        let mermaid = options.render_mermaid.then(MermaidDiagrams::default);
+        // The selection renumbers units, so changes are looked up in the selected track
+        let units_rerendered =
+            changed.map(|changed| track.affected_units(changed).unwrap_or_default());
+        let layout = Layout::new(&options.layout, &track).change_context(LoadTrackError)?;
 
         // Render the modules in the track
-        let mut book_builder = Book::builder(&self.name);
-        let mut slides_builder = SlidesPackage::builder(&self.name);
+        let mut book_builder = Book::builder(&track.name, &track.book);
+        track
+            .authors
+            .iter()
+            .for_each(|author| book_builder.author(author));
+        // The description of the book takes precedence over that of the track
+        if let Some(description) = track
+            .book
+            .description
+            .as_ref()
+            .or(track.description.as_ref())
+        {
+            book_builder.description(description);
+        }
+        if let Some(language) = &track.language {
+            book_builder.language(language);
+        }
+        if edition == Edition::Instructor {
+            book_builder.instructor_edition();
+        }
+        if track.normalize_headings {
+            book_builder.normalize_headings();
+        }
+        if let Some(schedule) = Schedule::new(&track) {
+            book_builder.schedule(schedule.markdown());
+        }
+        let mut slides_builder = SlidesPackage::builder(&track.name, &track.slides);
+        slides_builder.track_id(conditional::track_id(&track.path));
+        if let Some(language) = &track.language {
+            slides_builder.language(language);
+        }
+        if options.handouts {
+            slides_builder.handouts();
+        }
+        if edition == Edition::Instructor {
+            slides_builder.instructor_edition();
+        }
+        if track.normalize_headings {
+            slides_builder.normalize_headings();
+        }
+        track
+            .variables
+            .iter()
+            .for_each(|(name, value)| slides_builder.variable(name, value));
         let mut exercises_builder = ExerciseCollection::builder();
 
-        self.modules.iter().try_for_each(|module| {
+        track.modules.iter().try_for_each(|module| {
             module.render(
                 &mut book_builder,
                 &mut slides_builder,
@@ -79,31 +858,386 @@ impl Track {
             )
         })?;
 
+        track.appendices.iter().for_each(|appendix| {
+            book_builder.appendix(
+                &appendix.name,
+                &appendix.content,
+                &appendix.images,
+                appendix.draft,
+            )
+        });
+        glossary::glossary(
+            track
+                .modules
+                .iter()
+                .flat_map(|module| &module.data.units)
+                .flat_map(|unit| &unit.data.topics)
+                .flat_map(|topic| &topic.data.glossary),
+        )
+        .into_iter()
+        .for_each(|entry| book_builder.glossary_entry(entry));
+
         // Build and render exercise packages
         let exercises = exercises_builder.build();
         let exercise_paths = exercises
-            .render(output_dir)
+            .render(
+                &layout,
+                PackageOptions {
+                    keep_solutions: edition.keeps_solutions(options),
+                    cargo_workspaces: options.cargo_workspaces,
+                    zip_units: options.zip_exercises,
+                    grading_tests: edition == Edition::Instructor,
+                    // Assignments are for students
+                    classroom: options.classroom.filter(|_| edition == Edition::Student),
+                    readmes: options.exercise_readmes,
+                    base_url: track.base_url.as_deref(),
+                    book_build_dir: track.book.build_dir(),
+                    rust_toolchain: track.rust_toolchain.as_deref(),
+                },
+                units_rerendered.as_ref(),
+                output,
+            )
             .change_context(LoadTrackError)?;
         // Build and render the exercise book
         let book = book_builder.build();
-        book.render(&exercise_paths, output_dir)
+        let book_paths = book
+            .render(
+                &exercise_paths.exercises,
+                qr_codes.as_ref(),
+                mermaid.as_ref(),
+                output,
+            )
             .change_context(LoadTrackError)?;
 
         // Build and render the slides package
         let slides_package = slides_builder.build();
-        slides_package
-            .render(output_dir)
+        let deck_paths = slides_package
+            .render(
+                &exercise_paths.exercises,
+                qr_codes.as_ref(),
+                mermaid.as_ref(),
+                output,
+            )
             .change_context(LoadTrackError)?;
 
-        Ok(())
+        track.render_assets(output).change_context(LoadTrackError)?;
+        // Dry runs have no images to optimize
+        if let Some(image_options) = options.optimize_images.filter(|_| !output.is_dry_run()) {
+            images::optimize_images(output, image_options).change_context(LoadTrackError)?;
+        }
+        // Dry runs have no slides to build
+        if let Some(format) = options.build_slides.filter(|_| !output.is_dry_run()) {
+            slidev::build_slides(output, &deck_paths, &slides_package.backends(), format)
+                .change_context(LoadTrackError)?;
+        }
+        if options.build_book && !output.is_dry_run() {
+            book::build_book(output).change_context(LoadTrackError)?;
+        }
+
+        // Tell external tools where everything ended up
+        let output_map = OutputMap::new(&track, &exercise_paths, &book_paths, &deck_paths);
+        output
+            .create_file::<LoadTrackError>(output.root().join(OUTPUT_MAP_FILE))?
+            .write_all(serde_json::to_string_pretty(&output_map).unwrap())?;
+        let backends = slides_package.backends();
+        let search_index = |base_url| {
+            SearchIndex::new(
+                &track,
+                &exercise_paths,
+                &book_paths,
+                &deck_paths,
+                &backends,
+                base_url,
+            )
+        };
+        if options.search_index {
+            output
+                .create_file::<LoadTrackError>(output.root().join(SEARCH_INDEX_FILE))?
+                .write_all(
+                    serde_json::to_string_pretty(&search_index(track.base_url.as_deref())).unwrap(),
+                )?;
+        }
+        let quizzes = Quizzes::new(&track);
+        if !quizzes.is_empty() {
+            output
+                .create_file::<LoadTrackError>(output.root().join(QUIZZES_FILE))?
+                .write_all(serde_json::to_string_pretty(&quizzes).unwrap())?;
+        }
+        // The landing page links relative to itself, wherever the output is published
+        output
+            .create_file::<LoadTrackError>(output.root().join(LANDING_PAGE_FILE))?
+            .write_all(landing_page(&track, &search_index(None)))?;
+        // Dry runs have no files to check
+        if !output.is_dry_run() {
+            output_map.check(output.root())?;
+            if let Some(link_options) = options.check_links {
+                track
+                    .check_links(&deck_paths, &book_paths, link_options, output)
+                    .change_context(LoadTrackError)?;
+            }
+        }
+
+        let units = track.modules.iter().flat_map(|m| &m.data.units);
+        let topics = units.clone().flat_map(|u| &u.data.topics);
+
+        // Exercises are vendored before they're verified, which then needs no internet either
+        if options.vendor_crates && !output.is_dry_run() {
+            for unit_dir in exercise_paths.units.values() {
+                let unit_dir = output.root().join(unit_dir);
+                // The members of a workspace share its dependencies
+                if options.cargo_workspaces && unit_dir.join("Cargo.toml").is_file() {
+                    crates::vendor_crates(output, &unit_dir).change_context(LoadTrackError)?;
+                    continue;
+                }
+                for exercise_dir in exercise_paths.exercises.values() {
+                    let exercise_dir = output.root().join(exercise_dir);
+                    if exercise_dir.starts_with(&unit_dir)
+                        && exercise_dir.join("Cargo.toml").is_file()
+                    {
+                        crates::vendor_crates(output, &exercise_dir)
+                            .change_context(LoadTrackError)?;
+                    }
+                }
+            }
+        }
+
+        // The instructor edition contains the same exercises, with solutions,
+        // and dry runs have no exercises to verify
+        let mut verification = vec![];
+        if let Some(verify_options) = options
+            .verify
+            .as_ref()
+            .filter(|_| edition == Edition::Student && !output.is_dry_run())
+        {
+            let verifier = Verifier::new(verify_options, output);
+            for (key, exercise) in track.exercises_by_index() {
+                verification.push(
+                    verifier
+                        .verify(exercise, &exercise_paths.exercises[&key])
+                        .change_context(LoadTrackError)?,
+                );
+            }
+        }
+
+        // Starter repositories have no solutions, and dry runs have no files to commit
+        if let Some(grouping) = options
+            .git_init
+            .filter(|_| edition == Edition::Student && !output.is_dry_run())
+        {
+            let exercise_dirs = exercise_paths.exercises.values();
+            let mut dirs: Vec<_> = match grouping {
+                ExerciseGrouping::Exercise => exercise_dirs.collect(),
+                ExerciseGrouping::Unit => exercise_paths
+                    .units
+                    .values()
+                    .filter(|unit| exercise_dirs.clone().any(|dir| dir.starts_with(unit)))
+                    .collect(),
+            };
+            dirs.sort();
+            for dir in dirs {
+                repositories::init_repository(output, &output.root().join(dir))
+                    .change_context(LoadTrackError)?;
+            }
+        }
+
+        let (notes, warnings): (Vec<_>, Vec<_>) = output
+            .warnings()
+            .into_iter()
+            .map(|warning| warning.allowed_by(&track.allow))
+            .partition(|warning| warning.allowed);
+        if options.strict && !warnings.is_empty() {
+            let mut report = Report::new(LoadTrackError).attach_printable(format!(
+                "Rendering produced {} warning(s), which are errors in strict mode",
+                warnings.len()
+            ));
+            for warning in warnings {
+                report = report.attach_printable(warning.to_string());
+            }
+            return Err(report);
+        }
+
+        output.save_cache::<LoadTrackError>()?;
+        let (files_written, bytes_written) = output.written();
+        Ok(RenderReport {
+            track_name: track.name.clone(),
+            output_dir: output.root().to_path_buf(),
+            modules: track.modules.len(),
+            units: units.count(),
+            topics: topics.clone().count(),
+            exercises: topics.map(|t| t.data.exercises.len()).sum(),
+            files_written,
+            bytes_written,
+            elapsed: Duration::ZERO,
+            instructor_output_dir: None,
+            planned: output.planned(),
+            files_unchanged: output.unchanged(),
+            units_rerendered: units_rerendered.map(|units| units.into_iter().collect()),
+            warnings,
+            notes,
+            drafts_skipped,
+            drafts_included,
+            verification,
+            output_map,
+        })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Remove the contents of the output directory before rendering
+    pub clear_output: bool,
+    /// Render into a staging directory and only replace the output directory
+    /// once rendering has succeeded
+    pub atomic: bool,
+    /// Treat warnings as errors
+    pub strict: bool,
+    /// Render draft units, topics and appendices, marked as such, instead of leaving them out
+    pub include_drafts: bool,
+    /// Only render the modules with these numbers or names. Modules keep the numbers they
+    /// have in the whole track.
+    pub modules: Vec<String>,
+    /// Only render the units with these numbers or names, of the modules that are rendered.
+    /// Numbers are either the number of the unit in its module, like `2`, which selects
+    /// that unit in every module, or the number of the module and the unit, like `1.2`.
+    pub units: Vec<String>,
+    /// Skip the check that the output directory doesn't overlap with any directory
+    /// that sources are read from
+    pub allow_output_in_sources: bool,
+    /// Templates of the paths the output is rendered into
+    pub layout: OutputLayout,
+    /// Keep the solutions in exercise files, instead of stripping them
+    pub keep_solutions: bool,
+    /// Also render the exercises as assignment repositories for GitHub Classroom,
+    /// of single exercises or whole units, into `classroom` in the output directory
+    pub classroom: Option<ExerciseGrouping>,
+    /// Write a Cargo workspace into the exercise directory of every unit, with
+    /// the exercises of the unit that are Cargo packages as its members
+    pub cargo_workspaces: bool,
+    /// Also pack the exercises of every unit into a ZIP archive next to the
+    /// exercise directory of the unit, like `exercises/2-foundations/1-basic-syntax.zip`
+    pub zip_exercises: bool,
+    /// Vendor the crates that the Cargo exercises depend on into the output with
+    /// `cargo vendor`, so that they can be built without internet access
+    pub vendor_crates: bool,
+    /// Make every rendered exercise, or the exercise directory of every unit, a git
+    /// repository with an initial commit, to push as the starter repositories of students
+    pub git_init: Option<ExerciseGrouping>,
+    /// Give every rendered exercise that has no README of its own a `README.md` with its
+    /// description and a link to its unit in the book, for students that only get the exercise
+    pub exercise_readmes: bool,
+    /// Also render a handout of every unit into `handouts` in the output directory: its
+    /// slides as one document to study from, with its objectives, summary and further reading
+    pub handouts: bool,
+    /// Also write a [SearchIndex] of the modules, units, topics and exercises of the track
+    /// into the output directory, for a site that publishes the course to search through
+    pub search_index: bool,
+    /// Render the Mermaid diagrams in slides, exercise descriptions and appendices to SVG
+    /// images with `mmdc`, instead of leaving them for the slides and the book to render
+    pub render_mermaid: bool,
+    /// Downsize and recompress the PNG and JPEG images in the slides and the book with
+    /// ImageMagick, see [ImageOptions]
+    pub optimize_images: Option<ImageOptions>,
+    /// Build the rendered slide decks with the tools of their backends, after optimizing
+    /// their images
+    pub build_slides: Option<SlidesFormat>,
+    /// Build the rendered book with mdbook, into its [build directory](BookConfig::build_dir)
+    pub build_book: bool,
+    /// Check the links in the rendered slides and book, and warn about the ones that point
+    /// nowhere, see [LinkCheckOptions]
+    pub check_links: Option<LinkCheckOptions>,
+    /// Also render an instructor edition into this directory, with the solutions of the
+    /// exercises and the instructor notes of the topics. Those are never part of the
+    /// output of the regular render.
+    pub instructor_output: Option<PathBuf>,
+    /// Render into the output of an earlier render, and only copy the exercise files
+    /// that changed since then, going by the content hashes in the [RENDER_CACHE_FILE]
+    /// in the output directory. Exercise files that are no longer part of the
+    /// output are removed. The book and the slides are always rendered again.
+    pub incremental: bool,
+    /// Only work out which directories and files the render would create,
+    /// without writing anything or verifying the exercises
+    pub dry_run: bool,
+    /// Verify the rendered exercises, see [VerifyOptions]
+    pub verify: Option<VerifyOptions>,
+    /// Token that can be used to cancel the render. On cancellation,
+    /// everything created during the render is removed again
+    pub cancellation: Option<CancellationToken>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Appendix {
+    pub name: String,
+    pub content: PathBuf,
+    pub images: Vec<PathBuf>,
+    /// Draft appendices are only rendered when drafts are included explicitly
+    pub draft: bool,
+}
+
+#[derive(Debug, Clone)]
 pub struct Module {
     pub name: String,
+    /// Path to the module definition file
+    pub path: PathBuf,
     pub description: String,
+    /// Objectives of the module as a whole, as opposed to those of its topics
+    pub objectives: Vec<String>,
+    pub further_reading: Vec<String>,
     pub units: Vec<Indexed<Unit>>,
+    /// Directory of files that are copied into the slides and the book, see [Topic::assets]
+    pub assets: Option<PathBuf>,
+}
+
+impl Module {
+    /// Add the source files that are read when rendering this module to `files`
+    fn collect_source_files(&self, files: &mut BTreeSet<PathBuf>) -> Result<(), LoadTrackError> {
+        files.insert(self.path.clone());
+        if let Some(assets) = &self.assets {
+            files.extend(assets::asset_files::<LoadTrackError>(assets)?);
+        }
+        for unit in self.units.iter() {
+            files.extend(unit.data.templates.iter().map(|t| t.path.clone()));
+            for topic in unit.data.topics.iter() {
+                let topic = &topic.data;
+                files.insert(topic.path.clone());
+                if let TopicContent::File(content) = &topic.content {
+                    files.insert(content.clone());
+                    // Content that can't be read is reported when rendering
+                    if let Ok(markdown) = fs::read_to_string(content) {
+                        let dir = content.parent().unwrap();
+                        files.extend(anchors::included_files(&markdown, dir));
+                        files.extend(
+                            slides::local_assets(&markdown, dir)
+                                .into_iter()
+                                .map(|(_, asset)| asset),
+                        );
+                    }
+                }
+                files.extend(topic.instructor_notes.clone());
+                files.extend(topic.images.iter().cloned());
+                if let Some(assets) = &topic.assets {
+                    files.extend(assets::asset_files::<LoadTrackError>(assets)?);
+                }
+                for exercise in topic.exercises.iter() {
+                    let exercise = &exercise.data;
+                    files.insert(exercise.description.clone());
+                    files.extend(
+                        exercises::included_files(
+                            &exercise.path,
+                            &exercise.includes,
+                            &exercise.excludes,
+                        )
+                        .change_context(LoadTrackError)?,
+                    );
+                    // Grading tests are copied into the instructor edition
+                    files.extend(
+                        exercises::grading_files(&exercise.path, &exercise.excludes)
+                            .change_context(LoadTrackError)?,
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Indexed<Module> {
@@ -119,6 +1253,15 @@ impl Indexed<Module> {
         } = self;
 
         let mut chapter = book_builder.chapter(&data.name, *module_index);
+        if !data.description.trim().is_empty() {
+            chapter.description(&data.description);
+        }
+        data.objectives
+            .iter()
+            .for_each(|obj| chapter.objective(obj));
+        data.further_reading
+            .iter()
+            .for_each(|item| chapter.further_reading(item));
         let mut module_exercises = exercises.module(&data.name, *module_index);
 
         // Render all units in this module
@@ -126,6 +1269,7 @@ impl Indexed<Module> {
             unit.render(
                 &data.name,
                 *module_index,
+                &data.objectives,
                 &mut chapter,
                 slides,
                 &mut module_exercises,
@@ -138,11 +1282,41 @@ impl Indexed<Module> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Unit {
     pub name: String,
-    pub template: Option<PathBuf>,
+    /// Settings of the slides of the unit, which take precedence over those of the track
+    pub slides: SlidesConfig,
+    /// The templates the unit is rendered with, of which at most one is that of its slides
+    pub templates: Vec<UnitTemplate>,
     pub topics: Vec<Indexed<Topic>>,
+    /// Draft units are only rendered when drafts are included explicitly
+    pub draft: bool,
+    /// Codes of warnings about the unit that are reported as notes
+    pub allow: Vec<WarningCode>,
+    /// Whether to normalize the headings of the topics of the unit, instead of doing what
+    /// the track does, see [Track::normalize_headings]
+    pub normalize_headings: Option<bool>,
+}
+
+impl Unit {
+    /// The template of the slides of the unit, if it doesn't use the default one
+    pub fn slides_template(&self) -> Option<&Path> {
+        self.templates
+            .iter()
+            .find(|template| template.output.is_none())
+            .map(|template| template.path.as_path())
+    }
+}
+
+/// A template that the slides of a unit, or another document made from its topics like a
+/// handout, are rendered with
+#[derive(Debug, Clone)]
+pub struct UnitTemplate {
+    pub path: PathBuf,
+    /// Name of what the template is rendered into next to the slides, like `handout`, or
+    /// `None` for the slides themselves
+    pub output: Option<String>,
 }
 
 impl Indexed<Unit> {
@@ -150,6 +1324,7 @@ impl Indexed<Unit> {
         &'me self,
         module_name: &'me str,
         module_index: usize,
+        module_objectives: &'me [String],
         chapter: &mut ChapterBuilder<'me, '_>,
         slides: &mut SlidesPackageBuilder<'me>,
         module_exercises: &mut ModuleExercisesBuilder<'me, '_>,
@@ -165,8 +1340,24 @@ impl Indexed<Unit> {
             module_name,
             module_index,
             *unit_index,
-            data.template.as_deref(),
+            data.slides_template(),
         );
+        deck.config(&data.slides);
+        if let Some(normalize) = data.normalize_headings {
+            section.normalize_headings(normalize);
+            deck.normalize_headings(normalize);
+        }
+        data.templates
+            .iter()
+            .filter_map(|template| Some((template.path.as_path(), template.output.as_deref()?)))
+            .for_each(|(path, output)| deck.extra_template(path, output));
+        if data.draft {
+            section.draft();
+            deck.draft();
+        }
+        module_objectives
+            .iter()
+            .for_each(|obj| deck.module_objective(obj));
         let mut unit_exercises = module_exercises.unit(&data.name, *unit_index);
 
         data.topics
@@ -181,15 +1372,76 @@ impl Indexed<Unit> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Topic {
     pub name: String,
+    /// Path to the topic definition file
+    pub path: PathBuf,
     pub exercises: Vec<Indexed<Exercise>>,
     pub summary: Vec<String>,
     pub objectives: Vec<String>,
-    pub content: PathBuf,
+    pub content: TopicContent,
     pub further_reading: Vec<String>,
     pub images: Vec<PathBuf>,
+    /// Draft topics are only rendered when drafts are included explicitly
+    pub draft: bool,
+    /// Whether the topic is defined inline in the module definition,
+    /// in which case `path` is the path to the module definition
+    pub inline: bool,
+    /// Paths to the definitions of topics that should be taught before this one
+    pub dependencies: Vec<PathBuf>,
+    /// Markdown file with speaker notes and teaching tips, only rendered in the instructor edition
+    pub instructor_notes: Option<PathBuf>,
+    /// Labels that tracks can select topics by, see [Track::include_tags] and [Track::exclude_tags]
+    pub tags: Vec<String>,
+    /// How long teaching the topic takes, leaving out its exercises
+    pub duration_minutes: Option<u32>,
+    /// Directory of files that are copied into `assets` in the slides and the book, so that
+    /// all topics can refer to them, like diagrams that are shown in more than one topic
+    pub assets: Option<PathBuf>,
+    /// Terms that the topic introduces, which are collected into the glossary of the book
+    pub glossary: Vec<GlossaryEntry>,
+    /// Questions that check what students learned from the topic, at the end of its unit in
+    /// the book
+    pub quizzes: Vec<Quiz>,
+}
+
+/// A term in the glossary of the book, from the `[[glossary]]` tables of a topic definition
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GlossaryEntry {
+    pub term: String,
+    /// Markdown that explains the term
+    pub definition: String,
+}
+
+/// A multiple choice question, from the `[[quizzes]]` tables of a topic definition
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Quiz {
+    pub question: String,
+    pub choices: Vec<String>,
+    /// The correct one of the `choices`
+    pub answer: String,
+    /// Markdown that explains the answer, shown along with it
+    pub explanation: Option<String>,
+}
+
+impl Quiz {
+    /// Index of the answer among the choices, which loading the topic checks there is
+    pub fn answer_index(&self) -> usize {
+        self.choices
+            .iter()
+            .position(|choice| choice.trim() == self.answer.trim())
+            .expect("the answer is one of the choices")
+    }
+}
+
+/// The slides of a topic
+#[derive(Debug, Clone)]
+pub enum TopicContent {
+    /// Path to a markdown file
+    File(PathBuf),
+    /// Markdown that is part of the topic definition
+    Inline(String),
 }
 
 impl Indexed<Topic> {
@@ -201,27 +1453,46 @@ impl Indexed<Topic> {
     ) -> Result<(), LoadTrackError> {
         let Indexed { data, .. } = self;
 
-        let mut slides_section = deck.section(&data.content);
+        let mut slides_section = deck.section(&data.name, &data.content);
+        if data.draft {
+            slides_section.draft();
+        }
 
         data.summary
             .iter()
             .for_each(|item| slides_section.summary(item));
 
-        data.objectives
-            .iter()
-            .for_each(|obj| slides_section.objective(obj));
+        data.objectives.iter().for_each(|obj| {
+            slides_section.objective(obj);
+            section.objective(obj);
+        });
 
         data.further_reading
             .iter()
             .for_each(|item| slides_section.further_reading(item));
 
+        data.quizzes.iter().for_each(|quiz| section.quiz(quiz));
+
+        if let Some(notes) = &data.instructor_notes {
+            slides_section.instructor_notes(notes);
+            section.instructor_notes(&data.name, notes);
+        }
+
         data.images
             .iter()
             .for_each(|image| slides_section.image(image));
 
+        data.exercises.iter().for_each(|exercise| {
+            slides_section.exercise(
+                &exercise.data.name,
+                &exercise.data.path,
+                &exercise.data.description,
+            )
+        });
+
         data.exercises
             .iter()
-            .try_for_each(|exercise| exercise.render(section, unit_exercises))?;
+            .try_for_each(|exercise| exercise.render(data.draft, section, unit_exercises))?;
 
         slides_section.add();
 
@@ -229,25 +1500,76 @@ impl Indexed<Topic> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Exercise {
     pub name: String,
     pub path: PathBuf,
     pub description: PathBuf,
+    pub kind: ExerciseKind,
+    /// The include profile the includes are based on, if any
+    pub include_profile: Option<String>,
     pub includes: Vec<String>,
+    /// Files matching these globs are not copied, even if they match an include
+    pub excludes: Vec<String>,
+    /// Whether the exercise is checked when verifying the rendered exercises
+    pub verify: bool,
+    /// Command used to verify this exercise instead of the global verify command
+    pub verify_command: Option<String>,
+    pub verify_timeout: Option<Duration>,
+    /// Lints that are run when verifying this exercise instead of the lints of the
+    /// [VerifyOptions]
+    pub lints: Option<Vec<ExerciseLint>>,
+    /// Codes of warnings about the exercise that are reported as notes
+    pub allow: Vec<WarningCode>,
+    /// How long doing the exercise takes
+    pub duration_minutes: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExerciseKind {
+    /// A Cargo package
+    #[default]
+    Cargo,
+    /// A plain directory of files, for instance C sources or project files of a simulator
+    Plain,
+}
+
+impl ExerciseKind {
+    pub fn default_includes(self) -> Vec<String> {
+        match self {
+            ExerciseKind::Cargo => vec!["Cargo.toml", "Cargo.lock", "src/**/*"],
+            ExerciseKind::Plain => vec!["**/*"],
+        }
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
+    pub fn default_excludes(self) -> Vec<String> {
+        match self {
+            ExerciseKind::Cargo => vec![],
+            // Build artifacts and VCS data
+            ExerciseKind::Plain => vec!["target/**", ".git/**", "node_modules/**", "**/.DS_Store"],
+        }
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
 }
 
 impl Indexed<Exercise> {
     fn render<'me>(
         &'me self,
+        draft: bool,
         section: &mut SectionBuilder<'me, '_, '_>,
         unit_exercises: &mut UnitExercisesBuilder<'me, '_, '_>,
     ) -> Result<(), LoadTrackError> {
         let Indexed { data, .. } = self;
 
-        section.subsection(&data.name, &data.description, &data.path);
+        section.subsection(&data.name, &data.description, draft);
 
-        unit_exercises.package(&data.name, &data.path, &data.includes);
+        unit_exercises.package(data);
 
         Ok(())
     }
@@ -264,6 +1586,84 @@ impl fmt::Display for LoadTrackError {
 
 impl error_stack::Context for LoadTrackError {}
 
+/// A render that succeeded into a staging directory, but isn't in place yet
+struct StagedRender {
+    output_dir: PathBuf,
+    staging_dir: PathBuf,
+    /// Where the previous output is kept until every staged render is in place
+    old_dir: PathBuf,
+    report: RenderReport,
+}
+
+impl StagedRender {
+    /// Move the staged render into place, and the previous output out of the way
+    fn swap_in(&self) -> Result<(), LoadTrackError> {
+        if self.output_dir.exists() {
+            fs::rename(&self.output_dir, &self.old_dir)
+                .into_report()
+                .attach_printable("Unable to move previous output out of the way")
+                .change_context(LoadTrackError)?;
+        }
+        if let Err(e) = fs::rename(&self.staging_dir, &self.output_dir) {
+            // Put the previous output back where it was
+            if self.old_dir.exists() {
+                let _ = fs::rename(&self.old_dir, &self.output_dir);
+            }
+            return Err(e)
+                .into_report()
+                .attach_printable_lazy(|| {
+                    format!(
+                        "Unable to move staging directory {path} into place",
+                        path = self.staging_dir.to_string_lossy()
+                    )
+                })
+                .change_context(LoadTrackError);
+        }
+        Ok(())
+    }
+
+    /// Undo [StagedRender::swap_in], restoring the previous output
+    fn roll_back(&self) {
+        let _ = fs::remove_dir_all(&self.output_dir);
+        if self.old_dir.exists() {
+            let _ = fs::rename(&self.old_dir, &self.output_dir);
+        }
+    }
+
+    /// Remove the staged render without moving it into place
+    fn discard(&self) {
+        let _ = fs::remove_dir_all(&self.staging_dir);
+    }
+
+    /// Remove the previous output, once every staged render is in place
+    fn finish(self) -> Result<RenderReport, LoadTrackError> {
+        if self.old_dir.exists() {
+            fs::remove_dir_all(&self.old_dir)
+                .into_report()
+                .change_context(LoadTrackError)?;
+        }
+        let mut report = self.report;
+        report.output_dir = self.output_dir;
+        Ok(report)
+    }
+}
+
+/// Refuse to render the instructor edition into or around the student output,
+/// where it would end up in front of students
+fn check_editions_apart(output_dir: &Path, instructor_dir: &Path) -> Result<(), LoadTrackError> {
+    let output_dir = output::resolve_path::<LoadTrackError>(output_dir)?;
+    let instructor_dir = output::resolve_path::<LoadTrackError>(instructor_dir)?;
+    if output_dir.starts_with(&instructor_dir) || instructor_dir.starts_with(&output_dir) {
+        return Err(Report::new(LoadTrackError).attach_printable(format!(
+            "Instructor output directory {instructor} overlaps with output directory {output}. \
+            Choose an instructor output directory outside of the student output",
+            instructor = instructor_dir.to_string_lossy(),
+            output = output_dir.to_string_lossy(),
+        )));
+    }
+    Ok(())
+}
+
 fn to_prefixed_tag<S, P>(s: S, p: P) -> String
 where
     S: Display,
@@ -0,0 +1,368 @@
+//! Checking the links in the rendered slides and book: relative links have to point to
+//! files in the output, and external links, if they're checked at all, have to respond.
+//! Further reading lists in particular rot over time.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use error_stack::{IntoReport, Result, ResultExt};
+
+use crate::{
+    book::BookPaths,
+    codes::WarningCode,
+    io::{PathExt, WriteExt},
+    output::Output,
+    parallel::parallel_map,
+    remote::cache_dir,
+    report::Warning,
+    TopicContent, Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct CheckLinksError;
+
+impl fmt::Display for CheckLinksError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to check links")
+    }
+}
+
+impl error_stack::Context for CheckLinksError {}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LinkCheckOptions {
+    /// Also request every external `http` and `https` link, which needs `curl`. Links that
+    /// responded are cached for [EXTERNAL_LINK_TTL], so that renders close after each other
+    /// don't request them all again.
+    pub external: bool,
+}
+
+/// How long an external link that responded is taken to be alive without checking again
+const EXTERNAL_LINK_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How long to wait for an external link to respond
+const EXTERNAL_LINK_TIMEOUT_SECS: u32 = 20;
+
+/// Name of the file in the [cache_dir] that external links that responded are cached in
+const LINK_CACHE_FILE: &str = "links.json";
+
+/// Descriptions of the sources of a page, like `topic 'Traits' (traits.toml)`, along with
+/// their content
+type Sources = Vec<(String, String)>;
+
+/// A rendered markdown page, with the sources its content came from
+struct Page {
+    /// Path of the page, relative to the output directory
+    path: PathBuf,
+    /// Directory that links starting with `/` are relative to
+    root: PathBuf,
+    sources: Sources,
+    /// Codes of the warnings that are allowed for the unit of the page
+    allow: Vec<WarningCode>,
+}
+
+impl Track {
+    /// Check the links in the slide decks and the book pages that were rendered into
+    /// `output`, warning about every link that points nowhere. Warnings name the topic,
+    /// exercise description or appendix that the link is in, if it can be found.
+    pub(crate) fn check_links(
+        &self,
+        deck_paths: &HashMap<(usize, usize), PathBuf>,
+        book_paths: &BookPaths,
+        options: LinkCheckOptions,
+        output: &Output,
+    ) -> Result<(), CheckLinksError> {
+        let pages = self.rendered_pages(deck_paths, book_paths, output);
+        let mut external: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for page in pages.iter() {
+            let content = output.root().join(&page.path).read_to_string()?;
+            let dir = page.path.parent().unwrap();
+            // Links that are on a page more than once are only reported once
+            let mut seen = HashSet::new();
+            for link in links(&content)
+                .into_iter()
+                .filter(|link| seen.insert(*link))
+            {
+                let location = page.locate(link);
+                if link.starts_with("http://") || link.starts_with("https://") {
+                    external.entry(link.to_string()).or_default().push(location);
+                    continue;
+                }
+                // Other schemes, like `mailto:`, and links within the page
+                if link.contains(':') || link.starts_with('#') {
+                    continue;
+                }
+                let target = link.split(['#', '?']).next().unwrap().replace("%20", " ");
+                if target.is_empty() {
+                    continue;
+                }
+                let resolved = match target.strip_prefix('/') {
+                    Some(target) => page.root.join(target),
+                    None => dir.join(&target),
+                };
+                if !exists(output.root(), &resolved) {
+                    output.warn(
+                        Warning::new(
+                            WarningCode::DeadLink,
+                            format!(
+                                "{location}: link {link} points to {resolved}, which is not part of the output",
+                                resolved = resolved.to_string_lossy()
+                            ),
+                        )
+                        .allowed_by(&page.allow),
+                    );
+                }
+            }
+        }
+
+        if options.external && !external.is_empty() {
+            for (url, problem) in check_external_links(external.keys())? {
+                for location in external[&url].iter() {
+                    output.warn(Warning::new(
+                        WarningCode::DeadLink,
+                        format!("{location}: link {url} {problem}"),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The markdown pages of the slides and the book that were written into `output`.
+    /// Slide decks and the pages of units get the topics of their unit as sources.
+    fn rendered_pages(
+        &self,
+        deck_paths: &HashMap<(usize, usize), PathBuf>,
+        book_paths: &BookPaths,
+        output: &Output,
+    ) -> Vec<Page> {
+        // Sources of the pages of units, by their page
+        let mut units: HashMap<&Path, (Sources, &[WarningCode])> = HashMap::new();
+        for module in self.modules.iter() {
+            for unit in module.data.units.iter() {
+                let key = (module.index, unit.index);
+                let topics = unit.data.topics.iter().map(|t| &t.data);
+                let mut slides = vec![];
+                let mut book = vec![];
+                for topic in topics {
+                    let name = format!(
+                        "topic '{name}' ({path})",
+                        name = topic.name,
+                        path = topic.path.to_string_lossy()
+                    );
+                    let further_reading = topic.further_reading.join("\n");
+                    let content = match &topic.content {
+                        TopicContent::File(path) => fs::read_to_string(path).unwrap_or_default(),
+                        TopicContent::Inline(markdown) => markdown.clone(),
+                    };
+                    slides.push((name.clone(), format!("{content}\n{further_reading}")));
+                    let descriptions: Vec<String> = topic
+                        .exercises
+                        .iter()
+                        .filter_map(|e| fs::read_to_string(&e.data.description).ok())
+                        .collect();
+                    book.push((
+                        name,
+                        format!("{}\n{further_reading}", descriptions.join("\n")),
+                    ));
+                }
+                if let Some(deck) = deck_paths.get(&key) {
+                    units.insert(deck, (slides, &unit.data.allow));
+                }
+                if let Some(page) = book_paths.sections.get(&key) {
+                    units.insert(page, (book, &unit.data.allow));
+                }
+            }
+        }
+        // The pages of appendices aren't part of the book paths, so every other page gets
+        // the appendices as sources
+        let appendices: Sources = self
+            .appendices
+            .iter()
+            .map(|appendix| {
+                let name = format!(
+                    "appendix '{name}' ({path})",
+                    name = appendix.name,
+                    path = appendix.content.to_string_lossy()
+                );
+                let content = fs::read_to_string(&appendix.content).unwrap_or_default();
+                (name, content)
+            })
+            .collect();
+
+        let slides_dir = Path::new("slides");
+        let book_dir = Path::new("book").join("src");
+        output
+            .written_files()
+            .into_iter()
+            .filter(|file| file.extension().is_some_and(|ext| ext == "md"))
+            .filter_map(|file| {
+                let path = file.strip_prefix(output.root()).ok()?.to_path_buf();
+                let root = if path.parent() == Some(slides_dir) {
+                    slides_dir.to_path_buf()
+                } else if path.starts_with(&book_dir) {
+                    book_dir.clone()
+                } else {
+                    return None;
+                };
+                let (sources, allow) = units
+                    .get(path.as_path())
+                    .map(|(sources, allow)| (sources.clone(), allow.to_vec()))
+                    .unwrap_or_else(|| (appendices.clone(), vec![]));
+                Some(Page {
+                    path,
+                    root,
+                    sources,
+                    allow,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Page {
+    /// Where in the sources of the page `link` comes from, or the page itself if that
+    /// can't be told
+    fn locate(&self, link: &str) -> String {
+        let page = self.path.to_string_lossy();
+        match self
+            .sources
+            .iter()
+            .find(|(_, content)| content.contains(link))
+        {
+            Some((source, _)) => format!("{source} in {page}"),
+            None => page.to_string(),
+        }
+    }
+}
+
+/// Whether the file or directory at `path`, relative to the output directory `root`, exists.
+/// Books link to the HTML pages that mdbook builds from their markdown pages.
+fn exists(root: &Path, path: &Path) -> bool {
+    let absolute = root.join(path);
+    absolute.exists()
+        || (absolute.extension().is_some_and(|ext| ext == "html")
+            && absolute.with_extension("md").is_file())
+        // Slidev serves the `public` directory of the slides package at its root
+        || path
+            .strip_prefix("slides")
+            .is_ok_and(|path| root.join("slides").join("public").join(path).exists())
+}
+
+/// The targets of the links in the markdown `content`: of links like `[text](target)`,
+/// images, `href` and `src` attributes, and autolinks like `<https://...>`. Code blocks
+/// and inline code are left out.
+fn links(content: &str) -> Vec<&str> {
+    let mut links = vec![];
+    let mut in_code = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        // Every other part of the line is inline code
+        for text in line.split('`').step_by(2) {
+            for (start, _) in text.match_indices("](") {
+                let target = &text[start + 2..];
+                let target = &target[..target.find(')').unwrap_or(target.len())];
+                // Links may have a title after their target
+                let target = target.split_whitespace().next().unwrap_or("");
+                links.push(target.trim_start_matches('<').trim_end_matches('>'));
+            }
+            for attribute in ["href=", "src="] {
+                for (start, _) in text.match_indices(attribute) {
+                    // Bound attributes of Vue components, like `:src`, are expressions
+                    if text[..start].ends_with(':') {
+                        continue;
+                    }
+                    let value = &text[start + attribute.len()..];
+                    let Some(quote) = value.chars().next().filter(|c| ['"', '\''].contains(c))
+                    else {
+                        continue;
+                    };
+                    let value = &value[1..];
+                    links.push(&value[..value.find(quote).unwrap_or(value.len())]);
+                }
+            }
+            for (start, _) in text.match_indices("<http") {
+                let target = &text[start + 1..];
+                if let Some(end) = target.find('>') {
+                    links.push(&target[..end]);
+                }
+            }
+        }
+    }
+    links.retain(|link| !link.is_empty());
+    links
+}
+
+/// Request the external `urls`, returning the ones that didn't respond successfully along
+/// with what went wrong
+fn check_external_links<'a>(
+    urls: impl Iterator<Item = &'a String>,
+) -> Result<Vec<(String, String)>, CheckLinksError> {
+    let cache_file = cache_dir::<CheckLinksError>()?.join(LINK_CACHE_FILE);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    // When each of the links that responded was checked, in seconds since the Unix epoch
+    let mut cache: BTreeMap<String, u64> = fs::read_to_string(&cache_file)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    cache.retain(|_, checked| now.saturating_sub(*checked) < EXTERNAL_LINK_TTL.as_secs());
+
+    let unchecked: Vec<&String> = urls.filter(|url| !cache.contains_key(*url)).collect();
+    let results = parallel_map(&unchecked, |url| request(url));
+    let mut dead = vec![];
+    for (url, result) in unchecked.into_iter().zip(results) {
+        match result? {
+            None => {
+                cache.insert(url.clone(), now);
+            }
+            Some(problem) => dead.push((url.clone(), problem)),
+        }
+    }
+
+    if !cache.is_empty() {
+        cache_file
+            .parent()
+            .unwrap()
+            .create_dir_all::<CheckLinksError>()?;
+        cache_file
+            .create_file::<CheckLinksError>()?
+            .write_all(serde_json::to_string_pretty(&cache).unwrap())?;
+    }
+    Ok(dead)
+}
+
+/// Request `url` with `curl`, following redirects, returning what went wrong if it
+/// didn't respond successfully
+fn request(url: &str) -> Result<Option<String>, CheckLinksError> {
+    let result = Command::new("curl")
+        .args(["--silent", "--location", "--output", "/dev/null"])
+        .args(["--max-time", &EXTERNAL_LINK_TIMEOUT_SECS.to_string()])
+        .args(["--write-out", "%{http_code}", url])
+        .stdin(Stdio::null())
+        .output()
+        .into_report()
+        .attach_printable("Unable to run curl, which is needed to check external links")
+        .change_context(CheckLinksError)?;
+    let status = String::from_utf8_lossy(&result.stdout).trim().to_string();
+    Ok(match status.parse::<u16>() {
+        Ok(status) if (200..400).contains(&status) => None,
+        Ok(0) | Err(_) => Some("doesn't respond".to_string()),
+        Ok(status) => Some(format!("responds with status {status}")),
+    })
+}
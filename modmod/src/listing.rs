@@ -0,0 +1,249 @@
+//! Tree view of the structure of a track, as it would be rendered
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::{select::Edition, RenderOptions, Topic, Track, Unit};
+
+/// The modules, units, topics and exercises of a track, numbered as they would be rendered
+#[derive(Debug)]
+pub struct Listing {
+    pub name: String,
+    pub path: PathBuf,
+    pub items: Vec<ListedItem>,
+}
+
+#[derive(Debug)]
+pub struct ListedItem {
+    pub kind: ListedKind,
+    /// The number the item gets in the output, if it's rendered
+    pub number: Option<String>,
+    pub name: String,
+    /// Path to the definition file, or the directory of an exercise,
+    /// relative to the track definition. Units have no path of their own
+    pub path: Option<PathBuf>,
+    /// Why the item is left out of the render, if it is
+    pub left_out: Option<LeftOut>,
+    pub items: Vec<ListedItem>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListedKind {
+    Module,
+    Unit,
+    Topic,
+    Exercise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeftOut {
+    /// A draft, while drafts are not included
+    Draft,
+    /// Excluded by the track, by its path or its tags
+    Excluded,
+    /// A unit of which every topic is left out
+    Empty,
+    /// Not one of the selected modules or units
+    NotSelected,
+}
+
+impl fmt::Display for LeftOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LeftOut::Draft => "draft",
+            LeftOut::Excluded => "excluded",
+            LeftOut::Empty => "every topic is left out",
+            LeftOut::NotSelected => "not selected",
+        })
+    }
+}
+
+impl Track {
+    /// List every module, unit, topic and exercise of the track, with the numbers they
+    /// get when rendering with `options`, or why they are left out
+    pub fn list(&self, options: &RenderOptions) -> Listing {
+        let base_path = self.path.parent().unwrap();
+        let relative = |path: &Path| path.strip_prefix(base_path).unwrap_or(path).to_owned();
+        let selected = self.select(options, Edition::Student).track;
+        let topic_left_out = |topic: &Topic| {
+            if self.excludes(topic) {
+                Some(LeftOut::Excluded)
+            } else if topic.draft && !options.include_drafts {
+                Some(LeftOut::Draft)
+            } else {
+                None
+            }
+        };
+
+        let items = self
+            .modules
+            .iter()
+            .map(|module| {
+                let selected_module = selected.modules.iter().find(|m| m.index == module.index);
+                // Selected units and topics are in the same order as in the whole track,
+                // so the selected ones are found by walking both lists
+                let mut selected_units = selected_module
+                    .map(|m| m.data.units.as_slice())
+                    .unwrap_or_default()
+                    .iter()
+                    .peekable();
+                let units = module
+                    .data
+                    .units
+                    .iter()
+                    .map(|unit| {
+                        let selected_unit =
+                            selected_units.next_if(|u| is_same_unit(&u.data, &unit.data));
+                        let unit_number =
+                            selected_unit.map(|u| format!("{}.{}", module.index, u.index));
+                        let left_out = match selected_unit {
+                            Some(_) => None,
+                            None if unit.data.draft && !options.include_drafts => {
+                                Some(LeftOut::Draft)
+                            }
+                            None if !unit.data.topics.is_empty()
+                                && unit
+                                    .data
+                                    .topics
+                                    .iter()
+                                    .all(|t| topic_left_out(&t.data).is_some()) =>
+                            {
+                                Some(LeftOut::Empty)
+                            }
+                            None => Some(LeftOut::NotSelected),
+                        };
+
+                        let mut selected_topics = selected_unit
+                            .map(|u| u.data.topics.as_slice())
+                            .unwrap_or_default()
+                            .iter()
+                            .peekable();
+                        let mut exercise_count = 0;
+                        let topics = unit
+                            .data
+                            .topics
+                            .iter()
+                            .map(|topic| {
+                                let selected_topic = selected_topics.next_if(|t| {
+                                    t.data.path == topic.data.path && t.data.name == topic.data.name
+                                });
+                                let number = selected_topic
+                                    .zip(unit_number.as_ref())
+                                    .map(|(t, unit)| format!("{unit}.{}", t.index));
+                                let exercises = topic
+                                    .data
+                                    .exercises
+                                    .iter()
+                                    .map(|exercise| {
+                                        // Exercises are numbered within their unit
+                                        let number =
+                                            selected_topic.and(unit_number.as_ref()).map(|unit| {
+                                                exercise_count += 1;
+                                                format!("{unit}.{exercise_count}")
+                                            });
+                                        ListedItem {
+                                            kind: ListedKind::Exercise,
+                                            number,
+                                            name: exercise.data.name.clone(),
+                                            path: Some(relative(&exercise.data.path)),
+                                            left_out: None,
+                                            items: vec![],
+                                        }
+                                    })
+                                    .collect();
+                                ListedItem {
+                                    kind: ListedKind::Topic,
+                                    number,
+                                    name: topic.data.name.clone(),
+                                    path: Some(relative(&topic.data.path)),
+                                    left_out: match selected_topic {
+                                        Some(_) => None,
+                                        None => topic_left_out(&topic.data)
+                                            .or(left_out.is_none().then_some(LeftOut::NotSelected)),
+                                    },
+                                    items: exercises,
+                                }
+                            })
+                            .collect();
+                        ListedItem {
+                            kind: ListedKind::Unit,
+                            number: unit_number,
+                            name: unit.data.name.clone(),
+                            path: None,
+                            left_out,
+                            items: topics,
+                        }
+                    })
+                    .collect();
+                ListedItem {
+                    kind: ListedKind::Module,
+                    number: selected_module.map(|m| m.index.to_string()),
+                    name: module.data.name.clone(),
+                    path: Some(relative(&module.data.path)),
+                    left_out: selected_module.is_none().then_some(LeftOut::NotSelected),
+                    items: units,
+                }
+            })
+            .collect();
+
+        Listing {
+            name: self.name.clone(),
+            path: self.path.clone(),
+            items,
+        }
+    }
+}
+
+/// Units have no path, but the selection keeps their names and topics in order
+fn is_same_unit(selected: &Unit, unit: &Unit) -> bool {
+    selected.name == unit.name
+        && selected
+            .topics
+            .iter()
+            .all(|t| unit.topics.iter().any(|u| u.data.path == t.data.path))
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{name} ({path})",
+            name = self.name,
+            path = self.path.to_string_lossy()
+        )?;
+        write_items(f, &self.items, "")
+    }
+}
+
+fn write_items(f: &mut fmt::Formatter<'_>, items: &[ListedItem], indent: &str) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        let last = i + 1 == items.len();
+        let kind = match item.kind {
+            ListedKind::Module => "module",
+            ListedKind::Unit => "unit",
+            ListedKind::Topic => "topic",
+            ListedKind::Exercise => "exercise",
+        };
+        write!(
+            f,
+            "{indent}{branch}{kind} ",
+            branch = if last { "└─ " } else { "├─ " }
+        )?;
+        if let Some(number) = &item.number {
+            write!(f, "{number} ")?;
+        }
+        f.write_str(&item.name)?;
+        if let Some(path) = &item.path {
+            write!(f, " ({path})", path = path.to_string_lossy())?;
+        }
+        if let Some(left_out) = item.left_out {
+            write!(f, " [left out: {left_out}]")?;
+        }
+        writeln!(f)?;
+        let indent = format!("{indent}{}", if last { "   " } else { "│  " });
+        write_items(f, &item.items, &indent)?;
+    }
+    Ok(())
+}
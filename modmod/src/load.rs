@@ -1,44 +1,600 @@
 use std::{
     any::type_name,
+    collections::{BTreeMap, HashMap, HashSet},
     fmt, fs,
     path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
 };
 
-use error_stack::{IntoReport, Result, ResultExt};
-use serde::{de::DeserializeOwned, Deserialize};
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use serde::{
+    de::{self, value::MapAccessDeserializer, DeserializeOwned, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
 
-use crate::io::PathExt;
+use crate::{
+    codes::WarningCode,
+    diagnostic::Diagnostic,
+    io::PathExt,
+    parallel::parallel_map,
+    remote::{is_git_source, GitSource, Lockfile},
+    schema::{unknown_fields, DefinitionKind},
+    select::refers_to,
+};
 
-use super::{Exercise, Module, Topic, Track, Unit};
+use super::{
+    Appendix, BookConfig, Exercise, ExerciseKind, ExerciseLint, GlossaryEntry, Module, Quiz,
+    SlidesConfig, Topic, TopicContent, Track, Unit, UnitTemplate,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct TrackDef {
     pub name: String,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    pub language: Option<String>,
     pub modules: Vec<PathBuf>,
+    #[serde(default)]
+    pub appendices: Vec<AppendixDef>,
+    #[serde(default)]
+    pub include_profiles: BTreeMap<String, IncludeProfileDef>,
+    #[serde(default)]
+    pub warn_near_duplicates: bool,
+    #[serde(default)]
+    pub book: BookConfig,
+    #[serde(default)]
+    pub slides: SlidesConfig,
+    pub version: Option<String>,
+    pub base_url: Option<String>,
+    pub rust_toolchain: Option<String>,
+    #[serde(default)]
+    pub allow: Vec<WarningCode>,
+    /// Fail to load the track if any of its definitions has fields that modmod doesn't know
+    #[serde(default)]
+    pub deny_unknown_fields: bool,
+    /// Topics that are left out of renders, by the path of their definition or directory
+    #[serde(default)]
+    pub excluded_topics: Vec<PathBuf>,
+    /// If not empty, only topics with at least one of these tags are part of renders
+    #[serde(default)]
+    pub include_tags: Vec<String>,
+    /// Topics with any of these tags are left out of renders
+    #[serde(default)]
+    pub exclude_tags: Vec<String>,
+    /// Changes to topics that only apply to this track
+    #[serde(default)]
+    pub topic_overrides: Vec<TopicOverrideDef>,
+    /// Values that slides templates and topics refer to with `#[modmod:var(name)]`
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    /// Move the headings of every topic and exercise description to the same levels
+    #[serde(default)]
+    pub normalize_headings: bool,
+    /// Paths to the definitions of the tracks this track extends, from the nearest
+    /// to the furthest. Not part of the definition file, but found when loading it.
+    #[serde(skip)]
+    pub bases: Vec<PathBuf>,
+}
+
+/// A named include set that exercises can refer to with `include_profile`
+#[derive(Debug, Deserialize)]
+pub struct IncludeProfileDef {
+    pub includes: Vec<String>,
+}
+
+/// Include sets by profile name
+pub type IncludeProfiles = BTreeMap<String, Vec<String>>;
+
+fn resolve_profiles(defs: BTreeMap<String, IncludeProfileDef>) -> IncludeProfiles {
+    defs.into_iter()
+        .map(|(name, def)| (name, def.includes))
+        .collect()
+}
+
+/// Topics that were loaded already, so that topics that are part of several units or tracks
+/// are only loaded and checked once. Topics are loaded differently with other include
+/// profiles, so those are part of the key, and so is whether unknown fields were denied.
+#[derive(Debug, Default)]
+pub struct TopicCache(Mutex<HashMap<(PathBuf, IncludeProfiles, bool), Topic>>);
+
+impl TopicCache {
+    /// Load the topic defined at `path`, relative to `base_path`, or take it from the cache
+    fn load(
+        &self,
+        path: &Path,
+        base_path: &Path,
+        topic_index: usize,
+        profiles: &IncludeProfiles,
+        deny_unknown_fields: bool,
+    ) -> Result<Indexed<Topic>, HydrateTrackError> {
+        // Paths that can't be canonicalized are reported when loading
+        let key = base_path
+            .join(path)
+            .canonicalize()
+            .ok()
+            .map(|path| (path, profiles.clone(), deny_unknown_fields));
+        if let Some(topic) = key
+            .as_ref()
+            .and_then(|key| self.0.lock().unwrap().get(key).cloned())
+        {
+            return Ok(topic.with_index(topic_index));
+        }
+        let topic = TopicDef::load_checked(path, Some(base_path), deny_unknown_fields)
+            .change_context(HydrateTrackError)?
+            .resolve(topic_index, profiles, false)?;
+        if let Some(key) = key {
+            self.0.lock().unwrap().insert(key, topic.data.clone());
+        }
+        Ok(topic)
+    }
 }
 
 impl PathTo<TrackDef> {
-    pub fn resolve(self) -> Result<Track, HydrateTrackError> {
+    /// Resolve the track, loading topics through `topics`. If `locked`, git sources that
+    /// aren't pinned in the lockfile are an error, rather than being added to it.
+    pub fn resolve(self, topics: &TopicCache, locked: bool) -> Result<Track, HydrateTrackError> {
         let PathTo {
             data,
             path: track_path,
         } = self;
         let TrackDef {
             name,
+            authors,
+            description,
+            language,
             modules: module_paths,
+            appendices: appendix_defs,
+            include_profiles,
+            warn_near_duplicates,
+            book,
+            slides,
+            version,
+            base_url,
+            rust_toolchain,
+            allow,
+            deny_unknown_fields,
+            excluded_topics,
+            include_tags,
+            exclude_tags,
+            topic_overrides,
+            variables,
+            normalize_headings,
+            bases,
         } = data;
 
-        let mut modules = Vec::with_capacity(module_paths.len());
+        let include_profiles = resolve_profiles(include_profiles);
         let base_path = track_path.parent().unwrap();
-        for (module_path, module_index) in module_paths.into_iter().zip(1..) {
-            modules.push(
-                ModuleDef::load(&module_path, Some(base_path))
-                    .change_context(HydrateTrackError)?
-                    .resolve(module_index)?,
-            );
+        // Repositories are fetched one by one, as modules from the same repository share a clone
+        let mut lockfile = None;
+        let mut local_module_paths = Vec::new();
+        for (path, index) in module_paths.into_iter().zip(1..) {
+            let path = match GitSource::parse(&path) {
+                Some(source) => {
+                    if lockfile.is_none() {
+                        lockfile = Some(
+                            Lockfile::load(&track_path, locked)
+                                .change_context(HydrateTrackError)?,
+                        );
+                    }
+                    let lockfile = lockfile.as_mut().unwrap();
+                    source
+                        .and_then(|source| lockfile.fetch(&source))
+                        .change_context(HydrateTrackError)?
+                }
+                None => path,
+            };
+            local_module_paths.push((path, index));
+        }
+        if let Some(lockfile) = lockfile {
+            lockfile.save().change_context(HydrateTrackError)?;
+        }
+        let module_paths = local_module_paths;
+        // Modules are loaded independently of each other
+        let modules = parallel_map(&module_paths, |(module_path, module_index)| {
+            ModuleDef::load_checked(module_path, Some(base_path), deny_unknown_fields)
+                .change_context(HydrateTrackError)?
+                .resolve(
+                    *module_index,
+                    &include_profiles,
+                    topics,
+                    deny_unknown_fields,
+                )
+        });
+        let appendices = appendix_defs.into_iter().map(|def| def.resolve(base_path));
+        let mut errors = Errors::default();
+        let modules = errors.take(collect_all(modules));
+        let appendices = errors.take(collect_all(appendices));
+        errors.finish()?;
+        let (Some(mut modules), Some(appendices)) = (modules, appendices) else {
+            unreachable!("errors are returned above");
+        };
+        for topic_override in topic_overrides {
+            topic_override.apply(&mut modules, base_path, &include_profiles)?;
+        }
+
+        // Exclusions that don't exist are reported when rendering, rather than failing the load
+        let excluded_topics = excluded_topics
+            .into_iter()
+            .map(|path| {
+                let path = base_path.join(path);
+                path.canonicalize().unwrap_or(path)
+            })
+            .collect();
+
+        Ok(Track {
+            name,
+            authors,
+            description,
+            language,
+            path: track_path,
+            modules,
+            appendices,
+            include_profiles,
+            warn_near_duplicates,
+            book,
+            slides,
+            version,
+            base_url,
+            rust_toolchain,
+            allow,
+            excluded_topics,
+            include_tags,
+            exclude_tags,
+            bases,
+            variables,
+            normalize_headings,
+        })
+    }
+}
+
+/// Keys of a track definition that only have a meaning in tracks that extend another track
+const EXTENDING_KEYS: [&str; 3] = ["extends", "add_modules", "remove_modules"];
+
+impl TrackDef {
+    /// Read the track definition at `path` as a table. If it `extends` another track, the
+    /// table is merged into that of the other track: its keys replace those of the other
+    /// track, `excluded_topics` and `exclude_tags` are added to those of the other track, and modules are
+    /// added and removed with `add_modules` and `remove_modules`. Paths in the table are
+    /// made absolute, as the tracks may be in different directories.
+    /// Unknown fields are an error if `deny_unknown_fields` or if the track sets
+    /// `deny_unknown_fields`, in which case they're denied in the tracks it extends as well.
+    fn load_table(
+        path: &Path,
+        bases: &mut Vec<PathBuf>,
+        deny_unknown_fields: bool,
+    ) -> Result<toml::Table, LoadError> {
+        let error = || LoadError(type_name::<Self>(), path.to_path_buf());
+        let content = fs::read_to_string(path)
+            .into_report()
+            .attach_printable_lazy(|| {
+                format!(
+                    "Unable to read contents of file at path {path}",
+                    path = path.to_string_lossy()
+                )
+            })
+            .change_context_lazy(error)?;
+        let mut table: toml::Table = parse_definition(path, &content, error)?;
+        let deny_unknown_fields = deny_unknown_fields
+            || table
+                .get("deny_unknown_fields")
+                .and_then(toml::Value::as_bool)
+                .unwrap_or_default();
+        if deny_unknown_fields {
+            let value = serde_json::to_value(&table)
+                .into_report()
+                .change_context_lazy(error)?;
+            check_unknown_fields::<Self>(path, &value)?;
+        }
+        let dir = path.parent().unwrap();
+        make_paths_absolute(&mut table, dir);
+
+        let Some(extends) = table.get("extends") else {
+            return Ok(table);
+        };
+        let Some(extends) = extends.as_str() else {
+            return Err(Report::new(error())
+                .attach_printable("`extends` must be the path to a track definition"));
+        };
+        let base_path = dir
+            .join(extends)
+            .canonicalize()
+            .into_report()
+            .attach_printable_lazy(|| {
+                format!("Unable to canonicalize path {extends} of the track this track extends")
+            })
+            .change_context_lazy(error)?;
+        if base_path == path || bases.contains(&base_path) {
+            return Err(Report::new(error()).attach_printable(format!(
+                "Track {path} extends itself through {base}",
+                path = path.to_string_lossy(),
+                base = base_path.to_string_lossy()
+            )));
+        }
+        bases.push(base_path.clone());
+        let mut merged = Self::load_table(&base_path, bases, deny_unknown_fields)?;
+
+        let array = |table: &toml::Table, key: &str| -> Vec<toml::Value> {
+            table
+                .get(key)
+                .and_then(toml::Value::as_array)
+                .cloned()
+                .unwrap_or_default()
+        };
+        let canonical = |module: &toml::Value| {
+            let module = PathBuf::from(module.as_str().unwrap_or_default());
+            module.canonicalize().unwrap_or(module)
+        };
+        let mut merged_modules = match table.contains_key("modules") {
+            true => array(&table, "modules"),
+            false => array(&merged, "modules"),
+        };
+        for removed in array(&table, "remove_modules") {
+            let removed = canonical(&removed);
+            let count = merged_modules.len();
+            merged_modules.retain(|module| canonical(module) != removed);
+            if merged_modules.len() == count {
+                return Err(Report::new(error()).attach_printable(format!(
+                    "Track {path} removes module {module}, which is not part of the track it extends",
+                    path = path.to_string_lossy(),
+                    module = removed.to_string_lossy()
+                )));
+            }
         }
+        merged_modules.extend(array(&table, "add_modules"));
 
-        Ok(Track { name, modules })
+        // Exclusions add up
+        let exclusions = ["excluded_topics", "exclude_tags"].map(|key| {
+            let mut exclusions = array(&merged, key);
+            exclusions.extend(array(&table, key));
+            (key, exclusions)
+        });
+        // Variables are overridden one by one, so that a cohort can change just its dates
+        let mut variables = merged
+            .get("variables")
+            .and_then(toml::Value::as_table)
+            .cloned()
+            .unwrap_or_default();
+        if let Some(own) = table.get("variables").and_then(toml::Value::as_table) {
+            variables.extend(own.clone());
+        }
+        for (key, value) in table {
+            if !EXTENDING_KEYS.contains(&key.as_str()) {
+                merged.insert(key, value);
+            }
+        }
+        if !variables.is_empty() {
+            merged.insert("variables".into(), variables.into());
+        }
+        merged.insert("modules".into(), merged_modules.into());
+        for (key, exclusions) in exclusions {
+            merged.insert(key.into(), exclusions.into());
+        }
+        Ok(merged)
+    }
+}
+
+impl Load for TrackDef {
+    const KIND: DefinitionKind = DefinitionKind::Track;
+
+    fn load_checked(
+        path: &Path,
+        base_path: Option<&Path>,
+        deny_unknown_fields: bool,
+    ) -> Result<PathTo<Self>, LoadError> {
+        let path = base_path.map(|b| b.join(path)).unwrap_or(path.to_owned());
+        let path = path
+            .canonicalize()
+            .into_report()
+            .attach_printable_lazy(|| {
+                format!(
+                    "Unable to canonicalize path {path}. Make sure the path leads to an existing file.",
+                    path = path.to_string_lossy()
+                )
+            })
+            .change_context_lazy(|| LoadError(type_name::<Self>(), path.clone()))?;
+        let mut bases = vec![];
+        let table = Self::load_table(&path, &mut bases, deny_unknown_fields)?;
+        let error = || LoadError(type_name::<Self>(), path.clone());
+        let mut data: Self = match toml::Value::Table(table).try_into() {
+            Ok(data) => data,
+            // The table has no locations, so parse the definition again to find where the
+            // problem is. Tracks that extend others aren't complete by themselves.
+            Err(e) => {
+                if bases.is_empty() {
+                    if let Ok(content) = fs::read_to_string(&path) {
+                        parse_definition::<Self>(&path, &content, error)?;
+                    }
+                }
+                return Err(Report::new(error()).attach_printable(format!(
+                    "{path}: {message}",
+                    path = path.to_string_lossy(),
+                    message = e.message()
+                )));
+            }
+        };
+        data.bases = bases;
+        data.deny_unknown_fields |= deny_unknown_fields;
+        Ok(data.with_path(path))
+    }
+}
+
+/// Make the paths in a track definition table absolute, relative to `dir`
+fn make_paths_absolute(table: &mut toml::Table, dir: &Path) {
+    let absolute = |value: &mut toml::Value| {
+        if let Some(path) = value.as_str().filter(|path| !is_git_source(path)) {
+            *value = dir.join(path).to_string_lossy().into_owned().into();
+        }
+    };
+    for key in [
+        "modules",
+        "add_modules",
+        "remove_modules",
+        "excluded_topics",
+    ] {
+        if let Some(paths) = table.get_mut(key).and_then(toml::Value::as_array_mut) {
+            paths.iter_mut().for_each(absolute);
+        }
+    }
+    if let Some(appendices) = table
+        .get_mut("appendices")
+        .and_then(toml::Value::as_array_mut)
+    {
+        appendices
+            .iter_mut()
+            .filter_map(|appendix| appendix.get_mut("path"))
+            .for_each(absolute);
+    }
+    if let Some(preprocessors) = table
+        .get_mut("book")
+        .and_then(|book| book.get_mut("preprocessors"))
+        .and_then(toml::Value::as_table_mut)
+    {
+        preprocessors
+            .iter_mut()
+            .filter_map(|(_, preprocessor)| preprocessor.get_mut("assets"))
+            .filter_map(toml::Value::as_array_mut)
+            .flat_map(|assets| assets.iter_mut())
+            .for_each(absolute);
+    }
+    if let Some(overrides) = table
+        .get_mut("topic_overrides")
+        .and_then(toml::Value::as_array_mut)
+    {
+        for topic_override in overrides.iter_mut() {
+            for key in ["topic", "content"] {
+                if let Some(value) = topic_override.get_mut(key) {
+                    absolute(value);
+                }
+            }
+            if let Some(exercises) = topic_override
+                .get_mut("exercises")
+                .and_then(toml::Value::as_array_mut)
+            {
+                exercises
+                    .iter_mut()
+                    .filter_map(|exercise| exercise.get_mut("path"))
+                    .for_each(absolute);
+            }
+        }
+    }
+}
+
+/// Fields of a topic that a track replaces, without changing the topic definition.
+/// Paths are relative to the track definition.
+#[derive(Debug, Deserialize)]
+pub struct TopicOverrideDef {
+    /// The topic, by the path of its definition or the directory it's in
+    pub topic: PathBuf,
+    pub content: Option<ContentDef>,
+    pub exercises: Option<Vec<ExerciseDef>>,
+    pub objectives: Option<Vec<String>>,
+}
+
+impl TopicOverrideDef {
+    /// Apply the override to every occurrence of the topic in `modules`
+    fn apply(
+        self,
+        modules: &mut [Indexed<Module>],
+        base_path: &Path,
+        profiles: &IncludeProfiles,
+    ) -> Result<(), HydrateTrackError> {
+        let TopicOverrideDef {
+            topic: topic_path,
+            content,
+            exercises: exercise_defs,
+            objectives,
+        } = self;
+        let topic_path = base_path
+            .join(&topic_path)
+            .canonicalize()
+            .into_report()
+            .attach_printable_lazy(|| {
+                format!(
+                    "Topic {path} of an override doesn't exist",
+                    path = topic_path.to_string_lossy()
+                )
+            })
+            .change_context(HydrateTrackError)?;
+        let content = match content {
+            Some(ContentDef::Path(content)) => Some(TopicContent::File(
+                base_path
+                    .join(content)
+                    .canonicalize()
+                    .into_report()
+                    .change_context(HydrateTrackError)?,
+            )),
+            Some(ContentDef::Inline { markdown }) => Some(TopicContent::Inline(markdown)),
+            None => None,
+        };
+        let exercises = exercise_defs
+            .map(|defs| {
+                defs.into_iter()
+                    .zip(1..)
+                    .map(|(def, index)| def.resolve(index, base_path, profiles))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+
+        let topics = modules
+            .iter_mut()
+            .flat_map(|m| m.data.units.iter_mut())
+            .flat_map(|u| u.data.topics.iter_mut())
+            .map(|t| &mut t.data)
+            .filter(|topic| refers_to(&topic_path, topic));
+        let mut overridden = false;
+        for topic in topics {
+            overridden = true;
+            if let Some(content) = &content {
+                topic.content = content.clone();
+            }
+            if let Some(exercises) = &exercises {
+                topic.exercises = exercises.clone();
+            }
+            if let Some(objectives) = &objectives {
+                topic.objectives = objectives.clone();
+            }
+        }
+        if !overridden {
+            return Err(Report::new(HydrateTrackError).attach_printable(format!(
+                "Topic {path} of an override is not part of the track",
+                path = topic_path.to_string_lossy()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppendixDef {
+    pub name: String,
+    pub path: PathBuf,
+    #[serde(default)]
+    pub draft: bool,
+}
+
+impl AppendixDef {
+    fn resolve(self, base_path: &Path) -> Result<Appendix, HydrateTrackError> {
+        let AppendixDef { name, path, draft } = self;
+        let content = base_path
+            .join(&path)
+            .canonicalize()
+            .into_report()
+            .attach_printable_lazy(|| {
+                format!(
+                    "Appendix {path} doesn't exist",
+                    path = path.to_string_lossy()
+                )
+            })
+            .change_context(HydrateTrackError)?;
+        let images = images_next_to(&content)?;
+        Ok(Appendix {
+            name,
+            content,
+            images,
+            draft,
+        })
     }
 }
 
@@ -46,11 +602,27 @@ impl PathTo<TrackDef> {
 pub struct ModuleDef {
     pub name: String,
     pub description: String,
+    #[serde(default)]
+    pub objectives: Vec<String>,
+    #[serde(default)]
+    pub further_reading: Vec<String>,
     pub units: Vec<UnitDef>,
+    /// Profiles that are only available to the exercises in this module,
+    /// which take precedence over profiles of the track with the same name
+    #[serde(default)]
+    pub include_profiles: BTreeMap<String, IncludeProfileDef>,
+    /// Directory of files that the slides and the book of the track can refer to
+    pub assets: Option<PathBuf>,
 }
 
 impl PathTo<ModuleDef> {
-    fn resolve(self, module_index: usize) -> Result<Indexed<Module>, HydrateTrackError> {
+    pub fn resolve(
+        self,
+        module_index: usize,
+        track_profiles: &IncludeProfiles,
+        topics: &TopicCache,
+        deny_unknown_fields: bool,
+    ) -> Result<Indexed<Module>, HydrateTrackError> {
         let PathTo {
             data: def,
             path: module_path,
@@ -58,19 +630,42 @@ impl PathTo<ModuleDef> {
         let ModuleDef {
             name,
             description,
+            objectives,
+            further_reading,
             units: unit_defs,
+            include_profiles,
+            assets,
         } = def;
+        let assets = assets
+            .map(|assets| assets_dir(module_path.parent().unwrap(), &assets, &name))
+            .transpose()?;
 
-        let mut units = Vec::with_capacity(unit_defs.len());
-        let base_path = module_path.parent().unwrap();
-        for (unit_def, unit_index) in unit_defs.into_iter().zip(1..) {
-            units.push(unit_def.resolve(unit_index, base_path)?);
-        }
+        let mut profiles = track_profiles.clone();
+        profiles.extend(resolve_profiles(include_profiles));
+
+        let units = collect_all(
+            unit_defs
+                .into_iter()
+                .zip(1..)
+                .map(|(unit_def, unit_index)| {
+                    unit_def.resolve(
+                        unit_index,
+                        &module_path,
+                        &profiles,
+                        topics,
+                        deny_unknown_fields,
+                    )
+                }),
+        )?;
 
         Ok(Module {
             name,
+            path: module_path,
             description,
+            objectives,
+            further_reading,
             units,
+            assets,
         }
         .with_index(module_index))
     }
@@ -79,46 +674,184 @@ impl PathTo<ModuleDef> {
 #[derive(Debug, Deserialize)]
 pub struct UnitDef {
     pub name: String,
-    pub template: Option<PathBuf>,
-    pub topics: Vec<PathBuf>,
+    pub template: Option<UnitTemplatesDef>,
+    #[serde(default)]
+    pub slides: SlidesConfig,
+    pub topics: Vec<TopicRef>,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub allow: Vec<WarningCode>,
+    pub normalize_headings: Option<bool>,
+}
+
+/// The templates of a unit: that of its slides, or a list of templates that are each rendered
+/// into a file of their own
+#[derive(Debug, Deserialize)]
+#[serde(
+    untagged,
+    expecting = "a path to a slides template, or a list of tables with a template `path` and an `output` name"
+)]
+pub enum UnitTemplatesDef {
+    Path(PathBuf),
+    List(Vec<UnitTemplateDef>),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnitTemplateDef {
+    pub path: PathBuf,
+    /// Name of what the template is rendered into, like `handout`. The template without one
+    /// is that of the slides.
+    pub output: Option<String>,
+}
+
+/// A topic in a unit, either defined in a file of its own or inline in the module definition
+#[derive(Debug)]
+pub enum TopicRef {
+    Path(PathBuf),
+    Inline(Box<TopicDef>),
+}
+
+// Implemented by hand rather than as an untagged enum, so that errors in inline
+// topics aren't swallowed by a generic "did not match any variant" message
+impl<'de> Deserialize<'de> for TopicRef {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct TopicRefVisitor;
+
+        impl<'de> Visitor<'de> for TopicRefVisitor {
+            type Value = TopicRef;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a path to a topic definition file, or an inline topic table")
+            }
+
+            fn visit_str<E: de::Error>(self, path: &str) -> std::result::Result<TopicRef, E> {
+                if path.trim().is_empty() {
+                    return Err(E::custom("topic path is empty"));
+                }
+                Ok(TopicRef::Path(PathBuf::from(path)))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(
+                self,
+                map: A,
+            ) -> std::result::Result<TopicRef, A::Error> {
+                TopicDef::deserialize(MapAccessDeserializer::new(map))
+                    .map(|def| TopicRef::Inline(Box::new(def)))
+                    .map_err(|e| de::Error::custom(format!("invalid inline topic: {e}")))
+            }
+        }
+
+        deserializer.deserialize_any(TopicRefVisitor)
+    }
+}
+
+/// Check that only one of the `templates` of the unit called `name` is that of its slides,
+/// and that the others have names that can be part of a file name, which differ
+fn check_template_outputs(name: &str, templates: &[UnitTemplate]) -> Result<(), HydrateTrackError> {
+    let error = |problem: String| {
+        Err(Report::new(HydrateTrackError).attach_printable(format!("{problem}, in unit '{name}'")))
+    };
+    if templates.iter().filter(|t| t.output.is_none()).count() > 1 {
+        return error(
+            "More than one template has no `output`, while only the slides template has none"
+                .to_string(),
+        );
+    }
+    let mut outputs = HashSet::new();
+    for output in templates.iter().filter_map(|t| t.output.as_deref()) {
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if output.is_empty() || !output.chars().all(valid) {
+            return error(format!(
+                "Template output '{output}' is not a name of letters, digits, - and _"
+            ));
+        }
+        if !outputs.insert(output) {
+            return error(format!("More than one template has output '{output}'"));
+        }
+    }
+    Ok(())
 }
 
 impl UnitDef {
     fn resolve(
         self,
         unit_index: usize,
-        base_path: &Path,
+        module_path: &Path,
+        profiles: &IncludeProfiles,
+        topic_cache: &TopicCache,
+        deny_unknown_fields: bool,
     ) -> Result<Indexed<Unit>, HydrateTrackError> {
         let UnitDef {
             name,
             template,
-            topics: topic_paths,
+            slides,
+            topics: topic_refs,
+            draft,
+            allow,
+            normalize_headings,
         } = self;
 
-        let mut topics = Vec::with_capacity(topic_paths.len());
-        for (topic_path, topic_index) in topic_paths.into_iter().zip(1..) {
-            topics.push(
-                TopicDef::load(&topic_path, Some(base_path))
-                    .change_context(HydrateTrackError)?
-                    .resolve(topic_index)?,
-            );
-        }
+        let base_path = module_path.parent().unwrap();
+        let topics =
+            topic_refs
+                .into_iter()
+                .zip(1..)
+                .map(|(topic_ref, topic_index)| match topic_ref {
+                    TopicRef::Path(topic_path) => topic_cache.load(
+                        &topic_path,
+                        base_path,
+                        topic_index,
+                        profiles,
+                        deny_unknown_fields,
+                    ),
+                    // Paths in inline topics are relative to the module definition
+                    TopicRef::Inline(def) => (*def).with_path(module_path.to_path_buf()).resolve(
+                        topic_index,
+                        profiles,
+                        true,
+                    ),
+                });
 
-        let template = match template {
-            Some(t) => Some(
-                base_path
-                    .join(t)
+        let template_defs = match template {
+            None => vec![],
+            Some(UnitTemplatesDef::Path(path)) => vec![UnitTemplateDef { path, output: None }],
+            Some(UnitTemplatesDef::List(defs)) => defs,
+        };
+        let templates = template_defs
+            .into_iter()
+            .map(|UnitTemplateDef { path, output }| {
+                let path = base_path
+                    .join(&path)
                     .canonicalize()
                     .into_report()
-                    .change_context(HydrateTrackError)?,
-            ),
-            None => None,
+                    .attach_printable_lazy(|| {
+                        format!(
+                            "Template {path} of unit '{name}' doesn't exist",
+                            path = path.to_string_lossy()
+                        )
+                    })
+                    .change_context(HydrateTrackError)?;
+                Ok(UnitTemplate { path, output })
+            });
+
+        let mut errors = Errors::default();
+        let topics = errors.take(collect_all(topics));
+        let templates = errors.take(collect_all(templates));
+        errors.finish()?;
+        let (Some(topics), Some(templates)) = (topics, templates) else {
+            unreachable!("errors are returned above");
         };
+        check_template_outputs(&name, &templates)?;
 
         Ok(Unit {
             name,
-            template,
+            slides,
+            templates,
             topics,
+            draft,
+            allow,
+            normalize_headings,
         }
         .with_index(unit_index))
     }
@@ -134,13 +867,50 @@ pub struct TopicDef {
     #[serde(default)]
     pub objectives: Vec<String>,
     #[serde(default = "crate::load::serde_defaults::topic_slides_md")]
-    pub content: PathBuf,
+    pub content: ContentDef,
     #[serde(default)]
     pub further_reading: Vec<String>,
+    #[serde(default)]
+    pub draft: bool,
+    /// Topics that should be taught before this one, by the path of their definition
+    #[serde(default)]
+    pub dependencies: Vec<PathBuf>,
+    /// Markdown file with notes for instructors, only part of the instructor edition
+    pub instructor_notes: Option<PathBuf>,
+    /// Labels that tracks can select topics by
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// How long teaching the topic takes, leaving out its exercises
+    pub duration_minutes: Option<u32>,
+    /// Directory of files that the slides and the book of the track can refer to
+    pub assets: Option<PathBuf>,
+    /// Terms that the topic introduces, for the glossary of the book
+    #[serde(default)]
+    pub glossary: Vec<GlossaryEntry>,
+    /// Questions for the knowledge check at the end of the unit in the book
+    #[serde(default)]
+    pub quizzes: Vec<Quiz>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(
+    untagged,
+    expecting = "a path to a markdown file, or a table with inline `markdown`"
+)]
+pub enum ContentDef {
+    Path(PathBuf),
+    Inline { markdown: String },
 }
 
 impl PathTo<TopicDef> {
-    fn resolve(self, topic_index: usize) -> Result<Indexed<Topic>, HydrateTrackError> {
+    /// Resolve the topic, of which `path` is the definition file. For `inline`
+    /// topics, that is the module definition the topic is part of.
+    fn resolve(
+        self,
+        topic_index: usize,
+        profiles: &IncludeProfiles,
+        inline: bool,
+    ) -> Result<Indexed<Topic>, HydrateTrackError> {
         let PathTo {
             data: def,
             path: topic_path,
@@ -153,54 +923,179 @@ impl PathTo<TopicDef> {
             objectives,
             content,
             further_reading,
+            draft,
+            dependencies,
+            instructor_notes,
+            tags,
+            duration_minutes,
+            assets,
+            glossary,
+            quizzes,
         } = def;
 
-        let mut exercises = Vec::new();
         let base_path = topic_path.parent().unwrap();
-        for (exercise_def, exercise_index) in exercise_defs.into_iter().zip(1..) {
-            exercises.push(exercise_def.resolve(exercise_index, base_path)?)
-        }
+        let exercises = collect_all(exercise_defs.into_iter().zip(1..).map(
+            |(exercise_def, exercise_index)| {
+                exercise_def.resolve(exercise_index, base_path, profiles)
+            },
+        ));
 
-        let content = base_path
-            .join(content)
-            .canonicalize()
-            .into_report()
-            .change_context(HydrateTrackError)?;
+        let content = match content {
+            ContentDef::Path(content) => base_path
+                .join(&content)
+                .canonicalize()
+                .into_report()
+                .attach_printable_lazy(|| {
+                    format!(
+                        "Slides {path} of topic '{name}' don't exist",
+                        path = content.to_string_lossy()
+                    )
+                })
+                .change_context(HydrateTrackError)
+                .map(TopicContent::File),
+            ContentDef::Inline { markdown } => Ok(TopicContent::Inline(markdown)),
+        };
 
-        let images = base_path.join("images");
-        let images = images
-            .is_dir()
-            .then_some(
-                images
-                    .get_dir_content()
-                    .map(|c| c.files.into_iter().map(PathBuf::from)),
-            )
-            .transpose()?
-            .into_iter()
-            .flatten()
-            .collect();
+        let dependencies = collect_all(dependencies.into_iter().map(|dependency| {
+            base_path
+                .join(&dependency)
+                .canonicalize()
+                .into_report()
+                .attach_printable_lazy(|| {
+                    format!(
+                        "Topic '{name}' depends on {path}, which doesn't exist",
+                        path = dependency.to_string_lossy()
+                    )
+                })
+                .change_context(HydrateTrackError)
+        }));
+
+        let instructor_notes = instructor_notes
+            .map(|notes| {
+                base_path
+                    .join(&notes)
+                    .canonicalize()
+                    .into_report()
+                    .attach_printable_lazy(|| {
+                        format!(
+                            "Instructor notes {path} of topic '{name}' don't exist",
+                            path = notes.to_string_lossy()
+                        )
+                    })
+                    .change_context(HydrateTrackError)
+            })
+            .transpose();
+
+        let assets = assets
+            .map(|assets| assets_dir(base_path, &assets, &name))
+            .transpose();
+
+        // The images next to a module definition don't belong to its inline topics
+        let images = if inline {
+            Ok(vec![])
+        } else {
+            images_next_to(&topic_path)
+        };
+
+        let mut errors = Errors::default();
+        let exercises = errors.take(exercises);
+        let content = errors.take(content);
+        let dependencies = errors.take(dependencies);
+        let instructor_notes = errors.take(instructor_notes);
+        let images = errors.take(images);
+        let assets = errors.take(assets);
+        for quiz in quizzes.iter() {
+            errors.take(check_quiz(quiz, &name));
+        }
+        errors.finish()?;
+        let (
+            Some(exercises),
+            Some(content),
+            Some(dependencies),
+            Some(instructor_notes),
+            Some(images),
+            Some(assets),
+        ) = (
+            exercises,
+            content,
+            dependencies,
+            instructor_notes,
+            images,
+            assets,
+        )
+        else {
+            unreachable!("errors are returned above");
+        };
 
         Ok(Topic {
             name,
+            path: topic_path,
             exercises,
             summary,
             objectives,
             content,
             further_reading,
             images,
+            draft,
+            inline,
+            dependencies,
+            instructor_notes,
+            tags,
+            duration_minutes,
+            assets,
+            glossary,
+            quizzes,
         }
         .with_index(topic_index))
     }
 }
 
+/// Fail if `quiz` of topic `name` can't be answered
+fn check_quiz(quiz: &Quiz, name: &str) -> Result<(), HydrateTrackError> {
+    let problem = if quiz.choices.len() < 2 {
+        "has fewer than two choices"
+    } else if !quiz
+        .choices
+        .iter()
+        .any(|choice| choice.trim() == quiz.answer.trim())
+    {
+        "has an answer that is not one of its choices"
+    } else {
+        return Ok(());
+    };
+    Err(Report::new(HydrateTrackError).attach_printable(format!(
+        "Question '{}' of topic '{name}' {problem}",
+        quiz.question.trim()
+    )))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ExerciseDef {
     pub name: String,
     pub path: PathBuf,
     #[serde(default = "crate::load::serde_defaults::exercise_description_md")]
     pub description: PathBuf,
-    #[serde(default = "crate::load::serde_defaults::exercise_includes")]
-    pub includes: Vec<String>,
+    #[serde(default)]
+    pub kind: ExerciseKind,
+    /// Name of an include profile defined in the track or module
+    pub include_profile: Option<String>,
+    /// Extends the include set of the profile if there is one,
+    /// and defaults to the include set of the exercise kind otherwise.
+    /// Patterns starting with `!` are excludes
+    pub includes: Option<Vec<String>>,
+    /// Extends the exclude set of the exercise kind
+    #[serde(default)]
+    pub excludes: Vec<String>,
+    #[serde(default = "crate::load::serde_defaults::exercise_verify")]
+    pub verify: bool,
+    pub verify_command: Option<String>,
+    pub verify_timeout_secs: Option<u64>,
+    /// Lints that are run when verifying the exercise, overriding the ones given when rendering
+    pub lints: Option<Vec<ExerciseLint>>,
+    #[serde(default)]
+    pub allow: Vec<WarningCode>,
+    /// How long doing the exercise takes
+    pub duration_minutes: Option<u32>,
 }
 
 impl ExerciseDef {
@@ -208,12 +1103,22 @@ impl ExerciseDef {
         self,
         exercise_index: usize,
         base_path: &Path,
+        profiles: &IncludeProfiles,
     ) -> Result<Indexed<Exercise>, HydrateTrackError> {
         let ExerciseDef {
             name,
             path: exercise_path,
             description,
+            kind,
+            include_profile,
             includes,
+            excludes: exclude_defs,
+            verify,
+            verify_command,
+            verify_timeout_secs,
+            lints,
+            allow,
+            duration_minutes,
         } = self;
         let path = base_path
             .join(exercise_path)
@@ -225,16 +1130,80 @@ impl ExerciseDef {
             .canonicalize()
             .into_report()
             .change_context(HydrateTrackError)?;
+        let includes = match &include_profile {
+            Some(profile) => {
+                let Some(profile_includes) = profiles.get(profile) else {
+                    let available = if profiles.is_empty() {
+                        "no include profiles are defined".to_string()
+                    } else {
+                        let names: Vec<_> = profiles.keys().map(String::as_str).collect();
+                        format!("available profiles are {}", names.join(", "))
+                    };
+                    return Err(Report::new(HydrateTrackError).attach_printable(format!(
+                        "Exercise '{name}' refers to unknown include profile '{profile}', {available}"
+                    )));
+                };
+                let mut profile_includes = profile_includes.clone();
+                profile_includes.extend(includes.unwrap_or_default());
+                profile_includes
+            }
+            None => includes.unwrap_or_else(|| kind.default_includes()),
+        };
+        let (negated, includes): (Vec<_>, Vec<_>) =
+            includes.into_iter().partition(|i| i.starts_with('!'));
+        let mut excludes = kind.default_excludes();
+        excludes.extend(exclude_defs);
+        excludes.extend(negated.into_iter().map(|i| i[1..].to_string()));
         Ok(Exercise {
             name,
             path,
             description,
+            kind,
+            include_profile,
             includes,
+            excludes,
+            verify,
+            verify_command,
+            verify_timeout: verify_timeout_secs.map(Duration::from_secs),
+            lints,
+            allow,
+            duration_minutes,
         }
         .with_index(exercise_index))
     }
 }
 
+/// The `assets` directory of the module or topic `name`, relative to `base_path`
+fn assets_dir(base_path: &Path, assets: &Path, name: &str) -> Result<PathBuf, HydrateTrackError> {
+    base_path
+        .join(assets)
+        .canonicalize()
+        .ok()
+        .filter(|dir| dir.is_dir())
+        .ok_or_else(|| {
+            Report::new(HydrateTrackError).attach_printable(format!(
+                "Assets directory {path} of '{name}' doesn't exist",
+                path = assets.to_string_lossy()
+            ))
+        })
+}
+
+/// Collect the files in the `images` directory next to the file at `path`, if it exists
+fn images_next_to(path: &Path) -> Result<Vec<PathBuf>, HydrateTrackError> {
+    let images = path.parent().unwrap().join("images");
+    Ok(images
+        .is_dir()
+        .then_some(
+            images
+                .get_dir_content()
+                .map(|c| c.files.into_iter().map(PathBuf::from)),
+        )
+        .transpose()?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
 #[derive(Debug)]
 pub struct LoadError(&'static str, PathBuf);
 
@@ -252,7 +1221,20 @@ impl fmt::Display for LoadError {
 impl error_stack::Context for LoadError {}
 
 pub trait Load: DeserializeOwned + Sized + 'static {
+    /// The kind of definition, of which the schema lists the fields modmod knows
+    const KIND: DefinitionKind;
+
     fn load(path: &Path, base_path: Option<&Path>) -> Result<PathTo<Self>, LoadError> {
+        Self::load_checked(path, base_path, false)
+    }
+
+    /// Load the definition, failing if it has fields that modmod doesn't know
+    /// and `deny_unknown_fields` is set
+    fn load_checked(
+        path: &Path,
+        base_path: Option<&Path>,
+        deny_unknown_fields: bool,
+    ) -> Result<PathTo<Self>, LoadError> {
         let path = base_path.map(|b| b.join(path)).unwrap_or(path.to_owned());
         let path = path
             .canonicalize()
@@ -273,19 +1255,58 @@ pub trait Load: DeserializeOwned + Sized + 'static {
                 )
             })
             .change_context_lazy(|| LoadError(type_name::<Self>(), path.clone()))?;
-        let data: Self = toml::from_str(&content)
-            .into_report()
-            .attach_printable_lazy(|| {
-                format!("Unable to parse TOML file with contents '{content}'")
-            })
-            .change_context_lazy(|| LoadError(type_name::<Self>(), path.clone()))?;
+        let error = || LoadError(type_name::<Self>(), path.clone());
+        if deny_unknown_fields {
+            let value: serde_json::Value = parse_definition(&path, &content, error)?;
+            check_unknown_fields::<Self>(&path, &value)?;
+        }
+        let data: Self = parse_definition(&path, &content, error)?;
         Ok(data.with_path(path))
     }
 }
 
-impl Load for TrackDef {}
-impl Load for ModuleDef {}
-impl Load for TopicDef {}
+/// Fail if `value`, the definition at `path`, has fields that modmod doesn't know
+fn check_unknown_fields<T: Load>(path: &Path, value: &serde_json::Value) -> Result<(), LoadError> {
+    let unknown = unknown_fields(T::KIND, value);
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    let mut report = Report::new(LoadError(type_name::<T>(), path.to_path_buf()));
+    for field in unknown {
+        report = report.attach_printable(format!(
+            "Unknown field `{field}` in {path}",
+            path = path.to_string_lossy()
+        ));
+    }
+    Err(report)
+}
+
+/// Parse a definition in the format of its file extension: YAML for `.yaml` and `.yml`,
+/// JSON for `.json`, and TOML for any other extension. Errors point at the line that is wrong.
+fn parse_definition<T: DeserializeOwned>(
+    path: &Path,
+    content: &str,
+    error: impl Fn() -> LoadError,
+) -> Result<T, LoadError> {
+    let parsed = match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml" | "yml") => {
+            serde_yaml::from_str(content).map_err(|e| Diagnostic::yaml(path, content, &e))
+        }
+        Some("json") => {
+            serde_json::from_str(content).map_err(|e| Diagnostic::json(path, content, &e))
+        }
+        _ => toml::from_str(content).map_err(|e| Diagnostic::toml(path, content, &e)),
+    };
+    parsed.map_err(|diagnostic| Report::new(error()).attach_printable(diagnostic.to_string()))
+}
+
+impl Load for ModuleDef {
+    const KIND: DefinitionKind = DefinitionKind::Module;
+}
+
+impl Load for TopicDef {
+    const KIND: DefinitionKind = DefinitionKind::Topic;
+}
 
 #[derive(Debug)]
 pub struct PathTo<T> {
@@ -301,7 +1322,7 @@ trait WithPath: Sized {
 
 impl<T> WithPath for T {}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Indexed<T> {
     pub data: T,
     pub index: usize,
@@ -326,6 +1347,51 @@ impl fmt::Display for HydrateTrackError {
 
 impl error_stack::Context for HydrateTrackError {}
 
+/// The errors of the parts of a definition, so that every broken part is reported
+/// at once, instead of only the first one
+#[derive(Debug)]
+pub(crate) struct Errors<C>(Option<Report<C>>);
+
+impl<C> Default for Errors<C> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<C> Errors<C> {
+    /// Take the value of `result`, or keep its error
+    pub(crate) fn take<T>(&mut self, result: Result<T, C>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(report) => {
+                match &mut self.0 {
+                    Some(errors) => errors.extend_one(report),
+                    None => self.0 = Some(report),
+                }
+                None
+            }
+        }
+    }
+
+    /// Fail with every error that was kept
+    pub(crate) fn finish(self) -> Result<(), C> {
+        self.0.map_or(Ok(()), Err)
+    }
+}
+
+/// Collect the values of `results`, or fail with every error among them
+pub(crate) fn collect_all<T, C>(
+    results: impl IntoIterator<Item = Result<T, C>>,
+) -> Result<Vec<T>, C> {
+    let mut errors = Errors::default();
+    let values: Vec<T> = results
+        .into_iter()
+        .filter_map(|result| errors.take(result))
+        .collect();
+    errors.finish()?;
+    Ok(values)
+}
+
 #[doc(hidden)]
 pub mod serde_defaults {
     use std::path::PathBuf;
@@ -334,13 +1400,11 @@ pub mod serde_defaults {
         PathBuf::from("description.md")
     }
 
-    pub fn exercise_includes() -> Vec<String> {
-        ["Cargo.toml", "Cargo.lock", "src/**/*"]
-            .map(String::from)
-            .to_vec()
+    pub fn exercise_verify() -> bool {
+        true
     }
 
-    pub fn topic_slides_md() -> PathBuf {
-        PathBuf::from("slides.md")
+    pub fn topic_slides_md() -> super::ContentDef {
+        super::ContentDef::Path(PathBuf::from("slides.md"))
     }
 }
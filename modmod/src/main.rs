@@ -1,10 +1,81 @@
-use clap::Parser;
-use error_stack::Result;
-use modmod::LoadTrackError;
-use std::{path::PathBuf, process::exit};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use modmod::{
+    syllabus_pdf, AddOptions, CancellationToken, Cancelled, CheckReport, Cohort, Counts, Date,
+    DefinitionKind, ExerciseGrouping, ExerciseLint, FileTimes, ImageOptions, LinkCheckOptions,
+    LoadOptions, LoadTrackError, OrphanReport, OutputLayout, PlannedOperation, PreviewServer,
+    RenderOptions, RenderReport, SlidesFormat, TimeOfDay, Track, TrackStats, VendorReport,
+    VerifyOptions, VerifyOutcome, Weekday, DEFAULT_ORPHAN_ALLOWLIST,
+};
+use std::{
+    collections::BTreeSet,
+    env,
+    ffi::OsString,
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process::exit,
+    time::Duration,
+};
 
 #[derive(Parser)]
-struct Args {
+#[command(
+    about = "Stitch together teach-rs content into a course",
+    after_help = "Without a command, modmod renders: `modmod -o out track.toml` is `modmod render -o out track.toml`."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Render a track into exercise scaffolding, an exercise book, and slides
+    Render(Box<RenderArgs>),
+    /// Check that a track can be rendered, without writing anything
+    Check(CheckArgs),
+    /// Render a track, and render it again whenever its sources change
+    Watch(WatchArgs),
+    /// Watch a track, and serve its book with pages that reload after every render. The slides are not previewed
+    Serve(ServeArgs),
+    /// Copy modules that live outside of the course repository into it
+    Vendor(VendorArgs),
+    /// List files in topic and exercise directories that no render reads
+    Orphans(OrphansArgs),
+    /// Print the JSON Schema of a definition format, for editors to validate definitions with
+    Schema(SchemaArgs),
+    /// Create a track, module, topic or exercise, with the files it refers to
+    New(NewArgs),
+    /// Add an existing module, topic or exercise to the track, unit or topic it's part of
+    Add(AddArgs),
+    /// Print the modules, units, topics and exercises of a track, numbered as they're rendered
+    List(ListArgs),
+    /// Count the slides, words, exercises, exercise files and planned minutes of every unit and module of a track
+    Stats(StatsArgs),
+    /// Create an iCalendar file with an event for every unit of a track, on the sessions of a cohort
+    Calendar(CalendarArgs),
+    /// Write a one-page overview of a track, with the objectives and exercises of every unit
+    Syllabus(SyllabusArgs),
+    /// Render a track into a single ZIP archive, to hand to training environments without internet access
+    Bundle(BundleArgs),
+    /// Export the content of a track for use in other tools
+    Export(ExportArgs),
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    #[command(subcommand)]
+    format: ExportFormat,
+}
+
+#[derive(Subcommand)]
+enum ExportFormat {
+    /// Write a deck of Anki flashcards per module, from the objectives and summaries of topics and their glossary terms
+    Anki(AnkiArgs),
+}
+
+#[derive(Args)]
+struct RenderArgs {
     #[arg(
         short = 'o',
         long = "output",
@@ -13,27 +84,1613 @@ struct Args {
     output_dir: PathBuf,
     #[arg(short = 'c', long = "clear", help = "Clear the output folder")]
     clear_output_dir: bool,
+    #[arg(
+        short = 'a',
+        long = "atomic",
+        help = "Render into a staging folder first, and only replace the output folder once rendering succeeded"
+    )]
+    atomic: bool,
+    #[arg(long = "strict", help = "Treat warnings as errors")]
+    strict: bool,
+    #[arg(
+        long = "include-drafts",
+        help = "Render draft units, topics and appendices, marked as drafts, instead of leaving them out"
+    )]
+    include_drafts: bool,
+    #[arg(
+        long = "module",
+        value_name = "MODULE",
+        help = "Only render this module, by number or name. Can be given more than once"
+    )]
+    modules: Vec<String>,
+    #[arg(
+        long = "unit",
+        value_name = "UNIT",
+        help = "Only render this unit, by number in its module like 2, by module and unit number like 1.2, or by name. Can be given more than once"
+    )]
+    units: Vec<String>,
+    #[arg(
+        long = "allow-output-in-sources",
+        help = "Render even if the output folder overlaps with a folder sources are read from"
+    )]
+    allow_output_in_sources: bool,
+    #[arg(
+        long = "keep-solutions",
+        help = "Keep the solutions in the exercises, for instance for instructors"
+    )]
+    keep_solutions: bool,
+    #[arg(
+        long = "classroom",
+        value_name = "GROUPING",
+        help = "Also render the exercises as GitHub Classroom assignment repositories, of every exercise or unit"
+    )]
+    classroom: Option<ExerciseGrouping>,
+    #[arg(
+        long = "cargo-workspaces",
+        help = "Make the exercise folder of every unit a Cargo workspace, so that its exercises can be built at once"
+    )]
+    cargo_workspaces: bool,
+    #[arg(
+        long = "zip-exercises",
+        help = "Also pack the exercises of every unit into a ZIP archive, to hand out as a single download"
+    )]
+    zip_exercises: bool,
+    #[arg(
+        long = "vendor-crates",
+        help = "Vendor the dependencies of the exercises with cargo vendor, so that they build without internet access"
+    )]
+    vendor_crates: bool,
+    #[arg(
+        long = "git-init",
+        value_name = "GROUPING",
+        help = "Make every exercise or the exercises of every unit a git repository with an initial commit"
+    )]
+    git_init: Option<ExerciseGrouping>,
+    #[arg(
+        long = "exercise-readmes",
+        help = "Give every exercise a README with its description and a link to its unit in the book"
+    )]
+    exercise_readmes: bool,
+    #[arg(
+        long = "handouts",
+        help = "Also render a handout of every unit from its slides, to study from"
+    )]
+    handouts: bool,
+    #[arg(
+        long = "search-index",
+        help = "Also write an index of the modules, units, topics and exercises to search the whole course with"
+    )]
+    search_index: bool,
+    #[arg(
+        long = "render-mermaid",
+        help = "Render the Mermaid diagrams in slides and the book to SVG images with mmdc"
+    )]
+    render_mermaid: bool,
+    #[arg(
+        long = "optimize-images",
+        help = "Downsize and recompress the PNG and JPEG images in the slides and the book with ImageMagick"
+    )]
+    optimize_images: bool,
+    #[arg(
+        long = "max-image-width",
+        value_name = "PIXELS",
+        default_value_t = ImageOptions::default().max_width,
+        help = "Width that --optimize-images scales wider images down to"
+    )]
+    max_image_width: u32,
+    #[arg(
+        long = "image-quality",
+        value_name = "QUALITY",
+        default_value_t = ImageOptions::default().quality,
+        value_parser = clap::value_parser!(u8).range(1..=100),
+        help = "Quality from 1 to 100 that --optimize-images recompresses images with"
+    )]
+    image_quality: u8,
+    #[arg(
+        long = "build-slides",
+        visible_alias = "slides-format",
+        value_name = "FORMAT",
+        help = "Build the slide decks into html or pdf with the tools of their backend, like Slidev"
+    )]
+    build_slides: Option<SlidesFormat>,
+    #[arg(
+        long = "build-book",
+        help = "Build the book with mdbook into its build folder, book/target by default, after building the slides"
+    )]
+    build_book: bool,
+    #[arg(
+        long = "check-links",
+        help = "Warn about links in the slides and the book that point to nothing in the output"
+    )]
+    check_links: bool,
+    #[arg(
+        long = "check-external-links",
+        help = "Also check that external links respond, with curl. Links that responded are cached for a week"
+    )]
+    check_external_links: bool,
+    #[arg(
+        long = "instructor",
+        value_name = "DIR",
+        help = "Also render an instructor edition, with solutions and instructor notes, into this folder"
+    )]
+    instructor: Option<PathBuf>,
+    #[arg(
+        short = 'i',
+        long = "incremental",
+        conflicts_with_all = ["clear_output_dir", "atomic"],
+        help = "Render into the output of an earlier render, only copying the exercise files that changed since then"
+    )]
+    incremental: bool,
+    #[arg(
+        long = "locked",
+        help = "Fail instead of updating modmod.lock when a git source isn't pinned in it"
+    )]
+    locked: bool,
+    #[arg(
+        long = "deny-unknown-fields",
+        help = "Fail if any definition has fields that modmod doesn't know, like misspelled ones"
+    )]
+    deny_unknown_fields: bool,
+    #[arg(
+        long = "dry-run",
+        help = "Print the folders and files the render would create, without writing anything"
+    )]
+    dry_run: bool,
+    #[arg(
+        long = "root-template",
+        value_name = "TEMPLATE",
+        help = "Path inside the output folder to render into, like {track_slug}/{version}"
+    )]
+    root_template: Option<String>,
+    #[arg(
+        long = "module-dir-template",
+        value_name = "TEMPLATE",
+        help = "Name of the exercise folder of each module [default: {module_number}-{module_slug}]"
+    )]
+    module_dir_template: Option<String>,
+    #[arg(
+        long = "unit-dir-template",
+        value_name = "TEMPLATE",
+        help = "Name of the exercise folder of each unit [default: {unit_number}-{unit_slug}]"
+    )]
+    unit_dir_template: Option<String>,
+    #[arg(
+        long = "exercise-dir-template",
+        value_name = "TEMPLATE",
+        help = "Name of the folder of each exercise [default: {exercise_number}-{exercise_slug}]"
+    )]
+    exercise_dir_template: Option<String>,
+    #[arg(
+        long = "emit-deps",
+        value_name = "FILE",
+        help = "Write the list of source files read by the render to this file, as JSON if it has a .json extension"
+    )]
+    emit_deps: Option<PathBuf>,
+    #[arg(
+        long = "outline",
+        value_name = "FILE",
+        help = "Write a versioned JSON outline of the track to this file"
+    )]
+    outline: Option<PathBuf>,
+    #[arg(
+        long = "warnings-json",
+        value_name = "FILE",
+        help = "Write the warnings and notes of the render to this file as JSON"
+    )]
+    warnings_json: Option<PathBuf>,
+    #[arg(
+        long = "verify",
+        visible_alias = "verify-exercises",
+        help = "Check that the rendered exercises build"
+    )]
+    verify: bool,
+    #[arg(
+        long = "verify-tests",
+        help = "Also run the tests of the rendered Cargo exercises that build, implies --verify"
+    )]
+    verify_tests: bool,
+    #[arg(
+        long = "verify-lint",
+        value_name = "LINT",
+        help = "Also run this lint, clippy or rustfmt, on the rendered Cargo exercises that build, unless they set their own lints. Can be given more than once, implies --verify"
+    )]
+    verify_lints: Vec<ExerciseLint>,
+    #[arg(
+        long = "verify-command",
+        value_name = "COMMAND",
+        default_value = "cargo check",
+        help = "Command that is run in each rendered exercise to verify it"
+    )]
+    verify_command: String,
+    #[arg(
+        long = "verify-test-command",
+        value_name = "COMMAND",
+        default_value = "cargo test",
+        help = "Command that runs the tests of each rendered Cargo exercise with --verify-tests"
+    )]
+    verify_test_command: String,
+    #[arg(
+        long = "verify-timeout",
+        value_name = "SECONDS",
+        help = "Maximum duration of verifying a single exercise"
+    )]
+    verify_timeout_secs: Option<u64>,
+    /// Tracks to render. Several tracks are each rendered into a folder named after their slug
+    #[arg(value_name = "TRACK_TOML_PATH", required = true)]
+    track_toml_paths: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+struct CheckArgs {
+    #[arg(long = "strict", help = "Treat warnings as errors")]
+    strict: bool,
+    #[arg(long = "include-drafts", help = "Also check draft units and topics")]
+    include_drafts: bool,
+    #[arg(
+        long = "deny-unknown-fields",
+        help = "Fail if any definition has fields that modmod doesn't know, like misspelled ones"
+    )]
+    deny_unknown_fields: bool,
+    #[arg(
+        long = "check-snippets",
+        help = "Also compile the Rust code blocks on the slides with rustc, like doctests"
+    )]
+    check_snippets: bool,
+    track_toml_path: PathBuf,
+}
+
+#[derive(Args)]
+struct WatchArgs {
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "The folder the output will be written to"
+    )]
+    output_dir: PathBuf,
+    #[arg(
+        short = 'c',
+        long = "clear",
+        help = "Clear the output folder before the first render"
+    )]
+    clear_output_dir: bool,
+    #[arg(
+        long = "include-drafts",
+        help = "Render draft units, topics and appendices, marked as drafts, instead of leaving them out"
+    )]
+    include_drafts: bool,
+    #[arg(
+        long = "interval",
+        value_name = "MILLISECONDS",
+        default_value_t = 500,
+        help = "How often to look for changes"
+    )]
+    interval_ms: u64,
+    #[arg(
+        long = "build-book",
+        help = "Build the book with mdbook into its build folder, book/target by default, after every render"
+    )]
+    build_book: bool,
     track_toml_path: PathBuf,
 }
 
+#[derive(Args)]
+struct ServeArgs {
+    #[command(flatten)]
+    watch: WatchArgs,
+    #[arg(
+        short = 'p',
+        long = "port",
+        default_value_t = 3000,
+        help = "The port to serve the output at, on localhost"
+    )]
+    port: u16,
+}
+
+#[derive(Args)]
+struct VendorArgs {
+    #[arg(
+        short = 'd',
+        long = "dir",
+        default_value = "vendor",
+        help = "The folder modules are copied into, relative to the track definition"
+    )]
+    vendor_dir: PathBuf,
+    track_toml_path: PathBuf,
+}
+
+#[derive(Args)]
+struct OrphansArgs {
+    #[arg(
+        long = "allow",
+        value_name = "PATTERN",
+        help = "Never report files matching this pattern, in addition to README.md, .gitignore, .DS_Store, and target folders"
+    )]
+    allow: Vec<String>,
+    #[arg(
+        long = "json",
+        value_name = "FILE",
+        help = "Write the orphaned files to this file as JSON"
+    )]
+    json: Option<PathBuf>,
+    #[arg(
+        long = "deny",
+        help = "Exit with an error if any orphaned files are found"
+    )]
+    deny: bool,
+    track_toml_path: PathBuf,
+}
+
+#[derive(Args)]
+struct SchemaArgs {
+    #[arg(
+        short = 'o',
+        long = "output",
+        value_name = "FILE",
+        help = "Write the schema to this file instead of printing it"
+    )]
+    output: Option<PathBuf>,
+    #[arg(help = "The kind of definition: track, module, topic or exercise")]
+    kind: DefinitionKind,
+}
+
+#[derive(Args)]
+struct NewArgs {
+    #[arg(help = "The kind of definition: track, module, topic or exercise")]
+    kind: DefinitionKind,
+    #[arg(help = "The folder to create the definition in")]
+    dir: PathBuf,
+    #[arg(help = "The name of the track, module, topic or exercise")]
+    name: String,
+}
+
+#[derive(Args)]
+struct AddArgs {
+    #[arg(help = "The kind of definition: module, topic or exercise")]
+    kind: DefinitionKind,
+    #[arg(help = "The definition to add, or the folder of an exercise")]
+    path: PathBuf,
+    #[arg(
+        long = "to",
+        value_name = "DEFINITION",
+        help = "The track, module or topic definition to add to. Defaults to the closest one in the folders containing PATH"
+    )]
+    parent: Option<PathBuf>,
+    #[arg(
+        long = "unit",
+        help = "The unit to add a topic to, by number or name. Only needed if the module has more than one unit"
+    )]
+    unit: Option<String>,
+    #[arg(
+        long = "name",
+        help = "The name of an added exercise. Defaults to the name of its folder"
+    )]
+    name: Option<String>,
+}
+
+#[derive(Args)]
+struct ListArgs {
+    #[arg(
+        long = "include-drafts",
+        help = "List draft units and topics as rendered, instead of as left out"
+    )]
+    include_drafts: bool,
+    #[arg(
+        long = "module",
+        value_name = "MODULE",
+        help = "Only number this module, like when rendering it, by number or name. Can be given more than once"
+    )]
+    modules: Vec<String>,
+    #[arg(
+        long = "unit",
+        value_name = "UNIT",
+        help = "Only number this unit, like when rendering it, by number in its module like 2, by module and unit number like 1.2, or by name. Can be given more than once"
+    )]
+    units: Vec<String>,
+    track_toml_path: PathBuf,
+}
+
+#[derive(Args)]
+struct StatsArgs {
+    #[arg(long = "include-drafts", help = "Also count draft units and topics")]
+    include_drafts: bool,
+    #[arg(
+        long = "module",
+        value_name = "MODULE",
+        help = "Only count this module, by number or name. Can be given more than once"
+    )]
+    modules: Vec<String>,
+    #[arg(
+        long = "unit",
+        value_name = "UNIT",
+        help = "Only count this unit, by number in its module like 2, by module and unit number like 1.2, or by name. Can be given more than once"
+    )]
+    units: Vec<String>,
+    track_toml_path: PathBuf,
+}
+
+#[derive(Args)]
+struct CalendarArgs {
+    #[arg(
+        short = 'o',
+        long = "output",
+        value_name = "FILE",
+        help = "Write the calendar to this file instead of printing it"
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        long = "cohort",
+        value_name = "FILE",
+        help = "TOML file with the start date and sessions of the cohort. The other options override its settings"
+    )]
+    cohort: Option<PathBuf>,
+    #[arg(
+        long = "start",
+        value_name = "DATE",
+        required_unless_present = "cohort",
+        help = "Date of the first session, like 2024-09-02"
+    )]
+    start: Option<Date>,
+    #[arg(
+        long = "weekday",
+        value_name = "DAY",
+        value_delimiter = ',',
+        help = "Day of the week that sessions are on, like mon or thursday. Can be given more than once [default: the weekday of the start date]"
+    )]
+    weekdays: Vec<Weekday>,
+    #[arg(
+        long = "time",
+        value_name = "TIME",
+        help = "When sessions start, like 13:30 [default: 09:00]"
+    )]
+    time: Option<TimeOfDay>,
+    #[arg(
+        long = "session-minutes",
+        value_name = "MINUTES",
+        help = "How long sessions are [default: 180]"
+    )]
+    session_minutes: Option<u32>,
+    #[arg(
+        long = "skip",
+        value_name = "DATE",
+        help = "Date without a session, like a holiday. Can be given more than once"
+    )]
+    skip: Vec<Date>,
+    #[arg(long = "include-drafts", help = "Also plan draft units")]
+    include_drafts: bool,
+    track_toml_path: PathBuf,
+}
+
+#[derive(Args)]
+struct SyllabusArgs {
+    #[arg(
+        short = 'o',
+        long = "output",
+        value_name = "FILE",
+        help = "Write the syllabus to this file, like SYLLABUS.md, instead of printing it"
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        long = "pdf",
+        value_name = "FILE",
+        help = "Also convert the syllabus to a PDF with pandoc, which has to be installed"
+    )]
+    pdf: Option<PathBuf>,
+    #[arg(long = "include-drafts", help = "Also describe draft units and topics")]
+    include_drafts: bool,
+    #[arg(
+        long = "module",
+        value_name = "MODULE",
+        help = "Only describe this module, by number or name. Can be given more than once"
+    )]
+    modules: Vec<String>,
+    #[arg(
+        long = "unit",
+        value_name = "UNIT",
+        help = "Only describe this unit, by number in its module like 2, by module and unit number like 1.2, or by name. Can be given more than once"
+    )]
+    units: Vec<String>,
+    track_toml_path: PathBuf,
+}
+
+#[derive(Args)]
+struct AnkiArgs {
+    #[arg(
+        short = 'o',
+        long = "output",
+        help = "The folder the decks will be written to"
+    )]
+    output_dir: PathBuf,
+    #[arg(long = "include-drafts", help = "Also export draft units and topics")]
+    include_drafts: bool,
+    #[arg(
+        long = "module",
+        value_name = "MODULE",
+        help = "Only export this module, by number or name. Can be given more than once"
+    )]
+    modules: Vec<String>,
+    #[arg(
+        long = "unit",
+        value_name = "UNIT",
+        help = "Only export this unit, by number in its module like 2, by module and unit number like 1.2, or by name. Can be given more than once"
+    )]
+    units: Vec<String>,
+    track_toml_path: PathBuf,
+}
+
+#[derive(Args)]
+struct BundleArgs {
+    #[arg(
+        short = 'o',
+        long = "output",
+        value_name = "FILE",
+        help = "Write the archive to this file, like course.zip"
+    )]
+    output: PathBuf,
+    #[arg(
+        long = "vendor-crates",
+        help = "Also bundle the dependencies of the exercises, vendored with cargo vendor"
+    )]
+    vendor_crates: bool,
+    #[arg(
+        long = "cargo-workspaces",
+        help = "Make the exercise folder of every unit a Cargo workspace, so that its exercises can be built at once"
+    )]
+    cargo_workspaces: bool,
+    #[arg(long = "include-drafts", help = "Also bundle draft units and topics")]
+    include_drafts: bool,
+    #[arg(
+        long = "module",
+        value_name = "MODULE",
+        help = "Only bundle this module, by number or name. Can be given more than once"
+    )]
+    modules: Vec<String>,
+    #[arg(
+        long = "unit",
+        value_name = "UNIT",
+        help = "Only bundle this unit, by number in its module like 2, by module and unit number like 1.2, or by name. Can be given more than once"
+    )]
+    units: Vec<String>,
+    #[arg(
+        long = "locked",
+        help = "Fail instead of updating modmod.lock when a git source isn't pinned in it"
+    )]
+    locked: bool,
+    track_toml_path: PathBuf,
+}
+
+/// The command line arguments, with `render` as the command if none is given, so that
+/// `modmod -o out track.toml` renders like it did before there were other commands
+fn args_with_default_command() -> Vec<OsString> {
+    let mut args: Vec<OsString> = env::args_os().collect();
+    let command = Cli::command();
+    let is_command = |arg: &str| {
+        matches!(arg, "help" | "-h" | "--help")
+            || command
+                .get_subcommands()
+                .any(|c| c.get_name() == arg || c.get_all_aliases().any(|a| a == arg))
+    };
+    if args
+        .get(1)
+        .is_some_and(|arg| !is_command(&arg.to_string_lossy()))
+    {
+        args.insert(1, "render".into());
+    }
+    args
+}
+
 fn main() {
-    let args = Args::parse();
+    let cli = Cli::parse_from(args_with_default_command());
+    match cli.command {
+        Command::Render(args) => render(*args),
+        Command::Check(args) => check(args),
+        Command::Watch(args) => watch(args, || {}),
+        Command::Serve(args) => serve(args),
+        Command::Vendor(args) => vendor(args),
+        Command::Orphans(args) => orphans(args),
+        Command::Schema(args) => schema(args),
+        Command::New(args) => new(args),
+        Command::Add(args) => add(args),
+        Command::List(args) => list(args),
+        Command::Stats(args) => stats(args),
+        Command::Calendar(args) => calendar(args),
+        Command::Syllabus(args) => syllabus(args),
+        Command::Bundle(args) => bundle(args),
+        Command::Export(args) => match args.format {
+            ExportFormat::Anki(args) => export_anki(args),
+        },
+    }
+}
 
-    fn run(args: Args) -> Result<(), LoadTrackError> {
-        let Args {
+fn render(args: RenderArgs) {
+    let cancellation = CancellationToken::new();
+    {
+        let cancellation = cancellation.clone();
+        ctrlc::set_handler(move || {
+            if cancellation.is_cancelled() {
+                // Second Ctrl-C: stop waiting for the cleanup
+                exit(130);
+            }
+            eprintln!("Cancelling render...");
+            cancellation.cancel();
+        })
+        .expect("Error setting Ctrl-C handler");
+    }
+
+    fn run(
+        args: RenderArgs,
+        cancellation: CancellationToken,
+    ) -> Result<Vec<RenderReport>, LoadTrackError> {
+        let RenderArgs {
             output_dir,
             clear_output_dir,
-            track_toml_path,
+            atomic,
+            strict,
+            include_drafts,
+            modules,
+            units,
+            allow_output_in_sources,
+            keep_solutions,
+            classroom,
+            cargo_workspaces,
+            zip_exercises,
+            vendor_crates,
+            git_init,
+            exercise_readmes,
+            handouts,
+            search_index,
+            render_mermaid,
+            optimize_images,
+            max_image_width,
+            image_quality,
+            build_slides,
+            build_book,
+            check_links,
+            check_external_links,
+            instructor,
+            incremental,
+            locked,
+            deny_unknown_fields,
+            dry_run,
+            root_template,
+            module_dir_template,
+            unit_dir_template,
+            exercise_dir_template,
+            emit_deps,
+            outline,
+            warnings_json,
+            verify,
+            verify_tests,
+            verify_lints,
+            verify_command,
+            verify_test_command,
+            verify_timeout_secs,
+            track_toml_paths,
         } = args;
-        let track = modmod::Track::load_toml_def(track_toml_path)?;
-        track.render(output_dir, clear_output_dir)?;
-        Ok(())
+        let tracks = Track::load_toml_defs(
+            &track_toml_paths,
+            LoadOptions {
+                locked,
+                deny_unknown_fields,
+            },
+        )?;
+        if let Some(deps_path) = emit_deps {
+            write_deps(&tracks, &deps_path)?;
+        }
+        if let Some(outline_path) = outline {
+            let [track] = tracks.as_slice() else {
+                return Err(Report::new(LoadTrackError).attach_printable(
+                    "An outline can only be written when rendering a single track",
+                ));
+            };
+            let outline = serde_json::to_string_pretty(&track.outline()).unwrap();
+            write_file(&outline_path, outline)?;
+        }
+        let default_layout = OutputLayout::default();
+        let layout = OutputLayout {
+            root: root_template.unwrap_or(default_layout.root),
+            module_dir: module_dir_template.unwrap_or(default_layout.module_dir),
+            unit_dir: unit_dir_template.unwrap_or(default_layout.unit_dir),
+            exercise_dir: exercise_dir_template.unwrap_or(default_layout.exercise_dir),
+        };
+        let options = RenderOptions {
+            clear_output: clear_output_dir,
+            atomic,
+            strict,
+            include_drafts,
+            modules,
+            units,
+            allow_output_in_sources,
+            layout,
+            keep_solutions,
+            classroom,
+            cargo_workspaces,
+            zip_exercises,
+            vendor_crates,
+            git_init,
+            exercise_readmes,
+            handouts,
+            search_index,
+            render_mermaid,
+            optimize_images: optimize_images.then_some(ImageOptions {
+                max_width: max_image_width,
+                quality: image_quality,
+            }),
+            build_slides,
+            build_book,
+            check_links: (check_links || check_external_links).then_some(LinkCheckOptions {
+                external: check_external_links,
+            }),
+            instructor_output: instructor,
+            incremental,
+            dry_run,
+            verify: (verify || verify_tests || !verify_lints.is_empty()).then(|| VerifyOptions {
+                command: verify_command,
+                lints: verify_lints,
+                test_command: verify_tests.then_some(verify_test_command),
+                timeout: verify_timeout_secs.map(Duration::from_secs),
+            }),
+            cancellation: Some(cancellation),
+        };
+        let reports = match tracks.as_slice() {
+            [track] => vec![track.render(output_dir, &options)?],
+            tracks => Track::render_all(tracks, output_dir, &options)?,
+        };
+        if let Some(warnings_path) = warnings_json {
+            let warnings = serde_json::json!({
+                "warnings": reports.iter().flat_map(|r| &r.warnings).collect::<Vec<_>>(),
+                "notes": reports.iter().flat_map(|r| &r.notes).collect::<Vec<_>>(),
+            });
+            write_file(
+                &warnings_path,
+                serde_json::to_string_pretty(&warnings).unwrap(),
+            )?;
+        }
+        Ok(reports)
+    }
+
+    fn write_deps(tracks: &[Track], deps_path: &Path) -> Result<(), LoadTrackError> {
+        let mut files = BTreeSet::new();
+        for track in tracks.iter() {
+            files.extend(track.source_files()?);
+        }
+        let content = if deps_path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::to_string_pretty(&files).unwrap()
+        } else {
+            files
+                .iter()
+                .map(|f| format!("{}\n", f.to_string_lossy()))
+                .collect()
+        };
+        write_file(deps_path, content)
+    }
+
+    match run(args, cancellation) {
+        Ok(reports) => {
+            reports.iter().for_each(print_report);
+            let verification_failed = reports.iter().flat_map(|r| &r.verification).any(|v| {
+                matches!(
+                    v.outcome,
+                    VerifyOutcome::Failed { .. } | VerifyOutcome::TimedOut
+                )
+            });
+            if verification_failed {
+                exit(1);
+            }
+        }
+        Err(e) if e.contains::<Cancelled>() => {
+            eprintln!("Render cancelled, partial output was removed");
+            exit(130);
+        }
+        Err(e) => {
+            eprintln!("Error rendering track: {e:?}");
+            exit(1);
+        }
+    }
+}
+
+fn check(args: CheckArgs) {
+    let CheckArgs {
+        strict,
+        include_drafts,
+        deny_unknown_fields,
+        check_snippets,
+        track_toml_path,
+    } = args;
+
+    fn run(
+        track_toml_path: PathBuf,
+        include_drafts: bool,
+        deny_unknown_fields: bool,
+        check_snippets: bool,
+    ) -> Result<CheckReport, LoadTrackError> {
+        let track = Track::load_toml_def_with(
+            track_toml_path,
+            LoadOptions {
+                deny_unknown_fields,
+                ..Default::default()
+            },
+        )?;
+        let options = RenderOptions {
+            include_drafts,
+            ..Default::default()
+        };
+        let mut report = track.check(&options)?;
+        if check_snippets {
+            let warnings = track
+                .check_snippets(&options)
+                .change_context(LoadTrackError)?;
+            let (notes, warnings): (Vec<_>, Vec<_>) =
+                warnings.into_iter().partition(|warning| warning.allowed);
+            report.warnings.extend(warnings);
+            report.notes.extend(notes);
+        }
+        Ok(report)
+    }
+
+    match run(
+        track_toml_path,
+        include_drafts,
+        deny_unknown_fields,
+        check_snippets,
+    ) {
+        Ok(report) => {
+            print_check_report(&report);
+            if !report.is_ok() || (strict && !report.warnings.is_empty()) {
+                exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("Error checking track: {e:?}");
+            exit(1);
+        }
+    }
+}
+
+fn print_check_report(report: &CheckReport) {
+    if report.is_ok() {
+        println!("'{}' can be rendered", report.track_name);
+    } else {
+        println!(
+            "'{}' has {} problem(s):",
+            report.track_name,
+            report.problems.len()
+        );
+        for problem in report.problems.iter() {
+            println!("  - {problem}");
+        }
+    }
+    if !report.warnings.is_empty() {
+        println!("{} warning(s):", report.warnings.len());
+        for warning in report.warnings.iter() {
+            println!("  - {warning}");
+        }
+    }
+    if !report.notes.is_empty() {
+        println!("{} note(s), from allowed warnings:", report.notes.len());
+        for note in report.notes.iter() {
+            println!("  - {note}");
+        }
     }
+}
+
+/// Render the track, and render it again whenever its sources change,
+/// calling `on_render` after every render that succeeded
+fn watch(args: WatchArgs, on_render: impl Fn()) {
+    let WatchArgs {
+        output_dir,
+        clear_output_dir,
+        include_drafts,
+        interval_ms,
+        build_book,
+        track_toml_path,
+    } = args;
+    let mut options = RenderOptions {
+        clear_output: clear_output_dir,
+        include_drafts,
+        build_book,
+        ..Default::default()
+    };
 
-    if let Err(e) = run(args) {
-        eprintln!("Error rendering track: {e:?}");
+    let mut track = match Track::load_toml_def(&track_toml_path) {
+        Ok(track) => track,
+        Err(e) => {
+            eprintln!("Error loading track: {e:?}");
+            exit(1);
+        }
+    };
+    let mut rendered = match track.render(&output_dir, &options) {
+        Ok(report) => {
+            print_report(&report);
+            on_render();
+            true
+        }
+        Err(e) => {
+            eprintln!("Error rendering track: {e:?}");
+            false
+        }
+    };
+    // Later renders replace the output of the first one
+    options.clear_output = true;
+
+    let watched = |track: &Track| FileTimes::of(track.watched_files().unwrap_or_default());
+    let mut times = watched(&track);
+    println!("Watching for changes, press Ctrl-C to stop");
+    loop {
+        std::thread::sleep(Duration::from_millis(interval_ms));
+        let current = watched(&track);
+        let changed = current.changed_since(&times);
+        if changed.is_empty() {
+            continue;
+        }
+        times = current;
+        for file in changed.iter() {
+            println!("Changed: {}", file.to_string_lossy());
+        }
+
+        // Errors are reported, and rendering is tried again after the next change
+        track = match Track::load_toml_def(&track_toml_path) {
+            Ok(track) => track,
+            Err(e) => {
+                eprintln!("Error loading track: {e:?}");
+                // The changes so far are only picked up by rendering everything
+                rendered = false;
+                continue;
+            }
+        };
+        // Only output that is complete can be updated partially
+        let result = if rendered {
+            track.rerender(&output_dir, &options, &changed)
+        } else {
+            track.render(&output_dir, &options)
+        };
+        rendered = match result {
+            Ok(report) => {
+                print_report(&report);
+                on_render();
+                true
+            }
+            Err(e) => {
+                eprintln!("Error rendering track: {e:?}");
+                false
+            }
+        };
+        times = watched(&track);
+    }
+}
+
+fn serve(args: ServeArgs) {
+    let address = format!("127.0.0.1:{}", args.port);
+    let server = match PreviewServer::start(&address, &args.watch.output_dir) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Error starting server: {e:?}");
+            exit(1);
+        }
+    };
+    println!("Serving the output at http://{}/", server.address());
+    if args.watch.build_book {
+        if let Ok(track) = Track::load_toml_def(&args.watch.track_toml_path) {
+            println!(
+                "The book is at http://{}/book/{}/",
+                server.address(),
+                track.book.build_dir()
+            );
+        }
+    }
+    watch(args.watch, || server.reload());
+}
+
+fn vendor(args: VendorArgs) {
+    let VendorArgs {
+        vendor_dir,
+        track_toml_path,
+    } = args;
+    let track = match Track::load_toml_def(track_toml_path) {
+        Ok(track) => track,
+        Err(e) => {
+            eprintln!("Error loading track: {e:?}");
+            exit(1);
+        }
+    };
+    match track.vendor(vendor_dir) {
+        Ok(report) => print_vendor_report(&report),
+        Err(e) => {
+            eprintln!("Error vendoring modules: {e:?}");
+            exit(1);
+        }
+    }
+}
+
+fn print_vendor_report(report: &VendorReport) {
+    if report.modules.is_empty() {
+        println!("No modules outside of the course repository, nothing to vendor");
+    }
+    for module in report.modules.iter() {
+        println!("'{}': {} -> {}", module.name, module.origin, module.path);
+        if module.is_unchanged() {
+            println!("  unchanged");
+        }
+        for (label, files) in [
+            ("added", &module.added),
+            ("updated", &module.updated),
+            ("removed", &module.removed),
+        ] {
+            for file in files {
+                println!("  {label} {}", file.to_string_lossy());
+            }
+        }
+    }
+}
+
+fn orphans(args: OrphansArgs) {
+    let OrphansArgs {
+        allow,
+        json,
+        deny,
+        track_toml_path,
+    } = args;
+
+    fn run(
+        track_toml_path: PathBuf,
+        allow: Vec<String>,
+        json: Option<PathBuf>,
+    ) -> Result<OrphanReport, LoadTrackError> {
+        let track = Track::load_toml_def(track_toml_path)?;
+        let allowlist: Vec<String> = DEFAULT_ORPHAN_ALLOWLIST
+            .iter()
+            .map(|p| p.to_string())
+            .chain(allow)
+            .collect();
+        let report = track.orphaned_files(&allowlist)?;
+        if let Some(json_path) = json {
+            write_file(&json_path, serde_json::to_string_pretty(&report).unwrap())?;
+        }
+        Ok(report)
+    }
+
+    match run(track_toml_path, allow, json) {
+        Ok(report) => {
+            print_orphan_report(&report);
+            if deny && !report.is_empty() {
+                exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("Error finding orphaned files: {e:?}");
+            exit(1);
+        }
+    }
+}
+
+fn schema(args: SchemaArgs) {
+    let SchemaArgs { output, kind } = args;
+    let schema = serde_json::to_string_pretty(&kind.schema()).unwrap();
+    match output {
+        Some(path) => {
+            if let Err(e) = write_file(&path, schema) {
+                eprintln!("Error writing schema: {e:?}");
+                exit(1);
+            }
+        }
+        None => println!("{schema}"),
+    }
+}
+
+fn new(args: NewArgs) {
+    let NewArgs { kind, dir, name } = args;
+    match kind.scaffold(&dir, &name) {
+        Ok(scaffold) => {
+            println!("Created {kind} '{name}':");
+            for file in scaffold.files {
+                println!("  {}", file.to_string_lossy());
+            }
+            if let Some(reference) = scaffold.reference {
+                println!("\n{reference}");
+            }
+        }
+        Err(e) => {
+            eprintln!("Error creating {kind}: {e:?}");
+            exit(1);
+        }
+    }
+}
+
+fn add(args: AddArgs) {
+    let AddArgs {
+        kind,
+        path,
+        parent,
+        unit,
+        name,
+    } = args;
+    match kind.add(&path, AddOptions { parent, unit, name }) {
+        Ok(added) => println!(
+            "Added {entry} to {parent}",
+            entry = added.entry,
+            parent = added.parent.to_string_lossy()
+        ),
+        Err(e) => {
+            eprintln!("Error adding {kind}: {e:?}");
+            exit(1);
+        }
+    }
+}
+
+fn list(args: ListArgs) {
+    let ListArgs {
+        include_drafts,
+        modules,
+        units,
+        track_toml_path,
+    } = args;
+    match Track::load_toml_def(track_toml_path) {
+        Ok(track) => print!(
+            "{}",
+            track.list(&RenderOptions {
+                include_drafts,
+                modules,
+                units,
+                ..Default::default()
+            })
+        ),
+        Err(e) => {
+            eprintln!("Error loading track: {e:?}");
+            exit(1);
+        }
+    }
+}
+
+fn stats(args: StatsArgs) {
+    let StatsArgs {
+        include_drafts,
+        modules,
+        units,
+        track_toml_path,
+    } = args;
+    let stats = Track::load_toml_def(track_toml_path).and_then(|track| {
+        track.stats(&RenderOptions {
+            include_drafts,
+            modules,
+            units,
+            ..Default::default()
+        })
+    });
+    match stats {
+        Ok(stats) => print_stats(&stats),
+        Err(e) => {
+            eprintln!("Error counting track: {e:?}");
+            exit(1);
+        }
+    }
+}
+
+fn print_stats(stats: &TrackStats) {
+    let rows: Vec<(String, &Counts)> = stats
+        .modules
+        .iter()
+        .flat_map(|module| {
+            std::iter::once((format!("{} {}", module.index, module.name), &module.counts)).chain(
+                module.units.iter().map(move |unit| {
+                    (
+                        format!("  {}.{} {}", module.index, unit.index, unit.name),
+                        &unit.counts,
+                    )
+                }),
+            )
+        })
+        .chain(std::iter::once((
+            format!("Total of {}", stats.name),
+            &stats.counts,
+        )))
+        .collect();
+    let width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    println!(
+        "{:width$}  {:>6}  {:>6}  {:>9}  {:>5}  {:>7}",
+        "", "slides", "words", "exercises", "files", "minutes"
+    );
+    for (name, counts) in rows {
+        println!(
+            "{name:width$}  {:>6}  {:>6}  {:>9}  {:>5}  {:>7}",
+            counts.slides, counts.words, counts.exercises, counts.files, counts.minutes
+        );
+    }
+}
+
+fn calendar(args: CalendarArgs) {
+    let CalendarArgs {
+        output,
+        cohort,
+        start,
+        weekdays,
+        time,
+        session_minutes,
+        skip,
+        include_drafts,
+        track_toml_path,
+    } = args;
+    let mut cohort = match (cohort, start) {
+        (Some(path), _) => match Cohort::load(&path) {
+            Ok(cohort) => cohort,
+            Err(e) => {
+                eprintln!("Error loading cohort: {e:?}");
+                exit(1);
+            }
+        },
+        (None, Some(start)) => Cohort::new(start),
+        (None, None) => unreachable!("clap requires a start date without a cohort"),
+    };
+    if let Some(start) = start {
+        cohort.start = start;
+    }
+    if !weekdays.is_empty() {
+        cohort.weekdays = weekdays;
+    }
+    if let Some(time) = time {
+        cohort.time = time;
+    }
+    if let Some(session_minutes) = session_minutes {
+        cohort.session_minutes = session_minutes;
+    }
+    cohort.skip.extend(skip);
+
+    let calendar = Track::load_toml_def(track_toml_path).and_then(|track| {
+        track
+            .calendar(
+                &cohort,
+                &RenderOptions {
+                    include_drafts,
+                    ..Default::default()
+                },
+            )
+            .change_context(LoadTrackError)
+    });
+    let result = calendar.and_then(|calendar| match output {
+        Some(path) => write_file(&path, calendar),
+        None => {
+            print!("{calendar}");
+            Ok(())
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("Error creating calendar: {e:?}");
         exit(1);
     }
+}
 
-    println!("Done!");
+fn syllabus(args: SyllabusArgs) {
+    let SyllabusArgs {
+        output,
+        pdf,
+        include_drafts,
+        modules,
+        units,
+        track_toml_path,
+    } = args;
+    let syllabus = match Track::load_toml_def(track_toml_path) {
+        Ok(track) => track.syllabus(&RenderOptions {
+            include_drafts,
+            modules,
+            units,
+            ..Default::default()
+        }),
+        Err(e) => {
+            eprintln!("Error loading track: {e:?}");
+            exit(1);
+        }
+    };
+    if let Some(pdf) = pdf {
+        if let Err(e) = syllabus_pdf(&syllabus, &pdf) {
+            eprintln!("Error writing syllabus: {e:?}");
+            exit(1);
+        }
+    }
+    match output {
+        Some(path) => {
+            if let Err(e) = write_file(&path, syllabus) {
+                eprintln!("Error writing syllabus: {e:?}");
+                exit(1);
+            }
+        }
+        None => print!("{syllabus}"),
+    }
+}
+
+fn export_anki(args: AnkiArgs) {
+    let AnkiArgs {
+        output_dir,
+        include_drafts,
+        modules,
+        units,
+        track_toml_path,
+    } = args;
+    let decks = match Track::load_toml_def(track_toml_path) {
+        Ok(track) => track.anki_decks(&RenderOptions {
+            include_drafts,
+            modules,
+            units,
+            ..Default::default()
+        }),
+        Err(e) => {
+            eprintln!("Error loading track: {e:?}");
+            exit(1);
+        }
+    };
+    if decks.is_empty() {
+        println!("No topics have a summary or glossary terms to make cards of");
+        return;
+    }
+    let result = fs::create_dir_all(&output_dir)
+        .into_report()
+        .attach_printable_lazy(|| {
+            format!(
+                "Unable to create {path}",
+                path = output_dir.to_string_lossy()
+            )
+        })
+        .change_context(LoadTrackError)
+        .and_then(|()| {
+            decks.iter().try_for_each(|deck| {
+                let path = output_dir.join(&deck.file_name);
+                write_file(&path, deck.csv())?;
+                println!("{} ({} cards)", path.to_string_lossy(), deck.cards.len());
+                Ok(())
+            })
+        });
+    if let Err(e) = result {
+        eprintln!("Error exporting flashcards: {e:?}");
+        exit(1);
+    }
+}
+
+fn bundle(args: BundleArgs) {
+    let BundleArgs {
+        output,
+        vendor_crates,
+        cargo_workspaces,
+        include_drafts,
+        modules,
+        units,
+        locked,
+        track_toml_path,
+    } = args;
+    let track = match Track::load_toml_def_with(
+        track_toml_path,
+        LoadOptions {
+            locked,
+            ..Default::default()
+        },
+    ) {
+        Ok(track) => track,
+        Err(e) => {
+            eprintln!("Error loading track: {e:?}");
+            exit(1);
+        }
+    };
+    let options = RenderOptions {
+        include_drafts,
+        modules,
+        units,
+        cargo_workspaces,
+        vendor_crates,
+        ..Default::default()
+    };
+    match track.bundle(&output, &options) {
+        Ok(report) => print_report(&report),
+        Err(e) => {
+            eprintln!("Error bundling track: {e:?}");
+            exit(1);
+        }
+    }
+}
+
+fn print_orphan_report(report: &OrphanReport) {
+    if report.is_empty() {
+        println!("No orphaned files found");
+        return;
+    }
+    for topic in report.topics.iter() {
+        println!(
+            "'{}' ({}): {} file(s), {}",
+            topic.topic,
+            topic.path.to_string_lossy(),
+            topic.files.len(),
+            format_size(topic.size())
+        );
+        for file in topic.files.iter() {
+            println!(
+                "  {} ({})",
+                file.path.to_string_lossy(),
+                format_size(file.size)
+            );
+        }
+    }
+    println!(
+        "{} orphaned file(s), {} in total",
+        report.file_count(),
+        format_size(report.size())
+    );
+}
+
+fn write_file(path: &Path, content: String) -> Result<(), LoadTrackError> {
+    fs::write(path, content)
+        .into_report()
+        .attach_printable_lazy(|| {
+            format!("Unable to write to {path}", path = path.to_string_lossy())
+        })
+        .change_context(LoadTrackError)
+}
+
+/// The number of warnings that are printed in full
+const WARNINGS_SHOWN: usize = 5;
+
+fn print_report(report: &RenderReport) {
+    let color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty());
+    let paint = |code: &str, text: &str| {
+        if color {
+            format!("\x1b[{code}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    };
+
+    let RenderReport {
+        track_name,
+        output_dir,
+        modules,
+        units,
+        topics,
+        exercises,
+        files_written,
+        bytes_written,
+        elapsed,
+        files_unchanged,
+        instructor_output_dir,
+        planned,
+        units_rerendered,
+        warnings,
+        notes,
+        drafts_skipped,
+        drafts_included,
+        verification,
+        output_map: _,
+    } = report;
+
+    if let Some(planned) = planned {
+        // Paths in the output are shown relative to it
+        let show = |path: &Path| match path.strip_prefix(output_dir) {
+            Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+            Ok(relative) => relative.to_string_lossy().into_owned(),
+            Err(_) => path.to_string_lossy().into_owned(),
+        };
+        for operation in planned.iter() {
+            match operation {
+                PlannedOperation::CreateDir { path } => println!("create {}/", show(path)),
+                PlannedOperation::CreateFile { path } => println!("write  {}", show(path)),
+                PlannedOperation::CopyFile { from, to } => {
+                    println!("copy   {} (from {})", show(to), from.to_string_lossy())
+                }
+            }
+        }
+    }
+
+    let (verb, written) = match planned {
+        Some(_) => ("Dry run of", "would be written"),
+        None => ("Rendered", "written"),
+    };
+    println!(
+        "{} {}",
+        paint("1;32", verb),
+        paint("1", &format!("'{track_name}'"))
+    );
+    println!("  {modules} modules, {units} units, {topics} topics, {exercises} exercises");
+    println!(
+        "  {files_written} files {written} ({}), finished in {:.2}s",
+        format_size(*bytes_written),
+        elapsed.as_secs_f64()
+    );
+    println!("  Output: {}", output_dir.to_string_lossy());
+    if let Some(files_unchanged) = files_unchanged {
+        println!("  {files_unchanged} unchanged exercise file(s) kept from the earlier render");
+    }
+    if let Some(units) = units_rerendered {
+        let units: Vec<_> = units.iter().map(|(m, u)| format!("{m}.{u}")).collect();
+        match units.is_empty() {
+            true => println!("  No exercises rendered again"),
+            false => println!("  Exercises of unit(s) {} rendered again", units.join(", ")),
+        }
+    }
+    if let Some(instructor_output_dir) = instructor_output_dir {
+        println!(
+            "  Instructor edition: {}",
+            instructor_output_dir.to_string_lossy()
+        );
+    }
+    if *drafts_skipped > 0 {
+        println!("  {drafts_skipped} draft unit(s), topic(s) and appendices left out");
+    }
+    if *drafts_included > 0 {
+        println!(
+            "  {}",
+            paint(
+                "1;33",
+                &format!("{drafts_included} draft unit(s), topic(s) and appendices included")
+            )
+        );
+    }
+
+    if !warnings.is_empty() {
+        println!(
+            "{}",
+            paint("1;33", &format!("{} warning(s):", warnings.len()))
+        );
+        for warning in warnings.iter().take(WARNINGS_SHOWN) {
+            println!("  - {warning}");
+        }
+        if warnings.len() > WARNINGS_SHOWN {
+            println!("  ... and {} more", warnings.len() - WARNINGS_SHOWN);
+        }
+    }
+    if !notes.is_empty() {
+        println!("{} note(s), from allowed warnings:", notes.len());
+        for note in notes.iter().take(WARNINGS_SHOWN) {
+            println!("  - {note}");
+        }
+        if notes.len() > WARNINGS_SHOWN {
+            println!("  ... and {} more", notes.len() - WARNINGS_SHOWN);
+        }
+    }
+
+    if !verification.is_empty() {
+        let count =
+            |f: fn(&VerifyOutcome) -> bool| verification.iter().filter(|v| f(&v.outcome)).count();
+        println!(
+            "Verified exercises: {} passed, {} failed, {} timed out, {} skipped",
+            count(|o| *o == VerifyOutcome::Passed),
+            count(|o| matches!(o, VerifyOutcome::Failed { .. })),
+            count(|o| *o == VerifyOutcome::TimedOut),
+            count(|o| *o == VerifyOutcome::Skipped),
+        );
+        for v in verification {
+            let status = match &v.outcome {
+                VerifyOutcome::Passed => paint("32", "passed"),
+                VerifyOutcome::Failed { .. } => paint("1;31", "FAILED"),
+                VerifyOutcome::TimedOut => paint("1;31", "TIMED OUT"),
+                VerifyOutcome::Skipped => paint("2", "skipped"),
+            };
+            print!(
+                "  {status} {} ({}) in {:.1}s",
+                v.exercise,
+                v.output_dir.to_string_lossy(),
+                v.duration.as_secs_f64()
+            );
+            match &v.outcome {
+                VerifyOutcome::Failed { command, .. } => println!(": {command}"),
+                _ => println!(),
+            }
+            if let VerifyOutcome::Failed { output, .. } = &v.outcome {
+                for line in output.lines() {
+                    println!("      {line}");
+                }
+            }
+        }
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
 }
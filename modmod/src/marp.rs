@@ -0,0 +1,49 @@
+//! Slide decks in the markdown of Marp, so that they can be converted into PDF or PowerPoint
+//! with marp-cli
+
+use crate::{
+    slides::{relative_images, split_headmatter},
+    stats::split_slides,
+};
+
+/// The theme of decks that don't set one
+const DEFAULT_THEME: &str = "default";
+
+/// The Marp deck for the Slidev `slides`, with the rendered `settings` of the deck, in
+/// `language`. The headmatter is replaced by Marp front matter, and the settings of single
+/// slides, like layouts, are left out, as Marp would show them as slides of their own.
+pub(crate) fn deck(slides: &str, settings: &[(&str, String)], language: &str) -> String {
+    let setting = |key| {
+        settings
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value.as_str())
+    };
+    let (headmatter, slides) = split_headmatter(slides);
+    let headmatter: serde_yaml::Value =
+        serde_yaml::from_str(&headmatter.join("\n")).unwrap_or_default();
+    let mut front_matter = serde_yaml::Mapping::new();
+    front_matter.insert("marp".into(), true.into());
+    front_matter.insert(
+        "theme".into(),
+        setting("theme").unwrap_or(DEFAULT_THEME).into(),
+    );
+    if let Some(title) = headmatter["title"].as_str() {
+        front_matter.insert("title".into(), title.into());
+    }
+    front_matter.insert("lang".into(), language.into());
+    front_matter.insert("paginate".into(), true.into());
+
+    let mut markdown: Vec<String> = split_slides(slides)
+        .into_iter()
+        .map(|slide| slide.join("\n").trim().to_string())
+        .collect();
+    if let (Some(background), Some(cover)) = (setting("background"), markdown.first_mut()) {
+        *cover = format!("![bg]({background})\n\n{cover}");
+    }
+    format!(
+        "---\n{front_matter}---\n\n{markdown}\n",
+        front_matter = serde_yaml::to_string(&front_matter).unwrap(),
+        markdown = relative_images(&markdown.join("\n\n---\n\n"), "images/")
+    )
+}
@@ -0,0 +1,113 @@
+//! Mermaid diagrams in slides and exercise descriptions, rendered to SVG images with the
+//! Mermaid CLI, `mmdc`, so that the output doesn't need Mermaid to show them
+
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use crate::{io::WriteExt, output::Output};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderMermaidError;
+
+impl fmt::Display for RenderMermaidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render Mermaid diagram")
+    }
+}
+
+impl error_stack::Context for RenderMermaidError {}
+
+/// Renders the Mermaid diagrams of markdown into SVG images
+#[derive(Debug, Default)]
+pub struct MermaidDiagrams {
+    /// Images that were written already, which diagrams that occur more than once share
+    written: RefCell<HashSet<PathBuf>>,
+}
+
+impl MermaidDiagrams {
+    /// Replace every ```` ```mermaid ```` code block in `markdown` by an image of the
+    /// diagram, written into `image_dir` and linked to as `<link_dir>/<name>`
+    pub(crate) fn render(
+        &self,
+        markdown: &str,
+        image_dir: &Path,
+        link_dir: &str,
+        output: &Output,
+    ) -> Result<String, RenderMermaidError> {
+        let mut rendered = String::with_capacity(markdown.len());
+        // Source of the diagram we're in, if any
+        let mut diagram: Option<String> = None;
+        for line in markdown.split_inclusive('\n') {
+            match diagram.as_mut() {
+                None if line.trim() == "```mermaid" => diagram = Some(String::new()),
+                None => rendered += line,
+                Some(source) if line.trim_start().starts_with("```") => {
+                    // Equal diagrams get the same name, so that renders are reproducible
+                    let name = format!("mermaid-{:08x}.svg", crc32fast::hash(source.as_bytes()));
+                    let image = image_dir.join(&name);
+                    if self.written.borrow_mut().insert(image.clone()) {
+                        let svg = match output.is_dry_run() {
+                            true => String::new(),
+                            false => run_mmdc(source)?,
+                        };
+                        output.create_dir_all(image_dir)?;
+                        output.create_file(image)?.write_all(svg)?;
+                    }
+                    rendered += &format!("![Diagram]({link_dir}/{name})\n");
+                    diagram = None;
+                }
+                Some(source) => source.push_str(line),
+            }
+        }
+        if diagram.is_some() {
+            return Err(Report::new(RenderMermaidError)
+                .attach_printable("Mermaid code block is never closed"));
+        }
+        Ok(rendered)
+    }
+}
+
+/// Render the diagram `source` with `mmdc`, which has to be installed
+fn run_mmdc(source: &str) -> Result<String, RenderMermaidError> {
+    let name = format!(
+        "modmod-mermaid-{}-{:08x}",
+        std::process::id(),
+        crc32fast::hash(source.as_bytes())
+    );
+    let input = std::env::temp_dir().join(format!("{name}.mmd"));
+    let svg = std::env::temp_dir().join(format!("{name}.svg"));
+    fs::write(&input, source)
+        .into_report()
+        .change_context(RenderMermaidError)?;
+    let result = Command::new("mmdc")
+        .arg("--input")
+        .arg(&input)
+        .arg("--output")
+        .arg(&svg)
+        .stdin(Stdio::null())
+        .output()
+        .into_report()
+        .attach_printable("Unable to run mmdc, which is needed to render Mermaid diagrams")
+        .change_context(RenderMermaidError)
+        .and_then(|result| match result.status.success() {
+            true => fs::read_to_string(&svg)
+                .into_report()
+                .change_context(RenderMermaidError),
+            false => Err(Report::new(RenderMermaidError).attach_printable(format!(
+                "mmdc failed on diagram:\n{source}{stderr}",
+                stderr = String::from_utf8_lossy(&result.stderr).trim()
+            ))),
+        });
+    for file in [input, svg] {
+        let _ = fs::remove_file(file);
+    }
+    result
+}
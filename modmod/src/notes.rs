@@ -0,0 +1,80 @@
+//! Notes for presenters in topic slides and exercise descriptions, that only the instructor
+//! edition has. Notes start at a `<!-- notes -->` line and run up to a `<!-- /notes -->`
+//! line or the end of the slide:
+//!
+//! ```text
+//! # Borrowing
+//!
+//! <!-- notes -->
+//! Ask who has used references in C++ before.
+//! ```
+
+const NOTES_START: &str = "<!-- notes -->";
+const NOTES_END: &str = "<!-- /notes -->";
+
+/// Notes on one slide
+#[derive(Debug, Clone)]
+pub(crate) struct Notes {
+    /// The last heading before the notes, which is usually the title of their slide
+    pub heading: Option<String>,
+    pub text: String,
+}
+
+/// `content` with every block of notes replaced by what `replace` makes of its text, along
+/// with the notes in order
+pub(crate) fn extract_notes(
+    content: &str,
+    replace: impl Fn(&str) -> String,
+) -> (String, Vec<Notes>) {
+    if !content.contains(NOTES_START) {
+        return (content.to_string(), vec![]);
+    }
+    let mut extracted = String::with_capacity(content.len());
+    let mut notes: Vec<Notes> = vec![];
+    let mut heading = None;
+    let mut in_code = false;
+    // Text of the notes we're in, if any
+    let mut current: Option<String> = None;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let is_fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        if !in_code {
+            if let Some(text) = current.take() {
+                // Notes end at their end marker, or at the separator of the next slide
+                let ends = trimmed == NOTES_END || trimmed == "---";
+                if !ends {
+                    current = Some(text + line);
+                    in_code = is_fence;
+                    continue;
+                }
+                let text = text.trim().to_string();
+                extracted.push_str(&replace(&text));
+                notes.push(Notes {
+                    heading: heading.clone(),
+                    text,
+                });
+                if trimmed == NOTES_END {
+                    continue;
+                }
+            } else if trimmed == NOTES_START {
+                current = Some(String::new());
+                continue;
+            } else if trimmed.starts_with('#') && !is_fence {
+                heading = Some(trimmed.trim_start_matches('#').trim().to_string());
+            }
+        }
+        if is_fence {
+            in_code = !in_code;
+        }
+        match current.as_mut() {
+            Some(text) => text.push_str(line),
+            None => extracted.push_str(line),
+        }
+    }
+    if let Some(text) = current {
+        let text = text.trim().to_string();
+        extracted.push_str(&replace(&text));
+        notes.push(Notes { heading, text });
+    }
+    (extracted, notes)
+}
@@ -0,0 +1,188 @@
+//! Detection of files in topic and exercise directories that no render ever reads
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{IntoReport, Result, ResultExt};
+use serde::Serialize;
+
+use crate::{io::PathExt, LoadTrackError, Topic, Track};
+
+/// Patterns of files that are never reported as orphaned
+pub const DEFAULT_ORPHAN_ALLOWLIST: [&str; 4] =
+    ["README.md", ".gitignore", ".DS_Store", "**/target/**"];
+
+#[derive(Debug, Default, Serialize)]
+pub struct OrphanReport {
+    /// Topics that contain orphaned files, in track order
+    pub topics: Vec<TopicOrphans>,
+}
+
+impl OrphanReport {
+    pub fn is_empty(&self) -> bool {
+        self.topics.is_empty()
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.topics.iter().map(|t| t.files.len()).sum()
+    }
+
+    /// Total size of the orphaned files in bytes
+    pub fn size(&self) -> u64 {
+        self.topics.iter().map(TopicOrphans::size).sum()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TopicOrphans {
+    pub topic: String,
+    /// Directory of the topic definition
+    pub path: PathBuf,
+    pub files: Vec<OrphanedFile>,
+}
+
+impl TopicOrphans {
+    /// Total size of the orphaned files in bytes
+    pub fn size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrphanedFile {
+    /// Path of the file relative to the topic directory
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+impl Track {
+    /// Find files in the directories of the topics and exercises of this track
+    /// that a full render doesn't read or copy, and that aren't linked from any
+    /// markdown file. Files matching any of the `allowlist` patterns are left out.
+    /// Patterns without a `/` are matched against file names, others against
+    /// the path relative to the topic directory.
+    pub fn orphaned_files(&self, allowlist: &[String]) -> Result<OrphanReport, LoadTrackError> {
+        let allowlist = Allowlist::new(allowlist)?;
+        let mut used: BTreeSet<PathBuf> = self.source_files()?.into_iter().collect();
+        for file in used.clone().iter() {
+            if file.extension().is_some_and(|ext| ext == "md") {
+                used.extend(linked_files(file)?);
+            }
+        }
+
+        let mut report = OrphanReport::default();
+        // Topics that are part of several units, or directories shared between
+        // topics, are only reported once
+        let mut seen = BTreeSet::new();
+        let topics = self
+            .modules
+            .iter()
+            .flat_map(|m| m.data.units.iter())
+            .flat_map(|u| u.data.topics.iter())
+            .map(|t| &t.data);
+        for topic in topics {
+            let topic_dir = topic.path.parent().unwrap();
+            let mut files = vec![];
+            for dir in topic_dirs(topic) {
+                for file in dir.get_dir_content::<LoadTrackError>()?.files {
+                    let file = PathBuf::from(file);
+                    if used.contains(&file) || !seen.insert(file.clone()) {
+                        continue;
+                    }
+                    let relative = file.strip_prefix(topic_dir).unwrap_or(&file);
+                    if allowlist.is_match(relative) {
+                        continue;
+                    }
+                    let size = fs::metadata(&file)
+                        .into_report()
+                        .change_context(LoadTrackError)?
+                        .len();
+                    files.push(OrphanedFile {
+                        path: relative.to_path_buf(),
+                        size,
+                    });
+                }
+            }
+            if !files.is_empty() {
+                files.sort_by(|a, b| a.path.cmp(&b.path));
+                report.topics.push(TopicOrphans {
+                    topic: topic.name.clone(),
+                    path: topic_dir.to_path_buf(),
+                    files,
+                });
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// The topic directory, and the directories of exercises that live outside of it.
+/// Inline topics share the module directory, so only their exercise directories are theirs.
+fn topic_dirs(topic: &Topic) -> Vec<&Path> {
+    let exercise_dirs = topic.exercises.iter().map(|e| e.data.path.as_path());
+    if topic.inline {
+        return exercise_dirs.collect();
+    }
+    let topic_dir = topic.path.parent().unwrap();
+    let mut dirs = vec![topic_dir];
+    dirs.extend(exercise_dirs.filter(|p| !p.starts_with(topic_dir)));
+    dirs
+}
+
+struct Allowlist {
+    names: globset::GlobSet,
+    paths: globset::GlobSet,
+}
+
+impl Allowlist {
+    fn new(patterns: &[String]) -> Result<Self, LoadTrackError> {
+        let mut names = globset::GlobSetBuilder::new();
+        let mut paths = globset::GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = globset::Glob::new(pattern)
+                .into_report()
+                .attach_printable_lazy(|| format!("Error parsing glob '{pattern}'"))
+                .change_context(LoadTrackError)?;
+            if pattern.contains('/') {
+                paths.add(glob);
+            } else {
+                names.add(glob);
+            }
+        }
+        Ok(Self {
+            names: names.build().unwrap(),
+            paths: paths.build().unwrap(),
+        })
+    }
+
+    fn is_match(&self, path: &Path) -> bool {
+        self.paths.is_match(path) || path.file_name().is_some_and(|n| self.names.is_match(n))
+    }
+}
+
+/// Existing files that the markdown file at `path` links to with a relative path,
+/// either as `[text](target)` or as `src="target"`
+fn linked_files(path: &Path) -> Result<Vec<PathBuf>, LoadTrackError> {
+    let content = path.read_to_string::<LoadTrackError>()?;
+    let dir = path.parent().unwrap();
+    let targets = content
+        .split("](")
+        .skip(1)
+        .filter_map(|s| s.split(')').next())
+        .chain(
+            content
+                .split("src=\"")
+                .skip(1)
+                .filter_map(|s| s.split('"').next()),
+        );
+
+    Ok(targets
+        .map(|target| target.split(['#', '?', ' ']).next().unwrap_or_default())
+        .filter(|target| !target.is_empty() && !target.starts_with('/') && !target.contains(':'))
+        .filter_map(|target| dir.join(target).canonicalize().ok())
+        .filter(|file| file.is_file())
+        .collect())
+}
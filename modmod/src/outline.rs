@@ -0,0 +1,196 @@
+//! Machine-readable outline of a course, for instance to render it on a website.
+//!
+//! The outline is versioned with [OUTLINE_SCHEMA_VERSION]. Within a schema version,
+//! fields are only ever added, never removed or changed in meaning, so consumers
+//! should ignore fields they don't know. Any other change bumps the version.
+//!
+//! Ids are derived from the location of the source files relative to the track definition,
+//! so they stay the same when items are renamed or reordered.
+
+use std::{collections::HashSet, path::Path};
+
+use serde::Serialize;
+
+use crate::{to_tag, validate::normalize_item, Exercise, ExerciseKind, Module, Topic, Track, Unit};
+
+pub const OUTLINE_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct Outline {
+    pub schema_version: u32,
+    pub name: String,
+    pub modules: Vec<OutlineModule>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutlineModule {
+    pub id: String,
+    pub index: usize,
+    pub name: String,
+    pub description: String,
+    /// Objectives of the module as a whole
+    pub objectives: Vec<String>,
+    pub further_reading: Vec<String>,
+    pub exercise_count: usize,
+    pub units: Vec<OutlineUnit>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutlineUnit {
+    pub id: String,
+    pub index: usize,
+    pub name: String,
+    /// Drafts are left out of renders unless drafts are included explicitly
+    pub draft: bool,
+    /// Objectives of all topics in this unit, in order and without duplicates
+    pub objectives: Vec<String>,
+    pub exercise_count: usize,
+    pub topics: Vec<OutlineTopic>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutlineTopic {
+    pub id: String,
+    pub index: usize,
+    pub name: String,
+    pub draft: bool,
+    pub objectives: Vec<String>,
+    pub summary: Vec<String>,
+    pub exercise_count: usize,
+    pub exercises: Vec<OutlineExercise>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OutlineExercise {
+    pub id: String,
+    pub index: usize,
+    pub name: String,
+    pub kind: ExerciseKind,
+    pub include_profile: Option<String>,
+    /// Globs of the files that are copied into the output, after resolving the include profile
+    pub includes: Vec<String>,
+    /// Globs of the files that are not copied, even if they match an include
+    pub excludes: Vec<String>,
+}
+
+impl Outline {
+    pub fn new(track: &Track) -> Self {
+        let base_path = track.path.parent().unwrap();
+        Self {
+            schema_version: OUTLINE_SCHEMA_VERSION,
+            name: track.name.clone(),
+            modules: track
+                .modules
+                .iter()
+                .map(|m| OutlineModule::new(&m.data, m.index, base_path))
+                .collect(),
+        }
+    }
+}
+
+impl OutlineModule {
+    fn new(module: &Module, index: usize, base_path: &Path) -> Self {
+        let id = relative_id(module.path.parent().unwrap(), base_path);
+        let units: Vec<_> = module
+            .units
+            .iter()
+            .map(|u| OutlineUnit::new(&u.data, u.index, &id, base_path))
+            .collect();
+        Self {
+            exercise_count: units.iter().map(|u| u.exercise_count).sum(),
+            id,
+            index,
+            name: module.name.clone(),
+            description: module.description.clone(),
+            objectives: module.objectives.clone(),
+            further_reading: module.further_reading.clone(),
+            units,
+        }
+    }
+}
+
+impl OutlineUnit {
+    fn new(unit: &Unit, index: usize, module_id: &str, base_path: &Path) -> Self {
+        let topics: Vec<_> = unit
+            .topics
+            .iter()
+            .map(|t| OutlineTopic::new(&t.data, t.index, base_path))
+            .collect();
+        Self {
+            id: unit_id(module_id, unit),
+            index,
+            name: unit.name.clone(),
+            draft: unit.draft,
+            objectives: dedup_items(topics.iter().flat_map(|t| t.objectives.iter())),
+            exercise_count: topics.iter().map(|t| t.exercise_count).sum(),
+            topics,
+        }
+    }
+}
+
+impl OutlineTopic {
+    fn new(topic: &Topic, index: usize, base_path: &Path) -> Self {
+        Self {
+            id: topic_id(topic, base_path),
+            index,
+            name: topic.name.clone(),
+            draft: topic.draft,
+            objectives: topic.objectives.clone(),
+            summary: topic.summary.clone(),
+            exercise_count: topic.exercises.len(),
+            exercises: topic
+                .exercises
+                .iter()
+                .map(|e| OutlineExercise::new(&e.data, e.index, base_path))
+                .collect(),
+        }
+    }
+}
+
+impl OutlineExercise {
+    fn new(exercise: &Exercise, index: usize, base_path: &Path) -> Self {
+        Self {
+            id: relative_id(&exercise.path, base_path),
+            index,
+            name: exercise.name.clone(),
+            kind: exercise.kind,
+            include_profile: exercise.include_profile.clone(),
+            includes: exercise.includes.clone(),
+            excludes: exercise.excludes.clone(),
+        }
+    }
+}
+
+/// Items in order of first occurrence, leaving out duplicates like the slides do
+fn dedup_items<'i>(items: impl Iterator<Item = &'i String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    items
+        .filter(|item| seen.insert(normalize_item(item)))
+        .cloned()
+        .collect()
+}
+
+/// Units don't have a directory of their own, so they're identified by their name within the module
+pub(crate) fn unit_id(module_id: &str, unit: &Unit) -> String {
+    format!("{module_id}#{}", to_tag(&unit.name))
+}
+
+/// Inline topics share the directory of their module, so they're identified by their name within it
+pub(crate) fn topic_id(topic: &Topic, base_path: &Path) -> String {
+    let id = relative_id(topic.path.parent().unwrap(), base_path);
+    if topic.inline {
+        format!("{id}#{}", to_tag(&topic.name))
+    } else {
+        id
+    }
+}
+
+/// Id of the item at `path`, which is its path relative to `base_path` with `/` separators.
+/// Items outside of `base_path` are identified by their full path.
+pub(crate) fn relative_id(path: &Path, base_path: &Path) -> String {
+    let path = path.strip_prefix(base_path).unwrap_or(path);
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
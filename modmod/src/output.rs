@@ -0,0 +1,589 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    fmt,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use error_stack::{Context, IntoReport, Report, Result, ResultExt};
+use serde::Serialize;
+
+use crate::{
+    cache::{content_hash, RenderCache, RENDER_CACHE_FILE},
+    io::{PathExt, WriteExt},
+    parallel::parallel_map,
+    report::Warning,
+};
+
+/// Token that can be used to cancel a render that is in progress,
+/// for instance from a Ctrl-C handler.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("render was cancelled")
+    }
+}
+
+impl Context for Cancelled {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Dir,
+    File,
+}
+
+/// Record of every directory and file created during a render,
+/// in order of creation.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    entries: Vec<(EntryKind, PathBuf)>,
+    /// Files that were there before the render, like those of an earlier render that an
+    /// incremental one overwrites
+    existing: HashSet<PathBuf>,
+}
+
+impl Manifest {
+    fn record(&mut self, kind: EntryKind, path: PathBuf) {
+        self.entries.push((kind, path));
+    }
+
+    /// Record a file, which `existed` before the render wrote it
+    fn record_file(&mut self, path: PathBuf, existed: bool) {
+        if existed {
+            self.existing.insert(path.clone());
+        }
+        self.record(EntryKind::File, path);
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.entries
+            .iter()
+            .filter(|(kind, _)| *kind == EntryKind::File)
+            .map(|(_, path)| path.as_path())
+    }
+}
+
+/// A file operation that a dry run would have performed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "operation", rename_all = "snake_case")]
+pub enum PlannedOperation {
+    CreateDir { path: PathBuf },
+    CreateFile { path: PathBuf },
+    CopyFile { from: PathBuf, to: PathBuf },
+}
+
+/// The file operations of a dry run, and the number of bytes they would write
+#[derive(Debug, Default)]
+struct Plan {
+    operations: Vec<PlannedOperation>,
+    bytes: Rc<Cell<u64>>,
+}
+
+/// A file created in the output. In dry runs, nothing is written, but the bytes are counted.
+#[derive(Debug)]
+pub enum OutputFile {
+    Written(File),
+    Planned(Rc<Cell<u64>>),
+}
+
+impl io::Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            OutputFile::Written(file) => file.write(buf),
+            OutputFile::Planned(bytes) => {
+                bytes.set(bytes.get() + buf.len() as u64);
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            OutputFile::Written(file) => file.flush(),
+            OutputFile::Planned(_) => Ok(()),
+        }
+    }
+}
+
+/// Handle to the output directory of a render. All writes into the output
+/// go through here, so that they get recorded in the [Manifest] and
+/// the render can be cancelled in between file operations.
+#[derive(Debug)]
+pub struct Output {
+    root: PathBuf,
+    cancellation: Option<CancellationToken>,
+    manifest: RefCell<Manifest>,
+    warnings: RefCell<Vec<Warning>>,
+    /// Set for dry runs, which record file operations instead of performing them
+    plan: Option<RefCell<Plan>>,
+    /// Set for incremental renders, see [Output::use_cache]
+    cache: Option<Cache>,
+}
+
+/// The cache of the render before, and the one of the current render
+#[derive(Debug)]
+struct Cache {
+    earlier: RenderCache,
+    current: RefCell<RenderCache>,
+    unchanged: Cell<usize>,
+    /// Files that failed to copy, of which the content is unknown
+    failed: RefCell<HashSet<PathBuf>>,
+}
+
+impl Output {
+    /// Create the output root directory if it does not exist yet,
+    /// and return a handle to it
+    pub fn create<C: Context + Default>(
+        root: &Path,
+        cancellation: Option<CancellationToken>,
+    ) -> Result<Self, C> {
+        let mut output = Self {
+            root: root.to_path_buf(),
+            cancellation,
+            manifest: Default::default(),
+            warnings: Default::default(),
+            plan: None,
+            cache: None,
+        };
+        output.create_dir_all(root)?;
+        output.root = root
+            .canonicalize()
+            .into_report()
+            .change_context(C::default())?;
+        Ok(output)
+    }
+
+    /// Return a handle to an output root directory for a dry run,
+    /// which doesn't create or write anything
+    pub fn plan<C: Context + Default>(
+        root: &Path,
+        cancellation: Option<CancellationToken>,
+    ) -> Result<Self, C> {
+        let root = resolve_path(root)?;
+        let output = Self {
+            root: root.clone(),
+            cancellation,
+            manifest: Default::default(),
+            warnings: Default::default(),
+            plan: Some(Default::default()),
+            cache: None,
+        };
+        output.create_dir_all(&root)?;
+        Ok(output)
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.plan.is_some()
+    }
+
+    /// The file operations of a dry run, in order
+    pub fn planned(&self) -> Option<Vec<PlannedOperation>> {
+        self.plan
+            .as_ref()
+            .map(|plan| plan.borrow().operations.clone())
+    }
+
+    /// Skip copying files that are the same as in the render before, which are listed in the
+    /// [RenderCache] in the output directory. The cache is only used if it was written by a
+    /// render with the same `fingerprint`. The cache file is removed until [Output::save_cache]
+    /// writes it again, or [Output::clean_up] writes one of the files it keeps, so that a
+    /// render that fails can't leave a cache behind that doesn't match the output.
+    pub fn use_cache<C: Context + Default>(&mut self, fingerprint: &str) -> Result<(), C> {
+        let earlier = RenderCache::load(&self.root, fingerprint);
+        if !self.is_dry_run() {
+            RenderCache::remove(&self.root)?;
+        }
+        self.cache = Some(Cache {
+            earlier,
+            current: RefCell::new(RenderCache::new(fingerprint)),
+            unchanged: Cell::new(0),
+            failed: Default::default(),
+        });
+        Ok(())
+    }
+
+    /// The number of files that were the same as in the render before, and weren't copied
+    /// again. `None` if the render doesn't use a cache.
+    pub fn unchanged(&self) -> Option<usize> {
+        self.cache.as_ref().map(|cache| cache.unchanged.get())
+    }
+
+    /// Remove the files that the render before copied but this one didn't,
+    /// and write the cache of this render into the output directory
+    pub fn save_cache<C: Context + Default>(&self) -> Result<(), C> {
+        let Some(cache) = self.cache.as_ref().filter(|_| !self.is_dry_run()) else {
+            return Ok(());
+        };
+        let current = cache.current.borrow();
+        for stale in cache.earlier.files().filter(|f| current.get(f).is_none()) {
+            let path = self.root.join(stale);
+            if path.is_file() {
+                fs::remove_file(&path)
+                    .into_report()
+                    .attach_printable_lazy(|| {
+                        format!(
+                            "Error removing file at path {path}",
+                            path = path.to_string_lossy()
+                        )
+                    })
+                    .change_context(C::default())?;
+            }
+        }
+        current.save(&self.root)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns an error with a [Cancelled] frame if the render was cancelled
+    pub fn check_cancelled<C: Context + Default>(&self) -> Result<(), C> {
+        check_cancelled(self.cancellation.as_ref())
+    }
+
+    /// Create a directory and all of its missing parents,
+    /// recording each directory that did not exist yet
+    pub fn create_dir_all<C: Context + Default>(&self, path: impl AsRef<Path>) -> Result<(), C> {
+        self.check_cancelled()?;
+        let path = path.as_ref();
+        let mut manifest = self.manifest.borrow_mut();
+        // Dry runs don't create directories, so they're only missing if not planned yet
+        let missing: Vec<_> = path
+            .ancestors()
+            .take_while(|p| {
+                !p.as_os_str().is_empty()
+                    && !p.exists()
+                    && !manifest
+                        .entries
+                        .contains(&(EntryKind::Dir, p.to_path_buf()))
+            })
+            .map(Path::to_path_buf)
+            .collect();
+        match &self.plan {
+            Some(plan) => plan.borrow_mut().operations.extend(
+                missing
+                    .iter()
+                    .rev()
+                    .map(|dir| PlannedOperation::CreateDir { path: dir.clone() }),
+            ),
+            None => path.create_dir_all()?,
+        }
+        missing
+            .into_iter()
+            .rev()
+            .for_each(|dir| manifest.record(EntryKind::Dir, dir));
+        Ok(())
+    }
+
+    /// Remove a directory of an earlier render and everything in it, if it exists
+    pub fn remove_dir_all<C: Context + Default>(&self, path: impl AsRef<Path>) -> Result<(), C> {
+        self.check_cancelled()?;
+        let path = path.as_ref();
+        if self.is_dry_run() || !path.exists() {
+            return Ok(());
+        }
+        fs::remove_dir_all(path)
+            .into_report()
+            .attach_printable_lazy(|| {
+                format!(
+                    "Error removing directory at path {path}",
+                    path = path.to_string_lossy()
+                )
+            })
+            .change_context(C::default())
+    }
+
+    pub fn create_file<C: Context + Default>(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<OutputFile, C> {
+        self.check_cancelled()?;
+        let path = path.as_ref();
+        let existed = path.exists();
+        let file = match &self.plan {
+            Some(plan) => {
+                let mut plan = plan.borrow_mut();
+                plan.operations.push(PlannedOperation::CreateFile {
+                    path: path.to_path_buf(),
+                });
+                OutputFile::Planned(plan.bytes.clone())
+            }
+            None => OutputFile::Written(path.create_file()?),
+        };
+        self.manifest
+            .borrow_mut()
+            .record_file(path.to_path_buf(), existed);
+        Ok(file)
+    }
+
+    pub fn copy<C: Context + Default>(
+        &self,
+        from: impl AsRef<Path>,
+        to: impl AsRef<Path>,
+    ) -> Result<(), C> {
+        self.check_cancelled()?;
+        let from = from.as_ref();
+        let to = to.as_ref();
+        let existed = to.exists();
+        match &self.plan {
+            Some(plan) => {
+                let size = file_size(from)?;
+                let mut plan = plan.borrow_mut();
+                plan.bytes.set(plan.bytes.get() + size);
+                plan.operations.push(PlannedOperation::CopyFile {
+                    from: from.to_path_buf(),
+                    to: to.to_path_buf(),
+                });
+            }
+            None => from.copy(to)?,
+        }
+        self.manifest
+            .borrow_mut()
+            .record_file(to.to_path_buf(), existed);
+        Ok(())
+    }
+
+    /// Copy files into the output, several at once, given as pairs of source and destination.
+    /// If `transform` returns content for a source file, that content is written to the
+    /// destination instead of a copy. Files are recorded in the order they are given, so that
+    /// the [Manifest] and the plans of dry runs don't depend on which copy finishes first.
+    pub fn copy_all<C: Context + Default>(
+        &self,
+        files: &[(PathBuf, PathBuf)],
+        transform: impl Fn(&Path) -> Result<Option<String>, C> + Sync,
+    ) -> Result<(), C> {
+        let dry_run = self.is_dry_run();
+        // The output itself can't be shared between threads, so its state is updated afterwards
+        let cancellation = self.cancellation.as_ref();
+        let earlier = self.cache.as_ref().map(|cache| &cache.earlier);
+        let root = self.root.as_path();
+        let results = parallel_map(files, |(from, to)| {
+            check_cancelled(cancellation)?;
+            let relative = to.strip_prefix(root).unwrap_or(to);
+            let hash = match earlier {
+                Some(earlier) => {
+                    let hash = content_hash(&read(from)?);
+                    if earlier.get(relative) == Some(hash.as_str()) && to.is_file() {
+                        return Ok((None, Some(hash)));
+                    }
+                    Some(hash)
+                }
+                None => None,
+            };
+
+            let content = transform(from)?;
+            let existed = to.exists();
+            if !dry_run {
+                match &content {
+                    Some(content) => to.create_file()?.write_all(content)?,
+                    None => from.copy(to)?,
+                }
+            }
+            let written = match content {
+                Some(content) => (
+                    PlannedOperation::CreateFile { path: to.clone() },
+                    content.len() as u64,
+                ),
+                // Sizes are only counted for dry runs
+                None => (
+                    PlannedOperation::CopyFile {
+                        from: from.clone(),
+                        to: to.clone(),
+                    },
+                    if dry_run { file_size(from)? } else { 0 },
+                ),
+            };
+            Ok((Some((written, existed)), hash))
+        });
+
+        // Files that were written before an error are recorded too, so that they get cleaned up
+        let mut manifest = self.manifest.borrow_mut();
+        let mut first_error = None;
+        for ((_, to), result) in files.iter().zip(results) {
+            let (written, hash) = match result {
+                Ok(result) => result,
+                Err(report) => {
+                    if let Some(cache) = &self.cache {
+                        let relative = to.strip_prefix(&self.root).unwrap_or(to);
+                        cache.failed.borrow_mut().insert(relative.to_path_buf());
+                    }
+                    first_error.get_or_insert(report);
+                    continue;
+                }
+            };
+            if let (Some(cache), Some(hash)) = (&self.cache, hash) {
+                let relative = to.strip_prefix(&self.root).unwrap_or(to);
+                cache
+                    .current
+                    .borrow_mut()
+                    .insert(relative.to_path_buf(), hash);
+            }
+            let Some(((operation, size), existed)) = written else {
+                let unchanged = &self.cache.as_ref().unwrap().unchanged;
+                unchanged.set(unchanged.get() + 1);
+                continue;
+            };
+            if let Some(plan) = &self.plan {
+                let mut plan = plan.borrow_mut();
+                plan.bytes.set(plan.bytes.get() + size);
+                plan.operations.push(operation);
+            }
+            manifest.record_file(to.clone(), existed);
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// The files written so far, in the order they were written
+    pub fn written_files(&self) -> Vec<PathBuf> {
+        self.manifest
+            .borrow()
+            .files()
+            .map(Path::to_path_buf)
+            .collect()
+    }
+
+    pub fn warn(&self, warning: Warning) {
+        self.warnings.borrow_mut().push(warning);
+    }
+
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.warnings.borrow().clone()
+    }
+
+    /// The number of files written so far, and their total size in bytes.
+    /// For dry runs, these are the files and bytes that would have been written.
+    pub fn written(&self) -> (usize, u64) {
+        let manifest = self.manifest.borrow();
+        if let Some(plan) = &self.plan {
+            return (manifest.files().count(), plan.borrow().bytes.get());
+        }
+        manifest.files().fold((0, 0), |(count, size), path| {
+            let file_size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            (count + 1, size + file_size)
+        })
+    }
+
+    /// Remove everything that was created during this render, newest first.
+    /// Directories are only removed if they are empty, and files that were there before,
+    /// like those of an earlier render that an incremental one overwrote, are kept, so that
+    /// files that were not created by us are left alone.
+    pub fn clean_up(&self) {
+        if self.is_dry_run() {
+            return;
+        }
+        let manifest = self.manifest.borrow();
+        for (kind, path) in manifest.entries.iter().rev() {
+            if manifest.existing.contains(path) {
+                continue;
+            }
+            // Best effort: there is nothing sensible to do if removal fails
+            let _ = match kind {
+                EntryKind::File => fs::remove_file(path),
+                EntryKind::Dir => fs::remove_dir(path),
+            };
+        }
+        // The files that are kept are as the cache of the render before has them, or as
+        // they were copied by this one
+        if let Some(cache) = &self.cache {
+            let current = cache.current.borrow();
+            let failed = cache.failed.borrow();
+            let mut kept = RenderCache::new(current.fingerprint());
+            for file in cache.earlier.files().chain(current.files()) {
+                let hash = current.get(file).or_else(|| cache.earlier.get(file));
+                let path = self.root.join(file);
+                if let Some(hash) = hash.filter(|_| !failed.contains(file) && path.is_file()) {
+                    kept.insert(file.to_path_buf(), hash.to_string());
+                }
+            }
+            let _ = fs::write(
+                self.root.join(RENDER_CACHE_FILE),
+                serde_json::to_string_pretty(&kept).unwrap(),
+            );
+        }
+    }
+}
+
+fn check_cancelled<C: Context + Default>(
+    cancellation: Option<&CancellationToken>,
+) -> Result<(), C> {
+    match cancellation {
+        Some(token) if token.is_cancelled() => {
+            Err(Report::new(Cancelled).change_context(C::default()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn read<C: Context + Default>(path: &Path) -> Result<Vec<u8>, C> {
+    fs::read(path)
+        .into_report()
+        .attach_printable_lazy(|| {
+            format!(
+                "Error reading file at path {path}",
+                path = path.to_string_lossy()
+            )
+        })
+        .change_context(C::default())
+}
+
+fn file_size<C: Context + Default>(path: &Path) -> Result<u64, C> {
+    Ok(fs::metadata(path)
+        .into_report()
+        .attach_printable_lazy(|| {
+            format!(
+                "Error reading metadata of file at path {path}",
+                path = path.to_string_lossy()
+            )
+        })
+        .change_context(C::default())?
+        .len())
+}
+
+/// Absolute version of `path` with symbolic links resolved, for paths that may not
+/// exist yet. The part of the path that doesn't exist is appended as is.
+pub fn resolve_path<C: Context + Default>(path: &Path) -> Result<PathBuf, C> {
+    let path = std::path::absolute(path)
+        .into_report()
+        .change_context(C::default())?;
+    let mut existing = path.as_path();
+    let mut rest = vec![];
+    while !existing.exists() {
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            break;
+        };
+        rest.push(name);
+        existing = parent;
+    }
+    let mut resolved = existing
+        .canonicalize()
+        .into_report()
+        .change_context(C::default())?;
+    resolved.extend(rest.into_iter().rev());
+    Ok(resolved)
+}
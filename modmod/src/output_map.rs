@@ -0,0 +1,204 @@
+//! Mapping from course items to the locations they were rendered into,
+//! so that external tools don't need to know how output is named and numbered.
+//!
+//! Items are identified by the same ids as in the [Outline](crate::Outline).
+//! The map is versioned with [OUTPUT_MAP_SCHEMA_VERSION], with the same compatibility
+//! guarantee as the outline. All paths are relative to the output directory.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use error_stack::{Report, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    book::BookPaths,
+    exercises::ExercisePaths,
+    outline::{relative_id, topic_id, unit_id},
+    LoadTrackError, Track,
+};
+
+pub const OUTPUT_MAP_SCHEMA_VERSION: u32 = 2;
+
+/// Name of the file the map is written to in the output directory
+pub const OUTPUT_MAP_FILE: &str = "modmod-map.json";
+
+/// Where the items of a track ended up in a render
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutputMap {
+    pub schema_version: u32,
+    pub modules: BTreeMap<String, MappedModule>,
+    pub units: BTreeMap<String, MappedUnit>,
+    /// Every occurrence of a topic, in the order of the track, as a topic can be part of
+    /// more than one unit
+    pub topics: BTreeMap<String, Vec<MappedTopic>>,
+    /// Every occurrence of an exercise, in the order of the track, which is rendered once for
+    /// every unit its topic is part of
+    pub exercises: BTreeMap<String, Vec<MappedExercise>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappedModule {
+    pub name: String,
+    pub number: String,
+    pub exercises_dir: PathBuf,
+    /// Only modules with a description, objectives or further reading have a page of their own
+    pub book_page: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappedUnit {
+    pub name: String,
+    pub module: String,
+    pub number: String,
+    pub exercises_dir: PathBuf,
+    /// ZIP archive of the exercises of the unit, if exercises are zipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exercises_archive: Option<PathBuf>,
+    pub book_page: PathBuf,
+    pub slides: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappedTopic {
+    pub name: String,
+    pub unit: String,
+    /// The slide deck of the unit, which the slides of the topic are part of
+    pub slides: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappedExercise {
+    pub name: String,
+    pub topic: String,
+    pub unit: String,
+    /// Number of the exercise as used in the book
+    pub number: String,
+    pub dir: PathBuf,
+    /// Tests for grading submissions of the exercise, only in the instructor edition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grading_dir: Option<PathBuf>,
+    /// The page of the unit, on which the exercise is described
+    pub book_page: PathBuf,
+}
+
+impl OutputMap {
+    pub(crate) fn new(
+        track: &Track,
+        exercise_paths: &ExercisePaths,
+        book_paths: &BookPaths,
+        deck_paths: &HashMap<(usize, usize), PathBuf>,
+    ) -> Self {
+        let base_path = track.path.parent().unwrap();
+        let mut map = Self {
+            schema_version: OUTPUT_MAP_SCHEMA_VERSION,
+            ..Default::default()
+        };
+
+        for module in track.modules.iter() {
+            let m = module.index;
+            let module_id = relative_id(module.data.path.parent().unwrap(), base_path);
+            map.modules.insert(
+                module_id.clone(),
+                MappedModule {
+                    name: module.data.name.clone(),
+                    number: m.to_string(),
+                    exercises_dir: exercise_paths.modules[&m].clone(),
+                    book_page: book_paths.chapters.get(&m).cloned(),
+                },
+            );
+
+            for unit in module.data.units.iter() {
+                let u = unit.index;
+                let unit_id = unit_id(&module_id, &unit.data);
+                let book_page = book_paths.sections[&(m, u)].clone();
+                let slides = deck_paths[&(m, u)].clone();
+                map.units.insert(
+                    unit_id.clone(),
+                    MappedUnit {
+                        name: unit.data.name.clone(),
+                        module: module_id.clone(),
+                        number: format!("{m}.{u}"),
+                        exercises_dir: exercise_paths.units[&(m, u)].clone(),
+                        exercises_archive: exercise_paths.unit_archives.get(&(m, u)).cloned(),
+                        book_page: book_page.clone(),
+                        slides: slides.clone(),
+                    },
+                );
+
+                // Exercises are numbered throughout the unit
+                let mut exercise_number = 0;
+                for topic in unit.data.topics.iter() {
+                    let topic_id = topic_id(&topic.data, base_path);
+                    map.topics
+                        .entry(topic_id.clone())
+                        .or_default()
+                        .push(MappedTopic {
+                            name: topic.data.name.clone(),
+                            unit: unit_id.clone(),
+                            slides: slides.clone(),
+                        });
+
+                    for exercise in topic.data.exercises.iter() {
+                        exercise_number += 1;
+                        let key = (m, u, exercise_number);
+                        map.exercises
+                            .entry(relative_id(&exercise.data.path, base_path))
+                            .or_default()
+                            .push(MappedExercise {
+                                name: exercise.data.name.clone(),
+                                topic: topic_id.clone(),
+                                unit: unit_id.clone(),
+                                number: format!("{m}.{u}.{exercise_number}"),
+                                dir: exercise_paths.exercises[&key].clone(),
+                                grading_dir: exercise_paths.grading.get(&key).cloned(),
+                                book_page: book_page.clone(),
+                            });
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    fn paths(&self) -> impl Iterator<Item = &Path> {
+        let modules = self
+            .modules
+            .values()
+            .flat_map(|m| [Some(&m.exercises_dir), m.book_page.as_ref()])
+            .flatten();
+        let units = self.units.values().flat_map(|u| {
+            [Some(&u.exercises_dir), u.exercises_archive.as_ref()]
+                .into_iter()
+                .flatten()
+                .chain([&u.book_page, &u.slides])
+        });
+        let topics = self.topics.values().flatten().map(|t| &t.slides);
+        let exercises = self.exercises.values().flatten().flat_map(|e| {
+            [Some(&e.dir), e.grading_dir.as_ref(), Some(&e.book_page)]
+                .into_iter()
+                .flatten()
+        });
+        modules
+            .chain(units)
+            .chain(topics)
+            .chain(exercises)
+            .map(PathBuf::as_path)
+    }
+
+    /// Check that every path in the map exists in the output directory at `root`
+    pub fn check(&self, root: &Path) -> Result<(), LoadTrackError> {
+        let missing: Vec<_> = self.paths().filter(|p| !root.join(p).exists()).collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let mut report = Report::new(LoadTrackError)
+            .attach_printable("Output map refers to paths that were not rendered");
+        for path in missing {
+            report = report.attach_printable(path.to_string_lossy().to_string());
+        }
+        Err(report)
+    }
+}
@@ -0,0 +1,408 @@
+//! Slide decks as PowerPoint presentations, for clients that require them. Every slide of a
+//! deck becomes a slide with its heading as the title and its text and code in text boxes.
+//! Presentations are written with the same ZIP writer as the archives of the exercises.
+
+use std::fmt::Write;
+
+use crate::{archive, slides::split_headmatter, stats::split_slides};
+
+const ROOT_RELS: &str = include_str!("../include/pptx/rels.xml");
+const SLIDE_MASTER: &str = include_str!("../include/pptx/slideMaster1.xml");
+const SLIDE_MASTER_RELS: &str = include_str!("../include/pptx/slideMaster1.xml.rels");
+const SLIDE_LAYOUT: &str = include_str!("../include/pptx/slideLayout1.xml");
+const SLIDE_LAYOUT_RELS: &str = include_str!("../include/pptx/slideLayout1.xml.rels");
+const THEME: &str = include_str!("../include/pptx/theme1.xml");
+
+const NAMESPACES: &str = r#"xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main""#;
+const RELATIONSHIPS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const CONTENT_TYPES: &str = "application/vnd.openxmlformats-officedocument.presentationml";
+
+/// Size of the slides in EMU, the unit of Office documents, which is 16:9
+const SLIDE_WIDTH: u64 = 12_192_000;
+const SLIDE_HEIGHT: u64 = 6_858_000;
+/// Half an inch
+const MARGIN: u64 = 457_200;
+/// Height of a line of text and of code, at their font sizes
+const TEXT_LINE: u64 = 304_800;
+const CODE_LINE: u64 = 213_360;
+/// Font sizes in hundredths of a point
+const TITLE_SIZE: u32 = 3200;
+const TEXT_SIZE: u32 = 2000;
+const CODE_SIZE: u32 = 1400;
+const CODE_FONT: &str = "Consolas";
+/// Background of code blocks
+const CODE_FILL: &str = "F3F4F6";
+
+/// What's on a slide besides its title
+enum Block {
+    /// Paragraphs of text, with the indentation level of the list item they are, if any
+    Text(Vec<(String, Option<usize>)>),
+    /// Lines of a code block
+    Code(Vec<String>),
+}
+
+/// The PowerPoint presentation of the Slidev `slides`, with the title of their headmatter,
+/// in `language`. Headings, text, lists and code are converted, while images, HTML and
+/// notes are left out. Fails if the presentation is too large for a ZIP archive.
+pub(crate) fn deck(slides: &str, language: &str) -> Result<Vec<u8>, String> {
+    let (headmatter, slides) = split_headmatter(slides);
+    let headmatter: serde_yaml::Value =
+        serde_yaml::from_str(&headmatter.join("\n")).unwrap_or_default();
+    let title = headmatter["title"].as_str().unwrap_or_default();
+    let slides: Vec<String> = split_slides(slides)
+        .iter()
+        .map(|lines| slide_xml(lines, language))
+        .collect();
+
+    let mut content_types = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+  <Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+  <Default Extension="xml" ContentType="application/xml"/>
+  <Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+"#,
+    );
+    for (part, kind) in [
+        ("presentation.xml", "presentation.main"),
+        ("slideMasters/slideMaster1.xml", "slideMaster"),
+        ("slideLayouts/slideLayout1.xml", "slideLayout"),
+    ] {
+        writeln!(
+            content_types,
+            r#"  <Override PartName="/ppt/{part}" ContentType="{CONTENT_TYPES}.{kind}+xml"/>"#
+        )
+        .unwrap();
+    }
+    content_types.push_str(r#"  <Override PartName="/ppt/theme/theme1.xml" ContentType="application/vnd.openxmlformats-officedocument.theme+xml"/>"#);
+    content_types.push('\n');
+    let mut slide_ids = String::new();
+    let mut presentation_rels = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId1" Type="{RELATIONSHIPS}/slideMaster" Target="slideMasters/slideMaster1.xml"/>
+  <Relationship Id="rId2" Type="{RELATIONSHIPS}/theme" Target="theme/theme1.xml"/>
+"#
+    );
+    for number in 1..=slides.len() {
+        writeln!(
+            content_types,
+            r#"  <Override PartName="/ppt/slides/slide{number}.xml" ContentType="{CONTENT_TYPES}.slide+xml"/>"#
+        )
+        .unwrap();
+        // Ids of slides start at 256, and the relationships of the first two are taken
+        writeln!(
+            slide_ids,
+            r#"    <p:sldId id="{id}" r:id="rId{rel}"/>"#,
+            id = 255 + number,
+            rel = number + 2
+        )
+        .unwrap();
+        writeln!(
+            presentation_rels,
+            r#"  <Relationship Id="rId{rel}" Type="{RELATIONSHIPS}/slide" Target="slides/slide{number}.xml"/>"#,
+            rel = number + 2
+        )
+        .unwrap();
+    }
+    content_types.push_str("</Types>\n");
+    presentation_rels.push_str("</Relationships>\n");
+    let presentation = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:presentation {NAMESPACES}>
+  <p:sldMasterIdLst><p:sldMasterId id="2147483648" r:id="rId1"/></p:sldMasterIdLst>
+  <p:sldIdLst>
+{slide_ids}  </p:sldIdLst>
+  <p:sldSz cx="{SLIDE_WIDTH}" cy="{SLIDE_HEIGHT}"/>
+  <p:notesSz cx="{SLIDE_HEIGHT}" cy="{SLIDE_WIDTH}"/>
+</p:presentation>
+"#
+    );
+    let core = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <dc:title>{title}</dc:title>
+  <dc:language>{language}</dc:language>
+</cp:coreProperties>
+"#,
+        title = xml_escape(title),
+        language = xml_escape(language)
+    );
+    let slide_rels = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId1" Type="{RELATIONSHIPS}/slideLayout" Target="../slideLayouts/slideLayout1.xml"/>
+</Relationships>
+"#
+    );
+
+    let mut parts: Vec<(String, &[u8])> = vec![
+        ("[Content_Types].xml".into(), content_types.as_bytes()),
+        ("_rels/.rels".into(), ROOT_RELS.as_bytes()),
+        ("docProps/core.xml".into(), core.as_bytes()),
+        ("ppt/presentation.xml".into(), presentation.as_bytes()),
+        (
+            "ppt/_rels/presentation.xml.rels".into(),
+            presentation_rels.as_bytes(),
+        ),
+        (
+            "ppt/slideMasters/slideMaster1.xml".into(),
+            SLIDE_MASTER.as_bytes(),
+        ),
+        (
+            "ppt/slideMasters/_rels/slideMaster1.xml.rels".into(),
+            SLIDE_MASTER_RELS.as_bytes(),
+        ),
+        (
+            "ppt/slideLayouts/slideLayout1.xml".into(),
+            SLIDE_LAYOUT.as_bytes(),
+        ),
+        (
+            "ppt/slideLayouts/_rels/slideLayout1.xml.rels".into(),
+            SLIDE_LAYOUT_RELS.as_bytes(),
+        ),
+        ("ppt/theme/theme1.xml".into(), THEME.as_bytes()),
+    ];
+    for (slide, number) in slides.iter().zip(1..) {
+        parts.push((format!("ppt/slides/slide{number}.xml"), slide.as_bytes()));
+        parts.push((
+            format!("ppt/slides/_rels/slide{number}.xml.rels"),
+            slide_rels.as_bytes(),
+        ));
+    }
+    archive::zip(
+        parts
+            .iter()
+            .map(|(name, content)| (name.as_str(), *content)),
+    )
+}
+
+/// The title of the slide with the markdown `lines`, its first heading, and what else is on it
+fn parse_slide(lines: &[&str]) -> (Option<String>, Vec<Block>) {
+    let mut title = None;
+    let mut blocks = vec![];
+    let mut paragraphs: Vec<(String, Option<usize>)> = vec![];
+    // Lines of the code block and of the HTML comment we're in, if any
+    let mut code: Option<Vec<String>> = None;
+    let mut in_comment = false;
+    // Whether the last paragraph continues on the next line
+    let mut open = false;
+    for line in lines {
+        let trimmed = line.trim();
+        if let Some(code_lines) = code.as_mut() {
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                blocks.push(Block::Code(code.take().unwrap()));
+            } else {
+                code_lines.push(line.trim_end().to_string());
+            }
+            continue;
+        }
+        if in_comment || trimmed.starts_with("<!--") {
+            in_comment = !trimmed.ends_with("-->");
+            open = false;
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            if !paragraphs.is_empty() {
+                blocks.push(Block::Text(std::mem::take(&mut paragraphs)));
+            }
+            code = Some(vec![]);
+            open = false;
+            continue;
+        }
+        // Images and HTML, which can't be converted
+        if trimmed.is_empty() || trimmed.starts_with("![") || trimmed.starts_with('<') {
+            open = false;
+            continue;
+        }
+        if let Some(heading) = trimmed.strip_prefix('#') {
+            let heading = heading.trim_start_matches('#').trim().to_string();
+            if title.is_none() && blocks.is_empty() && paragraphs.is_empty() {
+                title = Some(heading);
+            } else {
+                paragraphs.push((heading, None));
+            }
+            open = false;
+            continue;
+        }
+        let indentation = line.len() - line.trim_start().len();
+        let item = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|marker| trimmed.strip_prefix(marker));
+        match (item, paragraphs.last_mut()) {
+            (Some(item), _) => {
+                paragraphs.push((item.to_string(), Some(indentation / 2)));
+                open = true;
+            }
+            (None, Some((paragraph, _))) if open => {
+                paragraph.push(' ');
+                paragraph.push_str(trimmed);
+            }
+            (None, _) => {
+                paragraphs.push((trimmed.to_string(), None));
+                open = true;
+            }
+        }
+    }
+    if let Some(code) = code {
+        blocks.push(Block::Code(code));
+    }
+    if !paragraphs.is_empty() {
+        blocks.push(Block::Text(paragraphs));
+    }
+    (title, blocks)
+}
+
+/// The slide part for the slide with the markdown `lines`, with its blocks in text boxes
+/// below each other
+fn slide_xml(lines: &[&str], language: &str) -> String {
+    let (title, blocks) = parse_slide(lines);
+    let width = SLIDE_WIDTH - 2 * MARGIN;
+    let mut shapes = String::new();
+    let mut id = 2;
+    let mut y = MARGIN;
+    if let Some(title) = title {
+        let paragraph = paragraph_xml(&title, None, TITLE_SIZE, true, language);
+        shapes.push_str(&shape_xml(id, (y, 2 * TEXT_LINE), width, None, &paragraph));
+        id += 1;
+        y += 3 * TEXT_LINE;
+    }
+    for block in blocks {
+        let (height, fill, paragraphs) = match block {
+            Block::Text(paragraphs) => (
+                paragraphs.len() as u64 * TEXT_LINE,
+                None,
+                paragraphs
+                    .iter()
+                    .map(|(text, level)| paragraph_xml(text, *level, TEXT_SIZE, false, language))
+                    .collect::<String>(),
+            ),
+            Block::Code(lines) => (
+                lines.len() as u64 * CODE_LINE,
+                Some(CODE_FILL),
+                lines
+                    .iter()
+                    .map(|line| code_paragraph_xml(line, language))
+                    .collect(),
+            ),
+        };
+        // Text boxes have a margin of a tenth of an inch inside
+        let height = height + 2 * 91_440;
+        shapes.push_str(&shape_xml(id, (y, height), width, fill, &paragraphs));
+        id += 1;
+        y += height + TEXT_LINE / 2;
+    }
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:sld {NAMESPACES}>
+  <p:cSld>
+    <p:spTree>
+      <p:nvGrpSpPr><p:cNvPr id="1" name=""/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr>
+      <p:grpSpPr/>
+{shapes}    </p:spTree>
+  </p:cSld>
+  <p:clrMapOvr><a:masterClrMapping/></p:clrMapOvr>
+</p:sld>
+"#
+    )
+}
+
+/// A text box with shape id `id` at the vertical position and height `(y, height)`, filled
+/// with the color `fill` if it's set
+fn shape_xml(
+    id: u32,
+    (y, height): (u64, u64),
+    width: u64,
+    fill: Option<&str>,
+    paragraphs: &str,
+) -> String {
+    let fill = fill
+        .map(|color| format!(r#"<a:solidFill><a:srgbClr val="{color}"/></a:solidFill>"#))
+        .unwrap_or_default();
+    format!(
+        r#"      <p:sp>
+        <p:nvSpPr><p:cNvPr id="{id}" name="Text {id}"/><p:cNvSpPr txBox="1"/><p:nvPr/></p:nvSpPr>
+        <p:spPr><a:xfrm><a:off x="{MARGIN}" y="{y}"/><a:ext cx="{width}" cy="{height}"/></a:xfrm><a:prstGeom prst="rect"><a:avLst/></a:prstGeom>{fill}</p:spPr>
+        <p:txBody><a:bodyPr wrap="square"><a:normAutofit/></a:bodyPr><a:lstStyle/>{paragraphs}</p:txBody>
+      </p:sp>
+"#
+    )
+}
+
+/// A paragraph of the markdown `text`, as a list item at `level` if it's set. Inline code
+/// is shown in the font of code blocks, and the syntax of emphasis and links is left out.
+fn paragraph_xml(
+    text: &str,
+    level: Option<usize>,
+    size: u32,
+    bold: bool,
+    language: &str,
+) -> String {
+    let properties = match level {
+        Some(level) => format!(
+            r#"<a:pPr marL="{margin}" indent="-228600"><a:buChar char="•"/></a:pPr>"#,
+            margin = 228_600 * (level + 1)
+        ),
+        None => "<a:pPr><a:buNone/></a:pPr>".to_string(),
+    };
+    let bold = if bold { r#" b="1""# } else { "" };
+    let mut runs = String::new();
+    for (segment, is_code) in plain_text(text)
+        .split('`')
+        .zip([false, true].into_iter().cycle())
+    {
+        if segment.is_empty() {
+            continue;
+        }
+        let font = if is_code {
+            format!(r#"<a:latin typeface="{CODE_FONT}"/>"#)
+        } else {
+            String::new()
+        };
+        write!(
+            runs,
+            r#"<a:r><a:rPr lang="{language}" sz="{size}"{bold}>{font}</a:rPr><a:t>{text}</a:t></a:r>"#,
+            language = xml_escape(language),
+            text = xml_escape(segment)
+        )
+        .unwrap();
+    }
+    format!("<a:p>{properties}{runs}</a:p>")
+}
+
+/// A line of a code block, with its indentation
+fn code_paragraph_xml(line: &str, language: &str) -> String {
+    let properties = format!(
+        r#"lang="{language}" sz="{CODE_SIZE}""#,
+        language = xml_escape(language)
+    );
+    if line.is_empty() {
+        return format!("<a:p><a:endParaRPr {properties}/></a:p>");
+    }
+    format!(
+        r#"<a:p><a:r><a:rPr {properties}><a:latin typeface="{CODE_FONT}"/></a:rPr><a:t>{line}</a:t></a:r></a:p>"#,
+        line = xml_escape(line)
+    )
+}
+
+/// `text` without the markdown syntax of emphasis, and with links replaced by their text
+fn plain_text(text: &str) -> String {
+    let mut plain = text.replace("**", "").replace("__", "");
+    while let Some(start) = plain.find('[') {
+        let Some(middle) = plain[start..].find("](").map(|i| start + i) else {
+            break;
+        };
+        let Some(end) = plain[middle..].find(')').map(|i| middle + i) else {
+            break;
+        };
+        plain.replace_range(middle..=end, "");
+        plain.remove(start);
+    }
+    plain
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
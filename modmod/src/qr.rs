@@ -0,0 +1,75 @@
+//! QR codes pointing at the published location of rendered items,
+//! so that attendees can open exercises and slides on their phones.
+//!
+//! QR codes are only generated if modmod is built with the `qr` feature.
+
+use std::{fmt, path::Path};
+
+use error_stack::{Report, Result};
+
+use crate::{LoadTrackError, Track};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct RenderQrError;
+
+impl fmt::Display for RenderQrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to render QR code")
+    }
+}
+
+impl error_stack::Context for RenderQrError {}
+
+/// Generates QR codes for paths in the output directory, as published under a base URL
+#[derive(Debug)]
+pub struct QrCodes<'track> {
+    base_url: &'track str,
+}
+
+impl<'track> QrCodes<'track> {
+    /// QR codes for the track, if enabled. Enabling QR codes without
+    /// a `base_url` in the track definition is an error.
+    pub(crate) fn for_track(track: &'track Track) -> Result<Option<Self>, LoadTrackError> {
+        if !cfg!(feature = "qr") {
+            return Ok(None);
+        }
+        match &track.base_url {
+            Some(base_url) => Ok(Some(Self { base_url })),
+            None => Err(Report::new(LoadTrackError).attach_printable(
+                "QR codes are enabled, which requires `base_url` to be set in the track definition",
+            )),
+        }
+    }
+
+    /// The published URL of a path relative to the output directory
+    pub fn url(&self, path: &Path) -> String {
+        let path: Vec<_> = path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        format!("{}/{}", self.base_url.trim_end_matches('/'), path.join("/"))
+    }
+
+    /// An SVG image of a QR code pointing at the published URL of `path`
+    pub fn svg(&self, path: &Path) -> Result<String, RenderQrError> {
+        let url = self.url(path);
+        render_svg(&url).map_err(|e| e.attach_printable(format!("URL: {url}")))
+    }
+}
+
+#[cfg(feature = "qr")]
+fn render_svg(url: &str) -> Result<String, RenderQrError> {
+    use error_stack::{IntoReport, ResultExt};
+    use qrcode::{render::svg, QrCode};
+
+    let code = QrCode::new(url.as_bytes())
+        .into_report()
+        .change_context(RenderQrError)?;
+    Ok(code.render::<svg::Color>().min_dimensions(256, 256).build())
+}
+
+#[cfg(not(feature = "qr"))]
+fn render_svg(_url: &str) -> Result<String, RenderQrError> {
+    unreachable!("QR codes are only created with the qr feature")
+}
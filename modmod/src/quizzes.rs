@@ -0,0 +1,148 @@
+//! Quizzes of topics: the knowledge check at the end of every unit in the book, and
+//! `quizzes.json` in the output directory, for tools like learning platforms to import the
+//! questions from.
+//!
+//! Units and topics are identified by the same ids as in the [Outline](crate::Outline), and
+//! the file is versioned with [QUIZZES_SCHEMA_VERSION], with the same compatibility guarantee
+//! as the outline.
+
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    outline::{relative_id, topic_id, unit_id},
+    Quiz, Track,
+};
+
+pub const QUIZZES_SCHEMA_VERSION: u32 = 1;
+
+/// Name of the file the quizzes are written to in the output directory
+pub const QUIZZES_FILE: &str = "quizzes.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Quizzes {
+    pub schema_version: u32,
+    /// The units that have questions, in the order of the track
+    pub units: Vec<UnitQuizzes>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnitQuizzes {
+    pub id: String,
+    pub title: String,
+    /// Number of the unit as used in the book, like `2.1`
+    pub number: String,
+    pub questions: Vec<QuizQuestion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizQuestion {
+    /// Id of the topic that the question is about
+    pub topic: String,
+    pub question: String,
+    pub choices: Vec<String>,
+    /// Index of the correct one of the `choices`, starting at 0
+    pub answer: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+}
+
+impl Quizzes {
+    pub(crate) fn new(track: &Track) -> Self {
+        let base_path = track.path.parent().unwrap();
+        let mut quizzes = Self {
+            schema_version: QUIZZES_SCHEMA_VERSION,
+            units: vec![],
+        };
+        for module in track.modules.iter() {
+            let module_id = relative_id(module.data.path.parent().unwrap(), base_path);
+            for unit in module.data.units.iter() {
+                let questions: Vec<QuizQuestion> = unit
+                    .data
+                    .topics
+                    .iter()
+                    .flat_map(|topic| {
+                        let id = topic_id(&topic.data, base_path);
+                        topic.data.quizzes.iter().map(move |quiz| QuizQuestion {
+                            topic: id.clone(),
+                            question: quiz.question.trim().to_string(),
+                            choices: quiz.choices.iter().map(|c| c.trim().to_string()).collect(),
+                            answer: quiz.answer_index(),
+                            explanation: quiz.explanation.as_ref().map(|e| e.trim().to_string()),
+                        })
+                    })
+                    .collect();
+                if questions.is_empty() {
+                    continue;
+                }
+                quizzes.units.push(UnitQuizzes {
+                    id: unit_id(&module_id, &unit.data),
+                    title: unit.data.name.clone(),
+                    number: format!("{}.{}", module.index, unit.index),
+                    questions,
+                });
+            }
+        }
+        quizzes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+}
+
+/// The `quizzes` in the format of [mdbook-quiz](https://github.com/cognitive-engineering-lab/mdbook-quiz),
+/// with the choices other than the answer as distractors
+pub(crate) fn mdbook_quiz_toml(quizzes: &[&Quiz]) -> String {
+    let mut toml = String::new();
+    for quiz in quizzes {
+        let distractors: toml_edit::Array = quiz
+            .choices
+            .iter()
+            .map(|choice| choice.trim())
+            .filter(|choice| *choice != quiz.answer.trim())
+            .collect();
+        write!(
+            toml,
+            "[[questions]]\ntype = \"MultipleChoice\"\nprompt.prompt = {}\nprompt.distractors = {distractors}\nanswer.answer = {}\n",
+            toml_edit::Value::from(quiz.question.trim()),
+            toml_edit::Value::from(quiz.answer.trim()),
+        )
+        .unwrap();
+        if let Some(explanation) = &quiz.explanation {
+            writeln!(
+                toml,
+                "context = {}",
+                toml_edit::Value::from(explanation.trim())
+            )
+            .unwrap();
+        }
+        toml.push('\n');
+    }
+    toml
+}
+
+/// The markdown of the knowledge check of a unit, with its `quizzes` under `###` headings
+/// and their answers folded away
+pub(crate) fn knowledge_check(quizzes: &[&Quiz]) -> String {
+    let mut check = String::from("## Knowledge check\n");
+    for (quiz, i) in quizzes.iter().zip(1..) {
+        write!(check, "\n### Question {i}\n\n{}\n\n", quiz.question.trim()).unwrap();
+        for (choice, letter) in quiz.choices.iter().zip('A'..) {
+            writeln!(check, "- **{letter}.** {}", choice.trim()).unwrap();
+        }
+        let letter = ('A'..).nth(quiz.answer_index()).unwrap();
+        write!(
+            check,
+            "\n<details>\n<summary>Answer</summary>\n\n**{letter}.** {}\n",
+            quiz.answer.trim()
+        )
+        .unwrap();
+        if let Some(explanation) = &quiz.explanation {
+            write!(check, "\n{}\n", explanation.trim()).unwrap();
+        }
+        check.push_str("\n</details>\n");
+    }
+    check
+}
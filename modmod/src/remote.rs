@@ -0,0 +1,309 @@
+//! Modules that are loaded from git repositories, rather than from the file system
+//! next to the track. Repositories are cloned into a cache directory once per revision,
+//! and fetched again when a revision that isn't pinned yet is used. The commits that
+//! revisions pointed to are pinned in a lockfile next to the track.
+
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use serde::Deserialize;
+
+use crate::{
+    cache::content_hash,
+    io::{PathExt, WriteExt},
+};
+
+/// Prefix of module paths that refer to a git repository
+const GIT_PREFIX: &str = "git+";
+
+/// Name of the lockfile, which is written next to the track definition
+pub const LOCKFILE: &str = "modmod.lock";
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct FetchSourceError;
+
+impl fmt::Display for FetchSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to fetch remote source")
+    }
+}
+
+impl error_stack::Context for FetchSourceError {}
+
+/// A file in a git repository, written as `git+<url>#<revision>:<path>`.
+/// The revision is a branch, tag or commit, and the default branch if it's empty.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct GitSource {
+    pub(crate) url: String,
+    pub(crate) revision: String,
+    pub(crate) path: PathBuf,
+}
+
+impl GitSource {
+    /// Parse `path` as a git source, returning `None` if it's a regular path
+    pub(crate) fn parse(path: &Path) -> Option<Result<Self, FetchSourceError>> {
+        let source = path.to_str()?.strip_prefix(GIT_PREFIX)?;
+        let parsed = source.rsplit_once('#').and_then(|(url, rest)| {
+            let (revision, path) = rest.split_once(':')?;
+            (!url.is_empty() && !path.is_empty()).then(|| Self {
+                url: url.to_string(),
+                revision: revision.to_string(),
+                path: PathBuf::from(path),
+            })
+        });
+        Some(parsed.ok_or_else(|| {
+            Report::new(FetchSourceError).attach_printable(format!(
+                "Git source '{GIT_PREFIX}{source}' is not of the form 'git+<url>#<revision>:<path>'"
+            ))
+        }))
+    }
+
+    /// The repository and revision, which is what the lockfile pins
+    fn revision_id(&self) -> String {
+        format!(
+            "{GIT_PREFIX}{url}#{revision}",
+            url = self.url,
+            revision = self.revision
+        )
+    }
+
+    /// Clone the repository into the cache, unless it was cloned before, and return
+    /// the directory of the clone. If `update` is set, an earlier clone is fetched
+    /// again, so that a branch or tag is checked out at the commit it points to now.
+    fn fetch(&self, update: bool) -> Result<PathBuf, FetchSourceError> {
+        let clone_dir = cache_dir::<FetchSourceError>()?
+            .join("git")
+            .join(content_hash(self.revision_id().as_bytes()));
+        if !clone_dir.join(".git").is_dir() {
+            self.clone_into(&clone_dir)?;
+        } else if update {
+            git(
+                &["fetch", "--quiet", "--tags", "--force", "origin"],
+                &clone_dir,
+            )
+            .attach_printable_lazy(|| format!("Unable to fetch {url}", url = self.url))?;
+            self.check_out(&clone_dir)?;
+        }
+        Ok(clone_dir)
+    }
+
+    fn clone_into(&self, clone_dir: &Path) -> Result<(), FetchSourceError> {
+        // Clone next to the final directory first, so that an interrupted clone
+        // isn't mistaken for a complete one later
+        let partial_dir = clone_dir.with_extension("partial");
+        if partial_dir.exists() {
+            fs::remove_dir_all(&partial_dir)
+                .into_report()
+                .change_context(FetchSourceError)?;
+        }
+        fs::create_dir_all(&partial_dir)
+            .into_report()
+            .change_context(FetchSourceError)?;
+        let cloned = git(&["clone", "--quiet", "--", &self.url, "."], &partial_dir)
+            .attach_printable_lazy(|| format!("Unable to clone {url}", url = self.url))
+            .and_then(|_| self.check_out(&partial_dir));
+        if cloned.is_err() {
+            let _ = fs::remove_dir_all(&partial_dir);
+        }
+        cloned?;
+        fs::rename(&partial_dir, clone_dir)
+            .into_report()
+            .change_context(FetchSourceError)
+    }
+
+    /// Check out the commit that the revision points to in the clone at `clone_dir`.
+    /// Branches are looked up on the remote, as the clone only has its default branch.
+    fn check_out(&self, clone_dir: &Path) -> Result<(), FetchSourceError> {
+        let candidates = if self.revision.is_empty() {
+            vec!["refs/remotes/origin/HEAD".to_string()]
+        } else {
+            vec![
+                format!("refs/remotes/origin/{revision}", revision = self.revision),
+                format!("refs/tags/{revision}", revision = self.revision),
+                self.revision.clone(),
+            ]
+        };
+        let commit = candidates
+            .iter()
+            .find_map(|revision| {
+                git(
+                    &[
+                        "rev-parse",
+                        "--verify",
+                        "--quiet",
+                        &format!("{revision}^{{commit}}"),
+                    ],
+                    clone_dir,
+                )
+                .ok()
+            })
+            .ok_or_else(|| {
+                Report::new(FetchSourceError).attach_printable(format!(
+                    "Revision '{revision}' not found in {url}",
+                    revision = self.revision,
+                    url = self.url
+                ))
+            })?;
+        git(
+            &["checkout", "--quiet", "--detach", &commit, "--"],
+            clone_dir,
+        )
+        .map(|_| ())
+        .attach_printable_lazy(|| {
+            format!(
+                "Unable to check out revision '{revision}' of {url}",
+                revision = self.revision,
+                url = self.url
+            )
+        })
+    }
+}
+
+/// The commits that the revisions of git sources pointed to when they were first fetched,
+/// so that later renders use the same content even if a branch or tag moved.
+/// Several tracks in a folder share its lockfile, so sources are never removed from it.
+#[derive(Debug)]
+pub(crate) struct Lockfile {
+    path: PathBuf,
+    /// Fail instead of adding sources to the lockfile
+    locked: bool,
+    sources: LockedSources,
+    changed: bool,
+}
+
+/// The content of a lockfile
+#[derive(Debug, Default, Deserialize)]
+struct LockedSources {
+    /// Commits by repository and revision
+    #[serde(default)]
+    sources: BTreeMap<String, String>,
+}
+
+impl Lockfile {
+    /// Read the lockfile next to the track definition at `track_path`, if there is one
+    pub(crate) fn load(track_path: &Path, locked: bool) -> Result<Self, FetchSourceError> {
+        let path = track_path.parent().unwrap_or(Path::new("")).join(LOCKFILE);
+        let sources = if path.exists() {
+            let content: String = path.read_to_string()?;
+            toml::from_str(&content)
+                .into_report()
+                .attach_printable_lazy(|| {
+                    format!(
+                        "Unable to parse lockfile {path}",
+                        path = path.to_string_lossy()
+                    )
+                })
+                .change_context(FetchSourceError)?
+        } else {
+            LockedSources::default()
+        };
+        Ok(Self {
+            path,
+            locked,
+            sources,
+            changed: false,
+        })
+    }
+
+    /// Fetch `source` at the commit the lockfile pins it to, or pin it to the commit
+    /// its revision points to now. Returns the path to the file in the clone.
+    pub(crate) fn fetch(&mut self, source: &GitSource) -> Result<PathBuf, FetchSourceError> {
+        let id = source.revision_id();
+        let clone_dir = if let Some(commit) = self.sources.sources.get(&id) {
+            GitSource {
+                revision: commit.clone(),
+                ..source.clone()
+            }
+            .fetch(false)?
+        } else if self.locked {
+            return Err(Report::new(FetchSourceError).attach_printable(format!(
+                "{id} is not pinned in {path}, which can't be updated because it's locked",
+                path = self.path.to_string_lossy()
+            )));
+        } else {
+            let clone_dir = source.fetch(true)?;
+            let commit = git(&["rev-parse", "HEAD"], &clone_dir)?;
+            self.sources.sources.insert(id, commit);
+            self.changed = true;
+            clone_dir
+        };
+        Ok(clone_dir.join(&source.path))
+    }
+
+    /// Write the lockfile, if sources were pinned since it was read
+    pub(crate) fn save(&self) -> Result<(), FetchSourceError> {
+        if !self.changed {
+            return Ok(());
+        }
+        let mut content = String::from(
+            "# Generated by modmod, pins the commits of the git sources of the tracks in this folder\n\n[sources]\n",
+        );
+        for (id, commit) in &self.sources.sources {
+            content += &format!(
+                "{id} = {commit}\n",
+                id = toml_edit::Value::from(id.as_str()),
+                commit = toml_edit::Value::from(commit.as_str())
+            );
+        }
+        self.path.create_file()?.write_all(content)
+    }
+}
+
+impl fmt::Display for GitSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{GIT_PREFIX}{url}#{revision}:{path}",
+            url = self.url,
+            revision = self.revision,
+            path = self.path.to_string_lossy()
+        )
+    }
+}
+
+/// Whether `path` refers to a git repository, rather than to the file system
+pub(crate) fn is_git_source(path: &str) -> bool {
+    path.starts_with(GIT_PREFIX)
+}
+
+/// Directory that modmod caches things in across renders, like the repositories it clones:
+/// `MODMOD_CACHE_DIR` if it's set, and otherwise the `modmod` directory in the user's
+/// cache directory
+pub(crate) fn cache_dir<C: error_stack::Context + Default>() -> Result<PathBuf, C> {
+    let dir = if let Some(dir) = env::var_os("MODMOD_CACHE_DIR") {
+        PathBuf::from(dir)
+    } else if let Some(dir) = env::var_os("XDG_CACHE_HOME") {
+        PathBuf::from(dir).join("modmod")
+    } else if let Some(home) = env::var_os("HOME") {
+        PathBuf::from(home).join(".cache").join("modmod")
+    } else {
+        return Err(
+            Report::new(C::default()).attach_printable("No cache directory: set MODMOD_CACHE_DIR")
+        );
+    };
+    Ok(dir)
+}
+
+/// Run git with `args` in `dir`, failing with its error output if it fails
+fn git(args: &[&str], dir: &Path) -> Result<String, FetchSourceError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .into_report()
+        .attach_printable("Unable to run git")
+        .change_context(FetchSourceError)?;
+    if !output.status.success() {
+        return Err(Report::new(FetchSourceError)
+            .attach_printable(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
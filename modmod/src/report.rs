@@ -0,0 +1,79 @@
+use std::{fmt, path::PathBuf, time::Duration};
+
+use serde::Serialize;
+
+use crate::{
+    codes::WarningCode, output::PlannedOperation, output_map::OutputMap,
+    verify::ExerciseVerification,
+};
+
+/// Something that did not stop the render, but that the author should look at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Warning {
+    pub code: WarningCode,
+    pub message: String,
+    /// Allowed warnings are reported as notes, and don't fail strict renders
+    #[serde(skip)]
+    pub allowed: bool,
+}
+
+impl Warning {
+    pub fn new(code: WarningCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            allowed: false,
+        }
+    }
+
+    /// Allow the warning if its code is in `allow`, the allow list of the item it is about
+    pub fn allowed_by(mut self, allow: &[WarningCode]) -> Self {
+        self.allowed |= allow.contains(&self.code);
+        self
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+/// Overview of a successful render
+#[derive(Debug, Clone)]
+pub struct RenderReport {
+    pub track_name: String,
+    /// Absolute path of the directory the track was rendered into
+    pub output_dir: PathBuf,
+    pub modules: usize,
+    pub units: usize,
+    pub topics: usize,
+    pub exercises: usize,
+    pub files_written: usize,
+    /// Total size of the written files in bytes
+    pub bytes_written: u64,
+    pub elapsed: Duration,
+    /// For incremental renders, the number of exercise files that were the same as in the
+    /// render before, and weren't copied again
+    pub files_unchanged: Option<usize>,
+    /// Absolute path of the directory the instructor edition was rendered into, if any
+    pub instructor_output_dir: Option<PathBuf>,
+    /// The file operations the render would have performed, if it was a dry run
+    pub planned: Option<Vec<PlannedOperation>>,
+    /// For renders of only the parts of a track affected by changes, see [Track::rerender],
+    /// the units of which the exercises were rendered, by module and unit index
+    ///
+    /// [Track::rerender]: crate::Track::rerender
+    pub units_rerendered: Option<Vec<(usize, usize)>>,
+    pub warnings: Vec<Warning>,
+    /// Warnings that were allowed by the track or the item they are about
+    pub notes: Vec<Warning>,
+    /// Number of draft units, topics and appendices that were left out
+    pub drafts_skipped: usize,
+    /// Number of draft units, topics and appendices that were rendered because drafts were included
+    pub drafts_included: usize,
+    /// Results of verifying the rendered exercises, if verification was enabled
+    pub verification: Vec<ExerciseVerification>,
+    /// Where the items of the track were rendered into, see [OutputMap]
+    pub output_map: OutputMap,
+}
@@ -0,0 +1,79 @@
+//! Git repositories of rendered exercises, so that they can be pushed as starter repositories
+
+use std::{
+    fmt,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use crate::{io::WriteExt, output::Output};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct InitRepositoriesError;
+
+impl fmt::Display for InitRepositoriesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to initialize exercise repositories")
+    }
+}
+
+impl error_stack::Context for InitRepositoriesError {}
+
+/// Ignores the build artifacts of the exercises, at any depth
+const GITIGNORE: &str = "target/\n";
+
+/// Used for the initial commit when git has no identity configured
+const FALLBACK_IDENTITY: [&str; 4] = [
+    "-c",
+    "user.name=modmod",
+    "-c",
+    "user.email=modmod@localhost",
+];
+
+/// Make the rendered directory `dir` a git repository with a `.gitignore`, if it has none
+/// of its own, and an initial commit of its files. Directories that are git repositories
+/// already, from an earlier render, are left as they are.
+pub(crate) fn init_repository(output: &Output, dir: &Path) -> Result<(), InitRepositoriesError> {
+    if dir.join(".git").exists() {
+        return Ok(());
+    }
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        output.create_file(&gitignore)?.write_all(GITIGNORE)?;
+    }
+
+    git(&["init", "--quiet", "--initial-branch=main"], dir)?;
+    let mut commit = vec![];
+    if git(&["config", "user.email"], dir).is_err() {
+        commit.extend(FALLBACK_IDENTITY);
+    }
+    // The commit is made by modmod, not by whoever signs commits
+    commit.extend(["-c", "commit.gpgsign=false", "commit", "--quiet"]);
+    commit.extend(["--message", "Initial commit"]);
+    git(&["add", "--all"], dir)?;
+    git(&commit, dir)?;
+    Ok(())
+}
+
+fn git(args: &[&str], dir: &Path) -> Result<String, InitRepositoriesError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .into_report()
+        .attach_printable("Unable to run git")
+        .change_context(InitRepositoriesError)?;
+    if !output.status.success() {
+        return Err(Report::new(InitRepositoriesError).attach_printable(format!(
+            "git {args} failed in {dir}: {stderr}",
+            args = args.join(" "),
+            dir = dir.to_string_lossy(),
+            stderr = String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
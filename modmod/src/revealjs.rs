@@ -0,0 +1,98 @@
+//! Slide decks for reveal.js, for organizations that can't use Slidev: a page per deck that
+//! loads reveal.js from a CDN and has it render the markdown of the deck
+
+use crate::{
+    slides::{relative_images, split_headmatter},
+    stats::split_slides,
+};
+
+/// Where the pages load reveal.js and its plugins from
+const REVEALJS_URL: &str = "https://cdn.jsdelivr.net/npm/reveal.js@5.1.0";
+/// The theme of decks that don't set one
+const DEFAULT_THEME: &str = "white";
+
+/// The page of the reveal.js deck for the Slidev `slides`, with the rendered `settings` of
+/// the deck, in `language`. Slides are separated the same way, and the notes at the end of a
+/// slide become its speaker notes. Settings of single slides, like layouts, are left out.
+pub(crate) fn deck(slides: &str, settings: &[(&str, String)], language: &str) -> String {
+    let setting = |key| {
+        settings
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value.as_str())
+    };
+    let (headmatter, slides) = split_headmatter(slides);
+    let headmatter: serde_yaml::Value =
+        serde_yaml::from_str(&headmatter.join("\n")).unwrap_or_default();
+    let title = headmatter["title"].as_str().unwrap_or_default();
+    let theme = setting("theme").unwrap_or(DEFAULT_THEME);
+
+    let mut markdown: Vec<String> = split_slides(slides)
+        .into_iter()
+        .map(|slide| speaker_notes(&slide.join("\n")))
+        .collect();
+    if let (Some(background), Some(cover)) = (setting("background"), markdown.first_mut()) {
+        *cover = format!("<!-- .slide: data-background-image=\"{background}\" -->\n\n{cover}");
+    }
+    let markdown = relative_images(&markdown.join("\n\n---\n\n"), "images/")
+        // The markdown is in a textarea, which it mustn't close
+        .replace("</textarea", "&lt;/textarea");
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="{language}">
+<head>
+  <meta charset="utf-8">
+  <meta name="viewport" content="width=device-width, initial-scale=1.0">
+  <title>{title}</title>
+  <link rel="stylesheet" href="{REVEALJS_URL}/dist/reveal.css">
+  <link rel="stylesheet" href="{REVEALJS_URL}/dist/theme/{theme}.css">
+  <link rel="stylesheet" href="{REVEALJS_URL}/plugin/highlight/monokai.css">
+</head>
+<body>
+  <div class="reveal">
+    <div class="slides">
+      <section data-markdown data-separator="^\r?\n---\r?\n$" data-separator-notes="^Note:">
+        <textarea data-template>
+{markdown}
+        </textarea>
+      </section>
+    </div>
+  </div>
+  <script src="{REVEALJS_URL}/dist/reveal.js"></script>
+  <script src="{REVEALJS_URL}/plugin/markdown/markdown.js"></script>
+  <script src="{REVEALJS_URL}/plugin/highlight/highlight.js"></script>
+  <script src="{REVEALJS_URL}/plugin/notes/notes.js"></script>
+  <script>
+    Reveal.initialize({{ hash: true, plugins: [RevealMarkdown, RevealHighlight, RevealNotes] }});
+  </script>
+</body>
+</html>
+"#,
+        title = html_escape(title),
+    )
+}
+
+/// The markdown of `slide`, with the HTML comment that Slidev takes as the notes of a slide
+/// when it ends the slide turned into reveal.js speaker notes
+fn speaker_notes(slide: &str) -> String {
+    let slide = slide.trim();
+    let notes = slide
+        .strip_suffix("-->")
+        .and_then(|rest| Some((rest, rest.rfind("<!--")?)));
+    match notes {
+        Some((rest, start)) => format!(
+            "{content}\n\nNote:\n{notes}",
+            content = rest[..start].trim_end(),
+            notes = rest[start + 4..].trim()
+        ),
+        None => slide.to_string(),
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
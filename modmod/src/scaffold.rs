@@ -0,0 +1,135 @@
+//! Generators of new definitions, with the files they refer to, so that new content starts
+//! out with the right structure instead of as a copy of other content
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use error_stack::{Report, Result};
+
+use crate::{
+    io::{PathExt, WriteExt},
+    to_tag, DefinitionKind,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct ScaffoldError;
+
+impl fmt::Display for ScaffoldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to create definition")
+    }
+}
+
+impl error_stack::Context for ScaffoldError {}
+
+/// A definition that was created, along with the entry that refers to it from the
+/// definition it's part of
+#[derive(Debug)]
+pub struct Scaffold {
+    /// The created files, the definition first
+    pub files: Vec<PathBuf>,
+    /// How to add the new definition to the definition it's part of, if any
+    pub reference: Option<String>,
+}
+
+impl DefinitionKind {
+    /// Create a definition of this kind named `name` in `dir`, along with the files it
+    /// refers to: slides for topics, and a Cargo package with a description for exercises.
+    /// Existing files are never overwritten.
+    pub fn scaffold(self, dir: &Path, name: &str) -> Result<Scaffold, ScaffoldError> {
+        let quoted = toml_edit::Value::from(name).to_string();
+        // Slugs become file and package names, so only keep characters that are valid in both
+        let slug: String = to_tag(name)
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+            .collect();
+        let slug = match slug.is_empty() {
+            true => self.name().to_string(),
+            false => slug,
+        };
+        let dir_name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| slug.clone());
+        let (files, reference) = match self {
+            DefinitionKind::Track => (
+                vec![(
+                    format!("{slug}.track.toml"),
+                    format!("name = {quoted}\nauthors = []\nmodules = []\n"),
+                )],
+                None,
+            ),
+            DefinitionKind::Module => (
+                vec![(
+                    "mod.toml".to_string(),
+                    format!(
+                        "name = {quoted}\ndescription = \"\"\nobjectives = []\n\n[[units]]\nname = {quoted}\ntopics = []\n"
+                    ),
+                )],
+                Some(format!(
+                    "Add the path to {path}, relative to the track definition, to the `modules` of a track",
+                    path = dir.join("mod.toml").to_string_lossy()
+                )),
+            ),
+            DefinitionKind::Topic => (
+                vec![
+                    (
+                        "topic.toml".to_string(),
+                        format!("name = {quoted}\nsummary = []\nobjectives = []\n"),
+                    ),
+                    (
+                        "slides.md".to_string(),
+                        format!("---\nlayout: section\n---\n\n# {name}\n\n---\n\n# \n"),
+                    ),
+                ],
+                Some(format!(
+                    "Add the path to {path}, relative to the module definition, to the `topics` of a unit",
+                    path = dir.join("topic.toml").to_string_lossy()
+                )),
+            ),
+            DefinitionKind::Exercise => (
+                vec![
+                    (
+                        "Cargo.toml".to_string(),
+                        format!(
+                            "[package]\nname = \"{slug}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n"
+                        ),
+                    ),
+                    (
+                        "src/main.rs".to_string(),
+                        "fn main() {\n    todo!()\n}\n".to_string(),
+                    ),
+                    (
+                        "description.md".to_string(),
+                        "Open `#[modmod:exercise_dir]` in your editor.\n".to_string(),
+                    ),
+                ],
+                Some(format!(
+                    "Add the exercise to a topic, with its path relative to the topic definition:\n\n[[exercises]]\nname = {quoted}\npath = \"exercises/{dir_name}\""
+                )),
+            ),
+        };
+
+        let files: Vec<(PathBuf, String)> = files
+            .into_iter()
+            .map(|(file, content)| (dir.join(file), content))
+            .collect();
+        if let Some((existing, _)) = files.iter().find(|(path, _)| path.exists()) {
+            return Err(Report::new(ScaffoldError).attach_printable(format!(
+                "{path} exists already",
+                path = existing.to_string_lossy()
+            )));
+        }
+        for (path, content) in files.iter() {
+            path.parent().unwrap().create_dir_all::<ScaffoldError>()?;
+            path.create_file::<ScaffoldError>()?.write_all(content)?;
+        }
+        Ok(Scaffold {
+            files: files.into_iter().map(|(path, _)| path).collect(),
+            reference,
+        })
+    }
+}
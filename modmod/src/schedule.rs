@@ -0,0 +1,182 @@
+//! How long the parts of a track take to teach, from the `duration_minutes` of their
+//! topics and exercises, and the schedule page of the book that is based on it
+
+use std::fmt::Write;
+
+use crate::{Module, Topic, Track, Unit};
+
+impl Topic {
+    /// How long teaching the topic and doing its exercises takes, counting the parts
+    /// that have a duration. `None` if none of them have one.
+    pub fn planned_minutes(&self) -> Option<u32> {
+        sum(std::iter::once(self.duration_minutes)
+            .chain(self.exercises.iter().map(|e| e.data.duration_minutes)))
+    }
+}
+
+impl Unit {
+    /// How long teaching the topics of the unit takes, see [Topic::planned_minutes]
+    pub fn planned_minutes(&self) -> Option<u32> {
+        sum(self.topics.iter().map(|t| t.data.planned_minutes()))
+    }
+}
+
+impl Module {
+    /// How long teaching the units of the module takes, see [Topic::planned_minutes]
+    pub fn planned_minutes(&self) -> Option<u32> {
+        sum(self.units.iter().map(|u| u.data.planned_minutes()))
+    }
+}
+
+fn sum(minutes: impl Iterator<Item = Option<u32>>) -> Option<u32> {
+    minutes.flatten().reduce(|a, b| a + b)
+}
+
+/// The units of a track, each of which is a session, and the days they're taught on
+pub(crate) struct Schedule {
+    minutes_per_day: Option<u32>,
+    modules: Vec<ScheduledModule>,
+}
+
+struct ScheduledModule {
+    index: usize,
+    name: String,
+    minutes: Option<u32>,
+    units: Vec<ScheduledUnit>,
+}
+
+struct ScheduledUnit {
+    number: String,
+    name: String,
+    minutes: Option<u32>,
+}
+
+impl Schedule {
+    /// The schedule of `track`, if any of its topics or exercises has a duration
+    pub(crate) fn new(track: &Track) -> Option<Self> {
+        let modules: Vec<_> = track
+            .modules
+            .iter()
+            .map(|module| ScheduledModule {
+                index: module.index,
+                name: module.data.name.clone(),
+                minutes: module.data.planned_minutes(),
+                units: module
+                    .data
+                    .units
+                    .iter()
+                    .map(|unit| ScheduledUnit {
+                        number: format!("{}.{}", module.index, unit.index),
+                        name: unit.data.name.clone(),
+                        minutes: unit.data.planned_minutes(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        modules.iter().any(|m| m.minutes.is_some()).then_some(Self {
+            minutes_per_day: track.book.minutes_per_day.filter(|m| *m > 0),
+            modules,
+        })
+    }
+
+    /// Units are never split over days, so a unit that takes longer than a day has a day of its own
+    fn days(&self, minutes_per_day: u32) -> Vec<(u32, Vec<&ScheduledUnit>)> {
+        let mut days: Vec<(u32, Vec<&ScheduledUnit>)> = vec![];
+        for unit in self.modules.iter().flat_map(|m| &m.units) {
+            let minutes = unit.minutes.unwrap_or(0);
+            match days.last_mut() {
+                Some((used, units)) if *used + minutes <= minutes_per_day => {
+                    *used += minutes;
+                    units.push(unit);
+                }
+                _ => days.push((minutes, vec![unit])),
+            }
+        }
+        days
+    }
+
+    pub(crate) fn markdown(&self) -> String {
+        let total = sum(self.modules.iter().map(|m| m.minutes)).unwrap_or(0);
+        let mut page = String::from("# Schedule\n\n");
+        match self.minutes_per_day {
+            Some(minutes_per_day) => {
+                let days = self.days(minutes_per_day);
+                writeln!(
+                    page,
+                    "The course takes about {total}, in {count} days of at most {day}.\n",
+                    total = format_minutes(total),
+                    count = days.len(),
+                    day = format_minutes(minutes_per_day)
+                )
+                .unwrap();
+                for ((minutes, units), day) in days.iter().zip(1..) {
+                    writeln!(page, "## Day {day} ({})\n", format_minutes(*minutes)).unwrap();
+                    write_units(&mut page, units.iter().copied());
+                }
+                page.push_str("## Modules\n\n| Module | Duration |\n| --- | --- |\n");
+                for module in self.modules.iter() {
+                    writeln!(
+                        page,
+                        "| {} - {} | {} |",
+                        module.index,
+                        module.name,
+                        format_estimate(module.minutes)
+                    )
+                    .unwrap();
+                }
+                page.push('\n');
+            }
+            None => {
+                writeln!(page, "The course takes about {}.\n", format_minutes(total)).unwrap();
+                for module in self.modules.iter() {
+                    writeln!(
+                        page,
+                        "## Module {} - {} ({})\n",
+                        module.index,
+                        module.name,
+                        format_estimate(module.minutes)
+                    )
+                    .unwrap();
+                    write_units(&mut page, module.units.iter());
+                }
+            }
+        }
+        if self
+            .modules
+            .iter()
+            .flat_map(|m| &m.units)
+            .any(|u| u.minutes.is_none())
+        {
+            page.push_str("Units without a duration are not counted.\n");
+        }
+        page
+    }
+}
+
+fn write_units<'s>(page: &mut String, units: impl Iterator<Item = &'s ScheduledUnit>) {
+    page.push_str("| Unit | Duration |\n| --- | --- |\n");
+    for unit in units {
+        writeln!(
+            page,
+            "| {} - {} | {} |",
+            unit.number,
+            unit.name,
+            format_estimate(unit.minutes)
+        )
+        .unwrap();
+    }
+    page.push('\n');
+}
+
+fn format_estimate(minutes: Option<u32>) -> String {
+    minutes.map_or_else(|| "-".to_string(), format_minutes)
+}
+
+/// Minutes as hours and minutes, like `1 h 30 min`
+pub(crate) fn format_minutes(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes} min"),
+        (hours, 0) => format!("{hours} h"),
+        (hours, minutes) => format!("{hours} h {minutes} min"),
+    }
+}
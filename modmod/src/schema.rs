@@ -0,0 +1,652 @@
+//! JSON Schemas of the definition formats, so that editors can validate and complete
+//! track, module, topic and exercise definitions. The schemas follow the `Deserialize`
+//! implementations of the definitions, and fields that modmod doesn't know are reported,
+//! as they are most likely typos.
+
+use std::{collections::BTreeMap, fmt, path::PathBuf, str::FromStr};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    codes::WarningCode,
+    load::{
+        AppendixDef, ContentDef, ExerciseDef, IncludeProfileDef, ModuleDef, TopicDef,
+        TopicOverrideDef, TopicRef, TrackDef, UnitDef, UnitTemplateDef, UnitTemplatesDef,
+    },
+    BookConfig, BookPreprocessor, BookSearchConfig, BookTheme, ExerciseKind, ExerciseLint,
+    GlossaryEntry, Quiz, SlidesBackend, SlidesConfig,
+};
+
+/// The kinds of definition files there are schemas for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefinitionKind {
+    Track,
+    Module,
+    Topic,
+    /// A single exercise, as in the `exercises` of a topic
+    Exercise,
+}
+
+impl DefinitionKind {
+    pub const ALL: [DefinitionKind; 4] = [
+        DefinitionKind::Track,
+        DefinitionKind::Module,
+        DefinitionKind::Topic,
+        DefinitionKind::Exercise,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DefinitionKind::Track => "track",
+            DefinitionKind::Module => "module",
+            DefinitionKind::Topic => "topic",
+            DefinitionKind::Exercise => "exercise",
+        }
+    }
+
+    /// The JSON Schema of definitions of this kind
+    pub fn schema(self) -> Value {
+        let (title, mut schema) = match self {
+            DefinitionKind::Track => ("Track definition", TrackDef::schema()),
+            DefinitionKind::Module => ("Module definition", ModuleDef::schema()),
+            DefinitionKind::Topic => ("Topic definition", TopicDef::schema()),
+            DefinitionKind::Exercise => ("Exercise definition", ExerciseDef::schema()),
+        };
+        let object = schema.as_object_mut().unwrap();
+        object.insert(
+            "$schema".to_string(),
+            "https://json-schema.org/draft/2020-12/schema".into(),
+        );
+        object.insert("title".to_string(), title.into());
+        schema
+    }
+}
+
+impl fmt::Display for DefinitionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DefinitionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|kind| kind.name()).collect();
+                format!(
+                    "unknown definition kind '{s}', expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// The fields in `value`, a definition of `kind`, that modmod doesn't know.
+/// Fields are written as their path in the definition, like `units[0].topics[1].objectvies`.
+pub(crate) fn unknown_fields(kind: DefinitionKind, value: &Value) -> Vec<String> {
+    let mut unknown = Vec::new();
+    collect_unknown_fields(&kind.schema(), value, "", &mut unknown);
+    unknown
+}
+
+fn collect_unknown_fields(schema: &Value, value: &Value, path: &str, unknown: &mut Vec<String>) {
+    // Alternatives differ by type, like a path or an inline table
+    if let Some(alternatives) = schema.get("oneOf").and_then(Value::as_array) {
+        let value_type = match value {
+            Value::Object(_) => "object",
+            Value::Array(_) => "array",
+            _ => "string",
+        };
+        if let Some(schema) = alternatives
+            .iter()
+            .find(|alternative| alternative.get("type").and_then(Value::as_str) == Some(value_type))
+        {
+            collect_unknown_fields(schema, value, path, unknown);
+        }
+        return;
+    }
+    match value {
+        Value::Object(fields) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let additional = schema.get("additionalProperties");
+            for (name, value) in fields {
+                let field_path = match path.is_empty() {
+                    true => name.clone(),
+                    false => format!("{path}.{name}"),
+                };
+                match (properties.and_then(|p| p.get(name)), additional) {
+                    (Some(schema), _) => {
+                        collect_unknown_fields(schema, value, &field_path, unknown)
+                    }
+                    (None, Some(Value::Bool(false))) => unknown.push(field_path),
+                    (None, Some(schema)) => {
+                        collect_unknown_fields(schema, value, &field_path, unknown)
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    collect_unknown_fields(schema, item, &format!("{path}[{i}]"), unknown);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Types that definitions are deserialized into, of which the schema can be generated
+trait JsonSchema {
+    fn schema() -> Value;
+}
+
+/// The schema of an object with a fixed set of fields
+#[derive(Default)]
+struct Object {
+    properties: Map<String, Value>,
+    required: Vec<&'static str>,
+}
+
+impl Object {
+    /// Add a field that may be left out
+    fn field<T: JsonSchema>(mut self, name: &'static str, description: &str) -> Self {
+        let mut schema = T::schema();
+        if !description.is_empty() {
+            schema
+                .as_object_mut()
+                .unwrap()
+                .insert("description".to_string(), description.into());
+        }
+        self.properties.insert(name.to_string(), schema);
+        self
+    }
+
+    /// Add a field that must be present
+    fn required<T: JsonSchema>(mut self, name: &'static str, description: &str) -> Self {
+        self.required.push(name);
+        self.field::<T>(name, description)
+    }
+
+    fn build(self, description: &str) -> Value {
+        json!({
+            "type": "object",
+            "description": description,
+            "properties": self.properties,
+            "required": self.required,
+            "additionalProperties": false,
+        })
+    }
+}
+
+impl JsonSchema for String {
+    fn schema() -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+impl JsonSchema for PathBuf {
+    fn schema() -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+impl JsonSchema for bool {
+    fn schema() -> Value {
+        json!({ "type": "boolean" })
+    }
+}
+
+impl JsonSchema for u8 {
+    fn schema() -> Value {
+        json!({ "type": "integer", "minimum": 0, "maximum": u8::MAX })
+    }
+}
+
+impl JsonSchema for u32 {
+    fn schema() -> Value {
+        json!({ "type": "integer", "minimum": 0, "maximum": u32::MAX })
+    }
+}
+
+impl JsonSchema for u64 {
+    fn schema() -> Value {
+        json!({ "type": "integer", "minimum": 0 })
+    }
+}
+
+/// Optional fields are left out of `required` instead
+impl<T: JsonSchema> JsonSchema for Option<T> {
+    fn schema() -> Value {
+        T::schema()
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for Vec<T> {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for BTreeMap<String, T> {
+    fn schema() -> Value {
+        json!({ "type": "object", "additionalProperties": T::schema() })
+    }
+}
+
+impl JsonSchema for WarningCode {
+    fn schema() -> Value {
+        let codes: Vec<_> = WarningCode::ALL.iter().map(|code| code.code()).collect();
+        json!({ "type": "string", "enum": codes })
+    }
+}
+
+impl JsonSchema for ExerciseKind {
+    fn schema() -> Value {
+        json!({ "type": "string", "enum": ["cargo", "plain"] })
+    }
+}
+
+impl JsonSchema for BookTheme {
+    fn schema() -> Value {
+        let themes: Vec<_> = BookTheme::ALL.iter().map(|t| t.name()).collect();
+        json!({ "type": "string", "enum": themes })
+    }
+}
+
+impl JsonSchema for SlidesBackend {
+    fn schema() -> Value {
+        let backends: Vec<_> = SlidesBackend::ALL.iter().map(|b| b.name()).collect();
+        json!({ "type": "string", "enum": backends })
+    }
+}
+
+impl JsonSchema for ExerciseLint {
+    fn schema() -> Value {
+        let lints: Vec<_> = ExerciseLint::ALL.iter().map(|lint| lint.name()).collect();
+        json!({ "type": "string", "enum": lints })
+    }
+}
+
+impl JsonSchema for TrackDef {
+    fn schema() -> Value {
+        let mut schema = Object::default()
+            .field::<String>("name", "")
+            .field::<Vec<String>>("authors", "")
+            .field::<String>("description", "")
+            .field::<String>("language", "Language of the book, like `en`")
+            .field::<Vec<PathBuf>>(
+                "modules",
+                "Paths to module definitions, or git sources like `git+<url>#<revision>:<path>`",
+            )
+            .field::<Vec<AppendixDef>>("appendices", "")
+            .field::<BTreeMap<String, IncludeProfileDef>>("include_profiles", "")
+            .field::<bool>("warn_near_duplicates", "")
+            .field::<BookConfig>("book", "")
+            .field::<SlidesConfig>("slides", "")
+            .field::<String>("version", "")
+            .field::<String>("base_url", "")
+            .field::<String>(
+                "rust_toolchain",
+                "Toolchain the Cargo exercises are pinned to, like 1.75 or stable",
+            )
+            .field::<Vec<WarningCode>>("allow", "Warnings that aren't reported")
+            .field::<bool>(
+                "deny_unknown_fields",
+                "Fail to load the track if any of its definitions has fields that modmod doesn't know",
+            )
+            .field::<Vec<PathBuf>>(
+                "excluded_topics",
+                "Topics that are left out of renders, by the path of their definition or directory",
+            )
+            .field::<Vec<String>>(
+                "include_tags",
+                "If not empty, only topics with at least one of these tags are part of renders",
+            )
+            .field::<Vec<String>>(
+                "exclude_tags",
+                "Topics with any of these tags are left out of renders",
+            )
+            .field::<BTreeMap<String, String>>(
+                "variables",
+                "Values that slides templates and topics refer to with `#[modmod:var(name)]`",
+            )
+            .field::<bool>(
+                "normalize_headings",
+                "Move the topmost headings of every topic to level 1 in the slides, and those of every exercise description right below the heading of the exercise in the book",
+            )
+            .field::<Vec<TopicOverrideDef>>(
+                "topic_overrides",
+                "Changes to topics that only apply to this track",
+            )
+            .field::<PathBuf>(
+                "extends",
+                "Path to the definition of the track this track extends",
+            )
+            .field::<Vec<PathBuf>>(
+                "add_modules",
+                "Modules that are added to those of the extended track",
+            )
+            .field::<Vec<PathBuf>>(
+                "remove_modules",
+                "Modules of the extended track that are left out",
+            )
+            .build("A track, which is a course made of modules");
+        // Tracks that extend another track take the name and modules from it
+        schema.as_object_mut().unwrap().insert(
+            "anyOf".to_string(),
+            json!([{ "required": ["name", "modules"] }, { "required": ["extends"] }]),
+        );
+        schema
+    }
+}
+
+impl JsonSchema for AppendixDef {
+    fn schema() -> Value {
+        Object::default()
+            .required::<String>("name", "")
+            .required::<PathBuf>("path", "Path to the markdown file")
+            .field::<bool>("draft", "")
+            .build("A page of the book that isn't part of any module")
+    }
+}
+
+impl JsonSchema for IncludeProfileDef {
+    fn schema() -> Value {
+        Object::default()
+            .required::<Vec<String>>("includes", "")
+            .build("A named include set that exercises can refer to with `include_profile`")
+    }
+}
+
+impl JsonSchema for BookConfig {
+    fn schema() -> Value {
+        Object::default()
+            .field::<String>(
+                "description",
+                "Description of the book, if it differs from that of the track",
+            )
+            .field::<bool>(
+                "page_descriptions",
+                "Give module and unit pages an HTML meta description, based on their objectives",
+            )
+            .field::<bool>(
+                "link_glossary_terms",
+                "Link the first occurrence of every glossary term on a page of the book to its entry in the glossary",
+            )
+            .field::<bool>(
+                "mdbook_quiz",
+                "Make the knowledge checks at the end of units interactive with the mdbook-quiz preprocessor",
+            )
+            .field::<BookSearchConfig>("search", "")
+            .field::<u32>(
+                "minutes_per_day",
+                "How long a day of teaching is, to divide the units over days in the schedule",
+            )
+            .field::<BookTheme>("default_theme", "Theme that readers see until they pick one")
+            .field::<BookTheme>(
+                "preferred_dark_theme",
+                "Theme that readers who prefer a dark color scheme see until they pick one",
+            )
+            .field::<u8>(
+                "fold_level",
+                "Level up to which the table of contents is expanded, folding the rest away",
+            )
+            .field::<String>(
+                "git_repository_url",
+                "URL of the repository of the course, linked to from every page",
+            )
+            .field::<String>(
+                "edit_url_template",
+                "URL to suggest edits to a page at, in which {path} is replaced by the path of the page",
+            )
+            .field::<String>(
+                "build_dir",
+                "Directory that mdBook builds the book into, relative to the book",
+            )
+            .field::<BTreeMap<String, BookPreprocessor>>(
+                "preprocessors",
+                "The mdBook preprocessors that the book is built with, like admonish, by name",
+            )
+            .build("Settings of the exercise book")
+    }
+}
+
+impl JsonSchema for SlidesConfig {
+    fn schema() -> Value {
+        Object::default()
+            .field::<String>("theme", "Slidev theme, like default or seriph")
+            .field::<String>("highlighter", "Code highlighter, like shiki")
+            .field::<String>(
+                "title",
+                "Title of the slide deck, which can use the values that slides templates can, like {{ unit.title }}",
+            )
+            .field::<String>("background", "Background image of the cover slide")
+            .field::<SlidesBackend>(
+                "backend",
+                "What the slide decks are rendered for: slidev, the default, revealjs, marp or pptx",
+            )
+            .field::<String>(
+                "topic_separator",
+                "What comes between the slides of consecutive topics, a new slide with --- if not set",
+            )
+            .field::<String>(
+                "topic_title",
+                "Heading of a slide in front of every topic, like Topic: {{ topic.name }}",
+            )
+            .build("Settings of the slide decks, which are set in the headmatter of every deck")
+    }
+}
+
+impl JsonSchema for BookPreprocessor {
+    fn schema() -> Value {
+        let mut schema = Object::default()
+            .field::<Vec<PathBuf>>(
+                "assets",
+                "Stylesheets and scripts that the preprocessor needs in the book, relative to the track definition",
+            )
+            .build("An mdBook preprocessor, of which the other settings are passed to it as they are");
+        schema["additionalProperties"] = true.into();
+        schema
+    }
+}
+
+impl JsonSchema for BookSearchConfig {
+    fn schema() -> Value {
+        Object::default()
+            .field::<u8>("boost_title", "")
+            .field::<u8>("boost_hierarchy", "")
+            .field::<u8>("boost_paragraph", "")
+            .field::<Vec<String>>("exclude", "Globs of pages, relative to the book source directory, that are left out of the search index")
+            .build("Settings of the search index of the exercise book")
+    }
+}
+
+impl JsonSchema for TopicOverrideDef {
+    fn schema() -> Value {
+        Object::default()
+            .required::<PathBuf>(
+                "topic",
+                "The topic, by the path of its definition or the directory it's in",
+            )
+            .field::<ContentDef>("content", "")
+            .field::<Vec<ExerciseDef>>("exercises", "")
+            .field::<Vec<String>>("objectives", "")
+            .build("Fields of a topic that a track replaces, without changing the topic definition")
+    }
+}
+
+impl JsonSchema for ModuleDef {
+    fn schema() -> Value {
+        Object::default()
+            .required::<String>("name", "")
+            .required::<String>("description", "")
+            .field::<Vec<String>>("objectives", "")
+            .field::<Vec<String>>("further_reading", "")
+            .required::<Vec<UnitDef>>("units", "")
+            .field::<BTreeMap<String, IncludeProfileDef>>(
+                "include_profiles",
+                "Profiles that are only available to the exercises in this module",
+            )
+            .field::<PathBuf>(
+                "assets",
+                "Directory of files that the slides and the book can refer to under assets/",
+            )
+            .build("A module, which is a sequence of units")
+    }
+}
+
+impl JsonSchema for UnitDef {
+    fn schema() -> Value {
+        Object::default()
+            .required::<String>("name", "")
+            .field::<UnitTemplatesDef>("template", "")
+            .field::<SlidesConfig>(
+                "slides",
+                "Settings of the slides of the unit, which take precedence over those of the track",
+            )
+            .required::<Vec<TopicRef>>("topics", "")
+            .field::<bool>("draft", "")
+            .field::<Vec<WarningCode>>("allow", "Warnings that aren't reported")
+            .field::<bool>(
+                "normalize_headings",
+                "Whether to normalize the headings of the topics of the unit, instead of doing what the track does",
+            )
+            .build("A unit, which is a sequence of topics")
+    }
+}
+
+impl JsonSchema for UnitTemplatesDef {
+    fn schema() -> Value {
+        json!({
+            "description": "The slides template, or a list of templates that are each rendered into a file of their own",
+            "oneOf": [PathBuf::schema(), Vec::<UnitTemplateDef>::schema()],
+        })
+    }
+}
+
+impl JsonSchema for UnitTemplateDef {
+    fn schema() -> Value {
+        Object::default()
+            .required::<PathBuf>("path", "")
+            .field::<String>(
+                "output",
+                "Name of the file the template is rendered into next to the slides, like handout. The template without one is that of the slides.",
+            )
+            .build("A template of a unit")
+    }
+}
+
+impl JsonSchema for TopicRef {
+    fn schema() -> Value {
+        json!({
+            "description": "A topic, by the path of its definition or defined inline",
+            "oneOf": [PathBuf::schema(), TopicDef::schema()],
+        })
+    }
+}
+
+impl JsonSchema for TopicDef {
+    fn schema() -> Value {
+        Object::default()
+            .required::<String>("name", "")
+            .field::<Vec<ExerciseDef>>("exercises", "")
+            .field::<Vec<String>>("summary", "")
+            .field::<Vec<String>>("objectives", "")
+            .field::<ContentDef>("content", "")
+            .field::<Vec<String>>("further_reading", "")
+            .field::<bool>("draft", "")
+            .field::<Vec<PathBuf>>(
+                "dependencies",
+                "Topics that should be taught before this one, by the path of their definition",
+            )
+            .field::<PathBuf>(
+                "instructor_notes",
+                "Markdown file with notes for instructors, only part of the instructor edition",
+            )
+            .field::<Vec<String>>("tags", "Labels that tracks can select topics by")
+            .field::<u32>(
+                "duration_minutes",
+                "How long teaching the topic takes, leaving out its exercises",
+            )
+            .field::<PathBuf>(
+                "assets",
+                "Directory of files that the slides and the book can refer to under assets/",
+            )
+            .field::<Vec<GlossaryEntry>>(
+                "glossary",
+                "Terms that the topic introduces, for the glossary of the book",
+            )
+            .field::<Vec<Quiz>>(
+                "quizzes",
+                "Questions for the knowledge check at the end of the unit in the book",
+            )
+            .build("A topic, with its slides and exercises")
+    }
+}
+
+impl JsonSchema for GlossaryEntry {
+    fn schema() -> Value {
+        Object::default()
+            .required::<String>("term", "")
+            .required::<String>("definition", "Markdown that explains the term")
+            .build("A term in the glossary of the book")
+    }
+}
+
+impl JsonSchema for Quiz {
+    fn schema() -> Value {
+        Object::default()
+            .required::<String>("question", "")
+            .required::<Vec<String>>("choices", "At least two possible answers")
+            .required::<String>("answer", "The correct one of the choices")
+            .field::<String>("explanation", "Markdown that explains the answer")
+            .build("A multiple choice question")
+    }
+}
+
+impl JsonSchema for ContentDef {
+    fn schema() -> Value {
+        let inline = Object::default()
+            .required::<String>("markdown", "")
+            .build("Slides written inline");
+        json!({
+            "description": "The slides of the topic, as a path to a markdown file or inline",
+            "oneOf": [PathBuf::schema(), inline],
+        })
+    }
+}
+
+impl JsonSchema for ExerciseDef {
+    fn schema() -> Value {
+        Object::default()
+            .required::<String>("name", "")
+            .required::<PathBuf>("path", "")
+            .field::<PathBuf>("description", "")
+            .field::<ExerciseKind>("kind", "")
+            .field::<String>(
+                "include_profile",
+                "Name of an include profile defined in the track or module",
+            )
+            .field::<Vec<String>>(
+                "includes",
+                "Globs of the files that are copied. Patterns starting with `!` are excludes",
+            )
+            .field::<Vec<String>>("excludes", "")
+            .field::<bool>("verify", "")
+            .field::<String>("verify_command", "")
+            .field::<u64>("verify_timeout_secs", "")
+            .field::<Vec<ExerciseLint>>(
+                "lints",
+                "Lints that are run when verifying the exercise, overriding the ones given when rendering",
+            )
+            .field::<Vec<WarningCode>>("allow", "Warnings that aren't reported")
+            .field::<u32>("duration_minutes", "How long doing the exercise takes")
+            .build("An exercise")
+    }
+}
@@ -0,0 +1,205 @@
+//! An index of everything a render has, for a site that publishes the course to offer search
+//! across the book, the slides and the exercises at once, rather than across book pages only.
+//!
+//! Items are identified by the same ids as in the [Outline](crate::Outline), and the index is
+//! versioned with [SEARCH_INDEX_SCHEMA_VERSION], with the same compatibility guarantee as the
+//! outline. URLs are where the items are published: relative to the output directory, or
+//! under a base URL.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    book::BookPaths,
+    exercises::ExercisePaths,
+    outline::{relative_id, topic_id, unit_id},
+    SlidesBackend, Topic, Track,
+};
+
+pub const SEARCH_INDEX_SCHEMA_VERSION: u32 = 1;
+
+/// Name of the file the index is written to in the output directory
+pub const SEARCH_INDEX_FILE: &str = "search-index.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub schema_version: u32,
+    /// The modules, units, topics and exercises of the track, in the order of the track
+    pub entries: Vec<SearchEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchEntry {
+    pub kind: SearchEntryKind,
+    pub id: String,
+    pub title: String,
+    /// Number of the item as used in the book, like `2.1` for a unit. Topics have the number
+    /// of their unit.
+    pub number: String,
+    /// Objectives of the item, which are those of its topics for units
+    pub objectives: Vec<String>,
+    /// Tags and summary items of the item, and of its topics for modules and units
+    pub keywords: Vec<String>,
+    pub urls: SearchUrls,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchEntryKind {
+    Module,
+    Unit,
+    Topic,
+    Exercise,
+}
+
+/// Where an item is published
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchUrls {
+    /// The page of the book that the item is on, as built by mdBook
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub book: Option<String>,
+    /// The slide deck that the item is part of, as it's built for publishing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slides: Option<String>,
+    /// The directory of the exercises
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exercises: Option<String>,
+}
+
+impl SearchIndex {
+    pub(crate) fn new(
+        track: &Track,
+        exercise_paths: &ExercisePaths,
+        book_paths: &BookPaths,
+        deck_paths: &HashMap<(usize, usize), PathBuf>,
+        backends: &HashMap<(usize, usize), SlidesBackend>,
+        base_url: Option<&str>,
+    ) -> Self {
+        let base_path = track.path.parent().unwrap();
+        let url = |path: &Path| {
+            let path = relative_id(path, Path::new(""));
+            match base_url {
+                Some(base_url) => format!("{}/{path}", base_url.trim_end_matches('/')),
+                None => path,
+            }
+        };
+        // The book is published as the HTML that mdBook builds from its sources
+        let book_url = |page: &PathBuf| {
+            let page = page.strip_prefix("book/src").unwrap_or(page);
+            url(&Path::new("book")
+                .join(track.book.build_dir())
+                .join(page.with_extension("html")))
+        };
+        let dir_url = |dir: &PathBuf| url(dir) + "/";
+        let mut index = Self {
+            schema_version: SEARCH_INDEX_SCHEMA_VERSION,
+            entries: vec![],
+        };
+
+        for module in track.modules.iter() {
+            let m = module.index;
+            let module_id = relative_id(module.data.path.parent().unwrap(), base_path);
+            let topics = module.data.units.iter().flat_map(|u| &u.data.topics);
+            index.entries.push(SearchEntry {
+                kind: SearchEntryKind::Module,
+                id: module_id.clone(),
+                title: module.data.name.clone(),
+                number: m.to_string(),
+                objectives: module.data.objectives.clone(),
+                keywords: keywords(topics.map(|t| &t.data)),
+                urls: SearchUrls {
+                    book: book_paths.chapters.get(&m).map(book_url),
+                    slides: None,
+                    exercises: Some(dir_url(&exercise_paths.modules[&m])),
+                },
+            });
+
+            for unit in module.data.units.iter() {
+                let u = unit.index;
+                let book = book_url(&book_paths.sections[&(m, u)]);
+                // Slidev decks are built into a site of their own, see [SlidesFormat]
+                let slides = match backends[&(m, u)] {
+                    SlidesBackend::Slidev => dir_url(&Path::new("slides").join(format!("{m}_{u}"))),
+                    _ => url(&deck_paths[&(m, u)]),
+                };
+                let topics = unit.data.topics.iter().map(|t| &t.data);
+                index.entries.push(SearchEntry {
+                    kind: SearchEntryKind::Unit,
+                    id: unit_id(&module_id, &unit.data),
+                    title: unit.data.name.clone(),
+                    number: format!("{m}.{u}"),
+                    objectives: unique(topics.clone().flat_map(|t| &t.objectives)),
+                    keywords: keywords(topics),
+                    urls: SearchUrls {
+                        book: Some(book.clone()),
+                        slides: Some(slides.clone()),
+                        exercises: Some(dir_url(&exercise_paths.units[&(m, u)])),
+                    },
+                });
+
+                // Exercises are numbered throughout the unit
+                let mut exercise_number = 0;
+                for topic in unit.data.topics.iter() {
+                    let topic_keywords = keywords([&topic.data]);
+                    index.entries.push(SearchEntry {
+                        kind: SearchEntryKind::Topic,
+                        id: topic_id(&topic.data, base_path),
+                        title: topic.data.name.clone(),
+                        number: format!("{m}.{u}"),
+                        objectives: topic.data.objectives.clone(),
+                        keywords: topic_keywords.clone(),
+                        urls: SearchUrls {
+                            book: None,
+                            slides: Some(slides.clone()),
+                            exercises: None,
+                        },
+                    });
+
+                    for exercise in topic.data.exercises.iter() {
+                        exercise_number += 1;
+                        index.entries.push(SearchEntry {
+                            kind: SearchEntryKind::Exercise,
+                            id: relative_id(&exercise.data.path, base_path),
+                            title: exercise.data.name.clone(),
+                            number: format!("{m}.{u}.{exercise_number}"),
+                            objectives: vec![],
+                            keywords: topic_keywords.clone(),
+                            urls: SearchUrls {
+                                book: Some(book.clone()),
+                                slides: None,
+                                exercises: Some(dir_url(
+                                    &exercise_paths.exercises[&(m, u, exercise_number)],
+                                )),
+                            },
+                        });
+                    }
+                }
+            }
+        }
+        index
+    }
+}
+
+/// The tags and summary items of `topics`, each once
+fn keywords<'t>(topics: impl IntoIterator<Item = &'t Topic>) -> Vec<String> {
+    unique(
+        topics
+            .into_iter()
+            .flat_map(|topic| topic.tags.iter().chain(&topic.summary)),
+    )
+}
+
+/// The `items` without the ones that came before, ignoring surrounding whitespace
+fn unique<'i>(items: impl IntoIterator<Item = &'i String>) -> Vec<String> {
+    let mut unique: Vec<String> = vec![];
+    for item in items.into_iter().map(|item| item.trim()) {
+        if !unique.iter().any(|u| u == item) {
+            unique.push(item.to_string());
+        }
+    }
+    unique
+}
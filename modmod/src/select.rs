@@ -0,0 +1,201 @@
+//! Selection of the parts of a track that end up in a render
+
+use std::path::Path;
+
+use crate::{
+    codes::WarningCode, load::Indexed, report::Warning, to_tag, RenderOptions, Topic, Track, Unit,
+};
+
+/// The part of a track that is rendered with a set of [RenderOptions]
+pub struct Selection {
+    /// The track without the items that were left out. Units and topics are renumbered,
+    /// so that the output has no gaps where items were left out.
+    pub track: Track,
+    pub drafts_skipped: usize,
+    pub drafts_included: usize,
+    pub warnings: Vec<Warning>,
+    /// Problems with the [modules](RenderOptions::modules) and [units](RenderOptions::units)
+    /// to render, which select nothing
+    pub unmatched_selectors: Vec<String>,
+}
+
+/// Who a render is for. Only the instructor edition contains instructor notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Edition {
+    Student,
+    Instructor,
+}
+
+impl Edition {
+    /// Whether the exercises of the edition keep their solutions
+    pub(crate) fn keeps_solutions(self, options: &RenderOptions) -> bool {
+        options.keep_solutions || self == Edition::Instructor
+    }
+}
+
+impl Track {
+    pub(crate) fn select(&self, options: &RenderOptions, edition: Edition) -> Selection {
+        let mut track = self.clone();
+        let mut drafts_skipped = 0;
+        let mut drafts_included = 0;
+        let mut keep = |draft: bool| {
+            if !draft {
+                true
+            } else if options.include_drafts {
+                drafts_included += 1;
+                true
+            } else {
+                drafts_skipped += 1;
+                false
+            }
+        };
+
+        let topics = self
+            .modules
+            .iter()
+            .flat_map(|m| &m.data.units)
+            .flat_map(|u| &u.data.topics);
+        let mut warnings: Vec<_> = self
+            .excluded_topics
+            .iter()
+            .filter(|excluded| !topics.clone().any(|t| refers_to(excluded, &t.data)))
+            .map(|excluded| {
+                Warning::new(
+                    WarningCode::UnmatchedTopicExclusion,
+                    format!(
+                        "Excluded topic {path} is not part of the track",
+                        path = excluded.to_string_lossy()
+                    ),
+                )
+            })
+            .collect();
+        warnings.extend(
+            self.include_tags
+                .iter()
+                .chain(self.exclude_tags.iter())
+                .filter(|tag| !topics.clone().any(|t| t.data.tags.contains(tag)))
+                .map(|tag| {
+                    Warning::new(
+                        WarningCode::UnmatchedTopicExclusion,
+                        format!("No topic of the track is tagged '{tag}'"),
+                    )
+                }),
+        );
+
+        for module in track.modules.iter_mut() {
+            module.data.units.retain_mut(|unit| {
+                if !keep(unit.data.draft) {
+                    return false;
+                }
+                let had_topics = !unit.data.topics.is_empty();
+                // Excluded topics are left out even if drafts are included,
+                // so they don't count as drafts either way
+                unit.data
+                    .topics
+                    .retain(|topic| !self.excludes(&topic.data) && keep(topic.data.draft));
+                reindex(&mut unit.data.topics);
+                if edition == Edition::Student {
+                    for topic in unit.data.topics.iter_mut() {
+                        topic.data.instructor_notes = None;
+                    }
+                }
+                // Don't render units of which every topic was left out
+                !had_topics || !unit.data.topics.is_empty()
+            });
+            reindex(&mut module.data.units);
+        }
+        track.appendices.retain(|appendix| keep(appendix.draft));
+
+        // Items keep their numbers, so that they're rendered the same as in the whole track
+        let mut unmatched_selectors = vec![];
+        if !options.modules.is_empty() {
+            for selector in options.modules.iter() {
+                if !track
+                    .modules
+                    .iter()
+                    .any(|m| selects(selector, m.index, &m.data.name))
+                {
+                    unmatched_selectors.push(format!("No module of the track is '{selector}'"));
+                }
+            }
+            track.modules.retain(|module| {
+                options
+                    .modules
+                    .iter()
+                    .any(|selector| selects(selector, module.index, &module.data.name))
+            });
+        }
+        if !options.units.is_empty() {
+            let selects_unit = |selector: &str, module: usize, unit: &Indexed<Unit>| match selector
+                .split_once('.')
+            {
+                Some((m, u)) => m.parse() == Ok(module) && u.parse() == Ok(unit.index),
+                None => selects(selector, unit.index, &unit.data.name),
+            };
+            for selector in options.units.iter() {
+                if !track.modules.iter().any(|module| {
+                    module
+                        .data
+                        .units
+                        .iter()
+                        .any(|unit| selects_unit(selector, module.index, unit))
+                }) {
+                    unmatched_selectors
+                        .push(format!("No unit of the selected modules is '{selector}'"));
+                }
+            }
+            for module in track.modules.iter_mut() {
+                let module_index = module.index;
+                module.data.units.retain(|unit| {
+                    options
+                        .units
+                        .iter()
+                        .any(|selector| selects_unit(selector, module_index, unit))
+                });
+            }
+            track.modules.retain(|module| !module.data.units.is_empty());
+        }
+
+        Selection {
+            track,
+            drafts_skipped,
+            drafts_included,
+            warnings,
+            unmatched_selectors,
+        }
+    }
+}
+
+impl Track {
+    /// Whether `topic` is left out of the track, by its path or by its tags
+    pub(crate) fn excludes(&self, topic: &Topic) -> bool {
+        self.excluded_topics
+            .iter()
+            .any(|excluded| refers_to(excluded, topic))
+            || topic.tags.iter().any(|tag| self.exclude_tags.contains(tag))
+            || (!self.include_tags.is_empty()
+                && !topic.tags.iter().any(|tag| self.include_tags.contains(tag)))
+    }
+}
+
+/// Topics are referred to by the path of their definition file or of the directory it's in.
+/// Inline topics share the path of their module definition, so they can't be referred to.
+pub(crate) fn refers_to(path: &Path, topic: &Topic) -> bool {
+    !topic.inline && (topic.path == path || topic.path.parent() == Some(path))
+}
+
+/// Whether `selector`, a number or a name, refers to the item with `index` and `name`.
+/// Names may be written in any case, or as the slug they get in the output.
+pub(crate) fn selects(selector: &str, index: usize, name: &str) -> bool {
+    match selector.parse::<usize>() {
+        Ok(number) => number == index,
+        Err(_) => selector.eq_ignore_ascii_case(name) || selector == to_tag(name),
+    }
+}
+
+fn reindex<T>(items: &mut [Indexed<T>]) {
+    items
+        .iter_mut()
+        .zip(1..)
+        .for_each(|(item, index)| item.index = index);
+}
@@ -0,0 +1,277 @@
+//! A small HTTP server to preview the output of a track while it's being worked on.
+//! Pages reload by themselves whenever the track is rendered again.
+
+use std::{
+    fmt, fs,
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use error_stack::{IntoReport, Result, ResultExt};
+
+/// Path that pages poll to find out whether the output was rendered again
+const VERSION_PATH: &str = "/__modmod/version";
+
+/// Script that is added to every page, which reloads it once the version served at
+/// [VERSION_PATH] is no longer the version the page was served with
+const RELOAD_SCRIPT: &str = r##"<script>
+(function () {
+  const version = "#[modmod:version]";
+  setInterval(async function () {
+    try {
+      const response = await fetch("/__modmod/version");
+      if ((await response.text()) !== version) {
+        location.reload();
+      }
+    } catch (e) {}
+  }, 500);
+})();
+</script>"##;
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct ServeError;
+
+impl fmt::Display for ServeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to serve the output")
+    }
+}
+
+impl error_stack::Context for ServeError {}
+
+/// Serves the files in an output directory over HTTP, on a thread of its own, to preview
+/// the book built with [RenderOptions::build_book](crate::RenderOptions::build_book).
+/// HTML files are served as is, while other Markdown files, like the slides, are only
+/// shown as text. Every page reloads itself after [PreviewServer::reload] is called.
+pub struct PreviewServer {
+    address: SocketAddr,
+    version: Arc<AtomicU64>,
+}
+
+impl PreviewServer {
+    /// Start serving the files in `root` at `address`
+    pub fn start(address: &str, root: &Path) -> Result<Self, ServeError> {
+        let listener = TcpListener::bind(address)
+            .into_report()
+            .change_context(ServeError)
+            .attach_printable_lazy(|| format!("Unable to listen at {address}"))?;
+        let address = listener
+            .local_addr()
+            .into_report()
+            .change_context(ServeError)?;
+        let version = Arc::new(AtomicU64::new(0));
+        {
+            let root = root.to_path_buf();
+            let version = version.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let root = root.clone();
+                    let version = version.load(Ordering::SeqCst);
+                    // Errors only affect the request they happen in
+                    thread::spawn(move || {
+                        let _ = respond(stream, &root, version);
+                    });
+                }
+            });
+        }
+        Ok(Self { address, version })
+    }
+
+    /// The address the server listens at
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Make the pages that are open reload themselves, after the output changed
+    pub fn reload(&self) {
+        self.version.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Answer a single request for a file in `root`
+fn respond(mut stream: TcpStream, root: &Path, version: u64) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return send(&mut stream, "400 Bad Request", "text/plain", b"Bad request");
+    };
+    if method != "GET" {
+        return send(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Only GET requests are supported",
+        );
+    }
+    let target = target.split(['?', '#']).next().unwrap_or_default();
+    if target == VERSION_PATH {
+        return send(
+            &mut stream,
+            "200 OK",
+            "text/plain",
+            version.to_string().as_bytes(),
+        );
+    }
+
+    let relative = PathBuf::from(percent_decode(target.trim_start_matches('/')));
+    // Never serve files outside of the output directory
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return send(&mut stream, "404 Not Found", "text/plain", b"Not found");
+    }
+    let mut path = root.join(&relative);
+    if path.is_dir() && path.join("index.html").is_file() {
+        path = path.join("index.html");
+    }
+
+    if path.is_dir() {
+        let page = directory_page(&path, target, version)?;
+        send(
+            &mut stream,
+            "200 OK",
+            "text/html; charset=utf-8",
+            page.as_bytes(),
+        )
+    } else if let Ok(content) = fs::read(&path) {
+        let reload_script = RELOAD_SCRIPT.replace("#[modmod:version]", &version.to_string());
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("md") => {
+                let page = page(
+                    target,
+                    &format!("<pre>{}</pre>", escape(&String::from_utf8_lossy(&content))),
+                    version,
+                );
+                send(
+                    &mut stream,
+                    "200 OK",
+                    "text/html; charset=utf-8",
+                    page.as_bytes(),
+                )
+            }
+            Some("html") => {
+                let content = String::from_utf8_lossy(&content);
+                let page = match content.rfind("</body>") {
+                    Some(end) => format!("{}{reload_script}{}", &content[..end], &content[end..]),
+                    None => format!("{content}{reload_script}"),
+                };
+                send(
+                    &mut stream,
+                    "200 OK",
+                    "text/html; charset=utf-8",
+                    page.as_bytes(),
+                )
+            }
+            extension => send(&mut stream, "200 OK", content_type(extension), &content),
+        }
+    } else {
+        send(&mut stream, "404 Not Found", "text/plain", b"Not found")
+    }
+}
+
+fn send(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {length}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        length = body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+/// A page listing the entries of the directory at `path`, which is served at `target`
+fn directory_page(path: &Path, target: &str, version: u64) -> std::io::Result<String> {
+    let mut entries: Vec<(String, bool)> = fs::read_dir(path)?
+        .flatten()
+        .map(|entry| {
+            let is_dir = entry.path().is_dir();
+            (entry.file_name().to_string_lossy().into_owned(), is_dir)
+        })
+        .collect();
+    entries.sort();
+    let base = target.trim_end_matches('/');
+    let mut list = String::from("<ul>\n");
+    if !base.is_empty() {
+        let parent = base.rsplit_once('/').map_or("", |(parent, _)| parent);
+        list.push_str(&format!("<li><a href=\"{parent}/\">../</a></li>\n"));
+    }
+    for (name, is_dir) in entries {
+        let slash = if is_dir { "/" } else { "" };
+        list.push_str(&format!(
+            "<li><a href=\"{base}/{name}{slash}\">{name}{slash}</a></li>\n",
+            name = escape(&name)
+        ));
+    }
+    list.push_str("</ul>");
+    Ok(page(target, &list, version))
+}
+
+/// A page with `body`, that reloads itself once the output is rendered again
+fn page(title: &str, body: &str, version: u64) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n{body}\n{script}\n</body>\n</html>\n",
+        title = escape(title),
+        script = RELOAD_SCRIPT.replace("#[modmod:version]", &version.to_string())
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Decode the `%XX` escapes in the path of a URL
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn content_type(extension: Option<&str>) -> &'static str {
+    match extension {
+        Some("css") => "text/css",
+        Some("js") => "text/javascript",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("toml" | "rs" | "txt") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
@@ -1,19 +1,116 @@
 #![allow(dead_code)]
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use error_stack::Result;
-use serde_json::Value as JsonValue;
+use error_stack::{Report, Result, ResultExt};
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
 
 type JsonObject = serde_json::Map<String, JsonValue>;
 
 use crate::{
+    anchors::expand_includes,
+    conditional::select_conditional,
+    handout::Handout,
+    headings::normalize_headings,
     io::{PathExt, WriteExt},
+    marp,
+    mermaid::MermaidDiagrams,
+    notes::extract_notes,
+    output::Output,
+    parallel::parallel_map,
+    pptx,
+    qr::QrCodes,
+    revealjs,
+    template::{expand_variables, unescape_placeholders, unresolved_placeholder, Template},
     to_prefixed_tag, to_tag,
+    validate::normalize_item,
+    SlidesBackend, SlidesConfig, TopicContent,
 };
 
 const PACKAGE_JSON_CONTENT_STUB: &str = include_str!("../include/slides/package.json");
 const SLIDES_TEMPLATE_DEFAULT: &str = include_str!("../include/slides/default.md");
+/// Slide in front of draft topics, so that they can't be mistaken for finished material
+const DRAFT_SLIDE: &str =
+    "---\n\n# DRAFT\n\nThe following slides are work in progress, and not part of the published course\n";
+
+/// Placeholders in slide templates that are filled in when rendering the slides, along with
+/// the template expressions they are shorthands for
+pub(crate) const TEMPLATE_PLACEHOLDERS: [(&str, &str); 11] = [
+    ("mod_title", "module.name"),
+    ("mod_index", "module.index"),
+    ("unit_index", "unit.index"),
+    ("unit_title", "unit.title"),
+    ("content", "content"),
+    ("objectives", "objectives | list"),
+    ("module_objectives", "module.objectives | list"),
+    ("summary", "summary | list"),
+    ("further_reading", "further_reading"),
+    ("exercises", "exercise_overview"),
+    ("qr(unit)", "qr_code"),
+];
+
+/// The values that slides templates can use, see [Template]
+#[derive(Debug, Serialize)]
+struct TemplateContext<'a> {
+    module: ModuleContext<'a>,
+    unit: UnitContext<'a>,
+    topics: Vec<TopicContext<'a>>,
+    /// The exercises of all topics of the unit
+    exercises: Vec<ExerciseContext<'a>>,
+    /// The objectives of the topics, without duplicates
+    objectives: Vec<&'a str>,
+    /// The summary items of the topics, without duplicates
+    summary: Vec<&'a str>,
+    /// The slides of the topics
+    content: String,
+    /// The further reading of the topics, as a list per topic
+    further_reading: &'a str,
+    /// The exercises of the topics as a list, with their summaries and where to find them
+    exercise_overview: String,
+    /// The QR code of the published slides, if QR codes are enabled
+    qr_code: String,
+    /// The variables of the track
+    variables: &'a BTreeMap<&'a str, &'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct ModuleContext<'a> {
+    name: &'a str,
+    index: usize,
+    objectives: Vec<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct UnitContext<'a> {
+    name: &'a str,
+    /// The name, marked if the unit is a draft
+    title: String,
+    index: usize,
+    draft: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct TopicContext<'a> {
+    name: &'a str,
+    objectives: Vec<&'a str>,
+    summary: Vec<&'a str>,
+    further_reading: Vec<&'a str>,
+    exercises: Vec<ExerciseContext<'a>>,
+    draft: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ExerciseContext<'a> {
+    name: &'a str,
+    /// Number of the exercise as used in the book, like `2.1.3`
+    number: String,
+    /// The first paragraph of the description of the exercise
+    summary: String,
+    /// Directory of the rendered exercise, relative to the output directory
+    dir: String,
+}
 
 #[derive(Debug, Default)]
 #[non_exhaustive]
@@ -31,41 +128,108 @@ impl error_stack::Context for RenderSlidesError {}
 pub struct SlidesPackage<'track> {
     /// Name of the package, corresponds to the name of the track
     name: &'track str,
+    /// Settings of the decks that don't set their own
+    config: &'track SlidesConfig,
     decks: Vec<SlideDeck<'track>>,
+    /// Values of the `#[modmod:var(name)]` references in templates and topics, by name
+    variables: BTreeMap<&'track str, &'track str>,
+    /// Id of the track that conditional regions of topics refer to it by
+    track_id: String,
+    /// Language of the decks, for backends that render HTML
+    language: Option<&'track str>,
+    /// Also render a handout of every unit into `handouts`, see [Handout]
+    handouts: bool,
+    /// Whether the package is part of the instructor edition, which keeps the notes of
+    /// topics and has a file of them for every unit in `presenter-notes`
+    instructor: bool,
+    /// Whether the topmost headings of every topic are moved to level 1, in decks that
+    /// don't decide for themselves
+    normalize_headings: bool,
 }
 
 impl<'track> SlidesPackage<'track> {
-    pub fn builder(name: &'track str) -> SlidesPackageBuilder<'track> {
+    pub fn builder(
+        name: &'track str,
+        config: &'track SlidesConfig,
+    ) -> SlidesPackageBuilder<'track> {
         SlidesPackageBuilder {
             package: SlidesPackage {
                 name,
+                config,
                 decks: vec![],
+                variables: BTreeMap::new(),
+                track_id: String::new(),
+                language: None,
+                handouts: false,
+                instructor: false,
+                normalize_headings: false,
             },
         }
     }
 
-    pub fn render(&self, out_dir: impl AsRef<Path>) -> Result<(), RenderSlidesError> {
+    /// The backends that the slide decks are rendered for, by module and unit index
+    pub fn backends(&self) -> HashMap<(usize, usize), SlidesBackend> {
+        self.decks
+            .iter()
+            .map(|deck| {
+                let config = deck
+                    .config
+                    .map_or(self.config.clone(), |c| c.or(self.config));
+                let backend = config.backend.unwrap_or_default();
+                ((deck.module_index, deck.unit_index), backend)
+            })
+            .collect()
+    }
+
+    /// Render the slides package, returning the paths of the slide decks by
+    /// module and unit index, relative to the output directory
+    pub fn render(
+        &self,
+        exercise_paths: &HashMap<(usize, usize, usize), PathBuf>,
+        qr_codes: Option<&QrCodes>,
+        mermaid: Option<&MermaidDiagrams>,
+        output: &Output,
+    ) -> Result<HashMap<(usize, usize), PathBuf>, RenderSlidesError> {
+        let mut deck_paths = HashMap::new();
         let mut package_json: JsonObject = serde_json::from_str(PACKAGE_JSON_CONTENT_STUB).unwrap();
         package_json.insert("name".into(), to_tag(self.name).into());
         let mut package_scripts = JsonObject::new();
 
-        let output_dir = out_dir.as_ref();
-        let slides_output_dir = output_dir.join("slides");
-        slides_output_dir.create_dir_all()?;
+        let slides_output_dir = output.root().join("slides");
+        output.create_dir_all(&slides_output_dir)?;
 
         let slide_images_dir = slides_output_dir.join("images");
-        slide_images_dir.create_dir_all()?;
+        output.create_dir_all(&slide_images_dir)?;
+
+        let handouts_dir = output.root().join("handouts");
+        if self.handouts {
+            output.create_dir_all(&handouts_dir)?;
+        }
+        let presenter_notes_dir = output.root().join("presenter-notes");
+        if self.instructor {
+            output.create_dir_all(&presenter_notes_dir)?;
+        }
 
         for deck in self.decks.iter() {
+            let config = deck
+                .config
+                .map_or(self.config.clone(), |c| c.or(self.config));
+            let backend = config.backend.unwrap_or_default();
             let deck_prefix = format!("{}_{}", deck.module_index, deck.unit_index);
             let deck_output = {
                 let mut o = slides_output_dir.join(to_prefixed_tag(deck.name, &deck_prefix));
-                o.set_extension("md");
+                o.set_extension(backend.extension());
                 o
             };
-            let mut deck_file = deck_output.create_file()?;
+            deck_paths.insert(
+                (deck.module_index, deck.unit_index),
+                deck_output
+                    .strip_prefix(output.root())
+                    .unwrap()
+                    .to_path_buf(),
+            );
 
-            {
+            if backend == SlidesBackend::Slidev {
                 let deck_output_str = deck_output
                     .strip_prefix(&slides_output_dir)
                     .unwrap()
@@ -88,60 +252,583 @@ impl<'track> SlidesPackage<'track> {
                 );
             }
 
-            let template_content = deck
-                .template
-                .map(|t| t.read_to_string())
-                .unwrap_or(Ok(SLIDES_TEMPLATE_DEFAULT.to_string()))?;
             let mut unit_content = String::new();
-            let mut unit_objectives = String::new();
-            let mut unit_summary = String::new();
-
-            for section in deck.sections.iter() {
-                let topic_content = section.content.read_to_string()?;
+            let mut unit_objectives = vec![];
+            let mut unit_summary = vec![];
+            let mut unit_further_reading = String::new();
+            let mut topics = vec![];
+            let mut seen_objectives = HashSet::new();
+            let mut seen_summary = HashSet::new();
+            // Exercises are numbered throughout the unit, like in the book
+            let mut exercise_number = 0;
+            // Files that topics refer to by relative paths, by the names they're copied to
+            let mut assets: HashMap<String, PathBuf> = HashMap::new();
+            let mut presenter_notes = String::new();
+
+            // Topic files are read all at once, and concatenated in order
+            let topic_contents = parallel_map(&deck.sections, |section| match section.content {
+                TopicContent::File(path) => path.read_to_string::<RenderSlidesError>(),
+                TopicContent::Inline(markdown) => Ok(markdown.clone()),
+            });
+            let topic_separator = config.topic_separator.as_deref().unwrap_or("---").trim();
+            let topic_title = config
+                .topic_title
+                .as_deref()
+                .map(|title| Template::parse(title, &TEMPLATE_PLACEHOLDERS))
+                .transpose()
+                .map_err(|problem| {
+                    Report::new(RenderSlidesError).attach_printable(format!(
+                        "{problem} in slides setting 'topic_title' of unit '{}'",
+                        deck.name
+                    ))
+                })?;
+            for ((section, topic_content), topic_index) in
+                deck.sections.iter().zip(topic_contents).zip(1..)
+            {
+                let mut topic_content = topic_content?;
+                topic_content = select_conditional(&topic_content, &self.track_id)
+                    .and_then(|content| expand_variables(&content, &self.variables))
+                    .map_err(|problem| {
+                        let topic = match section.content {
+                            TopicContent::File(path) => path.to_string_lossy().to_string(),
+                            TopicContent::Inline(_) => {
+                                format!("the slides of topic {}", section.name)
+                            }
+                        };
+                        Report::new(RenderSlidesError)
+                            .attach_printable(format!("{problem}, in {topic}"))
+                    })?;
+                // Slidev shows a comment at the end of a slide as its speaker notes
+                let (content, notes) =
+                    extract_notes(&topic_content, |text| match self.instructor {
+                        true => format!("<!--\n{text}\n-->\n\n"),
+                        false => String::new(),
+                    });
+                topic_content = content;
+                if let TopicContent::File(path) = section.content {
+                    topic_content = expand_includes(&topic_content, path.parent().unwrap())
+                        .map_err(|problem| {
+                            Report::new(RenderSlidesError).attach_printable(format!(
+                                "{problem}, in {path}",
+                                path = path.to_string_lossy()
+                            ))
+                        })?;
+                    for (reference, asset) in local_assets(&topic_content, path.parent().unwrap()) {
+                        let name = asset.file_name().unwrap().to_string_lossy();
+                        // The images directory of the topic is copied as it is
+                        if reference == format!("images/{name}")
+                            && section.images.iter().any(|image| asset == *image)
+                        {
+                            continue;
+                        }
+                        // Assets of different topics may have the same name
+                        let mut asset_name = name.to_string();
+                        let mut copy = 1;
+                        while assets.get(&asset_name).is_some_and(|a| *a != asset) {
+                            copy += 1;
+                            asset_name = format!("{copy}-{name}");
+                        }
+                        if !assets.contains_key(&asset_name) {
+                            let asset_dir = slide_images_dir.join(&deck_prefix);
+                            output.create_dir_all(&asset_dir)?;
+                            output.copy(&asset, asset_dir.join(&asset_name))?;
+                            assets.insert(asset_name.clone(), asset);
+                        }
+                        let new_reference = format!("/images/{deck_prefix}/{asset_name}");
+                        topic_content =
+                            rewrite_reference(&topic_content, &reference, &new_reference);
+                    }
+                }
+                // Topics are written to be presented on their own as well, starting at any level
+                if deck.normalize_headings.unwrap_or(self.normalize_headings) {
+                    topic_content = normalize_headings(&topic_content, 1);
+                }
+                if let Some(mermaid) = mermaid {
+                    topic_content = mermaid
+                        .render(
+                            &topic_content,
+                            &slide_images_dir.join("mermaid"),
+                            "/images/mermaid",
+                            output,
+                        )
+                        .change_context(RenderSlidesError)?;
+                }
                 let topic_content = topic_content.trim();
 
+                if section.draft {
+                    unit_content.write_str(DRAFT_SLIDE).unwrap();
+                }
                 if !topic_content.is_empty() {
-                    if !topic_content.starts_with("---") {
-                        unit_content.write_str("---\n\n").unwrap();
+                    if let Some(topic_title) = &topic_title {
+                        let context = json!({
+                            "topic": { "name": section.name, "index": topic_index },
+                            "module": { "name": deck.module_name, "index": deck.module_index },
+                            "unit": { "name": deck.name, "index": deck.unit_index },
+                            "variables": &self.variables,
+                        });
+                        let title = topic_title.render(&context).map_err(|problem| {
+                            Report::new(RenderSlidesError).attach_printable(format!(
+                                "{problem} in slides setting 'topic_title' of unit '{}'",
+                                deck.name
+                            ))
+                        })?;
+                        // Units can leave out the title slides of the track with an empty title
+                        if !title.trim().is_empty() {
+                            write!(unit_content, "---\n\n# {}\n\n", title.trim()).unwrap();
+                        }
+                    }
+                    // Topics that start with settings for their first slide separate themselves
+                    if !topic_content.starts_with("---") && !topic_separator.is_empty() {
+                        write!(unit_content, "{topic_separator}\n\n").unwrap();
                     }
                     unit_content.write_str(topic_content).unwrap();
                     unit_content.write_str("\n").unwrap();
                 }
+                let instructor_notes = match section.instructor_notes {
+                    Some(notes) => Some(notes.read_to_string()?),
+                    None => None,
+                };
+                if let Some(instructor_notes) = instructor_notes
+                    .as_ref()
+                    .filter(|_| !topic_content.is_empty())
+                {
+                    write!(unit_content, "\n<!--\n{}\n-->\n", instructor_notes.trim()).unwrap();
+                }
+                if !notes.is_empty() || instructor_notes.is_some() {
+                    write!(presenter_notes, "## {}\n\n", section.name).unwrap();
+                    for notes in notes.iter() {
+                        let heading = notes.heading.as_deref().unwrap_or("Notes");
+                        write!(presenter_notes, "### {heading}\n\n{}\n\n", notes.text).unwrap();
+                    }
+                    if let Some(instructor_notes) = instructor_notes {
+                        // Sections of the notes become subsubsubsections, like in the book
+                        let instructor_notes =
+                            format!("\n{}", instructor_notes.trim()).replace("\n# ", "\n#### ");
+                        write!(
+                            presenter_notes,
+                            "### Instructor notes\n{instructor_notes}\n\n"
+                        )
+                        .unwrap();
+                    }
+                }
+
+                let mut exercises = vec![];
+                for (name, _, description) in section.exercises.iter() {
+                    exercise_number += 1;
+                    let exercise_ref = format!(
+                        "{}.{}.{exercise_number}",
+                        deck.module_index, deck.unit_index
+                    );
+                    let exercise_out_dir =
+                        &exercise_paths[&(deck.module_index, deck.unit_index, exercise_number)];
+                    let dir: Vec<_> = exercise_out_dir
+                        .iter()
+                        .map(|p| p.to_string_lossy())
+                        .collect();
+                    let summary = description_summary(&description.read_to_string()?);
+                    exercises.push(ExerciseContext {
+                        name,
+                        number: exercise_ref.clone(),
+                        summary,
+                        dir: dir.join("/"),
+                    });
+                    let Some(qr_codes) = qr_codes else {
+                        continue;
+                    };
+                    let qr_code = write_qr_code(
+                        qr_codes,
+                        exercise_out_dir,
+                        &slide_images_dir,
+                        &format!("exercise-{deck_prefix}_{exercise_number}"),
+                        output,
+                    )?;
+                    write!(
+                        unit_content,
+                        "---\n\n# Exercise {exercise_ref}: {name}\n\n{qr_code}\n\n<{}>\n",
+                        qr_codes.url(exercise_out_dir)
+                    )
+                    .unwrap();
+                }
 
+                // Duplicates are reported by validation
                 for objective in section.objectives.iter() {
-                    unit_objectives += &format!("- {}\n", objective.trim());
+                    if seen_objectives.insert(normalize_item(objective)) {
+                        unit_objectives.push(objective.trim());
+                    }
+                }
+
+                if !section.further_reading.is_empty() {
+                    write!(unit_further_reading, "### {}\n\n", section.name).unwrap();
+                    for item in section.further_reading.iter() {
+                        writeln!(unit_further_reading, "- {}", linkify(item.trim())).unwrap();
+                    }
+                    unit_further_reading.push('\n');
                 }
 
                 for item in section.summary.iter() {
-                    unit_summary += &format!("- {}\n", item.trim());
+                    if seen_summary.insert(normalize_item(item)) {
+                        unit_summary.push(item.trim());
+                    }
                 }
+                topics.push(TopicContext {
+                    name: section.name,
+                    objectives: section.objectives.iter().map(|o| o.trim()).collect(),
+                    summary: section.summary.iter().map(|s| s.trim()).collect(),
+                    further_reading: section.further_reading.iter().map(|f| f.trim()).collect(),
+                    exercises,
+                    draft: section.draft,
+                });
 
                 section
                     .images
                     .iter()
                     .filter_map(|path| path.file_name().map(|name| (path, name)))
-                    .try_for_each(|(path, name)| path.copy(slide_images_dir.join(name)))?;
+                    .try_for_each(|(path, name)| output.copy(path, slide_images_dir.join(name)))?;
             }
 
-            let slides_content = template_content
-                .replace("#[modmod:mod_title]", deck.module_name)
-                .replace("#[modmod:mod_index]", &deck.module_index.to_string())
-                .replace("#[modmod:unit_index]", &deck.unit_index.to_string())
-                .replace("#[modmod:unit_title]", deck.name)
-                .replace("#[modmod:content]", &unit_content)
-                .replace("#[modmod:objectives]", &unit_objectives)
-                .replace("#[modmod:summary]", &unit_summary);
+            // Published decks are built with this base path
+            let unit_qr_code = match qr_codes {
+                Some(qr_codes) => write_qr_code(
+                    qr_codes,
+                    &Path::new("slides").join(&deck_prefix),
+                    &slide_images_dir,
+                    &format!("unit-{deck_prefix}"),
+                    output,
+                )?,
+                None => String::new(),
+            };
 
-            deck_file.write_all(slides_content)?;
+            let unit_title = if deck.draft {
+                format!("{} (DRAFT)", deck.name)
+            } else {
+                deck.name.to_string()
+            };
+            let mut exercise_overview = String::new();
+            for exercise in topics.iter().flat_map(|t| &t.exercises) {
+                write!(
+                    exercise_overview,
+                    "- **{} {}**",
+                    exercise.number, exercise.name
+                )
+                .unwrap();
+                if !exercise.summary.is_empty() {
+                    write!(exercise_overview, ": {}", exercise.summary).unwrap();
+                }
+                writeln!(exercise_overview, " (`{}`)", exercise.dir).unwrap();
+            }
+            if self.instructor {
+                if presenter_notes.is_empty() {
+                    presenter_notes.push_str("*There are no notes for this unit*\n");
+                }
+                let mut notes_output = presenter_notes_dir.join(deck_output.file_name().unwrap());
+                notes_output.set_extension("md");
+                output.create_file(notes_output)?.write_fmt(format_args!(
+                    "# Presenter notes: {unit_title}\n\nModule {}: {}\n\n{}\n",
+                    deck.module_index,
+                    deck.module_name,
+                    presenter_notes.trim_end()
+                ))?;
+            }
+            if self.handouts {
+                let handout = Handout {
+                    title: &unit_title,
+                    subtitle: format!("Module {}: {}", deck.module_index, deck.module_name),
+                    content: &unit_content,
+                    objectives: &unit_objectives,
+                    summary: &unit_summary,
+                    further_reading: unit_further_reading.trim_end(),
+                };
+                let handout = unescape_placeholders(&handout.render());
+                // Handouts are next to the slides package that has the images
+                let handout = relative_images(&handout, "../slides/images/");
+                let mut handout_output = handouts_dir.join(deck_output.file_name().unwrap());
+                handout_output.set_extension("md");
+                output.create_file(handout_output)?.write_all(handout)?;
+            }
+            let context = TemplateContext {
+                module: ModuleContext {
+                    name: deck.module_name,
+                    index: deck.module_index,
+                    objectives: deck.module_objectives.iter().map(|o| o.trim()).collect(),
+                },
+                unit: UnitContext {
+                    name: deck.name,
+                    title: unit_title,
+                    index: deck.unit_index,
+                    draft: deck.draft,
+                },
+                exercises: topics.iter().flat_map(|t| t.exercises.clone()).collect(),
+                topics,
+                objectives: unit_objectives,
+                summary: unit_summary,
+                content: unit_content,
+                further_reading: unit_further_reading.trim_end(),
+                exercise_overview,
+                qr_code: unit_qr_code,
+                variables: &self.variables,
+            };
+            let slides = self.render_template(deck, deck.template, &context)?;
+            let mut settings = vec![];
+            for (key, value) in config.headmatter() {
+                let value = Template::parse(value, &TEMPLATE_PLACEHOLDERS)
+                    .and_then(|template| template.render(&context))
+                    .map_err(|problem| {
+                        Report::new(RenderSlidesError).attach_printable(format!(
+                            "{problem} in slides setting '{key}' of unit '{}'",
+                            deck.name
+                        ))
+                    })?;
+                settings.push((key, value));
+            }
+            let slides = set_headmatter(&slides, &settings);
+            let language = self.language.unwrap_or("en");
+            let deck_content = match backend {
+                SlidesBackend::Slidev => slides.into_bytes(),
+                SlidesBackend::Revealjs => {
+                    revealjs::deck(&slides, &settings, language).into_bytes()
+                }
+                SlidesBackend::Marp => marp::deck(&slides, &settings, language).into_bytes(),
+                SlidesBackend::Pptx => pptx::deck(&slides, language).map_err(|problem| {
+                    Report::new(RenderSlidesError)
+                        .attach_printable(format!("{problem}: slides of unit '{}'", deck.name))
+                })?,
+            };
+            output.create_file(&deck_output)?.write_all(deck_content)?;
+            // Other documents, like handouts, are named after the slides
+            for (template, name) in deck.extra_templates.iter() {
+                let content = self.render_template(deck, Some(template), &context)?;
+                output
+                    .create_file(deck_output.with_extension(format!("{name}.md")))?
+                    .write_all(content)?;
+            }
         }
 
-        package_json.insert("scripts".into(), package_scripts.into());
-        let package_json = serde_json::to_string_pretty(&package_json).unwrap();
-        let package_json_file = slides_output_dir.join("package.json");
-        let mut package_json_file = package_json_file.create_file()?;
-        package_json_file.write_all(package_json)?;
+        // Decks for other backends don't need Slidev
+        if self.decks.is_empty() || !package_scripts.is_empty() {
+            package_json.insert("scripts".into(), package_scripts.into());
+            let package_json = serde_json::to_string_pretty(&package_json).unwrap();
+            let package_json_file = slides_output_dir.join("package.json");
+            let mut package_json_file = output.create_file(&package_json_file)?;
+            package_json_file.write_all(package_json)?;
+        }
 
-        Ok(())
+        Ok(deck_paths)
+    }
+}
+
+/// Files that the slides `content` of a topic refers to by relative paths, in markdown
+/// images and `src` attributes, along with the references as they're written. References
+/// are resolved against `dir`, the directory of the content file, and those that aren't
+/// files, like URLs and absolute paths that point into the slides package, are left out.
+pub(crate) fn local_assets(content: &str, dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut references = vec![];
+    for (start, _) in content.match_indices("![") {
+        let rest = &content[start..];
+        let Some(target) = rest.find("](").map(|i| &rest[i + 2..]) else {
+            continue;
+        };
+        // Images may have a title after their path
+        let end = target.find([')', ' ']).unwrap_or(target.len());
+        references.push(&target[..end]);
+    }
+    for quote in ['"', '\''] {
+        for (start, attribute) in content.match_indices(&format!("src={quote}")) {
+            let value = &content[start + attribute.len()..];
+            references.push(&value[..value.find(quote).unwrap_or(value.len())]);
+        }
+    }
+
+    let mut assets: Vec<(String, PathBuf)> = vec![];
+    for reference in references {
+        let is_local =
+            !reference.is_empty() && !reference.contains(':') && !reference.starts_with(['/', '#']);
+        let path = dir.join(reference);
+        if is_local && path.is_file() && !assets.iter().any(|(r, _)| r == reference) {
+            assets.push((reference.to_string(), path.canonicalize().unwrap_or(path)));
+        }
+    }
+    assets
+}
+
+/// Replace `reference` by `new_reference` in the markdown images and `src` attributes of `content`
+pub(crate) fn rewrite_reference(content: &str, reference: &str, new_reference: &str) -> String {
+    content
+        .replace(&format!("]({reference})"), &format!("]({new_reference})"))
+        .replace(&format!("]({reference} "), &format!("]({new_reference} "))
+        .replace(
+            &format!("src=\"{reference}\""),
+            &format!("src=\"{new_reference}\""),
+        )
+        .replace(
+            &format!("src='{reference}'"),
+            &format!("src='{new_reference}'"),
+        )
+}
+
+/// `slides` with the `settings` in their headmatter, the front matter of the first slide.
+/// Settings that the headmatter has are replaced, along with their nested values, and the
+/// others are added at its end.
+fn set_headmatter(slides: &str, settings: &[(&str, String)]) -> String {
+    if settings.is_empty() {
+        return slides.to_string();
+    }
+    let (headmatter, rest) = split_headmatter(slides);
+    let mut headmatter: Vec<String> = headmatter.into_iter().map(String::from).collect();
+    for (key, value) in settings {
+        let value = serde_yaml::to_string(value).unwrap();
+        let setting = format!("{key}: {}", value.trim_end());
+        let prefix = format!("{key}:");
+        match headmatter.iter().position(|line| line.starts_with(&prefix)) {
+            Some(start) => {
+                let nested = headmatter[start + 1..]
+                    .iter()
+                    .take_while(|line| line.starts_with([' ', '\t']))
+                    .count();
+                headmatter.splice(start..=start + nested, [setting]);
+            }
+            None => headmatter.push(setting),
+        }
+    }
+    format!("---\n{}\n---\n{rest}", headmatter.join("\n"))
+}
+
+/// `markdown` with the images that modmod puts into the slides package referred to by
+/// `images_dir`, a path relative to the file of the markdown, for documents that have no
+/// package root for absolute paths to point into
+pub(crate) fn relative_images(markdown: &str, images_dir: &str) -> String {
+    ["](", "src=\"", "src='", "data-background-image=\""]
+        .iter()
+        .fold(markdown.to_string(), |markdown, prefix| {
+            markdown.replace(
+                &format!("{prefix}/images/"),
+                &format!("{prefix}{images_dir}"),
+            )
+        })
+}
+
+/// The lines of the headmatter of `slides`, if they have one, and the slides after it.
+/// Without a headmatter, a slide separator on the first line isn't part of the slides after it.
+pub(crate) fn split_headmatter(slides: &str) -> (Vec<&str>, &str) {
+    let mut lines = slides.split_inclusive('\n');
+    let first = lines.next().unwrap_or_default();
+    if first.trim_end() != "---" {
+        return (vec![], slides);
+    }
+    let mut length = first.len();
+    let mut block: Vec<&str> = vec![];
+    for line in lines {
+        length += line.len();
+        if line.trim_end() == "---" {
+            if block.iter().all(|line| is_yaml(line)) {
+                return (block, &slides[length..]);
+            }
+            break;
+        }
+        block.push(line.trim_end());
+    }
+    (vec![], &slides[first.len()..])
+}
+
+/// Whether `line` can be part of a YAML headmatter: `key: value`, nested under a key, or empty,
+/// unlike markdown like `# Title` that's on a slide
+fn is_yaml(line: &str) -> bool {
+    let key = line.split_once(':').map_or("", |(key, _)| key);
+    line.trim().is_empty()
+        || line.starts_with([' ', '\t', '-'])
+        || (!key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-'))
+}
+
+/// The first paragraph of an exercise description that's text, on one line. Headings,
+/// placeholders, code, images and HTML are skipped.
+fn description_summary(description: &str) -> String {
+    let mut in_code = false;
+    let mut paragraph: Vec<&str> = vec![];
+    for line in description.lines().map(str::trim) {
+        if line.starts_with("```") || line.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if line.is_empty() || in_code {
+            if !paragraph.is_empty() {
+                break;
+            }
+            continue;
+        }
+        let is_text = !["#", "<", "![", "|", "---"]
+            .iter()
+            .any(|prefix| line.starts_with(prefix));
+        if is_text {
+            paragraph.push(line);
+        } else if !paragraph.is_empty() {
+            break;
+        }
+    }
+    paragraph.join(" ")
+}
+
+/// Turn items that are nothing but a URL into a link
+fn linkify(item: &str) -> String {
+    let is_url = (item.starts_with("https://") || item.starts_with("http://"))
+        && !item.contains(char::is_whitespace);
+    if is_url {
+        format!("[{item}]({item})")
+    } else {
+        item.to_string()
+    }
+}
+
+/// Write the QR code of `path` into the slide images, returning the markup to show it
+fn write_qr_code(
+    qr_codes: &QrCodes,
+    path: &Path,
+    slide_images_dir: &Path,
+    name: &str,
+    output: &Output,
+) -> Result<String, RenderSlidesError> {
+    let svg = qr_codes.svg(path).change_context(RenderSlidesError)?;
+    let qr_dir = slide_images_dir.join("qr");
+    output.create_dir_all(&qr_dir)?;
+    output
+        .create_file(qr_dir.join(name).with_extension("svg"))?
+        .write_all(svg)?;
+    Ok(format!(
+        "<img src=\"/images/qr/{name}.svg\" class=\"mx-auto h-64\" />"
+    ))
+}
+
+impl SlidesPackage<'_> {
+    /// Render the `template` of `deck`, or the default slides template, with `context`
+    fn render_template(
+        &self,
+        deck: &SlideDeck,
+        template: Option<&Path>,
+        context: &TemplateContext,
+    ) -> Result<String, RenderSlidesError> {
+        let template_content = template
+            .map(|t| t.read_to_string())
+            .unwrap_or(Ok(SLIDES_TEMPLATE_DEFAULT.to_string()))?;
+        let template_path = template.map_or("the default slides template".into(), |t| {
+            t.to_string_lossy().to_string()
+        });
+        let template_error = |problem: String| {
+            Report::new(RenderSlidesError).attach_printable(format!("{problem} in {template_path}"))
+        };
+        let template_content =
+            expand_variables(&template_content, &self.variables).map_err(template_error)?;
+        let rendered = Template::parse(&template_content, &TEMPLATE_PLACEHOLDERS)
+            .and_then(|template| template.render(context))
+            .map_err(template_error)?;
+        // Placeholders in topic slides aren't expanded
+        if let Some(placeholder) = unresolved_placeholder(&rendered) {
+            return Err(Report::new(RenderSlidesError).attach_printable(format!(
+                "Placeholder '{placeholder}' is left in the slides of unit '{}', rendered from {template_path}",
+                deck.name
+            )));
+        }
+        Ok(unescape_placeholders(&rendered))
     }
 }
 
@@ -153,16 +840,31 @@ pub struct SlideDeck<'track> {
     module_index: usize,
     unit_index: usize,
     template: Option<&'track Path>,
+    /// Templates of other documents than the slides, with the names of their outputs
+    extra_templates: Vec<(&'track Path, &'track str)>,
+    /// Settings of the deck, which take precedence over those of the package
+    config: Option<&'track SlidesConfig>,
+    /// Whether to normalize the headings of the topics, instead of what the package does
+    normalize_headings: Option<bool>,
+    module_objectives: Vec<&'track str>,
     sections: Vec<Section<'track>>,
+    draft: bool,
 }
 
 #[derive(Debug)]
 pub struct Section<'track> {
-    content: &'track Path,
+    /// Name of the topic
+    name: &'track str,
+    content: &'track TopicContent,
     objectives: Vec<&'track str>,
     summary: Vec<&'track str>,
     further_reading: Vec<&'track str>,
     images: Vec<&'track Path>,
+    /// Names, paths and description files of the exercises of the topic
+    exercises: Vec<(&'track str, &'track Path, &'track Path)>,
+    /// Speaker notes of the topic, only set in the instructor edition
+    instructor_notes: Option<&'track Path>,
+    draft: bool,
 }
 
 pub struct SlidesPackageBuilder<'track> {
@@ -186,11 +888,40 @@ impl<'track> SlidesPackageBuilder<'track> {
                 module_index,
                 unit_index,
                 template,
+                extra_templates: vec![],
+                config: None,
+                normalize_headings: None,
+                module_objectives: vec![],
                 sections: vec![],
+                draft: false,
             },
         }
     }
 
+    pub fn track_id(&mut self, track_id: String) {
+        self.package.track_id = track_id;
+    }
+
+    pub fn variable(&mut self, name: &'track str, value: &'track str) {
+        self.package.variables.insert(name, value);
+    }
+
+    pub fn language(&mut self, language: &'track str) {
+        self.package.language = Some(language);
+    }
+
+    pub fn handouts(&mut self) {
+        self.package.handouts = true;
+    }
+
+    pub fn instructor_edition(&mut self) {
+        self.package.instructor = true;
+    }
+
+    pub fn normalize_headings(&mut self) {
+        self.package.normalize_headings = true;
+    }
+
     pub fn build(self) -> SlidesPackage<'track> {
         self.package
     }
@@ -202,19 +933,47 @@ pub struct SlideDeckBuilder<'track, 'p> {
 }
 
 impl<'track, 'p> SlideDeckBuilder<'track, 'p> {
-    pub fn section(&mut self, content: &'track Path) -> SlidesSectionBuilder<'track, 'p, '_> {
+    pub fn config(&mut self, config: &'track SlidesConfig) {
+        self.slide_deck.config = Some(config);
+    }
+
+    pub fn normalize_headings(&mut self, normalize: bool) {
+        self.slide_deck.normalize_headings = Some(normalize);
+    }
+
+    pub fn extra_template(&mut self, template: &'track Path, output: &'track str) {
+        self.slide_deck.extra_templates.push((template, output));
+    }
+
+    pub fn section(
+        &mut self,
+        name: &'track str,
+        content: &'track TopicContent,
+    ) -> SlidesSectionBuilder<'track, 'p, '_> {
         SlidesSectionBuilder {
             deck_builder: self,
             section: Section {
+                name,
                 content,
                 objectives: vec![],
                 summary: vec![],
                 further_reading: vec![],
                 images: vec![],
+                exercises: vec![],
+                instructor_notes: None,
+                draft: false,
             },
         }
     }
 
+    pub fn module_objective(&mut self, objective: &'track str) {
+        self.slide_deck.module_objectives.push(objective);
+    }
+
+    pub fn draft(&mut self) {
+        self.slide_deck.draft = true;
+    }
+
     pub fn add(self) -> &'p mut SlidesPackageBuilder<'track> {
         self.package_builder.package.decks.push(self.slide_deck);
         self.package_builder
@@ -243,6 +1002,18 @@ impl<'track, 'p, 'd> SlidesSectionBuilder<'track, 'p, 'd> {
         self.section.images.push(image);
     }
 
+    pub fn exercise(&mut self, name: &'track str, path: &'track Path, description: &'track Path) {
+        self.section.exercises.push((name, path, description));
+    }
+
+    pub fn instructor_notes(&mut self, notes: &'track Path) {
+        self.section.instructor_notes = Some(notes);
+    }
+
+    pub fn draft(&mut self) {
+        self.section.draft = true;
+    }
+
     pub fn add(self) -> &'d mut SlideDeckBuilder<'track, 'p> {
         self.deck_builder.slide_deck.sections.push(self.section);
         self.deck_builder
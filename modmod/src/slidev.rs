@@ -0,0 +1,192 @@
+//! Building the rendered slide decks with the tools of their backends, like Slidev, so that
+//! the output has decks that can be viewed without Node tooling, or PDFs to hand out
+
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    str::FromStr,
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use crate::{output::Output, SlidesBackend};
+
+/// The package that Marp decks are built with, run with `npx`
+const MARP_CLI: &str = "@marp-team/marp-cli";
+/// The package that reveal.js decks are exported to PDF with, run with `npx`
+const DECKTAPE: &str = "decktape";
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct BuildSlidesError;
+
+impl fmt::Display for BuildSlidesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to build slides")
+    }
+}
+
+impl error_stack::Context for BuildSlidesError {}
+
+/// What the slide decks are built into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlidesFormat {
+    /// A static website per deck: in `slides/dist/<module>_<unit>` for Slidev, next to the
+    /// deck for Marp, and the deck itself for reveal.js
+    Html,
+    /// A PDF per deck, next to the deck as `<deck>-export.pdf`
+    Pdf,
+}
+
+impl SlidesFormat {
+    pub const ALL: [SlidesFormat; 2] = [SlidesFormat::Html, SlidesFormat::Pdf];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SlidesFormat::Html => "html",
+            SlidesFormat::Pdf => "pdf",
+        }
+    }
+
+    /// The script in the `package.json` of the slides that builds a deck into this format
+    fn script(self) -> &'static str {
+        match self {
+            SlidesFormat::Html => "build",
+            SlidesFormat::Pdf => "export",
+        }
+    }
+}
+
+impl fmt::Display for SlidesFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SlidesFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|format| format.name()).collect();
+                format!(
+                    "unknown slides format '{s}', expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Build the slide decks at `deck_paths`, relative to the output directory and by module
+/// and unit index, into `format` with the tools of the backend that `backends` has them
+/// rendered for:
+///
+/// - Slidev decks with the scripts of the `package.json` of the slides, installing Slidev
+///   with `npm install` first if it isn't yet
+/// - Marp decks with marp-cli, into `<deck>.html` or `<deck>-export.pdf` next to the deck
+/// - reveal.js decks, which are HTML already, into `<deck>-export.pdf` with decktape
+///
+/// PowerPoint presentations aren't built.
+pub(crate) fn build_slides(
+    output: &Output,
+    deck_paths: &HashMap<(usize, usize), PathBuf>,
+    backends: &HashMap<(usize, usize), SlidesBackend>,
+    format: SlidesFormat,
+) -> Result<(), BuildSlidesError> {
+    let mut decks: Vec<_> = deck_paths
+        .iter()
+        .map(|(deck, path)| (*deck, output.root().join(path), backends[deck]))
+        .collect();
+    decks.sort_by_key(|(deck, _, _)| *deck);
+    let slides_dir = output.root().join("slides");
+    let uses_slidev = decks
+        .iter()
+        .any(|(_, _, backend)| *backend == SlidesBackend::Slidev);
+    if uses_slidev && !slides_dir.join("node_modules").is_dir() {
+        output.check_cancelled::<BuildSlidesError>()?;
+        run(&slides_dir, "npm", &["install"])?;
+    }
+    if uses_slidev && format == SlidesFormat::Html {
+        output.create_dir_all(slides_dir.join("dist"))?;
+    }
+    // Builds take long enough to not run several at once
+    for ((module_index, unit_index), path, backend) in decks {
+        output.check_cancelled::<BuildSlidesError>()?;
+        let deck = path.to_string_lossy();
+        let pdf = pdf_path(&path);
+        match (backend, format) {
+            (SlidesBackend::Slidev, _) => {
+                let script = format!("{}-{module_index}_{unit_index}", format.script());
+                run(&slides_dir, "npm", &["run", &script])?;
+            }
+            (SlidesBackend::Marp, _) => {
+                let (flag, target) = match format {
+                    SlidesFormat::Html => ("--html", path.with_extension("html")),
+                    SlidesFormat::Pdf => ("--pdf", pdf),
+                };
+                let target = target.to_string_lossy();
+                // Slides show HTML, like QR codes, and local images
+                let args = [
+                    MARP_CLI,
+                    flag,
+                    "--html",
+                    "--allow-local-files",
+                    &deck,
+                    "-o",
+                    &target,
+                ];
+                run(&slides_dir, "npx", &args)?;
+            }
+            // Presentations can only be exported to PDF by PowerPoint
+            (SlidesBackend::Revealjs, SlidesFormat::Html) | (SlidesBackend::Pptx, _) => {}
+            (SlidesBackend::Revealjs, SlidesFormat::Pdf) => {
+                let url = format!(
+                    "file://{}",
+                    path.canonicalize()
+                        .unwrap_or(path.clone())
+                        .to_string_lossy()
+                );
+                run(
+                    &slides_dir,
+                    "npx",
+                    &[DECKTAPE, "reveal", &url, &pdf.to_string_lossy()],
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Where the PDF of the deck at `path` is exported to, next to it, as Slidev does
+fn pdf_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}-export.pdf"))
+}
+
+/// Run `program`, `npm` or `npx`, with `args` in `dir`
+fn run(dir: &Path, program: &str, args: &[&str]) -> Result<(), BuildSlidesError> {
+    let result = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .into_report()
+        .attach_printable(format!(
+            "Unable to run {program}, which is needed to build the slides"
+        ))
+        .change_context(BuildSlidesError)?;
+    if !result.status.success() {
+        return Err(Report::new(BuildSlidesError).attach_printable(format!(
+            "{program} {args} failed in {dir}: {stderr}",
+            args = args.join(" "),
+            dir = dir.to_string_lossy(),
+            stderr = String::from_utf8_lossy(&result.stderr).trim()
+        )));
+    }
+    Ok(())
+}
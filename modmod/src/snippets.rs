@@ -0,0 +1,226 @@
+//! Compiling the Rust code blocks on the slides of topics, the way rustdoc compiles
+//! doctests, so that slides don't show students code that doesn't compile
+
+use std::{
+    fmt, fs,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use error_stack::{IntoReport, Result, ResultExt};
+
+use crate::{
+    anchors::expand_includes,
+    codes::WarningCode,
+    conditional::{select_conditional, track_id},
+    io::PathExt,
+    parallel::parallel_map,
+    report::Warning,
+    select::{Edition, Selection},
+    stats::split_slides,
+    RenderOptions, TopicContent, Track,
+};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct CheckSnippetsError;
+
+impl fmt::Display for CheckSnippetsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to check code snippets")
+    }
+}
+
+impl error_stack::Context for CheckSnippetsError {}
+
+/// A Rust code block on a slide
+struct Snippet<'a> {
+    /// The topic file the snippet is in
+    path: &'a Path,
+    /// Number of the slide the snippet is on, counted from 1
+    slide: usize,
+    code: String,
+    /// Whether the code block is marked `compile_fail`
+    compile_fail: bool,
+    /// Codes of the warnings that are allowed for the unit of the topic
+    allow: &'a [WarningCode],
+}
+
+impl Track {
+    /// Compile every ```` ```rust ```` code block on the slides of the topics that a render
+    /// with `options` includes, with `rustc --edition 2021`, warning about every snippet that
+    /// doesn't compile. Snippets are wrapped like doctests: the `# ` that rustdoc hides lines
+    /// with is removed, and code without a `fn main` is put into one. Code blocks marked
+    /// `ignore` are skipped, and `compile_fail` ones have to fail to compile.
+    pub fn check_snippets(
+        &self,
+        options: &RenderOptions,
+    ) -> Result<Vec<Warning>, CheckSnippetsError> {
+        let Selection { track, .. } = self.select(options, Edition::Student);
+        let mut contents = vec![];
+        for unit in track.modules.iter().flat_map(|m| &m.data.units) {
+            for topic in unit.data.topics.iter().map(|t| &t.data) {
+                // Inline content has no file to point authors at, and is rarely code
+                let TopicContent::File(path) = &topic.content else {
+                    continue;
+                };
+                // Topics that are part of more than one unit are only checked once
+                if contents.iter().any(|(p, _, _)| p == path) {
+                    continue;
+                }
+                let content = path.read_to_string::<CheckSnippetsError>()?;
+                // Broken regions and include directives are reported by checking the track
+                let content =
+                    select_conditional(&content, &track_id(&self.path)).unwrap_or(content);
+                let content = expand_includes(&content, path.parent().unwrap()).unwrap_or(content);
+                contents.push((path.clone(), content, unit.data.allow.as_slice()));
+            }
+        }
+        let snippets: Vec<Snippet> = contents
+            .iter()
+            .flat_map(|(path, content, allow)| snippets(path, content, allow))
+            .collect();
+
+        let dir = std::env::temp_dir().join(format!("modmod-snippets-{}", std::process::id()));
+        fs::create_dir_all(&dir)
+            .into_report()
+            .change_context(CheckSnippetsError)?;
+        let indexed: Vec<_> = snippets.iter().enumerate().collect();
+        let results = parallel_map(&indexed, |(i, snippet)| {
+            compile(snippet, &dir.join(i.to_string()))
+        });
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut warnings = vec![];
+        for (snippet, result) in snippets.iter().zip(results) {
+            let problem = match (result?, snippet.compile_fail) {
+                (Some(error), false) => format!("doesn't compile: {error}"),
+                (None, true) => "is marked compile_fail, but compiles".to_string(),
+                _ => continue,
+            };
+            warnings.push(
+                Warning::new(
+                    WarningCode::FailingSnippet,
+                    format!(
+                        "Rust snippet on slide {slide} of {path} {problem}",
+                        slide = snippet.slide,
+                        path = snippet.path.to_string_lossy()
+                    ),
+                )
+                .allowed_by(snippet.allow)
+                .allowed_by(&track.allow),
+            );
+        }
+        Ok(warnings)
+    }
+}
+
+/// The Rust code blocks in the slides `content` of the topic file at `path`
+fn snippets<'a>(path: &'a Path, content: &str, allow: &'a [WarningCode]) -> Vec<Snippet<'a>> {
+    let mut snippets = vec![];
+    for (slide, number) in split_slides(content).into_iter().zip(1..) {
+        // Attributes and code of the code block we're in, if any
+        let mut block: Option<(Vec<&str>, String)> = None;
+        for line in slide {
+            let trimmed = line.trim();
+            match block.as_mut() {
+                None => {
+                    if let Some(info) = trimmed.strip_prefix("```") {
+                        block = Some((info_attributes(info), String::new()));
+                    }
+                }
+                Some((attributes, code)) if trimmed.starts_with("```") => {
+                    let is_rust = attributes.first() == Some(&"rust");
+                    if is_rust && !attributes.contains(&"ignore") {
+                        snippets.push(Snippet {
+                            path,
+                            slide: number,
+                            code: doctest(code),
+                            compile_fail: attributes.contains(&"compile_fail"),
+                            allow,
+                        });
+                    }
+                    block = None;
+                }
+                Some((_, code)) => {
+                    code.push_str(line);
+                    code.push('\n');
+                }
+            }
+        }
+    }
+    snippets
+}
+
+/// The language and attributes in the info string of a code block, like `rust,ignore`.
+/// Slidev options like line highlights in `{1|3}` are left out.
+fn info_attributes(info: &str) -> Vec<&str> {
+    let info = info.split('{').next().unwrap();
+    info.split([',', ' ', '\t'])
+        .filter(|attribute| !attribute.is_empty())
+        .collect()
+}
+
+/// The code of a snippet as rustdoc compiles it: with the lines that are hidden with `# `
+/// shown again, crate attributes at the top, and in a `fn main` if it has none
+fn doctest(code: &str) -> String {
+    let mut attributes = String::from("#![allow(unused)]\n");
+    let mut body = String::new();
+    for line in code.lines() {
+        let line = match line.trim_start() {
+            "#" => "",
+            trimmed => trimmed.strip_prefix("# ").map_or(line, |_| &trimmed[2..]),
+        };
+        if line.trim_start().starts_with("#![") {
+            attributes.push_str(line);
+            attributes.push('\n');
+        } else {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+    if body.contains("fn main") {
+        format!("{attributes}{body}")
+    } else {
+        format!("{attributes}fn main() {{\n{body}}}\n")
+    }
+}
+
+/// Compile `snippet` into the directory `dir`, returning the first error if it fails
+fn compile(snippet: &Snippet, dir: &Path) -> Result<Option<String>, CheckSnippetsError> {
+    fs::create_dir_all(dir)
+        .into_report()
+        .change_context(CheckSnippetsError)?;
+    let source = dir.join("snippet.rs");
+    fs::write(&source, &snippet.code)
+        .into_report()
+        .change_context(CheckSnippetsError)?;
+    // Only checking the code is much faster than building it
+    let result = Command::new("rustc")
+        .args([
+            "--edition",
+            "2021",
+            "--crate-name",
+            "snippet",
+            "--emit",
+            "metadata",
+        ])
+        .arg("--out-dir")
+        .arg(dir)
+        .arg(&source)
+        .stdin(Stdio::null())
+        .output()
+        .into_report()
+        .attach_printable("Unable to run rustc, which is needed to check code snippets")
+        .change_context(CheckSnippetsError)?;
+    if result.status.success() {
+        return Ok(None);
+    }
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    let error = stderr
+        .lines()
+        .find(|line| line.starts_with("error"))
+        .unwrap_or("rustc failed")
+        .to_string();
+    Ok(Some(error))
+}
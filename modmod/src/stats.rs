@@ -0,0 +1,180 @@
+//! Statistics of the units and modules of a track, to spot units that hold
+//! too much content for a single session
+
+use std::ops::AddAssign;
+
+use error_stack::{Result, ResultExt};
+
+use crate::{
+    exercises, io::PathExt, select::Edition, LoadTrackError, RenderOptions, Topic, TopicContent,
+    Track,
+};
+
+/// How much content a part of a track holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// Slides of the topics, which are separated by `---` lines
+    pub slides: usize,
+    /// Words on the slides of the topics, leaving out code blocks and slide settings
+    pub words: usize,
+    pub exercises: usize,
+    /// Files that are copied into the exercises
+    pub files: usize,
+    /// How long teaching the topics and doing the exercises takes, counting
+    /// the ones with a `duration_minutes`
+    pub minutes: u32,
+}
+
+impl AddAssign for Counts {
+    fn add_assign(&mut self, other: Self) {
+        self.slides += other.slides;
+        self.words += other.words;
+        self.exercises += other.exercises;
+        self.files += other.files;
+        self.minutes += other.minutes;
+    }
+}
+
+#[derive(Debug)]
+pub struct TrackStats {
+    pub name: String,
+    pub counts: Counts,
+    pub modules: Vec<ModuleStats>,
+}
+
+#[derive(Debug)]
+pub struct ModuleStats {
+    pub index: usize,
+    pub name: String,
+    pub counts: Counts,
+    pub units: Vec<UnitStats>,
+}
+
+#[derive(Debug)]
+pub struct UnitStats {
+    pub index: usize,
+    pub name: String,
+    pub counts: Counts,
+}
+
+impl Track {
+    /// Count the slides, words, exercises, exercise files and planned minutes of every unit
+    /// and module that a render with `options` contains
+    pub fn stats(&self, options: &RenderOptions) -> Result<TrackStats, LoadTrackError> {
+        let track = self.select(options, Edition::Student).track;
+        let mut stats = TrackStats {
+            name: track.name.clone(),
+            counts: Counts::default(),
+            modules: vec![],
+        };
+        for module in track.modules.iter() {
+            let mut module_stats = ModuleStats {
+                index: module.index,
+                name: module.data.name.clone(),
+                counts: Counts::default(),
+                units: vec![],
+            };
+            for unit in module.data.units.iter() {
+                let mut counts = Counts::default();
+                for topic in unit.data.topics.iter() {
+                    counts += topic_counts(&topic.data)?;
+                }
+                module_stats.counts += counts;
+                module_stats.units.push(UnitStats {
+                    index: unit.index,
+                    name: unit.data.name.clone(),
+                    counts,
+                });
+            }
+            stats.counts += module_stats.counts;
+            stats.modules.push(module_stats);
+        }
+        Ok(stats)
+    }
+}
+
+fn topic_counts(topic: &Topic) -> Result<Counts, LoadTrackError> {
+    let content = match &topic.content {
+        TopicContent::File(path) => path.read_to_string::<LoadTrackError>()?,
+        TopicContent::Inline(content) => content.clone(),
+    };
+    let (slides, words) = count_slides(&content);
+    let mut files = 0;
+    for exercise in topic.exercises.iter() {
+        let exercise = &exercise.data;
+        files += exercises::included_files(&exercise.path, &exercise.includes, &exercise.excludes)
+            .change_context(LoadTrackError)?
+            .len();
+    }
+    Ok(Counts {
+        slides,
+        words,
+        exercises: topic.exercises.len(),
+        files,
+        minutes: topic.planned_minutes().unwrap_or(0),
+    })
+}
+
+/// The number of slides in `content`, and the number of words on them, leaving out
+/// code blocks. See [split_slides] for what makes a slide.
+fn count_slides(content: &str) -> (usize, usize) {
+    let slides = split_slides(content);
+    let mut words = 0;
+    for slide in slides.iter() {
+        let mut in_code = false;
+        for line in slide.iter().map(|line| line.trim()) {
+            if line.starts_with("```") || line.starts_with("~~~") {
+                in_code = !in_code;
+            } else if !in_code {
+                // Markdown markers like `#` and `-` are not words
+                words += line
+                    .split_whitespace()
+                    .filter(|word| word.chars().any(char::is_alphanumeric))
+                    .count();
+            }
+        }
+    }
+    (slides.len(), words)
+}
+
+/// The lines of the slides in `content`, which are separated by `---` lines. A `---` line
+/// right after a separator, with only `key: value` lines in between, closes the settings
+/// of the slide instead, and the settings aren't part of its lines. Slides without any
+/// content, like before the first separator, are left out.
+pub(crate) fn split_slides(content: &str) -> Vec<Vec<&str>> {
+    let mut slides = vec![];
+    let mut slide = vec![];
+    let mut in_code = false;
+    let mut has_content = false;
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            has_content = true;
+        } else if !in_code && trimmed == "---" {
+            if has_content {
+                slides.push(std::mem::take(&mut slide));
+            }
+            // Empty lines before the separator aren't part of the next slide either
+            slide.clear();
+            has_content = false;
+            let settings: Vec<&str> = lines
+                .clone()
+                .take_while(|l| l.trim() != "---" && !l.trim().is_empty())
+                .collect();
+            let closed = lines.clone().nth(settings.len()).map(str::trim) == Some("---");
+            if closed && settings.iter().all(|l| l.contains(':')) {
+                lines.nth(settings.len());
+            }
+            continue;
+        } else if !trimmed.is_empty() {
+            has_content = true;
+        }
+        slide.push(line);
+    }
+    if has_content {
+        slides.push(slide);
+    }
+    slides
+}
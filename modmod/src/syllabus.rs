@@ -0,0 +1,143 @@
+//! A one-page overview of a track, for prospective students and course approval,
+//! with the objectives and exercises of every module and unit
+
+use std::{
+    fmt::{self, Write},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+
+use crate::{schedule::format_minutes, select::Edition, RenderOptions, Track};
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct SyllabusError;
+
+impl fmt::Display for SyllabusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to create syllabus")
+    }
+}
+
+impl error_stack::Context for SyllabusError {}
+
+impl Track {
+    /// The syllabus of the part of the track that a render with `options` contains, in markdown
+    pub fn syllabus(&self, options: &RenderOptions) -> String {
+        let track = self.select(options, Edition::Student).track;
+        let outline = track.outline();
+        let mut syllabus = format!("# {}\n\n", outline.name);
+        if let Some(description) = &track.description {
+            writeln!(syllabus, "{}\n", description.trim()).unwrap();
+        }
+        if !track.authors.is_empty() {
+            writeln!(syllabus, "By {}\n", track.authors.join(", ")).unwrap();
+        }
+        let unit_count: usize = outline.modules.iter().map(|m| m.units.len()).sum();
+        let exercise_count: usize = outline.modules.iter().map(|m| m.exercise_count).sum();
+        write!(
+            syllabus,
+            "{}, {} and {}",
+            count(outline.modules.len(), "module"),
+            count(unit_count, "unit"),
+            count(exercise_count, "exercise")
+        )
+        .unwrap();
+        let minutes = track
+            .modules
+            .iter()
+            .filter_map(|m| m.data.planned_minutes());
+        match minutes.reduce(|a, b| a + b) {
+            Some(minutes) => writeln!(syllabus, ", taking about {}.\n", format_minutes(minutes)),
+            None => writeln!(syllabus, ".\n"),
+        }
+        .unwrap();
+
+        for (module, outline_module) in track.modules.iter().zip(outline.modules.iter()) {
+            writeln!(
+                syllabus,
+                "## Module {} - {}\n",
+                module.index, module.data.name
+            )
+            .unwrap();
+            if !module.data.description.trim().is_empty() {
+                writeln!(syllabus, "{}\n", module.data.description.trim()).unwrap();
+            }
+            if !module.data.objectives.is_empty() {
+                syllabus.push_str("Objectives:\n\n");
+                for objective in module.data.objectives.iter() {
+                    writeln!(syllabus, "- {}", objective.trim()).unwrap();
+                }
+                syllabus.push('\n');
+            }
+
+            for (unit, outline_unit) in module.data.units.iter().zip(outline_module.units.iter()) {
+                let duration = unit
+                    .data
+                    .planned_minutes()
+                    .map(|minutes| format!(" ({})", format_minutes(minutes)))
+                    .unwrap_or_default();
+                writeln!(
+                    syllabus,
+                    "### Unit {}.{} - {}{duration}\n",
+                    module.index, unit.index, unit.data.name
+                )
+                .unwrap();
+                for objective in outline_unit.objectives.iter() {
+                    writeln!(syllabus, "- {}", objective.trim()).unwrap();
+                }
+                if !outline_unit.objectives.is_empty() {
+                    syllabus.push('\n');
+                }
+                // Exercises are numbered within their unit, like in the book
+                let exercises: Vec<_> = outline_unit
+                    .topics
+                    .iter()
+                    .flat_map(|t| &t.exercises)
+                    .zip(1..)
+                    .map(|(exercise, index)| {
+                        format!("{}.{}.{index} {}", module.index, unit.index, exercise.name)
+                    })
+                    .collect();
+                if !exercises.is_empty() {
+                    writeln!(syllabus, "Exercises: {}\n", exercises.join(", ")).unwrap();
+                }
+            }
+        }
+        syllabus
+    }
+}
+
+fn count(count: usize, noun: &str) -> String {
+    match count {
+        1 => format!("1 {noun}"),
+        count => format!("{count} {noun}s"),
+    }
+}
+
+/// Convert the markdown `syllabus` to a PDF at `path` with pandoc, which has to be installed
+pub fn syllabus_pdf(syllabus: &str, path: &Path) -> Result<(), SyllabusError> {
+    let mut child = Command::new("pandoc")
+        .args(["--from", "markdown", "--output"])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .into_report()
+        .attach_printable("Unable to run pandoc, which is needed to create PDFs")
+        .change_context(SyllabusError)?;
+    std::io::Write::write_all(&mut child.stdin.take().unwrap(), syllabus.as_bytes())
+        .into_report()
+        .change_context(SyllabusError)?;
+    let output = child
+        .wait_with_output()
+        .into_report()
+        .change_context(SyllabusError)?;
+    if !output.status.success() {
+        return Err(Report::new(SyllabusError)
+            .attach_printable(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    Ok(())
+}
@@ -0,0 +1,392 @@
+//! Slides templates, which are [Tera](https://keats.github.io/tera/docs/) templates:
+//!
+//! - `{{ unit.title }}` shows a value of the context, and `{{ objectives | list }}` a list of
+//!   strings as a markdown list
+//! - `{% for topic in topics %}...{% endfor %}` repeats its body for every item of a list,
+//!   which can tell where it is with `loop.index`, `loop.first` and `loop.last`
+//! - `{% if exercises %}...{% else %}...{% endif %}` only shows its body if the value is
+//!   there, and not empty, `false` or `0`
+//! - `{% raw %}...{% endraw %}` shows its body as it is, like Vue's
+//!   `{{ $slidev.nav.currentPage }}`
+//!
+//! The `#[modmod:...]` placeholders of earlier versions are shorthands for expressions,
+//! see [Template::parse]. An escaped placeholder like `#[[modmod:content]]` isn't one, and
+//! is shown as `#[modmod:content]` by [unescape_placeholders]. Variables of the track, like
+//! `#[modmod:var(trainer)]`, are expanded before parsing, by [expand_variables].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+};
+
+use serde::Serialize;
+use tera::{Tera, Value};
+
+/// A parsed template
+#[derive(Debug)]
+pub(crate) struct Template {
+    tera: Tera,
+}
+
+/// Name of the template in its [Tera] instance, which errors refer to
+const TEMPLATE_NAME: &str = "template";
+
+const PLACEHOLDER_PREFIX: &str = "#[modmod:";
+/// Starts the name of placeholders that refer to variables, like `#[modmod:var(trainer)]`
+const VARIABLE_PREFIX: &str = "var(";
+/// Starts placeholders that are escaped, like `#[[modmod:content]]`, to show them as text
+const ESCAPED_PREFIX: &str = "#[[modmod:";
+
+impl Template {
+    /// Parse `source`. Each of the `placeholders`, like `("objectives", "objectives | list")`,
+    /// makes `#[modmod:objectives]` a shorthand for `{{ objectives | list }}`. Placeholders
+    /// that aren't one of them are an error.
+    pub(crate) fn parse(source: &str, placeholders: &[(&str, &str)]) -> Result<Self, String> {
+        let source = expand_placeholders(source, placeholders)?;
+        let mut tera = Tera::default();
+        tera.register_filter("list", list);
+        tera.add_raw_template(TEMPLATE_NAME, &source)
+            .map_err(|e| describe(&e))?;
+        Ok(Template { tera })
+    }
+
+    /// Render the template with the values in `context`, which serializes to a map
+    pub(crate) fn render(&self, context: &impl Serialize) -> Result<String, String> {
+        let context = tera::Context::from_serialize(context).map_err(|e| describe(&e))?;
+        self.tera
+            .render(TEMPLATE_NAME, &context)
+            .map_err(|e| describe(&e))
+    }
+}
+
+/// `source` with its `#[modmod:...]` placeholders replaced by the expressions they're
+/// shorthands for
+fn expand_placeholders(source: &str, placeholders: &[(&str, &str)]) -> Result<String, String> {
+    let mut expanded = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find(PLACEHOLDER_PREFIX) {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let line = source[..source.len() - rest.len()].matches('\n').count() + 1;
+        let (name, closed) = placeholder_name(rest);
+        if !closed {
+            return Err(format!(
+                "Placeholder on line {line} is never closed with ']'"
+            ));
+        }
+        let Some((_, expression)) = placeholders.iter().find(|(p, _)| *p == name) else {
+            let known: Vec<_> = placeholders
+                .iter()
+                .map(|(p, _)| format!("{PLACEHOLDER_PREFIX}{p}]"))
+                .collect();
+            return Err(format!(
+                "Unknown placeholder '{PLACEHOLDER_PREFIX}{name}]' on line {line}, known placeholders are {}",
+                known.join(", ")
+            ));
+        };
+        expanded.push_str(&format!("{{{{ {expression} }}}}"));
+        rest = &rest[PLACEHOLDER_PREFIX.len() + name.len() + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// The `list` filter, which shows a list of values as a markdown list
+fn list(value: &Value, _: &HashMap<String, Value>) -> tera::Result<Value> {
+    let Value::Array(items) = value else {
+        return Err(tera::Error::msg(format!(
+            "Filter `list` expects a list, got {value}"
+        )));
+    };
+    let mut list = String::new();
+    for item in items {
+        match item {
+            Value::String(item) => list += &format!("- {item}\n"),
+            item => list += &format!("- {item}\n"),
+        }
+    }
+    Ok(Value::String(list))
+}
+
+/// The problem that Tera reports, with the problems it follows from
+fn describe(error: &tera::Error) -> String {
+    let mut messages = vec![error.to_string()];
+    let mut source = error.source();
+    while let Some(error) = source {
+        messages.push(error.to_string());
+        source = error.source();
+    }
+    messages.join(": ")
+}
+
+/// The first placeholder in `rendered` text, like `#[modmod:objectives]`, if any. Rendered
+/// text should have none left.
+pub(crate) fn unresolved_placeholder(rendered: &str) -> Option<String> {
+    let start = rendered.find(PLACEHOLDER_PREFIX)?;
+    let (name, _) = placeholder_name(&rendered[start..]);
+    Some(format!("{PLACEHOLDER_PREFIX}{name}]"))
+}
+
+/// Replace escaped placeholders like `#[[modmod:content]]` in `rendered` text by the
+/// placeholders they show, like `#[modmod:content]`. This is the last step of rendering, as
+/// the placeholders are text from then on.
+pub(crate) fn unescape_placeholders(rendered: &str) -> String {
+    let mut unescaped = String::with_capacity(rendered.len());
+    let mut rest = rendered;
+    while let Some(start) = rest.find(ESCAPED_PREFIX) {
+        unescaped.push_str(&rest[..start]);
+        unescaped.push_str(PLACEHOLDER_PREFIX);
+        rest = &rest[start + ESCAPED_PREFIX.len()..];
+        // The placeholder ends at the first `]]`, of which one `]` was part of the escape
+        if let Some(end) = rest.find("]]") {
+            unescaped.push_str(&rest[..end + 1]);
+            rest = &rest[end + 2..];
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Replace the `#[modmod:var(name)]` references in `content` by the values of the
+/// `variables` of the track, or tell which one isn't defined
+pub(crate) fn expand_variables(
+    content: &str,
+    variables: &BTreeMap<&str, &str>,
+) -> Result<String, String> {
+    let reference = format!("{PLACEHOLDER_PREFIX}{VARIABLE_PREFIX}");
+    let mut expanded = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find(&reference) {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let (name, closed) = placeholder_name(rest);
+        if !closed {
+            let line = content[..content.len() - rest.len()].matches('\n').count() + 1;
+            return Err(format!(
+                "Variable reference on line {line} is never closed with ']'"
+            ));
+        }
+        let Some(variable) = name
+            .strip_prefix(VARIABLE_PREFIX)
+            .and_then(|name| name.strip_suffix(')'))
+            .map(str::trim)
+        else {
+            return Err(format!(
+                "Variable reference '{PLACEHOLDER_PREFIX}{name}]' is not like '{reference}name)]'"
+            ));
+        };
+        let Some(value) = variables.get(variable) else {
+            let defined = match variables.is_empty() {
+                true => "the track defines no variables".to_string(),
+                false => {
+                    let names: Vec<_> = variables.keys().copied().collect();
+                    format!("the track defines {}", names.join(", "))
+                }
+            };
+            return Err(format!("Unknown variable '{variable}', {defined}"));
+        };
+        expanded.push_str(value);
+        rest = &rest[PLACEHOLDER_PREFIX.len() + name.len() + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// The name of the placeholder that `text` starts with, up to the `]` that ends it or the
+/// end of the line, and whether it ends with a `]`
+fn placeholder_name(text: &str) -> (&str, bool) {
+    let name = &text[PLACEHOLDER_PREFIX.len()..];
+    match name.find([']', '\n']) {
+        Some(end) => (&name[..end], name[end..].starts_with(']')),
+        None => (name, false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(source: &str, context: Value) -> Result<String, String> {
+        Template::parse(source, &[("content", "content")])?.render(&context)
+    }
+
+    #[test]
+    fn unclosed_placeholder() {
+        for source in [
+            "---\n---\n#[modmod:content",
+            "---\n---\n#[modmod:content\nx\n",
+        ] {
+            let error = render(source, json!({"content": "x"})).unwrap_err();
+            assert_eq!(error, "Placeholder on line 3 is never closed with ']'");
+        }
+        assert_eq!(
+            render("a #[modmod:content] b", json!({"content": "x"})).unwrap(),
+            "a x b"
+        );
+        // Escaped placeholders are text, which is unescaped after rendering
+        assert_eq!(
+            render("#[[modmod:content]]", json!({"content": "x"})).unwrap(),
+            "#[[modmod:content]]"
+        );
+    }
+
+    #[test]
+    fn unclosed_variable_reference() {
+        let variables = BTreeMap::from([("trainer", "Ferris")]);
+        assert_eq!(
+            expand_variables("Hi #[modmod:var(trainer)]!", &variables).unwrap(),
+            "Hi Ferris!"
+        );
+        for content in ["Hi #[modmod:var(trainer)", "Hi #[modmod:var(trainer)\n"] {
+            assert_eq!(
+                expand_variables(content, &variables).unwrap_err(),
+                "Variable reference on line 1 is never closed with ']'"
+            );
+        }
+    }
+
+    #[test]
+    fn values_and_filters() {
+        let context = json!({"unit": {"title": "Traits", "index": 2}, "objectives": ["a", "b"]});
+        assert_eq!(
+            render("# {{ unit.title }} ({{unit.index}})", context.clone()).unwrap(),
+            "# Traits (2)"
+        );
+        assert_eq!(
+            render("{{ objectives | list }}", context).unwrap(),
+            "- a\n- b\n"
+        );
+    }
+
+    #[test]
+    fn whitespace_control() {
+        let context = json!({"items": ["a", "b"], "x": "a"});
+        assert_eq!(
+            render(
+                "{% for i in items %}\n  {{ i }}\n{% endfor %}",
+                context.clone()
+            )
+            .unwrap(),
+            "\n  a\n\n  b\n"
+        );
+        assert_eq!(
+            render(
+                "{% for i in items -%}\n  {{ i }}\n{%- endfor %}",
+                context.clone()
+            )
+            .unwrap(),
+            "ab"
+        );
+        assert_eq!(
+            render("x  {{- x -}}  y {# note -#}\n z", context).unwrap(),
+            "xay z"
+        );
+    }
+
+    #[test]
+    fn nested_statements() {
+        let context = json!({"topics": [
+            {"name": "Traits", "exercises": ["Shapes", "Iterators"]},
+            {"name": "Generics", "exercises": []},
+        ]});
+        let source = "{% for topic in topics %}{{ topic.name }}:\
+            {% if topic.exercises %}{% for e in topic.exercises %} {{ e }}{% endfor %}\
+            {% else %} none{% endif %};{% endfor %}";
+        assert_eq!(
+            render(source, context).unwrap(),
+            "Traits: Shapes Iterators;Generics: none;"
+        );
+    }
+
+    #[test]
+    fn loop_variables() {
+        let context = json!({"outer": [1, 2], "inner": ["a", "b", "c"]});
+        let source = "{% for i in inner %}{% if loop.first %}[{% endif %}{{ loop.index }}{{ i }}\
+            {% if not loop.last %},{% else %}]{% endif %}{% endfor %}";
+        assert_eq!(render(source, context.clone()).unwrap(), "[1a,2b,3c]");
+        // The loop variables are those of the innermost loop
+        let source = "{% for o in outer %}{% for i in inner %}{% if loop.last %}{{ o }}\
+            {{ loop.index }}{% endif %}{% endfor %}{{ loop.index }} {% endfor %}";
+        assert_eq!(render(source, context).unwrap(), "131 232 ");
+    }
+
+    #[test]
+    fn conditions() {
+        let context = json!({"empty": [], "zero": 0, "no": false, "text": "x", "list": [1]});
+        for value in ["empty", "zero", "no", "missing"] {
+            let source = format!("{{% if {value} %}}yes{{% else %}}no{{% endif %}}");
+            assert_eq!(render(&source, context.clone()).unwrap(), "no", "{value}");
+        }
+        for value in ["text", "list"] {
+            let source = format!("{{% if not {value} %}}no{{% else %}}yes{{% endif %}}");
+            assert_eq!(render(&source, context.clone()).unwrap(), "yes", "{value}");
+        }
+    }
+
+    #[test]
+    fn raw_blocks() {
+        let context = json!({"x": "x"});
+        assert_eq!(
+            render(
+                "{% raw %}{{ $slidev.nav.currentPage }} {% if %}{% endraw %}",
+                context.clone()
+            )
+            .unwrap(),
+            "{{ $slidev.nav.currentPage }} {% if %}"
+        );
+        // Only a whole tag ends the block, not the word in it
+        assert_eq!(
+            render("{% raw %}say endraw{% endraw %}", context.clone()).unwrap(),
+            "say endraw"
+        );
+        assert_eq!(
+            render(
+                "{% raw -%}  a {% endraw b %}  {%- endraw -%}  {{ x }}",
+                context.clone()
+            )
+            .unwrap(),
+            "a {% endraw b %}x"
+        );
+        let error = render("{% raw %}a\n{{ x }}", context).unwrap_err();
+        assert!(
+            error.starts_with("Failed to parse 'template':  --> 2:8"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let context = json!({"unit": {"title": "Traits"}, "topics": [{"name": "Traits"}]});
+        // Tera tells where templates don't parse
+        for (source, position) in [
+            ("a\n{{ unit.title", "2:14"),
+            ("{% for t in topics %}", "1:22"),
+            ("{% if topics %}", "1:16"),
+            ("\n{% endfor %}", "2:1"),
+            ("{% while topics %}", "1:1"),
+        ] {
+            let error = render(source, context.clone()).unwrap_err();
+            let expected = format!("Failed to parse 'template':  --> {position}\n");
+            assert!(error.starts_with(&expected), "{source}: {error}");
+        }
+        for (source, error) in [
+            ("{{ topics | nope }}", "Failed to render 'template': Filter 'nope' not found"),
+            (
+                "{{ unit.name }}",
+                "Failed to render 'template': Variable `unit.name` not found in context while rendering 'template'",
+            ),
+            (
+                "{{ unit.title | list }}",
+                "Failed to render 'template': Filter call 'list' failed: Filter `list` expects a list, got \"Traits\"",
+            ),
+            (
+                "#[modmod:objectives]",
+                "Unknown placeholder '#[modmod:objectives]' on line 1, known placeholders are #[modmod:content]",
+            ),
+        ] {
+            assert_eq!(render(source, context.clone()).unwrap_err(), error, "{source}");
+        }
+    }
+}
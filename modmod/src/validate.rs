@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use crate::{codes::WarningCode, report::Warning, Topic, Track, Unit};
+
+impl Track {
+    /// Look for problems in the track definition that don't prevent rendering it,
+    /// but probably aren't what the author intended
+    pub fn validate(&self) -> Vec<Warning> {
+        let mut warnings = vec![];
+        self.conflicting_glossary_entries(&mut warnings);
+        for module in self.modules.iter() {
+            for unit in module.data.units.iter() {
+                duplicate_exercise_names(&unit.data, &mut warnings);
+                for (kind, items) in AGGREGATED_ITEMS {
+                    duplicate_items(&unit.data, kind, items, &mut warnings);
+                    if self.warn_near_duplicates {
+                        near_duplicate_items(&unit.data, kind, items, &mut warnings);
+                    }
+                }
+            }
+        }
+        warnings
+    }
+
+    /// The glossary has one entry per term, so all but the first definition of a term are
+    /// left out of the book
+    fn conflicting_glossary_entries(&self, warnings: &mut Vec<Warning>) {
+        let mut definitions: HashMap<String, (&str, &str)> = HashMap::new();
+        let topics = self
+            .modules
+            .iter()
+            .flat_map(|module| &module.data.units)
+            .flat_map(|unit| &unit.data.topics);
+        for topic in topics {
+            for entry in topic.data.glossary.iter() {
+                let term = entry.term.trim();
+                let definition = entry.definition.trim();
+                let Some((first_topic, first_definition)) = definitions.get(&term.to_lowercase())
+                else {
+                    definitions.insert(term.to_lowercase(), (&topic.data.name, definition));
+                    continue;
+                };
+                if *first_definition != definition {
+                    warnings.push(
+                        Warning::new(
+                            WarningCode::ConflictingGlossaryEntry,
+                            format!(
+                                "Topic '{}' defines glossary term '{term}' differently from topic '{first_topic}', whose definition is used",
+                                topic.data.name
+                            ),
+                        )
+                        .allowed_by(&self.allow),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Exercises with the same name get identical titles in the book, which makes them hard to tell apart
+fn duplicate_exercise_names(unit: &Unit, warnings: &mut Vec<Warning>) {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let exercises = unit
+        .topics
+        .iter()
+        .flat_map(|t| t.data.exercises.iter())
+        .map(|e| e.data.name.trim());
+    for name in exercises.clone() {
+        *counts.entry(name).or_default() += 1;
+    }
+    // Report in order of occurrence
+    for name in exercises {
+        if let Some(count) = counts.remove(name).filter(|c| *c > 1) {
+            warnings.push(
+                Warning::new(
+                    WarningCode::DuplicateExerciseName,
+                    format!(
+                        "Unit '{unit}' contains {count} exercises named '{name}'",
+                        unit = unit.name
+                    ),
+                )
+                .allowed_by(&unit.allow),
+            );
+        }
+    }
+}
+
+/// Selects a list of items from a topic
+type TopicItems = fn(&Topic) -> &[String];
+
+/// Topic items that are combined into a single list on the slides of a unit
+const AGGREGATED_ITEMS: [(&str, TopicItems); 2] = [
+    ("Objective", |t| &t.objectives),
+    ("Summary item", |t| &t.summary),
+];
+
+/// Form of an objective or summary item that is used to detect duplicates,
+/// which ignores case and whitespace
+pub(crate) fn normalize_item(item: &str) -> String {
+    item.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Items of every topic in the unit, in order, together with the topic they come from
+fn unit_items(unit: &Unit, items: TopicItems) -> impl Iterator<Item = (&str, &str)> {
+    unit.topics.iter().flat_map(move |t| {
+        items(&t.data)
+            .iter()
+            .map(|i| (t.data.name.as_str(), i.trim()))
+    })
+}
+
+/// Duplicates are left out of the aggregated list, but probably mean that topics
+/// were written independently and need to be reconciled
+fn duplicate_items(unit: &Unit, kind: &str, items: TopicItems, warnings: &mut Vec<Warning>) {
+    let mut topics: HashMap<String, Vec<&str>> = HashMap::new();
+    for (topic, item) in unit_items(unit, items) {
+        topics.entry(normalize_item(item)).or_default().push(topic);
+    }
+    // Report in order of first occurrence
+    for (_, item) in unit_items(unit, items) {
+        if let Some(topics) = topics.remove(&normalize_item(item)).filter(|t| t.len() > 1) {
+            let topics: Vec<_> = topics.iter().map(|t| format!("'{t}'")).collect();
+            warnings.push(
+                Warning::new(
+                    WarningCode::DuplicateItem,
+                    format!(
+                        "{kind} '{item}' of unit '{unit}' is listed more than once, by topics {topics}",
+                        unit = unit.name,
+                        topics = topics.join(", ")
+                    ),
+                )
+                .allowed_by(&unit.allow),
+            );
+        }
+    }
+}
+
+/// Items of which one starts with the other, like "Understand ownership" and
+/// "Understand ownership and borrowing", are most likely about the same thing
+fn near_duplicate_items(unit: &Unit, kind: &str, items: TopicItems, warnings: &mut Vec<Warning>) {
+    let mut seen: Vec<(String, &str, &str)> = vec![];
+    for (topic, item) in unit_items(unit, items) {
+        let normalized = normalize_item(item);
+        let near = seen.iter().find(|(other, ..)| {
+            *other != normalized
+                && (is_word_prefix(other, &normalized) || is_word_prefix(&normalized, other))
+        });
+        if let Some((_, other_topic, other_item)) = near {
+            warnings.push(
+                Warning::new(
+                    WarningCode::NearDuplicateItem,
+                    format!(
+                        "{kind} '{item}' of topic '{topic}' is nearly the same as '{other_item}' of topic '{other_topic}' in unit '{unit}'",
+                        unit = unit.name
+                    ),
+                )
+                .allowed_by(&unit.allow),
+            );
+        }
+        seen.push((normalized, topic, item));
+    }
+}
+
+fn is_word_prefix(prefix: &str, s: &str) -> bool {
+    s.strip_prefix(prefix)
+        .is_some_and(|rest| rest.starts_with(' '))
+}
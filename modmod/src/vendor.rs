@@ -0,0 +1,268 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::{Component, Path, PathBuf},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    io::PathExt,
+    load::{Load, ModuleDef, TopicCache},
+    to_tag, Module, Track,
+};
+
+/// Name of the file in the vendor directory that records where vendored modules came from
+const VENDOR_MANIFEST: &str = "modmod-vendor.json";
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct VendorError;
+
+impl fmt::Display for VendorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to vendor modules")
+    }
+}
+
+impl error_stack::Context for VendorError {}
+
+/// Origins of the modules in a vendor directory, keyed by the module path as written in the track
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct VendorManifest {
+    modules: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default)]
+pub struct VendorReport {
+    pub modules: Vec<VendoredModule>,
+}
+
+#[derive(Debug)]
+pub struct VendoredModule {
+    pub name: String,
+    /// Module path as it was originally written in the track definition
+    pub origin: String,
+    /// Module path as it is written in the track definition now
+    pub path: String,
+    pub added: Vec<PathBuf>,
+    pub updated: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+}
+
+impl VendoredModule {
+    pub fn is_unchanged(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl Track {
+    /// Copy every module that lives outside of the directory of the track definition
+    /// into `vendor_dir`, together with everything it refers to, and point the track
+    /// definition at the copies. Modules that were vendored before are updated from their origin.
+    pub fn vendor(&self, vendor_dir: impl AsRef<Path>) -> Result<VendorReport, VendorError> {
+        let track_dir = self.path.parent().unwrap();
+        let vendor_dir = track_dir.join(vendor_dir);
+        vendor_dir.create_dir_all()?;
+        let vendor_dir = vendor_dir
+            .canonicalize()
+            .into_report()
+            .change_context(VendorError)?;
+        if !vendor_dir.starts_with(track_dir) {
+            return Err(Report::new(VendorError).attach_printable(format!(
+                "Vendor directory {path} must be inside the directory of the track definition",
+                path = vendor_dir.to_string_lossy()
+            )));
+        }
+
+        let manifest_path = vendor_dir.join(VENDOR_MANIFEST);
+        let mut manifest: VendorManifest = if manifest_path.exists() {
+            serde_json::from_str(&manifest_path.read_to_string::<VendorError>()?)
+                .into_report()
+                .change_context(VendorError)?
+        } else {
+            VendorManifest::default()
+        };
+
+        // The module paths are rewritten in place, which is only supported for TOML
+        if self.path.extension().and_then(|e| e.to_str()) != Some("toml") {
+            return Err(Report::new(VendorError).attach_printable(format!(
+                "Only modules of tracks defined in TOML can be vendored, not of {path}",
+                path = self.path.to_string_lossy()
+            )));
+        }
+        let track_toml = self.path.read_to_string::<VendorError>()?;
+        let mut track_doc: toml_edit::Document = track_toml
+            .parse()
+            .into_report()
+            .change_context(VendorError)?;
+        let entries = track_doc["modules"].as_array_mut().ok_or_else(|| {
+            Report::new(VendorError).attach_printable("`modules` is not an array")
+        })?;
+
+        let mut report = VendorReport::default();
+        let mut dir_names = BTreeSet::new();
+        for entry in entries.iter_mut() {
+            let Some(written) = entry.as_str().map(str::to_string) else {
+                continue;
+            };
+            let module_path = track_dir
+                .join(&written)
+                .canonicalize()
+                .into_report()
+                .change_context(VendorError)?;
+
+            let origin = match manifest.modules.get(&written) {
+                Some(origin) => origin.clone(),
+                None if !module_path.starts_with(track_dir) => written.clone(),
+                // Part of the course repository already
+                None => continue,
+            };
+
+            // Load the module from its origin, so that vendored copies get updated
+            let (index, _) = self
+                .modules
+                .iter()
+                .map(|m| (m.index, &m.data.path))
+                .find(|(_, path)| **path == module_path)
+                .unwrap();
+            let module = ModuleDef::load(Path::new(&origin), Some(track_dir))
+                .change_context(VendorError)?
+                .resolve(index, &self.include_profiles, &TopicCache::default(), false)
+                .change_context(VendorError)?
+                .data;
+
+            let dir_name = unique_dir_name(&module, &mut dir_names);
+            let vendored = vendor_module(&module, &vendor_dir.join(&dir_name))?;
+            let path = relative_path(&vendored.module_path, track_dir);
+
+            let decor = entry.decor().clone();
+            *entry = path.as_str().into();
+            *entry.decor_mut() = decor;
+
+            manifest.modules.remove(&written);
+            manifest.modules.insert(path.clone(), origin.clone());
+            report.modules.push(VendoredModule {
+                name: module.name.clone(),
+                origin,
+                path,
+                added: vendored.added,
+                updated: vendored.updated,
+                removed: vendored.removed,
+            });
+        }
+
+        fs::write(&self.path, track_doc.to_string())
+            .into_report()
+            .change_context(VendorError)?;
+        fs::write(
+            &manifest_path,
+            serde_json::to_string_pretty(&manifest).unwrap(),
+        )
+        .into_report()
+        .change_context(VendorError)?;
+
+        Ok(report)
+    }
+}
+
+struct VendoredFiles {
+    module_path: PathBuf,
+    added: Vec<PathBuf>,
+    updated: Vec<PathBuf>,
+    removed: Vec<PathBuf>,
+}
+
+/// Copy the source files of `module` into `dest_dir`, preserving their
+/// relative locations so that the paths in the definition files stay valid
+fn vendor_module(module: &Module, dest_dir: &Path) -> Result<VendoredFiles, VendorError> {
+    let mut files = BTreeSet::new();
+    module
+        .collect_source_files(&mut files)
+        .change_context(VendorError)?;
+    let root = common_ancestor(files.iter()).unwrap();
+
+    let mut vendored = VendoredFiles {
+        module_path: dest_dir.join(module.path.strip_prefix(&root).unwrap()),
+        added: vec![],
+        updated: vec![],
+        removed: vec![],
+    };
+
+    let mut copied = BTreeSet::new();
+    for file in files.iter() {
+        let relative = file.strip_prefix(&root).unwrap();
+        let dest = dest_dir.join(relative);
+        if dest.exists() {
+            let old = fs::read(&dest).into_report().change_context(VendorError)?;
+            let new = fs::read(file).into_report().change_context(VendorError)?;
+            if old == new {
+                copied.insert(dest);
+                continue;
+            }
+            vendored.updated.push(relative.to_path_buf());
+        } else {
+            vendored.added.push(relative.to_path_buf());
+        }
+        dest.parent().unwrap().create_dir_all()?;
+        file.copy(&dest)?;
+        copied.insert(dest);
+    }
+
+    // Remove files that the module doesn't refer to anymore
+    for file in dest_dir.get_dir_content::<VendorError>()?.files {
+        let file = PathBuf::from(file);
+        if !copied.contains(&file) {
+            fs::remove_file(&file)
+                .into_report()
+                .change_context(VendorError)?;
+            vendored
+                .removed
+                .push(file.strip_prefix(dest_dir).unwrap().to_path_buf());
+        }
+    }
+
+    Ok(vendored)
+}
+
+/// Name of the directory the module gets vendored into, based on the directory it lives in
+fn unique_dir_name(module: &Module, taken: &mut BTreeSet<String>) -> String {
+    let base = module
+        .path
+        .parent()
+        .and_then(Path::file_name)
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| to_tag(&module.name));
+    let mut name = base.clone();
+    let mut i = 2;
+    while !taken.insert(name.clone()) {
+        name = format!("{base}-{i}");
+        i += 1;
+    }
+    name
+}
+
+fn common_ancestor<'p>(mut paths: impl Iterator<Item = &'p PathBuf>) -> Option<PathBuf> {
+    let first = paths.next()?.parent()?.to_path_buf();
+    Some(paths.fold(first, |ancestor, path| {
+        ancestor
+            .ancestors()
+            .find(|a| path.starts_with(a))
+            .unwrap()
+            .to_path_buf()
+    }))
+}
+
+/// `path` relative to `base`, with `/` separators as written in definition files
+fn relative_path(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(c) => Some(c.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,364 @@
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    process::{self, Child, Command, Stdio},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
+
+use error_stack::{IntoReport, Report, Result, ResultExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    exercises::{included_files, GRADING_TESTS_DIR},
+    io::PathExt,
+    output::Output,
+    Exercise, ExerciseKind,
+};
+
+/// Interval at which running verification commands are polled for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct VerifyExercisesError;
+
+impl fmt::Display for VerifyExercisesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("unable to verify exercises")
+    }
+}
+
+impl error_stack::Context for VerifyExercisesError {}
+
+#[derive(Debug, Clone)]
+pub struct VerifyOptions {
+    /// Command that is run in each rendered exercise directory,
+    /// unless the exercise overrides it
+    pub command: String,
+    /// Lints that are run on the rendered Cargo exercises that build,
+    /// unless the exercise sets its own
+    pub lints: Vec<ExerciseLint>,
+    /// Command that is run in each rendered Cargo exercise once `command` and the lints
+    /// passed, to run its tests, like `cargo test`
+    pub test_command: Option<String>,
+    /// Maximum duration of a single verification, including the tests,
+    /// unless the exercise overrides it
+    pub timeout: Option<Duration>,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            command: "cargo check".to_string(),
+            lints: vec![],
+            test_command: None,
+            timeout: None,
+        }
+    }
+}
+
+/// A check of the style of the sources of a Cargo exercise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExerciseLint {
+    /// `cargo clippy`, failing on any warning. It checks the exercise with its solutions,
+    /// as starter code is usually full of unused arguments and such.
+    Clippy,
+    /// `cargo fmt --check`, failing on sources that aren't formatted
+    Rustfmt,
+}
+
+impl ExerciseLint {
+    pub const ALL: [ExerciseLint; 2] = [ExerciseLint::Clippy, ExerciseLint::Rustfmt];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ExerciseLint::Clippy => "clippy",
+            ExerciseLint::Rustfmt => "rustfmt",
+        }
+    }
+
+    /// The command that runs the lint in an exercise
+    pub fn command(self) -> &'static str {
+        match self {
+            ExerciseLint::Clippy => "cargo clippy --all-targets -- -D warnings",
+            ExerciseLint::Rustfmt => "cargo fmt --check",
+        }
+    }
+
+    /// Whether the lint runs on the exercise with its solutions, instead of as it's rendered
+    fn on_solutions(self) -> bool {
+        self == ExerciseLint::Clippy
+    }
+}
+
+impl fmt::Display for ExerciseLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ExerciseLint {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|lint| lint.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|lint| lint.name()).collect();
+                format!("unknown lint '{s}', expected one of {}", names.join(", "))
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyOutcome {
+    Passed,
+    /// The command exited unsuccessfully, or could not be started
+    Failed {
+        command: String,
+        output: String,
+    },
+    TimedOut,
+    /// The exercise opted out of verification, or is a plain exercise without a verify command
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExerciseVerification {
+    pub exercise: String,
+    /// Directory of the exercise, relative to the output directory
+    pub output_dir: PathBuf,
+    pub outcome: VerifyOutcome,
+    pub duration: Duration,
+}
+
+/// Runs verification commands on copies of rendered exercises,
+/// so that build artifacts don't end up in the output
+pub struct Verifier<'o> {
+    options: &'o VerifyOptions,
+    output: &'o Output,
+    scratch_dir: PathBuf,
+}
+
+impl<'o> Verifier<'o> {
+    pub fn new(options: &'o VerifyOptions, output: &'o Output) -> Self {
+        let scratch_dir = env::temp_dir().join(format!("modmod-verify-{}", process::id()));
+        Self {
+            options,
+            output,
+            scratch_dir,
+        }
+    }
+
+    /// Verify the exercise that was rendered into `output_dir`,
+    /// which is relative to the output root
+    pub fn verify(
+        &self,
+        exercise: &Exercise,
+        output_dir: &Path,
+    ) -> Result<ExerciseVerification, VerifyExercisesError> {
+        self.output.check_cancelled()?;
+        let start = Instant::now();
+        // Plain exercises have no sensible default command
+        let skip = !exercise.verify
+            || (exercise.kind == ExerciseKind::Plain && exercise.verify_command.is_none());
+        let outcome = if skip {
+            VerifyOutcome::Skipped
+        } else {
+            self.run(exercise, output_dir)?
+        };
+
+        Ok(ExerciseVerification {
+            exercise: exercise.name.clone(),
+            output_dir: output_dir.to_path_buf(),
+            outcome,
+            duration: start.elapsed(),
+        })
+    }
+
+    fn run(
+        &self,
+        exercise: &Exercise,
+        output_dir: &Path,
+    ) -> Result<VerifyOutcome, VerifyExercisesError> {
+        let check_command = exercise
+            .verify_command
+            .as_deref()
+            .unwrap_or(&self.options.command);
+        // Lints and tests are run with cargo
+        let cargo = exercise.kind == ExerciseKind::Cargo;
+        let lints = match &exercise.lints {
+            Some(lints) => lints.as_slice(),
+            None => &self.options.lints,
+        };
+        // Work on a copy, so that lock files and such don't end up in the output
+        let work_dir = self.scratch_dir.join("exercise");
+        let solution_dir = self.scratch_dir.join("solution");
+        let lints = lints.iter().filter(|_| cargo).map(|lint| {
+            let dir = match lint.on_solutions() {
+                true => &solution_dir,
+                false => &work_dir,
+            };
+            (lint.command(), dir)
+        });
+        let commands: Vec<(&str, &PathBuf)> = std::iter::once((check_command, &work_dir))
+            .chain(lints)
+            .chain(
+                self.options
+                    .test_command
+                    .as_deref()
+                    .filter(|_| cargo)
+                    .map(|command| (command, &work_dir)),
+            )
+            .collect();
+        for (command, _) in commands.iter() {
+            if command.split_whitespace().next().is_none() {
+                return Err(Report::new(VerifyExercisesError).attach_printable(format!(
+                    "Verify command of exercise '{}' is empty",
+                    exercise.name
+                )));
+            }
+        }
+
+        self.copy_exercise(output_dir, &work_dir)?;
+        if commands.iter().any(|(_, dir)| *dir == &solution_dir) {
+            // The rendered exercise, with the files that had their solutions stripped
+            // copied again from the exercise itself
+            self.copy_exercise(output_dir, &solution_dir)?;
+            let grading_tests_dir = exercise.path.join(GRADING_TESTS_DIR);
+            let files = included_files(&exercise.path, &exercise.includes, &exercise.excludes)
+                .change_context(VerifyExercisesError)?;
+            for file in files
+                .iter()
+                .filter(|file| !file.starts_with(&grading_tests_dir))
+            {
+                fs::copy(
+                    file,
+                    solution_dir.join(file.strip_prefix(&exercise.path).unwrap()),
+                )
+                .into_report()
+                .change_context(VerifyExercisesError)?;
+            }
+        }
+
+        // The lints and tests only run if the exercise builds, within the same timeout
+        let timeout = exercise.verify_timeout.or(self.options.timeout);
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        for (command, dir) in commands {
+            let outcome = self.run_command(command, dir, deadline)?;
+            if outcome != VerifyOutcome::Passed {
+                return Ok(outcome);
+            }
+        }
+        Ok(VerifyOutcome::Passed)
+    }
+
+    /// Copy the exercise that was rendered into `output_dir` to the empty directory `to`
+    fn copy_exercise(&self, output_dir: &Path, to: &Path) -> Result<(), VerifyExercisesError> {
+        if to.exists() {
+            fs::remove_dir_all(to)
+                .into_report()
+                .change_context(VerifyExercisesError)?;
+        }
+        to.create_dir_all()?;
+        fs_extra::dir::copy(
+            self.output.root().join(output_dir),
+            to,
+            &fs_extra::dir::CopyOptions::new().content_only(true),
+        )
+        .into_report()
+        .change_context(VerifyExercisesError)?;
+        Ok(())
+    }
+
+    /// Run `command` in `work_dir`, killing it if it's still running at `deadline`
+    fn run_command(
+        &self,
+        command: &str,
+        work_dir: &Path,
+        deadline: Option<Instant>,
+    ) -> Result<VerifyOutcome, VerifyExercisesError> {
+        let mut words = command.split_whitespace();
+        let program = words.next().unwrap();
+        let log_path = self.scratch_dir.join("verify.log");
+        let log = log_path.create_file()?;
+        let log_err = log
+            .try_clone()
+            .into_report()
+            .change_context(VerifyExercisesError)?;
+
+        let mut command_builder = Command::new(program);
+        command_builder
+            .args(words)
+            .current_dir(work_dir)
+            // Share build artifacts between exercises
+            .env("CARGO_TARGET_DIR", self.scratch_dir.join("target"))
+            .stdin(Stdio::null())
+            .stdout(log)
+            .stderr(log_err);
+        // A process group of its own, so that the processes the command starts, like the
+        // test binaries of `cargo test`, can be killed along with it
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command_builder, 0);
+        let child = command_builder.spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                return Ok(VerifyOutcome::Failed {
+                    command: command.to_string(),
+                    output: format!("Unable to run '{command}': {e}"),
+                })
+            }
+        };
+
+        let status = loop {
+            if let Some(status) = child
+                .try_wait()
+                .into_report()
+                .change_context(VerifyExercisesError)?
+            {
+                break Some(status);
+            }
+            let cancelled = self.output.check_cancelled::<VerifyExercisesError>();
+            if cancelled.is_err() || deadline.is_some_and(|d| Instant::now() > d) {
+                kill(&mut child);
+                cancelled?;
+                break None;
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        Ok(match status {
+            Some(status) if status.success() => VerifyOutcome::Passed,
+            Some(_) => VerifyOutcome::Failed {
+                command: command.to_string(),
+                output: log_path.read_to_string()?,
+            },
+            None => VerifyOutcome::TimedOut,
+        })
+    }
+}
+
+/// Kill `child` and the processes it started
+fn kill(child: &mut Child) {
+    // The child leads its process group, of which the ID is its own
+    #[cfg(unix)]
+    if let Ok(group) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill doesn't touch memory, and fails harmlessly on a stale group
+        unsafe {
+            libc::kill(-group, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+impl Drop for Verifier<'_> {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.scratch_dir);
+    }
+}
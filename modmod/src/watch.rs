@@ -0,0 +1,133 @@
+//! Support for re-rendering a track when its sources change
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+use error_stack::Result;
+
+use crate::{io::PathExt, LoadTrackError, TopicContent, Track, Unit};
+
+impl Track {
+    /// The files to watch for changes to the track: its [source files](Track::source_files),
+    /// plus every file in its exercise directories except for build artifacts,
+    /// so that new files that match the includes of an exercise are noticed as well
+    pub fn watched_files(&self) -> Result<Vec<PathBuf>, LoadTrackError> {
+        let mut files: BTreeSet<PathBuf> = self.source_files()?.into_iter().collect();
+        let exercise_dirs = self
+            .modules
+            .iter()
+            .flat_map(|m| &m.data.units)
+            .flat_map(|u| &u.data.topics)
+            .flat_map(|t| &t.data.exercises)
+            .map(|e| &e.data.path);
+        for dir in exercise_dirs {
+            files.extend(
+                dir.get_dir_content::<LoadTrackError>()?
+                    .files
+                    .into_iter()
+                    .map(PathBuf::from)
+                    .filter(|file| {
+                        !file
+                            .strip_prefix(dir)
+                            .unwrap_or(file)
+                            .components()
+                            .any(|c| c == Component::Normal("target".as_ref()))
+                    }),
+            );
+        }
+        Ok(files.into_iter().collect())
+    }
+
+    /// The units, by module and unit index, of which the rendered exercises depend on the
+    /// `changed` files. Returns `None` if any of the files affects more than units, like the
+    /// definitions of the track and its modules, appendices, or files that aren't part of the
+    /// track. The book and the slides are cheap to render, so they aren't tracked by unit.
+    pub(crate) fn affected_units(&self, changed: &[PathBuf]) -> Option<BTreeSet<(usize, usize)>> {
+        let mut affected = BTreeSet::new();
+        for path in changed {
+            if *path == self.path || self.modules.iter().any(|m| m.data.path == *path) {
+                return None;
+            }
+            let mut claimed = false;
+            for module in self.modules.iter() {
+                for unit in module.data.units.iter() {
+                    if exercises_use(&unit.data, path) {
+                        affected.insert((module.index, unit.index));
+                        claimed = true;
+                    } else if uses(&unit.data, path) {
+                        claimed = true;
+                    }
+                }
+            }
+            if !claimed {
+                return None;
+            }
+        }
+        Some(affected)
+    }
+}
+
+/// Whether rendering the exercises of `unit` depends on the file at `path`.
+/// Topic definitions list the exercises, so they count as well.
+fn exercises_use(unit: &Unit, path: &Path) -> bool {
+    unit.topics.iter().map(|t| &t.data).any(|topic| {
+        topic.path == path
+            || topic
+                .exercises
+                .iter()
+                .any(|exercise| path.starts_with(&exercise.data.path))
+    })
+}
+
+/// Whether rendering `unit` reads the file at `path`
+fn uses(unit: &Unit, path: &Path) -> bool {
+    unit.templates.iter().any(|template| template.path == path)
+        || unit.topics.iter().map(|t| &t.data).any(|topic| {
+            matches!(&topic.content, TopicContent::File(content) if content == path)
+                || topic.instructor_notes.as_deref() == Some(path)
+                || topic.images.iter().any(|image| image == path)
+                || topic
+                    .exercises
+                    .iter()
+                    .any(|exercise| exercise.data.description == path)
+        })
+}
+
+/// Modification times of a set of files, to find out which of them changed
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FileTimes(BTreeMap<PathBuf, Option<SystemTime>>);
+
+impl FileTimes {
+    /// Look up the modification times of `files`. Files that don't exist are recorded too,
+    /// so that creating them counts as a change.
+    pub fn of(files: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self(
+            files
+                .into_iter()
+                .map(|file| {
+                    let modified = fs::metadata(&file).and_then(|m| m.modified()).ok();
+                    (file, modified)
+                })
+                .collect(),
+        )
+    }
+
+    /// The files that were added, removed or modified since `earlier`
+    pub fn changed_since(&self, earlier: &FileTimes) -> Vec<PathBuf> {
+        let added_or_modified = self
+            .0
+            .iter()
+            .filter(|(file, modified)| earlier.0.get(*file) != Some(modified))
+            .map(|(file, _)| file.clone());
+        let removed = earlier
+            .0
+            .keys()
+            .filter(|file| !self.0.contains_key(*file))
+            .cloned();
+        added_or_modified.chain(removed).collect()
+    }
+}
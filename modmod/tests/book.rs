@@ -0,0 +1,128 @@
+mod common;
+
+use std::fs;
+
+use modmod::{RenderOptions, Track};
+
+#[test]
+fn page_descriptions_replace_the_description_of_the_book() {
+    let report = common::render_fixture(
+        "page-descriptions/pages.track.toml",
+        "page-descriptions",
+        &RenderOptions::default(),
+    );
+    let book_dir = report.output_dir.join("book");
+
+    let index = fs::read_to_string(book_dir.join("theme/index.hbs")).unwrap();
+    assert!(
+        index.contains(
+            r#"        {{#if (eq path "1-mod.md")}}
+        <meta name="description" content="Use &quot;quotes&quot; &amp; &#123;braces&#125;">
+        {{else}}
+        {{#if (eq path "1_1-unit.md")}}
+        <meta name="description" content="Write a &lt;program&gt;; Run it; write a  PROGRAM">
+        {{else}}
+        <meta name="description" content="{{ description }}">{{/if}}{{/if}}
+"#
+        ),
+        "{index}"
+    );
+    // The index template replaces the one of mdBook, which has the description meta of the
+    // book, so every branch of the conditions has the only description meta of a page
+    assert_eq!(index.matches(r#"<meta name="description""#).count(), 3);
+    assert!(!book_dir.join("theme/head.hbs").exists());
+    let book_toml = fs::read_to_string(book_dir.join("book.toml")).unwrap();
+    assert!(
+        book_toml.contains(r#"description = "A course with a description of every page""#),
+        "{book_toml}"
+    );
+    for page in ["1-mod.md", "1_1-unit.md", "appendix-setup.md"] {
+        let content = fs::read_to_string(book_dir.join("src").join(page)).unwrap();
+        assert!(content.starts_with("# "), "{page} starts with {content}");
+    }
+}
+
+#[test]
+fn draft_appendices_are_only_rendered_as_drafts_if_drafts_are_included() {
+    let path = "appendices/appendices.track.toml";
+    let report = common::render_fixture(path, "appendices", &RenderOptions::default());
+    let src_dir = report.output_dir.join("book/src");
+    assert!(src_dir.join("appendix-setup.md").exists());
+    assert!(!src_dir.join("appendix-tools.md").exists());
+    assert_eq!(report.drafts_skipped, 1);
+
+    let options = RenderOptions {
+        include_drafts: true,
+        ..Default::default()
+    };
+    let report = common::render_fixture(path, "appendices-drafts", &options);
+    let src_dir = report.output_dir.join("book/src");
+    let summary = fs::read_to_string(src_dir.join("SUMMARY.md")).unwrap();
+    assert!(
+        summary.contains("- [Tools (DRAFT)](appendix-tools.md)\n"),
+        "{summary}"
+    );
+    let tools = fs::read_to_string(src_dir.join("appendix-tools.md")).unwrap();
+    assert!(
+        tools.starts_with("# Tools (DRAFT)\n\n> **DRAFT**"),
+        "{tools}"
+    );
+    let setup = fs::read_to_string(src_dir.join("appendix-setup.md")).unwrap();
+    assert!(!setup.contains("DRAFT"));
+    assert_eq!(report.drafts_included, 1);
+}
+
+#[test]
+fn images_of_appendices_are_copied_to_a_folder_per_appendix() {
+    let options = RenderOptions {
+        include_drafts: true,
+        ..Default::default()
+    };
+    let path = "appendices/appendices.track.toml";
+    let report = common::render_fixture(path, "appendices-images", &options);
+    let src_dir = report.output_dir.join("book/src");
+
+    // Both appendices have an image named diagram.svg
+    let images = common::read_dir_files(&src_dir.join("images"));
+    let paths: Vec<_> = images.keys().map(|path| path.to_str().unwrap()).collect();
+    assert_eq!(
+        paths,
+        [
+            "appendix-setup/diagram.svg",
+            "appendix-tools/diagram.svg",
+            "appendix-tools/nested/logo.svg",
+        ]
+    );
+    let setup = fs::read_to_string(src_dir.join("appendix-setup.md")).unwrap();
+    assert!(setup.contains("![The toolchain](images/appendix-setup/diagram.svg)"));
+    let tools = fs::read_to_string(src_dir.join("appendix-tools.md")).unwrap();
+    assert!(tools.contains(r#"![Tools](images/appendix-tools/diagram.svg "Tools")"#));
+    assert!(tools.contains(r#"<img src="images/appendix-tools/nested/logo.svg" />"#));
+}
+
+#[test]
+fn appendices_with_the_same_file_fail_the_render() {
+    let track = Track::load_toml_def(common::fixture("appendices/same-file.track.toml")).unwrap();
+    let output_dir = common::output_dir("appendices-same-file");
+    let error = track
+        .render(&output_dir, &RenderOptions::default())
+        .unwrap_err();
+    assert!(
+        format!("{error:?}")
+            .contains("Appendices 'Setup' and 'setup' would both be written to appendix-setup.md"),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn unknown_placeholders_in_appendices_fail_the_render() {
+    let track = Track::load_toml_def(common::fixture("appendices/placeholder.track.toml")).unwrap();
+    let output_dir = common::output_dir("appendices-placeholder");
+    let error = track
+        .render(&output_dir, &RenderOptions::default())
+        .unwrap_err();
+    assert!(
+        format!("{error:?}").contains("Unknown placeholder '#[modmod:exercise_dir]' in"),
+        "{error:?}"
+    );
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use modmod::{RenderOptions, Track};
+
+#[test]
+fn every_missing_file_is_reported_at_once() {
+    let error = Track::load_toml_def(common::fixture("problems/missing.track.toml")).unwrap_err();
+    let error = format!("{error:?}");
+    for missing in ["gone/topic.toml", "also-gone/topic.toml", "cheat-sheet.md"] {
+        assert!(error.contains(missing), "{missing} in {error}");
+    }
+}
+
+#[test]
+fn every_problem_of_a_track_is_reported_at_once() {
+    let track = Track::load_toml_def(common::fixture("problems/problems.track.toml")).unwrap();
+    let report = track.check(&RenderOptions::default()).unwrap();
+    assert!(!report.is_ok());
+    let problems = &report.problems;
+    assert_eq!(problems.len(), 3, "{problems:#?}");
+    assert!(
+        problems[0].starts_with("Failed to parse 'template':  --> 3:7"),
+        "{problems:#?}"
+    );
+    assert!(problems[0].ends_with("template.md"), "{problems:#?}");
+    assert!(
+        problems[1].starts_with("Exercise 'Globs': Error parsing glob 'src/[*.rs'"),
+        "{problems:#?}"
+    );
+    assert!(
+        problems[2].starts_with("Unknown placeholder '#[modmod:exercise_path]' in "),
+        "{problems:#?}"
+    );
+
+    // Renders fail on the same problems, before writing anything
+    let output_dir = common::output_dir("problems");
+    let error = track
+        .render(&output_dir, &RenderOptions::default())
+        .unwrap_err();
+    let error = format!("{error:?}");
+    assert!(
+        error.contains("Checking the track found 3 problem(s), so nothing was rendered"),
+        "{error}"
+    );
+    for problem in problems {
+        assert!(error.contains(problem.lines().next().unwrap()), "{error}");
+    }
+    assert!(!output_dir.exists());
+}
@@ -0,0 +1,16 @@
+mod common;
+
+use std::process::Command;
+
+#[test]
+fn renders_without_a_command() {
+    let output_dir = common::output_dir("cli-default-command");
+    let output = Command::new(env!("CARGO_BIN_EXE_modmod"))
+        .arg("-o")
+        .arg(&output_dir)
+        .arg(common::fixture("shared-topic/shared.track.toml"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert!(output_dir.join("book/src/SUMMARY.md").is_file());
+}
@@ -0,0 +1,66 @@
+//! Helpers shared by the integration tests, which render the tracks in `tests/fixtures`
+
+// Every test crate includes this module, and each of them uses only some of its helpers
+#![allow(dead_code)]
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use modmod::{RenderOptions, RenderReport, Track};
+
+/// Path of `path` in the fixtures directory
+pub fn fixture(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path)
+}
+
+/// An empty directory for the output of test `name`
+pub fn output_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    dir
+}
+
+/// Load the track at `path` in the fixtures directory and render it into the output
+/// directory of test `name`
+pub fn render_fixture(path: &str, name: &str, options: &RenderOptions) -> RenderReport {
+    let track = Track::load_toml_def(fixture(path)).unwrap();
+    track.render(output_dir(name), options).unwrap()
+}
+
+/// Copy the directory at `from` with everything in it to `to`
+pub fn copy_dir(from: &Path, to: &Path) {
+    std::fs::create_dir_all(to).unwrap();
+    for entry in std::fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let target = to.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &target);
+        } else {
+            std::fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
+/// The files in the directory at `dir` by their path relative to it, with their contents
+pub fn read_dir_files(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current) = dirs.pop() {
+        for entry in std::fs::read_dir(current).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                let relative = path.strip_prefix(dir).unwrap().to_path_buf();
+                files.insert(relative, std::fs::read(path).unwrap());
+            }
+        }
+    }
+    files
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use modmod::{RenderOptions, Track, WarningCode};
+
+/// The error of rendering the track at `path` in the fixtures directory
+fn render_error(path: &str, name: &str) -> String {
+    let track = Track::load_toml_def(common::fixture(path)).unwrap();
+    let output_dir = common::output_dir(name);
+    let error = track
+        .render(&output_dir, &RenderOptions::default())
+        .unwrap_err();
+    assert!(!output_dir.exists());
+    format!("{error:?}")
+}
+
+#[test]
+fn topics_before_their_dependencies_and_missing_dependencies_are_warned_about() {
+    let path = "dependencies/order.track.toml";
+    let report = common::render_fixture(path, "dependencies", &RenderOptions::default());
+
+    let codes: Vec<WarningCode> = report.warnings.iter().map(|w| w.code).collect();
+    assert_eq!(
+        codes,
+        [
+            WarningCode::DependencyAfterDependent,
+            WarningCode::MissingDependency
+        ]
+    );
+    assert_eq!(
+        report.warnings[0].message,
+        "Topic 'Ownership' comes before topic 'Basics', which it depends on"
+    );
+    let missing = &report.warnings[1].message;
+    assert!(
+        missing.starts_with("Topic 'Borrowing' depends on "),
+        "{missing}"
+    );
+    assert!(
+        missing.ends_with("topic.toml, which is not part of the track"),
+        "{missing}"
+    );
+    assert!(missing.contains("lifetimes"), "{missing}");
+}
+
+#[test]
+fn excluding_a_dependency_of_a_topic_fails_the_render() {
+    let error = render_error("dependencies/excluded.track.toml", "dependencies-excluded");
+    assert!(
+        error.contains(
+            "Topic 'Ownership' depends on topic 'Basics', which is excluded from the track"
+        ),
+        "{error}"
+    );
+}
+
+#[test]
+fn dependency_cycles_fail_the_render() {
+    let error = render_error("dependencies/cycle.track.toml", "dependencies-cycle");
+    assert!(
+        error.contains("Topics depend on each other in a cycle: 'Chicken' -> 'Egg' -> 'Chicken'"),
+        "{error}"
+    );
+}
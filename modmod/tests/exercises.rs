@@ -0,0 +1,218 @@
+mod common;
+
+use std::{collections::BTreeMap, path::Path};
+
+use modmod::{ExerciseLint, RenderOptions, Track, VerifyOptions, VerifyOutcome};
+
+#[test]
+fn cargo_and_plain_exercises_copy_and_verify_by_kind() {
+    let options = RenderOptions {
+        verify: Some(VerifyOptions::default()),
+        ..Default::default()
+    };
+    let report = common::render_fixture(
+        "exercise-kinds/kinds.track.toml",
+        "exercise-kinds",
+        &options,
+    );
+    let unit_dir = report.output_dir.join("exercises/1-mod/1-unit");
+    let files = |exercise: &str| -> Vec<String> {
+        let files = common::read_dir_files(&unit_dir.join(exercise));
+        files
+            .keys()
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .collect()
+    };
+
+    // The package itself, without other files of the author
+    assert_eq!(
+        files("1-hello"),
+        [".gitignore", "Cargo.toml", "src/main.rs"]
+    );
+    // Everything but build artifacts and dependencies
+    assert_eq!(
+        files("2-lab"),
+        ["data/input.txt", "description.md", "lab.c"]
+    );
+
+    let outcomes: Vec<(&str, &Path, &VerifyOutcome)> = report
+        .verification
+        .iter()
+        .map(|v| (v.exercise.as_str(), v.output_dir.as_path(), &v.outcome))
+        .collect();
+    let unit_dir = Path::new("exercises/1-mod/1-unit");
+    assert_eq!(
+        outcomes,
+        [
+            ("Hello", &*unit_dir.join("1-hello"), &VerifyOutcome::Passed),
+            // Plain exercises have no default command to verify them with
+            ("Lab", &*unit_dir.join("2-lab"), &VerifyOutcome::Skipped),
+            (
+                "Checked lab",
+                &*unit_dir.join("3-checked-lab"),
+                &VerifyOutcome::Passed
+            ),
+        ]
+    );
+}
+
+/// Render the solutions fixture into a student and an instructor edition, and return the
+/// files of both
+fn render_editions(name: &str) -> [BTreeMap<String, String>; 2] {
+    let track = Track::load_toml_def(common::fixture("solutions/solutions.track.toml")).unwrap();
+    let dir = common::output_dir(name);
+    let options = RenderOptions {
+        instructor_output: Some(dir.join("instructor")),
+        ..Default::default()
+    };
+    track.render(dir.join("student"), &options).unwrap();
+    ["student", "instructor"].map(|edition| {
+        common::read_dir_files(&dir.join(edition))
+            .into_iter()
+            .map(|(path, content)| {
+                let path = path.to_string_lossy().replace('\\', "/");
+                (path, String::from_utf8(content).unwrap())
+            })
+            .collect()
+    })
+}
+
+#[test]
+fn solutions_are_only_in_the_instructor_edition() {
+    let [student, instructor] = render_editions("solution-editions");
+    let lib = "exercises/1-mod/1-unit/1-adder/src/lib.rs";
+    assert_eq!(
+        student[lib],
+        "/// The sum of `a` and `b`\npub fn add(a: u32, b: u32) -> u32 {\n    todo!(\"Add a and b\")\n}\n"
+    );
+    assert_eq!(
+        instructor[lib],
+        std::fs::read_to_string(common::fixture("solutions/m/t/ex/adder/src/lib.rs")).unwrap()
+    );
+}
+
+#[test]
+fn grading_tests_are_only_in_the_instructor_edition() {
+    let [student, instructor] = render_editions("grading-editions");
+    let exercise = "exercises/1-mod/1-unit/1-adder";
+    let grading = "grading/1-mod/1-unit/1-adder/tests/grading/add.rs";
+    let files = |edition: &BTreeMap<String, String>, prefix: &str| -> Vec<String> {
+        edition
+            .keys()
+            .filter_map(|path| path.strip_prefix(prefix))
+            .map(str::to_string)
+            .collect()
+    };
+
+    // The exercise includes its tests, apart from the grading tests
+    assert_eq!(
+        files(&student, exercise),
+        [
+            "/.gitignore",
+            "/Cargo.toml",
+            "/src/lib.rs",
+            "/tests/public.rs"
+        ]
+    );
+    assert!(files(&student, "grading/").is_empty());
+    assert_eq!(files(&instructor, exercise), files(&student, exercise));
+    assert_eq!(
+        instructor[grading],
+        std::fs::read_to_string(common::fixture(
+            "solutions/m/t/ex/adder/tests/grading/add.rs"
+        ))
+        .unwrap()
+    );
+    assert_eq!(
+        files(&instructor, "grading/"),
+        ["1-mod/1-unit/1-adder/tests/grading/add.rs"]
+    );
+}
+
+#[test]
+fn clippy_lints_exercises_with_their_solutions() {
+    // The starter code doesn't use the arguments of `add`, which clippy would deny
+    let options = RenderOptions {
+        verify: Some(VerifyOptions {
+            lints: vec![ExerciseLint::Clippy, ExerciseLint::Rustfmt],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let report =
+        common::render_fixture("solutions/solutions.track.toml", "lint-solutions", &options);
+    let outcomes: Vec<_> = report.verification.iter().map(|v| &v.outcome).collect();
+    assert_eq!(outcomes, [&VerifyOutcome::Passed]);
+}
+
+// Processes are looked up in /proc
+#[cfg(target_os = "linux")]
+#[test]
+fn timeouts_kill_the_processes_that_the_verify_command_started() {
+    let dir = common::output_dir("verify-timeout");
+    common::copy_dir(&common::fixture("verify-timeout"), &dir.join("track"));
+    // The command starts a process that would outlive it, and waits for it
+    let pid_file = dir.join("sleep.pid");
+    std::fs::write(
+        dir.join("track/m/t/ex/spawn/spawn.sh"),
+        format!(
+            "sleep 60 &\necho $! > '{}'\nwait\n",
+            pid_file.to_str().unwrap()
+        ),
+    )
+    .unwrap();
+
+    let track = Track::load_toml_def(dir.join("track/timeout.track.toml")).unwrap();
+    let options = RenderOptions {
+        verify: Some(VerifyOptions::default()),
+        ..Default::default()
+    };
+    let report = track.render(dir.join("output"), &options).unwrap();
+    assert_eq!(report.verification[0].outcome, VerifyOutcome::TimedOut);
+
+    // Killed processes may take a moment to exit, and linger as zombies until they're reaped
+    let pid = std::fs::read_to_string(pid_file).unwrap();
+    let stat = || std::fs::read_to_string(format!("/proc/{}/stat", pid.trim())).unwrap_or_default();
+    let exited = |stat: &str| stat.is_empty() || stat.rsplit(") ").next().unwrap().starts_with('Z');
+    for _ in 0..20 {
+        if exited(&stat()) {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    assert!(exited(&stat()), "{}", stat());
+}
+
+#[test]
+fn zipped_exercises_hold_the_rendered_files() {
+    let options = RenderOptions {
+        zip_exercises: true,
+        ..Default::default()
+    };
+    let report =
+        common::render_fixture("solutions/solutions.track.toml", "zip-exercises", &options);
+    let unit_dir = report.output_dir.join("exercises/1-mod/1-unit");
+    let archive = std::fs::read(unit_dir.with_extension("zip")).unwrap();
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+    let mut names: Vec<_> = archive
+        .file_names()
+        .map(|name| name.unwrap().to_string())
+        .collect();
+    names.sort();
+    let rendered: Vec<_> = common::read_dir_files(&unit_dir)
+        .into_keys()
+        .map(|path| format!("1-unit/{}", path.to_string_lossy().replace('\\', "/")))
+        .collect();
+    assert_eq!(names, rendered);
+    let mut lib = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name("1-unit/1-adder/src/lib.rs").unwrap(),
+        &mut lib,
+    )
+    .unwrap();
+    assert_eq!(
+        lib,
+        std::fs::read_to_string(unit_dir.join("1-adder/src/lib.rs")).unwrap()
+    );
+}
@@ -0,0 +1,6 @@
+/// The sum of `a` and `b`
+pub fn add(a: u32, b: u32) -> u32 {
+    // SOLUTION_START todo!("Add a and b")
+    a + b
+    // SOLUTION_END
+}
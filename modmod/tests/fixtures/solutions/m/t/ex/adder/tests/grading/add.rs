@@ -0,0 +1,4 @@
+#[test]
+fn adds() {
+    assert_eq!(adder::add(2, 3), 5);
+}
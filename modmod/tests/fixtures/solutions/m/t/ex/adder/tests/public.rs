@@ -0,0 +1,4 @@
+#[test]
+fn adds_zero() {
+    assert_eq!(adder::add(1, 0), 1);
+}
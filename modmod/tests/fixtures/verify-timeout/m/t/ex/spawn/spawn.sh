@@ -0,0 +1 @@
+# The test replaces this script with one that starts a process and records its ID
@@ -0,0 +1,58 @@
+mod common;
+
+use std::path::Path;
+
+use modmod::{OutputLayout, RenderOptions, Track};
+
+const SHARED: &str = "shared-topic/shared.track.toml";
+
+#[test]
+fn path_templates_change_the_layout_of_every_output() {
+    let options = RenderOptions {
+        layout: OutputLayout {
+            root: "{track_slug}".to_string(),
+            unit_dir: "unit-{unit_number}".to_string(),
+            exercise_dir: "{exercise_slug}".to_string(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let report = common::render_fixture(SHARED, "layout", &options);
+    assert!(report.output_dir.ends_with("layout/shared-topic"));
+
+    let exercises = &report.output_map.exercises["m/t1/ex/same"];
+    let dirs: Vec<&Path> = exercises.iter().map(|e| e.dir.as_path()).collect();
+    assert_eq!(
+        dirs,
+        [
+            Path::new("exercises/1-mod-one/unit-1/same"),
+            Path::new("exercises/1-mod-one/unit-2/same"),
+        ]
+    );
+    assert!(report.output_dir.join(dirs[1]).join("notes.txt").is_file());
+    let page = std::fs::read_to_string(report.output_dir.join("book/src/1_2-unit-b.md")).unwrap();
+    assert!(
+        page.contains("`exercises/1-mod-one/unit-2/same/notes.txt`"),
+        "{page}"
+    );
+    report.output_map.check(&report.output_dir).unwrap();
+}
+
+#[test]
+fn path_templates_must_stay_inside_the_output_directory() {
+    let track = Track::load_toml_def(common::fixture(SHARED)).unwrap();
+    let output_dir = common::output_dir("layout-outside");
+    let options = RenderOptions {
+        layout: OutputLayout {
+            unit_dir: "../{unit_number}".to_string(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let error = track.render(&output_dir, &options).unwrap_err();
+    assert!(
+        format!("{error:?}").contains("Template '../{unit_number}' expands to '../1', which has an empty segment or a segment that leaves the output directory"),
+        "{error:?}"
+    );
+    assert!(!output_dir.exists());
+}
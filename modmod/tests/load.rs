@@ -0,0 +1,127 @@
+mod common;
+
+use std::fs;
+
+use modmod::{LoadOptions, RenderOptions, Track};
+
+#[test]
+fn extending_tracks_change_the_track_they_extend() {
+    let report = common::render_fixture(
+        "extends/variants/shorter.track.toml",
+        "extends",
+        &RenderOptions::default(),
+    );
+    let summary = fs::read_to_string(report.output_dir.join("book/src/SUMMARY.md")).unwrap();
+    assert!(summary.starts_with("# Summary\n\n- [Module A](1-module-a.md)\n"));
+    // Module B is removed by the track in between, and module C by the last one
+    assert!(
+        !summary.contains("Module B") && !summary.contains("Module C"),
+        "{summary}"
+    );
+
+    let slides = fs::read_to_string(report.output_dir.join("slides/1_1-unit-a.md")).unwrap();
+    // Topic Two is excluded by the track in between
+    assert!(!slides.contains("# Two"), "{slides}");
+    // The variables of the extending tracks replace those of the same name
+    assert!(
+        slides.contains("Ask Corro at course@example.com."),
+        "{slides}"
+    );
+}
+
+#[test]
+fn extending_tracks_add_modules_to_the_end() {
+    let report = common::render_fixture(
+        "extends/variants/short.track.toml",
+        "extends-short",
+        &RenderOptions::default(),
+    );
+    let summary = fs::read_to_string(report.output_dir.join("book/src/SUMMARY.md")).unwrap();
+    assert!(
+        summary.contains("- [Module A](1-module-a.md)\n\t- [Unit A](1_1-unit-a.md)\n\n- [Module C](2-module-c.md)\n"),
+        "{summary}"
+    );
+    assert!(!summary.contains("Module B"), "{summary}");
+}
+
+#[test]
+fn topic_overrides_replace_the_fields_of_the_topic_for_the_track() {
+    let report = common::render_fixture(
+        "topic-overrides/overrides.track.toml",
+        "topic-overrides",
+        &RenderOptions::default(),
+    );
+    let slides = fs::read_to_string(report.output_dir.join("slides/1_1-unit.md")).unwrap();
+    assert!(slides.contains("The short version."), "{slides}");
+    assert!(slides.contains("- Short objective\n"), "{slides}");
+    assert!(!slides.contains("long"), "{slides}");
+    let files = common::read_dir_files(&report.output_dir.join("exercises"));
+    let exercises: Vec<_> = files.keys().map(|path| path.to_str().unwrap()).collect();
+    assert_eq!(exercises, ["1-mod/1-unit/1-short/description.md"]);
+    let book = fs::read_to_string(report.output_dir.join("book/src/1_1-unit.md")).unwrap();
+    assert!(book.contains("The short exercise."), "{book}");
+}
+
+#[test]
+fn overrides_of_topics_that_arent_part_of_the_track_are_errors() {
+    let error =
+        Track::load_toml_def(common::fixture("topic-overrides/missing.track.toml")).unwrap_err();
+    let error = format!("{error:?}");
+    assert!(
+        error.contains("of an override is not part of the track"),
+        "{error}"
+    );
+}
+
+#[test]
+fn definitions_in_yaml_and_json_render_like_those_in_toml() {
+    let options = RenderOptions::default();
+    let toml = common::render_fixture("formats/formats.track.toml", "formats-toml", &options);
+    // The same track, of which the track is YAML, the module JSON and one topic YAML
+    let yaml = common::render_fixture("formats/formats.track.yaml", "formats-yaml", &options);
+    let toml = common::read_dir_files(&toml.output_dir);
+    let yaml = common::read_dir_files(&yaml.output_dir);
+    assert_eq!(
+        toml.keys().collect::<Vec<_>>(),
+        yaml.keys().collect::<Vec<_>>()
+    );
+    assert!(toml == yaml);
+    let slides =
+        String::from_utf8(yaml[std::path::Path::new("slides/1_1-unit.md")].clone()).unwrap();
+    assert!(slides.contains("- Write TOML\n- Write YAML\n"), "{slides}");
+}
+
+#[test]
+fn syntax_errors_point_at_the_line_in_the_definition() {
+    let error = Track::load_toml_def(common::fixture("formats/broken.track.json")).unwrap_err();
+    let error = format!("{error:?}");
+    assert!(
+        error.contains("broken.track.json:4:1: expected value"),
+        "{error}"
+    );
+}
+
+#[test]
+fn unknown_fields_are_errors_only_if_they_are_denied() {
+    let lenient = common::fixture("unknown-fields/lenient.track.toml");
+    let track = Track::load_toml_def(&lenient).unwrap();
+    assert!(track.modules[0].data.units[0].data.topics[0]
+        .data
+        .objectives
+        .is_empty());
+
+    let options = LoadOptions {
+        deny_unknown_fields: true,
+        ..Default::default()
+    };
+    let denied = [
+        Track::load_toml_def_with(&lenient, options).unwrap_err(),
+        // The track asks for it itself
+        Track::load_toml_def(common::fixture("unknown-fields/strict.track.toml")).unwrap_err(),
+    ];
+    for error in denied {
+        let error = format!("{error:?}");
+        assert!(error.contains("Unknown field `objectvies` in "), "{error}");
+        assert!(error.contains("topic.toml"), "{error}");
+    }
+}
@@ -0,0 +1,17 @@
+mod common;
+
+use modmod::Track;
+
+/// The outline is a format that other tools depend on, so any change to it should be
+/// deliberate. After one, write the expected outline anew with `MODMOD_UPDATE_GOLDEN=1`.
+#[test]
+fn outline_matches_golden_file() {
+    let track = Track::load_toml_def(common::fixture("outline/outline.track.toml")).unwrap();
+    let outline = serde_json::to_string_pretty(&track.outline()).unwrap();
+
+    let golden = common::fixture("outline/outline.json");
+    if std::env::var_os("MODMOD_UPDATE_GOLDEN").is_some() {
+        std::fs::write(&golden, &outline).unwrap();
+    }
+    assert_eq!(outline, std::fs::read_to_string(golden).unwrap());
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use modmod::{RenderOptions, Track};
+
+/// Render a copy of a fixture track into `output_dir` relative to the directory of the copy,
+/// which is in the output directory of test `name`
+fn render_copy(name: &str, output_dir: &str) -> String {
+    let dir = common::output_dir(name);
+    let sources = dir.join("course");
+    common::copy_dir(&common::fixture("shared-topic"), &sources);
+    let before = common::read_dir_files(&dir);
+
+    let track = Track::load_toml_def(sources.join("shared.track.toml")).unwrap();
+    let options = RenderOptions {
+        clear_output: true,
+        ..Default::default()
+    };
+    let error = track
+        .render(sources.join(output_dir), &options)
+        .unwrap_err();
+    // Nothing was written or cleared
+    assert_eq!(common::read_dir_files(&dir), before);
+    format!("{error:?}")
+}
+
+#[test]
+fn refuses_output_inside_of_the_sources() {
+    let error = render_copy("output-inside-sources", "m/t1/out");
+    assert!(error.contains("is inside of"), "{error}");
+    assert!(
+        error.contains("/course, which the render reads sources from"),
+        "{error}"
+    );
+}
+
+#[test]
+fn refuses_output_that_contains_the_sources() {
+    let error = render_copy("output-contains-sources", "..");
+    assert!(error.contains("contains"), "{error}");
+    assert!(
+        error.contains("which the render reads sources from"),
+        "{error}"
+    );
+}
+
+#[test]
+fn failed_incremental_renders_keep_the_earlier_output() {
+    let track =
+        Track::load_toml_def(common::fixture("duplicate-exercise/duplicate.track.toml")).unwrap();
+    let output_dir = common::output_dir("failed-incremental-render");
+    let options = RenderOptions {
+        incremental: true,
+        ..Default::default()
+    };
+    track.render(&output_dir, &options).unwrap();
+    let before = common::read_dir_files(&output_dir);
+    assert!(before.contains_key(std::path::Path::new("book/book.toml")));
+
+    // A duplicate exercise name fails strict renders after everything was written
+    let options = RenderOptions {
+        strict: true,
+        ..options
+    };
+    let error = track.render(&output_dir, &options).unwrap_err();
+    assert!(format!("{error:?}").contains("W0001"));
+    let after = common::read_dir_files(&output_dir);
+    assert_eq!(
+        after.keys().collect::<Vec<_>>(),
+        before.keys().collect::<Vec<_>>()
+    );
+    assert!(after == before);
+}
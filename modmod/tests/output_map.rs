@@ -0,0 +1,30 @@
+mod common;
+
+use modmod::RenderOptions;
+
+#[test]
+fn maps_every_occurrence_of_a_shared_topic() {
+    let report = common::render_fixture(
+        "shared-topic/shared.track.toml",
+        "shared-topic",
+        &RenderOptions::default(),
+    );
+    let map = &report.output_map;
+
+    let topics = &map.topics["m/t1"];
+    let units: Vec<&str> = topics.iter().map(|t| t.unit.as_str()).collect();
+    assert_eq!(units, ["m#unit-a", "m#unit-b"]);
+
+    let exercises = &map.exercises["m/t1/ex/same"];
+    assert_eq!(exercises.len(), 2);
+    assert_ne!(exercises[0].dir, exercises[1].dir);
+    for exercise in exercises {
+        let book_page = report.output_dir.join(&exercise.book_page);
+        let page = std::fs::read_to_string(book_page).unwrap();
+        // The description of each occurrence refers to its own exercise directory
+        assert!(page.contains(&exercise.dir.to_string_lossy().to_string()));
+    }
+
+    // Every path in the map refers to something that was rendered
+    map.check(&report.output_dir).unwrap();
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use std::{path::Path, process::Command};
+
+use common::{copy_dir, fixture, output_dir, read_dir_files};
+use modmod::{RenderOptions, Track, LOCKFILE};
+
+/// Run git with `args` in `dir`
+fn git(args: &[&str], dir: &Path) {
+    let status = Command::new("git")
+        .args([
+            "-c",
+            "user.name=modmod",
+            "-c",
+            "user.email=modmod@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed");
+}
+
+/// Render the track at `track_path` and return the slides of its topic
+fn render_slides(track_path: &Path, name: &str) -> String {
+    let output = output_dir(name);
+    Track::load_toml_def(track_path)
+        .unwrap()
+        .render(&output, &RenderOptions::default())
+        .unwrap();
+    let files = read_dir_files(&output);
+    let (_, slides) = files
+        .iter()
+        .find(|(path, _)| path.starts_with("slides") && path.extension().is_some_and(|e| e == "md"))
+        .unwrap();
+    String::from_utf8(slides.clone()).unwrap()
+}
+
+#[test]
+fn removing_a_source_from_the_lockfile_fetches_its_branch_again() {
+    let dir = output_dir("remote-source");
+    let repository = dir.join("repository");
+    copy_dir(&fixture("shared-topic/m"), &repository);
+    git(&["init", "--quiet", "--initial-branch=main"], &repository);
+    git(&["add", "."], &repository);
+    git(&["commit", "--quiet", "-m", "First"], &repository);
+
+    std::env::set_var("MODMOD_CACHE_DIR", dir.join("cache"));
+    let track_dir = dir.join("track");
+    std::fs::create_dir_all(&track_dir).unwrap();
+    let track_path = track_dir.join("remote.track.toml");
+    std::fs::write(
+        &track_path,
+        format!(
+            "name = \"Remote\"\nmodules = [\"git+{url}#main:mod.toml\"]\n",
+            url = repository.to_str().unwrap()
+        ),
+    )
+    .unwrap();
+    assert!(render_slides(&track_path, "remote-source-first").contains("part of two units"));
+
+    std::fs::write(
+        repository.join("t1/slides.md"),
+        "# Shared\n\nThis topic moved on.\n",
+    )
+    .unwrap();
+    git(&["commit", "--quiet", "-am", "Second"], &repository);
+    // The lockfile keeps the source at its first commit
+    assert!(render_slides(&track_path, "remote-source-pinned").contains("part of two units"));
+
+    std::fs::remove_file(track_dir.join(LOCKFILE)).unwrap();
+    assert!(render_slides(&track_path, "remote-source-updated").contains("moved on"));
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use std::fs;
+
+use modmod::{RenderOptions, WarningCode};
+
+const DRAFTS: &str = "drafts/drafts.track.toml";
+
+#[test]
+fn drafts_are_left_out_unless_they_are_included() {
+    let report = common::render_fixture(DRAFTS, "drafts", &RenderOptions::default());
+    assert_eq!((report.drafts_skipped, report.drafts_included), (2, 0));
+    let files = common::read_dir_files(&report.output_dir);
+    assert!(files.keys().all(|path| !path.ends_with("1_2-later.md")));
+    assert!(files.keys().all(|path| !path.starts_with("exercises")));
+    let slides = fs::read_to_string(report.output_dir.join("slides/1_1-unit.md")).unwrap();
+    assert!(slides.contains("This topic is finished."));
+    assert!(!slides.contains("not finished yet"));
+    assert!(!slides.contains("DRAFT"));
+}
+
+#[test]
+fn included_drafts_are_marked_as_drafts() {
+    let options = RenderOptions {
+        include_drafts: true,
+        ..Default::default()
+    };
+    let report = common::render_fixture(DRAFTS, "drafts-included", &options);
+    assert_eq!((report.drafts_skipped, report.drafts_included), (0, 2));
+
+    let book_dir = report.output_dir.join("book/src");
+    let summary = fs::read_to_string(book_dir.join("SUMMARY.md")).unwrap();
+    assert!(
+        summary.contains("\t- [Later (DRAFT)](1_2-later.md)\n"),
+        "{summary}"
+    );
+    let later = fs::read_to_string(book_dir.join("1_2-later.md")).unwrap();
+    assert!(
+        later.contains("> **DRAFT**: this is work in progress"),
+        "{later}"
+    );
+    // The exercise of the draft topic in a finished unit
+    let unit = fs::read_to_string(book_dir.join("1_1-unit.md")).unwrap();
+    assert!(
+        unit.contains("## Exercise 1.1.1: Sketch (DRAFT)\n\n> **DRAFT**"),
+        "{unit}"
+    );
+
+    let slides = fs::read_to_string(report.output_dir.join("slides/1_1-unit.md")).unwrap();
+    let draft_slide = slides.find("# DRAFT\n").unwrap();
+    assert!(draft_slide > slides.find("This topic is finished.").unwrap());
+    assert!(draft_slide < slides.find("not finished yet").unwrap());
+    let later = fs::read_to_string(report.output_dir.join("slides/1_2-later.md")).unwrap();
+    assert!(
+        later.contains("title: \"Rust - 1.2: Later (DRAFT)\""),
+        "{later}"
+    );
+}
+
+#[test]
+fn excluded_drafts_are_left_out_even_if_drafts_are_included() {
+    let options = RenderOptions {
+        include_drafts: true,
+        ..Default::default()
+    };
+    let report = common::render_fixture("drafts/excluded.track.toml", "drafts-excluded", &options);
+    // Only the draft unit, as the excluded topic doesn't count as a draft
+    assert_eq!((report.drafts_skipped, report.drafts_included), (0, 1));
+    let slides = fs::read_to_string(report.output_dir.join("slides/1_1-unit.md")).unwrap();
+    assert!(!slides.contains("not finished yet"));
+    let files = common::read_dir_files(&report.output_dir);
+    assert!(files.keys().all(|path| !path.starts_with("exercises")));
+}
+
+#[test]
+fn excluded_topics_are_left_out_everywhere() {
+    let path = "excluded-topics/excluded.track.toml";
+    let report = common::render_fixture(path, "excluded-topics", &RenderOptions::default());
+
+    let slides = fs::read_to_string(report.output_dir.join("slides/1_1-unit.md")).unwrap();
+    assert!(
+        slides.contains("# Learning objectives\n\n- Keep this\n"),
+        "{slides}"
+    );
+    assert!(slides.contains("# Summary\n- Kept this"), "{slides}");
+    for dropped in ["Drop this", "Dropped this", "These slides go."] {
+        assert!(!slides.contains(dropped), "{dropped} in {slides}");
+    }
+    let book = fs::read_to_string(report.output_dir.join("book/src/1_1-unit.md")).unwrap();
+    assert!(!book.contains("Drop"), "{book}");
+    let files = common::read_dir_files(&report.output_dir.join("exercises"));
+    let exercises: Vec<_> = files.keys().map(|path| path.to_str().unwrap()).collect();
+    assert_eq!(exercises, ["1-mod/1-unit/1-keep/description.md"]);
+
+    // The exclusion that matches no topic
+    assert_eq!(report.warnings.len(), 1);
+    let warning = &report.warnings[0];
+    assert_eq!(warning.code, WarningCode::UnmatchedTopicExclusion);
+    assert!(
+        warning
+            .message
+            .ends_with("topic.toml is not part of the track"),
+        "{}",
+        warning.message
+    );
+    assert!(warning.message.contains("missing"));
+}
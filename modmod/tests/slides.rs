@@ -0,0 +1,94 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use modmod::{RenderOptions, Track};
+
+const EMBEDDED: &str = "shared-slides/embedded.track.toml";
+
+fn read(dir: &Path, path: &str) -> String {
+    fs::read_to_string(dir.join(path)).unwrap()
+}
+
+#[test]
+fn escaped_placeholders_are_rendered_as_text() {
+    let report =
+        common::render_fixture(EMBEDDED, "escaped-placeholders", &RenderOptions::default());
+    let dir = &report.output_dir;
+
+    let slides = read(dir, "slides/1_1-unit.md");
+    // In the template, and in the slides of the topic
+    assert!(
+        slides.contains("The slides of the topics go where #[modmod:content] is."),
+        "{slides}"
+    );
+    assert!(
+        slides.contains("includes the slides of its topics with #[modmod:content]."),
+        "{slides}"
+    );
+    let book = read(dir, "book/src/1_1-unit.md");
+    assert!(
+        book.contains("Where `#[modmod:exercise_dir]` is written, the book shows exercises/1-mod/1-unit/1-paths."),
+        "{book}"
+    );
+    let appendix = read(dir, "book/src/appendix-placeholders.md");
+    assert!(
+        appendix.contains("Placeholders like #[modmod:var(trainer)] are filled in"),
+        "{appendix}"
+    );
+}
+
+#[test]
+fn variables_of_the_track_are_filled_in() {
+    let report = common::render_fixture(EMBEDDED, "variables", &RenderOptions::default());
+    let slides = read(&report.output_dir, "slides/1_1-unit.md");
+    // In the template, and in the slides of the topic
+    assert!(slides.contains("Presented by Ferris\n"), "{slides}");
+    assert!(
+        slides.contains("Questions go to embedded@example.com."),
+        "{slides}"
+    );
+}
+
+#[test]
+fn variables_that_the_track_doesnt_define_are_problems() {
+    let path = common::fixture("shared-slides/no-variables.track.toml");
+    let report = Track::load_toml_def(path)
+        .unwrap()
+        .check(&RenderOptions::default())
+        .unwrap();
+    let problem = &report.problems[0];
+    assert!(
+        problem
+            .starts_with("Unknown variable 'support_email', the track defines no variables, in "),
+        "{problem}"
+    );
+    assert!(problem.ends_with("slides.md"), "{problem}");
+}
+
+#[test]
+fn conditional_regions_only_show_on_the_tracks_they_name() {
+    let options = RenderOptions::default();
+    let embedded = common::render_fixture(EMBEDDED, "conditional-embedded", &options);
+    let desktop = common::render_fixture(
+        "shared-slides/desktop.track.toml",
+        "conditional-desktop",
+        &options,
+    );
+    let embedded = read(&embedded.output_dir, "slides/1_1-unit.md");
+    let desktop = read(&desktop.output_dir, "slides/1_1-unit.md");
+
+    assert!(
+        embedded.ends_with(
+            "On a microcontroller, there's no allocator by default.\n\nBoth tracks show this.\n\n"
+        ),
+        "{embedded}"
+    );
+    // The region of the other track, and a nested region that's only for this track
+    assert!(desktop.ends_with(
+        "`Vec` allocates its items on the heap.\n\nBoth tracks show this.\nOnly the desktop track shows this.\n\n"
+    ), "{desktop}");
+    for slides in [&embedded, &desktop] {
+        assert!(!slides.contains("#[modmod:if") && !slides.contains("#[modmod:endif]"));
+    }
+}
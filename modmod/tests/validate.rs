@@ -0,0 +1,91 @@
+mod common;
+
+use modmod::{RenderOptions, Track, WarningCode};
+
+const DUPLICATE: &str = "duplicate-exercise/duplicate.track.toml";
+
+#[test]
+fn duplicate_exercise_names_get_distinct_headings_and_a_warning() {
+    let report = common::render_fixture(DUPLICATE, "duplicate-exercise", &RenderOptions::default());
+
+    let codes: Vec<WarningCode> = report.warnings.iter().map(|w| w.code).collect();
+    assert_eq!(codes, [WarningCode::DuplicateExerciseName]);
+    assert_eq!(report.warnings[0].code.code(), "W0001");
+
+    let page = std::fs::read_to_string(report.output_dir.join("book/src/1_1-unit.md")).unwrap();
+    assert!(page.contains("## Exercise 1.1.1: Practice"));
+    assert!(page.contains("## Exercise 1.1.2: Practice"));
+    let exercises = report.output_dir.join("exercises/1-mod/1-unit");
+    assert!(exercises.join("1-practice").is_dir());
+    assert!(exercises.join("2-practice").is_dir());
+}
+
+#[test]
+fn duplicate_exercise_names_fail_strict_renders() {
+    let track = Track::load_toml_def(common::fixture(DUPLICATE)).unwrap();
+    let output_dir = common::output_dir("duplicate-exercise-strict");
+    let options = RenderOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let error = track.render(&output_dir, &options).unwrap_err();
+    assert!(format!("{error:?}").contains("W0001"));
+    assert!(!output_dir.exists());
+}
+
+#[test]
+fn duplicate_items_are_listed_once_in_order_of_first_occurrence() {
+    let path = "duplicate-items/items.track.toml";
+    let report = common::render_fixture(path, "duplicate-items", &RenderOptions::default());
+
+    // Exact duplicates, and those that only differ in case and whitespace
+    let messages: Vec<&str> = report.warnings.iter().map(|w| w.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        [
+            "Objective 'Use traits' of unit 'Unit' is listed more than once, by topics 'Traits', 'Generics'",
+            "Objective 'Write generic code' of unit 'Unit' is listed more than once, by topics 'Traits', 'Generics'",
+            "Summary item 'Traits describe behavior' of unit 'Unit' is listed more than once, by topics 'Traits', 'Generics'",
+        ]
+    );
+    assert!(report
+        .warnings
+        .iter()
+        .all(|w| w.code == WarningCode::DuplicateItem));
+
+    let slides = std::fs::read_to_string(report.output_dir.join("slides/1_1-unit.md")).unwrap();
+    assert!(slides.contains(
+        "# Learning objectives\n\n- Use traits\n- Write generic code\n- Implement a trait\n"
+    ));
+    assert!(
+        slides.contains("# Summary\n- Traits describe behavior\n- Generics are monomorphized\n")
+    );
+
+    let track = Track::load_toml_def(common::fixture(path)).unwrap();
+    let outline = track.outline();
+    assert_eq!(
+        outline.modules[0].units[0].objectives,
+        ["Use traits", "Write generic code", "Implement a trait"]
+    );
+}
+
+#[test]
+fn near_duplicate_items_are_flagged_if_the_track_asks_for_it() {
+    let path = "near-duplicate-items/near.track.toml";
+    let report = common::render_fixture(path, "near-duplicate-items", &RenderOptions::default());
+
+    // "Understand owners" isn't a prefix of whole words of "Understand ownership"
+    assert_eq!(report.warnings.len(), 1);
+    let warning = &report.warnings[0];
+    assert_eq!(warning.code, WarningCode::NearDuplicateItem);
+    assert_eq!(warning.code.code(), "W0003");
+    assert_eq!(
+        warning.message,
+        "Objective 'Understand ownership and borrowing' of topic 'Borrowing' is nearly the same as 'Understand ownership' of topic 'Ownership' in unit 'Unit'"
+    );
+
+    let path = "near-duplicate-items/default.track.toml";
+    let report =
+        common::render_fixture(path, "near-duplicate-items-off", &RenderOptions::default());
+    assert!(report.warnings.is_empty());
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use std::path::Path;
+
+use modmod::{RenderOptions, Track};
+
+#[test]
+fn vendored_track_renders_the_same() {
+    let dir = common::output_dir("vendor");
+    // A course that uses a module of which the origin is outside of the course directory
+    common::copy_dir(&common::fixture("outline/basics"), &dir.join("upstream"));
+    std::fs::create_dir_all(dir.join("course")).unwrap();
+    let track_path = dir.join("course/track.toml");
+    let track_toml = std::fs::read_to_string(common::fixture("outline/outline.track.toml"))
+        .unwrap()
+        .replace("basics/mod.toml", "../upstream/mod.toml");
+    std::fs::write(&track_path, track_toml).unwrap();
+
+    let render = |output_dir: &Path| {
+        let track = Track::load_toml_def(&track_path).unwrap();
+        track.render(output_dir, &RenderOptions::default()).unwrap();
+        common::read_dir_files(output_dir)
+    };
+    let before = render(&dir.join("before"));
+
+    let track = Track::load_toml_def(&track_path).unwrap();
+    let report = track.vendor("vendor").unwrap();
+    assert_eq!(report.modules.len(), 1);
+    assert_eq!(report.modules[0].path, "vendor/upstream/mod.toml");
+    let after = render(&dir.join("after"));
+
+    // Ids in the output map are derived from where the sources are, which is what vendoring
+    // changes, and everything else stays the same
+    let without_map = |mut files: std::collections::BTreeMap<_, _>| {
+        assert!(files.remove(Path::new("modmod-map.json")).is_some());
+        files
+    };
+    assert_eq!(without_map(before), without_map(after));
+}